@@ -0,0 +1,270 @@
+// Machine generation for Adapted FRONT (see bin/adapted_front.rs): a START
+// state, and a chain of PADDING states approximating the Rayleigh distribution
+// of FRONT. AdaptedFront holds the parameters of the adapted_front binary,
+// parsed from its arguments by AdaptedFront::from_args().
+
+use std::f64::EPSILON;
+use std::f64::consts::E;
+use std::f64::consts::PI;
+use std::collections::HashMap;
+
+use maybenot::{
+machine::Machine,
+event::Event,
+state::State,
+dist::{Dist, DistType}
+};
+
+use crate::PACKET_SIZE;
+use crate::args::{try_parse_args, parse_finite_positive, parse_count, packet_size, machine_options, Args, MachineOptions};
+use crate::validate::check_has_states;
+use crate::dist_helpers::{fixed, uniform_range};
+use crate::defaults::{ADAPTED_FRONT_PADDING_WINDOW, ADAPTED_FRONT_PADDING_BUDGET, ADAPTED_FRONT_NUM_STATES};
+use crate::logging::debug;
+
+
+// Cap on the number of PADDING states
+pub const MAX_STATES: usize = crate::MAX_STATES;
+
+// Switches and options of the adapted_front binary
+pub const SWITCHES: &[&str] = &["--warn-unreachable", "--explain", "--dry-run", "--annotate", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--report", "--include-small-packets", "--strict"];
+pub const OPTIONS: &[&str] = &["--packet-size", "--max-blocking-frac", "--allowed-blocked-microsec", "--time-scale"];
+
+
+// Parameters of the adapted_front binary
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptedFront {
+    // W_max, upper bound of the padding window (sec)
+    pub padding_window: f64,
+    // N, padding packets per window
+    pub padding_budget: u32,
+    // PADDING states approximating the Rayleigh distribution
+    pub num_states: u32,
+    // Size of padding packets (bytes)
+    pub packet_size: f64,
+    pub options: MachineOptions,
+}
+
+
+impl Default for AdaptedFront {
+    // The paper's configuration
+    fn default() -> AdaptedFront {
+        return AdaptedFront {
+            padding_window: ADAPTED_FRONT_PADDING_WINDOW,
+            padding_budget: ADAPTED_FRONT_PADDING_BUDGET,
+            num_states: ADAPTED_FRONT_NUM_STATES,
+            packet_size: PACKET_SIZE,
+            options: MachineOptions::default(),
+        };
+    }
+}
+
+
+impl AdaptedFront {
+    // Parse the arguments of the adapted_front binary (see parse_args()).
+    pub fn from_args(cli: &Args) -> Result<AdaptedFront, String> {
+        let args = &cli.positional;
+        if args.len() > 4 {
+            return Err(format!("Usage: {} [padding window = 12] [padding budget = 4000] [num states = 30] [--warn-unreachable] [--explain] [--dry-run] [--annotate] [--hash] [--ffi] [--emit-rust] [--base64] [--report] [--include-small-packets] [--strict] [--packet-size <bytes>] [--max-blocking-frac <fraction>] [--allowed-blocked-microsec <usec>] [--time-scale <factor>]", args[0]));
+        }
+
+        let padding_window: f64 = args.get(1).map_or(Ok(ADAPTED_FRONT_PADDING_WINDOW), |v| parse_finite_positive(v, "padding window"))?; // FRONT param = W_max (sec)
+        let padding_budget: u32 = args.get(2).map_or(Ok(ADAPTED_FRONT_PADDING_BUDGET), |v| parse_count(v, "padding budget"))?; // FRONT param = N (num cells)
+        let num_states:     u32 = args.get(3).map_or(Ok(ADAPTED_FRONT_NUM_STATES), |v| parse_count(v, "num states"))?; // number of PADDING states
+
+        if num_states < 1 {
+            return Err(String::from("Num states must be at least 1"));
+        }
+        if num_states as usize > MAX_STATES {
+            return Err(format!("Adapted FRONT would take {} PADDING states, more than the cap of {}", num_states, MAX_STATES));
+        }
+
+        return Ok(AdaptedFront {
+            padding_window: padding_window,
+            padding_budget: padding_budget,
+            num_states: num_states,
+            packet_size: packet_size(cli)?,
+            options: machine_options(cli)?,
+        });
+    }
+
+
+    // The machine, with the options applied.
+    pub fn machine(&self) -> Result<Machine, String> {
+        let mut machine = generate_machine(self.padding_window * 1000000.0, self.padding_budget, self.num_states as usize, self.packet_size);
+        self.options.apply(&mut machine)?;
+        check_has_states("Machine", &machine)?;
+        return Ok(machine);
+    }
+
+
+    // The machine, with the name the binary prints.
+    pub fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return Ok(vec![(String::from("Machine"), self.machine()?)]);
+    }
+}
+
+
+// Parse the arguments of the adapted_front binary (without the program name)
+// into its parameters.
+pub fn parse(args: &[String]) -> Result<AdaptedFront, String> {
+    let cli = try_parse_args([&[String::from("adapted_front")], args].concat(), SWITCHES, OPTIONS)?;
+    return AdaptedFront::from_args(&cli);
+}
+
+
+// Generate the machines of the adapted_front binary for its arguments (without
+// the program name), e.g., generate(&["12", "4000", "30"]).
+pub fn generate(args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse(args)?.named_machines();
+}
+
+
+// Generate an Adapted FRONT machine with the specified number of PADDING states.
+pub fn generate_machine(padding_window: f64, padding_budget: u32, num_states: usize, packet_size: f64) -> Machine {
+    let area = 1.0 / (num_states as f64);       // Area under Rayleigh CDF curve of each state
+    let max_t = rayleigh_max_t(padding_window);
+
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(num_states + 1);
+    states.push(generate_start_state(num_states + 1));
+
+    let mut t1 = 0.0;                           // Starting time of next PADDING state
+    let mut total_padding_frac = 0.0;           // Area coverage of current PADDING states
+
+    for i in 1..num_states {
+        let width = calc_interval_width(t1, max_t, area, padding_window);
+        let middle = t1 + (width / 2.0);
+        let t2 = t1 + width;
+
+        let padding_count = area * (padding_budget as f64);
+        let timeout = width / padding_count;
+        let stdev = (padding_window).powi(2) / (padding_count * middle * PI.sqrt());
+        debug(|| format!("PADDING state {}: [{}, {}) sec, width {} sec, timeout {} sec, stdev {}", i, t1, t2, width, timeout, stdev));
+
+        states.push(generate_padding_state(i, i + 1, num_states + 1, padding_count, timeout, stdev, packet_size));
+
+        t1 = t2;
+        total_padding_frac += area;
+    }
+
+    // Last state, to max_t
+    let width = max_t - t1;
+    let middle = t1 + (width / 2.0);
+
+    let padding_count = (1.0 - total_padding_frac) * (padding_budget as f64);
+    let timeout = width / padding_count;
+    let stdev = (padding_window).powi(2) / (padding_count * middle * PI.sqrt());
+    debug(|| format!("PADDING state {}: [{}, {}) sec, width {} sec, timeout {} sec, stdev {}", num_states, t1, max_t, width, timeout, stdev));
+
+    states.push(generate_padding_state(num_states, 1, num_states + 1, padding_count, timeout, stdev, packet_size));
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: u64::MAX,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+
+    return machine;
+}
+
+
+// Generate a PADDING state for a machine.
+pub fn generate_padding_state(curr_index: usize, next_index: usize, num_states: usize, padding_count: f64, timeout: f64, stdev: f64, packet_size: f64) -> State {
+    // PaddingSent --> this PADDING state (100%)
+    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
+    padding_sent.insert(curr_index, 1.0);
+
+    // LimitReached --> next PADDING state or StateEnd (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(next_index, 1.0);
+
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingSent, padding_sent);
+    transitions.insert(Event::LimitReached, limit_reached);
+
+    let mut state = State::new(transitions, num_states);
+
+    state.timeout = Dist {
+        dist: DistType::Normal,
+        param1: timeout,
+        param2: stdev,
+        start: 0.0,
+        max: (timeout * 2.0),
+    };
+
+    state.action = fixed(packet_size);
+
+    state.limit = uniform_range(1.0, padding_count);
+
+    return state;
+}
+
+
+// Generate the START state for a machine.
+pub fn generate_start_state(num_states: usize) -> State {
+    // NonPaddingSent --> first PADDING state (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(1, 1.0);
+
+    // NonPaddingRecv --> first PADDING state (100%)
+    let mut nonpadding_recv: HashMap<usize, f64> = HashMap::new();
+    nonpadding_recv.insert(1, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+    transitions.insert(Event::NonPaddingRecv, nonpadding_recv);
+
+    return State::new(transitions, num_states);
+}
+
+
+// Find the width of an interval in the Rayleigh distribution,
+// starting at a, with the specified area. Uses a search algorithm
+// because numerical error affects direct calculation significantly.
+pub fn calc_interval_width(a: f64, max_t: f64, area: f64, scale: f64) -> f64 {
+    let mut b = max_t;
+    let mut increment = (b - a) / 2.0;
+
+    let mut curr_area = rayleigh_cdf(b, scale) - rayleigh_cdf(a, scale);
+    let mut curr_diff = area - curr_area;
+
+    while curr_diff.abs() > EPSILON {
+        if curr_diff < 0.0 {
+            b -= increment;
+        } else {
+            b += increment;
+        }
+        increment /= 2.0;
+
+        curr_area = rayleigh_cdf(b, scale) - rayleigh_cdf(a, scale);
+        curr_diff = area - curr_area;
+    }
+
+    return b - a;
+}
+
+
+// Cumulative distribution function of Rayleigh distribution
+pub fn rayleigh_cdf(t: f64, scale: f64) -> f64 {
+    let exp_num = -t.powi(2);
+    let exp_div = 2.0 * scale.powi(2);
+    let exp = exp_num / exp_div;
+
+    return 1.0 - E.powf(exp);
+}
+
+
+// Return the value of t (input to Rayleigh CDF) at which area = 0.9996645373720975, chosen
+// empirically. This is a bit more than 6 standard deviations.
+pub fn rayleigh_max_t(scale: f64) -> f64 {
+    let a: f64 = -2.0 * scale.powi(2);
+    let b: f64 = 1.0 - 0.9996645373720975;
+
+    return (a * b.log(E)).sqrt();
+}
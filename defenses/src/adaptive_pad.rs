@@ -0,0 +1,209 @@
+// Machine generation for Adaptive Padding (see bin/adaptive_pad.rs): the IDLE,
+// BURST, and GAP states of WTF-PAD, with a distribution in place of each
+// histogram. AdaptivePad holds the parameters of the adaptive_pad binary,
+// parsed from its arguments by AdaptivePad::from_args().
+
+use std::collections::HashMap;
+
+use maybenot::{
+machine::Machine,
+event::Event,
+state::State,
+dist::{Dist, DistType}
+};
+
+use crate::args::{try_parse_args, parse_finite, parse_finite_positive, packet_size, strict_count, dist_type, machine_options, Args, MachineOptions};
+use crate::validate::check_has_states;
+use crate::dist_helpers::{fixed, uniform_range};
+
+
+pub const NUM_STATES: usize = 3;
+
+pub const IDLE_STATE_INDEX:  usize = 0;
+pub const BURST_STATE_INDEX: usize = 1;
+pub const GAP_STATE_INDEX:   usize = 2;
+
+// Switches and options of the adaptive_pad binary
+pub const SWITCHES: &[&str] = &["--warn-unreachable", "--explain", "--dry-run", "--annotate", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--report", "--include-small-packets", "--strict"];
+pub const OPTIONS: &[&str] = &["--packet-size", "--max-blocking-frac", "--allowed-blocked-microsec", "--time-scale", "--dist"];
+
+
+// Parameters of the adaptive_pad binary
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptivePad {
+    // (param1, param2) of the distributions of the BURST and GAP timeouts
+    // (usec)
+    pub burst: (f64, f64),
+    pub gap: (f64, f64),
+    // Padding packets per gap, at most
+    pub max_gap: f64,
+    pub dist: DistType,
+    // Size of padding packets (bytes)
+    pub packet_size: f64,
+    pub options: MachineOptions,
+}
+
+
+impl AdaptivePad {
+    // Parse the arguments of the adaptive_pad binary (see parse_args()).
+    pub fn from_args(cli: &Args) -> Result<AdaptivePad, String> {
+        let args = &cli.positional;
+        if args.len() != 6 {
+            return Err(format!("Usage: {} <burst param1> <burst param2> <gap param1> <gap param2> <max gap padding> [--warn-unreachable] [--explain] [--dry-run] [--annotate] [--hash] [--ffi] [--emit-rust] [--base64] [--report] [--include-small-packets] [--strict] [--packet-size <bytes>] [--max-blocking-frac <fraction>] [--allowed-blocked-microsec <usec>] [--time-scale <factor>] [--dist <distribution>]", args[0]));
+        }
+
+        let burst1:  f64 = parse_finite(&args[1], "burst parameter 1")?; // burst histogram, e.g. mean (usec)
+        let burst2:  f64 = parse_finite(&args[2], "burst parameter 2")?; // burst histogram, e.g. stdev (usec)
+        let gap1:    f64 = parse_finite(&args[3], "gap parameter 1")?; // gap histogram, e.g. mean (usec)
+        let gap2:    f64 = parse_finite(&args[4], "gap parameter 2")?; // gap histogram, e.g. stdev (usec)
+        let max_gap: f64 = parse_finite_positive(&args[5], "max gap padding").and_then(|count| strict_count(cli, count, "max gap padding"))?; // padding packets per gap, at most
+
+        return Ok(AdaptivePad {
+            burst: (burst1, burst2),
+            gap: (gap1, gap2),
+            max_gap: max_gap,
+            dist: dist_type(cli, DistType::Normal)?,
+            packet_size: packet_size(cli)?,
+            options: machine_options(cli)?,
+        });
+    }
+
+
+    // The machine, with the options applied.
+    pub fn machine(&self) -> Result<Machine, String> {
+        let mut machine = generate_machine(self.dist, self.burst, self.gap, self.max_gap, self.packet_size);
+        self.options.apply(&mut machine)?;
+        check_has_states("Machine", &machine)?;
+        return Ok(machine);
+    }
+
+
+    // The machine, with the name the binary prints.
+    pub fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return Ok(vec![(String::from("Machine"), self.machine()?)]);
+    }
+}
+
+
+// Parse the arguments of the adaptive_pad binary (without the program name)
+// into its parameters.
+pub fn parse(args: &[String]) -> Result<AdaptivePad, String> {
+    let cli = try_parse_args([&[String::from("adaptive_pad")], args].concat(), SWITCHES, OPTIONS)?;
+    return AdaptivePad::from_args(&cli);
+}
+
+
+// Generate the machines of the adaptive_pad binary for its arguments
+// (without the program name), e.g.,
+// generate(&["1000", "500", "2000", "1000", "10"]).
+pub fn generate(args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse(args)?.named_machines();
+}
+
+
+// Generate an adaptive padding machine. The burst and gap timeouts are
+// (param1, param2) of a distribution of type dist.
+pub fn generate_machine(dist: DistType, burst: (f64, f64), gap: (f64, f64), max_gap: f64, packet_size: f64) -> Machine {
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(NUM_STATES);
+    states.push(generate_idle_state());
+    states.push(generate_burst_state(dist, burst, packet_size));
+    states.push(generate_gap_state(dist, gap, max_gap, packet_size));
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: u64::MAX,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+
+    return machine;
+}
+
+
+// Generate the IDLE state for a machine.
+pub fn generate_idle_state() -> State {
+    // NonPaddingSent --> BURST (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(BURST_STATE_INDEX, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+
+    return State::new(transitions, NUM_STATES);
+}
+
+
+// Generate the BURST state for a machine.
+pub fn generate_burst_state(dist: DistType, burst: (f64, f64), packet_size: f64) -> State {
+    // NonPaddingSent --> BURST (100%), re-arming the timer
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(BURST_STATE_INDEX, 1.0);
+
+    // PaddingSent --> GAP (100%)
+    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
+    padding_sent.insert(GAP_STATE_INDEX, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+    transitions.insert(Event::PaddingSent, padding_sent);
+
+    // BURST state
+    let mut state = State::new(transitions, NUM_STATES);
+
+    state.timeout = Dist {
+        dist: dist,
+        param1: burst.0,
+        param2: burst.1,
+        start: 0.0,
+        max: 0.0,
+    };
+
+    state.action = fixed(packet_size);
+
+    return state;
+}
+
+
+// Generate the GAP state for a machine.
+pub fn generate_gap_state(dist: DistType, gap: (f64, f64), max_gap: f64, packet_size: f64) -> State {
+    // PaddingSent --> GAP (100%)
+    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
+    padding_sent.insert(GAP_STATE_INDEX, 1.0);
+
+    // NonPaddingSent --> BURST (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(BURST_STATE_INDEX, 1.0);
+
+    // LimitReached --> IDLE (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(IDLE_STATE_INDEX, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingSent, padding_sent);
+    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+    transitions.insert(Event::LimitReached, limit_reached);
+
+    // GAP state
+    let mut state = State::new(transitions, NUM_STATES);
+
+    state.timeout = Dist {
+        dist: dist,
+        param1: gap.0,
+        param2: gap.1,
+        start: 0.0,
+        max: 0.0,
+    };
+
+    state.action = fixed(packet_size);
+
+    state.limit = uniform_range(1.0, max_gap);
+
+    return state;
+}
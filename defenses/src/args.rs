@@ -0,0 +1,408 @@
+// Minimal command-line handling shared by the binaries. Positional arguments
+// keep their original meaning (index 0 is the program name), while --flags may
+// appear anywhere. Flags that take a value consume the following argument, and
+// may be repeated.
+
+use std::process;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use maybenot::{
+dist::DistType,
+machine::Machine
+};
+
+use crate::PACKET_SIZE;
+use crate::dist_helpers::scale_timeouts;
+use crate::output::Encoding;
+use crate::logging;
+
+
+pub struct Args {
+    pub positional: Vec<String>,
+    flags: HashMap<String, Vec<String>>,
+}
+
+
+// Options shared by the defense generators, for their --help text
+pub const GENERATOR_OPTIONS: &str = "\
+Generator options:
+  --explain              describe the defense and the role of each parameter in
+                         prose, and exit without generating
+  --warn-unreachable     warn on stderr about states unreachable from state 0
+  --dry-run              validate the machine(s) instead of printing them
+  --annotate             precede the output with \"#\" lines listing the parameters
+  --hash                 also print the SHA-256 of the machine(s), over all the
+                         machines in order
+  --ffi                  print only the machine(s), one per line, as passed to
+                         maybenot_start() of maybenot-ffi
+  --emit-rust            print the machine(s) as Rust &str constants, to paste
+                         into source that embeds them
+  --base64               print the machine(s) in base64 instead of hex, with
+                         the encoded length (see decode --base64)
+  --pretty               follow each machine with its decoded states, in \"#\"
+                         lines (see decode)
+  --report               print the state count and serialized length of the
+                         machine(s), and estimates of the padding bandwidth and
+                         the delay they add, instead of the machine(s)
+  --packet-size <bytes>  size of padding packets (bytes, default 1500)
+  --max-blocking-frac <fraction>
+                         cap blocking at this fraction of each machine's
+                         lifetime (0-1, default 0 = no cap, see README.md)
+  --allowed-blocked-microsec <usec>
+                         blocking each machine may do regardless of
+                         --max-blocking-frac (usec, default 0)
+  --time-scale <factor>  multiply every timeout by this factor, but not limits,
+                         padding sizes, or block durations (> 0, default 1.0)
+  --strict               reject counts of packets or bytes that are not integers,
+                         instead of rounding them (see README.md)
+  --include-small-packets  let packets of at most 52 bytes (e.g., ACKs) trigger
+                         transitions too (see README.md)
+";
+
+
+// Split args into positional arguments and the supported switches (no value)
+// and options (one value). Exits with an error on unknown flags, like the rest
+// of the argument handling. --help is always accepted, see help().
+pub fn parse_args(args: Vec<String>, switches: &[&str], options: &[&str]) -> Args {
+    // Every binary parses its arguments first, so tracing starts here
+    logging::init();
+    
+    return try_parse_args(args, switches, options).unwrap_or_else(|e| exit_with_error(&e));
+}
+
+
+// Print an error on rejected arguments and exit with status 1, for the
+// binaries: the arguments are user input, so they do not panic on them.
+pub fn exit_with_error(e: &str) -> ! {
+    eprintln!("Error: {}", e);
+    process::exit(1);
+}
+
+
+// parse_args(), returning an error on unknown flags instead, for the
+// generators called in-process (see generate.rs).
+pub fn try_parse_args(args: Vec<String>, switches: &[&str], options: &[&str]) -> Result<Args, String> {
+    let mut positional: Vec<String> = Vec::new();
+    let mut flags: HashMap<String, Vec<String>> = HashMap::new();
+    let mut iter = args.into_iter();
+
+    while let Some(arg) = iter.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg);
+        } else if switches.contains(&arg.as_str()) || arg == "--help" {
+            flags.entry(arg).or_default();
+        } else if options.contains(&arg.as_str()) {
+            let value = match iter.next() {
+                Some(value) => value,
+                None => return Err(format!("Missing value for {}", arg)),
+            };
+            flags.entry(arg).or_default().push(value);
+        } else {
+            return Err(format!("Unknown option {}", arg));
+        }
+    }
+
+    return Ok(Args { positional: positional, flags: flags });
+}
+
+
+impl Args {
+    // True if the switch or option was given.
+    pub fn has(&self, flag: &str) -> bool {
+        return self.flags.contains_key(flag);
+    }
+
+    // The value of an option, if given (the last one, if repeated).
+    pub fn value(&self, flag: &str) -> Option<&str> {
+        return self.flags.get(flag).and_then(|values| values.last()).map(|value| value.as_str());
+    }
+
+    // All values of a repeatable option, in order.
+    pub fn values(&self, flag: &str) -> Vec<&str> {
+        return match self.flags.get(flag) {
+            Some(values) => values.iter().map(|value| value.as_str()).collect(),
+            None => Vec::new(),
+        };
+    }
+}
+
+
+// If --help was given, print the help text (made up of the given sections)
+// and exit.
+pub fn help(args: &Args, sections: &[&str]) {
+    if args.has("--help") {
+        print!("{}", sections.join("\n"));
+        process::exit(0);
+    }
+}
+
+
+// If --explain was given, print the prose description of the defense (what
+// it does, the role of each parameter, and where the paper covers it) and
+// exit, for the generators.
+pub fn explain(args: &Args, text: &str) {
+    if args.has("--explain") {
+        print!("{}", text);
+        process::exit(0);
+    }
+}
+
+
+// Parse a numeric argument that must be finite (not NaN or infinite, which
+// parse() accepts as "nan" and "inf"). The error names the argument.
+pub fn parse_finite(text: &str, name: &str) -> Result<f64, String> {
+    return match text.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        Ok(_) => Err(format!("{} must be a finite number, got {}", name, text)),
+        Err(_) => Err(format!("{} must be a number, got {}", name, text)),
+    };
+}
+
+
+// Parse a numeric argument that must be positive and finite, e.g. a rate, a
+// packet count, or a size. The error names the argument.
+pub fn parse_finite_positive(text: &str, name: &str) -> Result<f64, String> {
+    return match text.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() && value > 0.0 => Ok(value),
+        Ok(_) => Err(format!("{} must be a positive finite number, got {}", name, text)),
+        Err(_) => Err(format!("{} must be a number, got {}", name, text)),
+    };
+}
+
+
+// Parse a whole-number argument, e.g. a number of states or of L/R pairs, into
+// an unsigned integer type. parse() would report a bad value as its own error
+// type (e.g., ParseIntError { kind: InvalidDigit }), so the error names the
+// argument instead. Leading and trailing whitespace is ignored, as by
+// parse_finite().
+pub fn parse_count<T: FromStr>(text: &str, name: &str) -> Result<T, String> {
+    return text.trim().parse::<T>().map_err(|_| format!("{} must be a whole number, got {}", name, text));
+}
+
+
+// The --packet-size option shared by the generators (bytes), which sets the
+// size of every padding packet.
+pub fn packet_size(args: &Args) -> Result<f64, String> {
+    return match args.value("--packet-size") {
+        Some(value) => parse_finite_positive(value, "packet size").and_then(|size| strict_count(args, size, "packet size")),
+        None => Ok(PACKET_SIZE),
+    };
+}
+
+
+// Parse a comma-separated list of padding packet sizes (bytes), e.g.
+// "1200,1500" for generate --packet-sizes. The error names the bad size.
+pub fn parse_packet_sizes(text: &str) -> Result<Vec<f64>, String> {
+    return text.split(',').map(|size| parse_finite_positive(size, "packet size")).collect();
+}
+
+
+// The --strict switch of the generators: a parameter that counts packets or
+// bytes must then be an integer, instead of being rounded later on (by the
+// generator, or by maybenot when it samples a limit or a padding size).
+// Returns the value if it passes.
+pub fn strict_count(args: &Args, value: f64, name: &str) -> Result<f64, String> {
+    if args.has("--strict") && value.fract() != 0.0 {
+        return Err(format!("{} must be an integer with --strict, got {}", name, value));
+    }
+    return Ok(value);
+}
+
+
+// Parse an interval argument of a generator (usec), or with the --ms switch
+// of the generators that take intervals, an interval in milliseconds. None
+// unless the interval is a finite number.
+pub fn parse_interval(args: &Args, text: &str) -> Option<f64> {
+    let text = text.trim();
+    let interval = if args.has("--ms") { parse_ms(text)? } else { text.parse().ok()? };
+    if !interval.is_finite() {
+        return None;
+    }
+
+    return Some(interval);
+}
+
+
+// Parse milliseconds as usec. The decimal exponent is shifted rather than the
+// value multiplied by 1000, so that e.g. "1.005" gives exactly the same f64 as
+// "1005" (1.005 * 1000.0 does not), and the machine is the same either way.
+pub fn parse_ms(text: &str) -> Option<f64> {
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(i) => (&text[..i], text[i + 1..].parse::<i32>().ok()?),
+        None => (text, 0),
+    };
+
+    return format!("{}e{}", mantissa, exponent.checked_add(3)?).parse().ok();
+}
+
+
+// The blocking budget of a machine, see blocking_budget()
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockingBudget {
+    pub max_blocking_frac: f64,
+    pub allowed_blocked_microsec: u64,
+}
+
+
+impl BlockingBudget {
+    // Set the budget of a generated machine (whose fields are all zero).
+    pub fn apply(&self, machine: &mut Machine) {
+        machine.max_blocking_frac = self.max_blocking_frac;
+        machine.allowed_blocked_microsec = self.allowed_blocked_microsec;
+    }
+}
+
+
+// The --max-blocking-frac and --allowed-blocked-microsec options shared by the
+// generators, which set the blocking budget of every machine. The defaults
+// (zeros) leave blocking unbounded, as without the options.
+pub fn blocking_budget(args: &Args) -> Result<BlockingBudget, String> {
+    let max_blocking_frac: f64 = args.value("--max-blocking-frac").map_or(Ok(0.0), |v| parse_finite(v, "max blocking fraction"))?;
+    let allowed_blocked_microsec: u64 = args.value("--allowed-blocked-microsec").map_or(Ok(0), |v| parse_count(v, "allowed blocked microsec"))?;
+    if !(0.0..=1.0).contains(&max_blocking_frac) {
+        return Err(String::from("Max blocking fraction must be between 0 and 1"));
+    }
+
+    return Ok(BlockingBudget {
+        max_blocking_frac: max_blocking_frac,
+        allowed_blocked_microsec: allowed_blocked_microsec,
+    });
+}
+
+
+// The --time-scale option shared by the generators, which scales the timeouts
+// of every machine once built (see dist_helpers::scale_timeouts()), e.g., to
+// slow down or speed up a whole defense. The default (1.0) leaves them as
+// built.
+pub fn time_scale(args: &Args) -> Result<f64, String> {
+    return args.value("--time-scale").map_or(Ok(1.0), |v| parse_finite_positive(v, "time scale"));
+}
+
+
+// The --include-small-packets switch shared by the generators, which sets the
+// include_small_packets field of every machine. Without it, maybenot skips the
+// transitions on packets of at most MAXSMALLPACKETSIZE bytes (but not the limit
+// counting of a state left unchanged, see README.md).
+pub fn include_small_packets(args: &Args) -> bool {
+    return args.has("--include-small-packets");
+}
+
+
+// The options of machine_options(), which every generator applies to its
+// machines once built.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MachineOptions {
+    pub budget: BlockingBudget,
+    pub time_scale: f64,
+    pub include_small_packets: bool,
+}
+
+
+impl Default for MachineOptions {
+    // Without the options: no blocking budget, and the timeouts as built
+    fn default() -> MachineOptions {
+        return MachineOptions {
+            budget: BlockingBudget { max_blocking_frac: 0.0, allowed_blocked_microsec: 0 },
+            time_scale: 1.0,
+            include_small_packets: false,
+        };
+    }
+}
+
+
+impl MachineOptions {
+    // Apply the options to a generated machine.
+    pub fn apply(&self, machine: &mut Machine) -> Result<(), String> {
+        self.budget.apply(machine);
+        scale_timeouts(machine, self.time_scale)?;
+        machine.include_small_packets = self.include_small_packets;
+        return Ok(());
+    }
+}
+
+
+// The --max-blocking-frac, --allowed-blocked-microsec, --time-scale, and
+// --include-small-packets options shared by the generators.
+pub fn machine_options(args: &Args) -> Result<MachineOptions, String> {
+    return Ok(MachineOptions {
+        budget: blocking_budget(args)?,
+        time_scale: time_scale(args)?,
+        include_small_packets: include_small_packets(args),
+    });
+}
+
+
+// The --ffi switch of the generators (see output::ffi_machines()). The output
+// must hold nothing but the machines, as maybenot-ffi takes them, so
+// --annotate, --hash, --base64, and --pretty cannot be added.
+pub fn ffi_output(args: &Args) -> bool {
+    if !args.has("--ffi") {
+        return false;
+    }
+
+    if args.has("--annotate") || args.has("--hash") || args.has("--base64") || args.has("--pretty") {
+        exit_with_error("--ffi cannot be combined with --annotate, --hash, --base64, or --pretty");
+    }
+    return true;
+}
+
+
+// The --emit-rust switch of the generators (see output::rust_constants()). The
+// output must be nothing but Rust source, so it cannot be combined with the
+// other output switches.
+pub fn rust_output(args: &Args) -> bool {
+    if !args.has("--emit-rust") {
+        return false;
+    }
+
+    if args.has("--annotate") || args.has("--hash") || args.has("--ffi") || args.has("--base64") || args.has("--pretty") {
+        exit_with_error("--emit-rust cannot be combined with --annotate, --hash, --ffi, --base64, or --pretty");
+    }
+    return true;
+}
+
+
+// The --pretty switch of the generators (see output::pretty_listing()).
+pub fn pretty_output(args: &Args) -> bool {
+    return args.has("--pretty");
+}
+
+
+// The --base64 switch of the generators, for the encoding of the printed
+// machines (see output::Encoding). The --hash line stays over the hex
+// serialization, so it matches without the switch.
+pub fn encoding(args: &Args) -> Encoding {
+    if args.has("--base64") {
+        return Encoding::Base64;
+    }
+    return Encoding::Hex;
+}
+
+
+// The --dist option (a distribution name, case-insensitive), for generators
+// that let the user pick the distribution of a timeout or limit.
+pub fn dist_type(args: &Args, default: DistType) -> Result<DistType, String> {
+    return match args.value("--dist") {
+        Some(value) => parse_dist_type(value),
+        None => Ok(default),
+    };
+}
+
+
+// Parse a distribution name, e.g. "normal" or "LogNormal".
+pub fn parse_dist_type(name: &str) -> Result<DistType, String> {
+    return Ok(match name.to_lowercase().as_str() {
+        "uniform" => DistType::Uniform,
+        "normal" => DistType::Normal,
+        "lognormal" => DistType::LogNormal,
+        "binomial" => DistType::Binomial,
+        "geometric" => DistType::Geometric,
+        "pareto" => DistType::Pareto,
+        "poisson" => DistType::Poisson,
+        "weibull" => DistType::Weibull,
+        "gamma" => DistType::Gamma,
+        "beta" => DistType::Beta,
+        _ => return Err(format!("Unknown distribution {}", name)),
+    });
+}
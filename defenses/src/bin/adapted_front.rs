@@ -9,190 +9,108 @@
 // README.md).
 
 use std::env;
-use std::f64::EPSILON;
-use std::f64::consts::E;
-use std::f64::consts::PI;
-use std::collections::HashMap;
-
-use maybenot::{
-machine::Machine,
-event::Event,
-state::State,
-dist::{Dist, DistType}
-};
-
-
-const PACKET_SIZE: f64 = 1500.0;
+use std::process;
+
+use raising_the_bar::args::{parse_args, exit_with_error, ffi_output, rust_output, encoding, pretty_output, help, explain, GENERATOR_OPTIONS};
+use raising_the_bar::output::{print_annotation, print_machine, print_hash, print_ffi, print_rust};
+use raising_the_bar::report::print_report;
+use raising_the_bar::validate::{report_unreachable, dry_run};
+use raising_the_bar::adapted_front::{AdaptedFront, SWITCHES, OPTIONS};
+use raising_the_bar::defense::Defense;
+
+
+const HELP: &str = "\
+Usage: adapted_front [padding window] [padding budget] [num states] [options]
+
+Arguments:
+  [padding window]  W_max, upper bound of the window padding is sent in (sec, > 0,
+                    default 12)
+  [padding budget]  N, padding packets per window (packets, integer >= 1,
+                    default 4000)
+  [num states]      PADDING states approximating the Rayleigh distribution
+                    (integer >= 1, default 30)
+
+The defaults are the parameters of the paper (see defaults.rs).
+";
+
+
+const EXPLAIN: &str = "\
+Adapted FRONT adds padding without delaying real traffic, with its rate over
+time shaped like a Rayleigh distribution, as in FRONT: once real traffic is
+seen, the padding rate rises to a peak and then tails off. The curve is
+approximated by a chain of PADDING states, each sending an equal share of the
+padding budget at a fixed mean rate, and the last state leads back to the
+first, so the padding repeats for as long as the machine runs rather than once.
+
+Parameters:
+  padding window  W_max, scale of the Rayleigh distribution (sec): the padding
+                  peaks W_max seconds into each round, and a round lasts about
+                  four times as long.
+  padding budget  N, padding packets per round. More packets hide more of the
+                  traffic, at the cost of bandwidth.
+  num states      PADDING states approximating the Rayleigh distribution: more
+                  states follow it more closely, but make the machine larger.
+
+In the paper: the Adapted FRONT defense, one of the four defenses described in
+the paper (see README.md), with the paper's parameters as the defaults (see
+defaults.rs). It is based on FRONT (Gong and Wang, USENIX Security 2020, [4] in
+README.md).
+";
 
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    assert!(args.len() == 4, "Usage: {} <padding window> <padding budget> <num states>", &args[0]);
-    
-    let padding_window: f64 = args[1].parse().expect("Invalid padding window"); // FRONT param = W_max (sec)
-    let padding_budget: u32 = args[2].parse().expect("Invalid padding budget"); // FRONT param = N (num cells)
-    let num_states:     u32 = args[3].parse().expect("Invalid num states");     // number of PADDING states
-    
-    let machine = generate_machine(padding_window * 1000000.0, padding_budget, num_states as usize);
-    println!("Machine: {} ({})\n", machine, machine.len());
-}
-
-
-// Generate an Adapted FRONT machine with the specified number of PADDING states.
-fn generate_machine(padding_window: f64, padding_budget: u32, num_states: usize) -> String {
-    let area = 1.0 / (num_states as f64);       // Area under Rayleigh CDF curve of each state
-    let max_t = rayleigh_max_t(padding_window);
-    
-    // States
-    let mut states: Vec<State> = Vec::with_capacity(num_states + 1);
-    states.push(generate_start_state(num_states + 1));
-    
-    let mut t1 = 0.0;                           // Starting time of next PADDING state
-    let mut total_padding_frac = 0.0;           // Area coverage of current PADDING states
-    
-    for i in 1..num_states {
-        let width = calc_interval_width(t1, max_t, area, padding_window);
-        let middle = t1 + (width / 2.0);
-        let t2 = t1 + width;
-        
-        let padding_count = area * (padding_budget as f64);
-        let timeout = width / padding_count;
-        let stdev = (padding_window).powi(2) / (padding_count * middle * PI.sqrt());
-        
-        states.push(generate_padding_state(i, i + 1, num_states + 1, padding_count, timeout, stdev));
-        
-        t1 = t2;
-        total_padding_frac += area;
+    let cli = parse_args(env::args().collect(), SWITCHES, OPTIONS);
+    help(&cli, &[HELP, GENERATOR_OPTIONS]);
+    explain(&cli, EXPLAIN);
+    let front = AdaptedFront::from_args(&cli).unwrap_or_else(|e| exit_with_error(&e));
+    let machine = front.machine().unwrap_or_else(|e| exit_with_error(&e));
+    let options = front.options;
+    
+    if cli.has("--warn-unreachable") {
+        report_unreachable("Machine", &machine);
     }
     
-    // Last state, to max_t
-    let width = max_t - t1;
-    let middle = t1 + (width / 2.0);
-    
-    let padding_count = (1.0 - total_padding_frac) * (padding_budget as f64);
-    let timeout = width / padding_count;
-    let stdev = (padding_window).powi(2) / (padding_count * middle * PI.sqrt());
-    
-    states.push(generate_padding_state(num_states, 1, num_states + 1, padding_count, timeout, stdev));
-    
-    // Machine
-    let machine = Machine {
-        allowed_padding_bytes: u64::MAX,
-        max_padding_frac: 0.0,
-        allowed_blocked_microsec: 0,
-        max_blocking_frac: 0.0,
-        states: states,
-        include_small_packets: false,
-    };
-    
-    return machine.serialize();
-}
-
-
-// Generate a PADDING state for a machine.
-fn generate_padding_state(curr_index: usize, next_index: usize, num_states: usize, padding_count: f64, timeout: f64, stdev: f64) -> State {
-    // PaddingSent --> this PADDING state (100%)
-    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
-    padding_sent.insert(curr_index, 1.0);
-    
-    // LimitReached --> next PADDING state or StateEnd (100%)
-    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
-    limit_reached.insert(next_index, 1.0);
-    
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::PaddingSent, padding_sent);
-    transitions.insert(Event::LimitReached, limit_reached);
-    
-    let mut state = State::new(transitions, num_states);
-    
-    state.timeout = Dist {
-        dist: DistType::Normal,
-        param1: timeout,
-        param2: stdev,
-        start: 0.0,
-        max: (timeout * 2.0),
-    };
-    
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: PACKET_SIZE,
-        param2: PACKET_SIZE,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    state.limit = Dist {
-        dist: DistType::Uniform,
-        param1: 1.0,
-        param2: padding_count,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    return state;
-}
-
-
-// Generate the START state for a machine.
-fn generate_start_state(num_states: usize) -> State {
-    // NonPaddingSent --> first PADDING state (100%)
-    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
-    nonpadding_sent.insert(1, 1.0);
-    
-    // NonPaddingRecv --> first PADDING state (100%)
-    let mut nonpadding_recv: HashMap<usize, f64> = HashMap::new();
-    nonpadding_recv.insert(1, 1.0);
-    
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
-    transitions.insert(Event::NonPaddingRecv, nonpadding_recv);
+    if cli.has("--report") {
+        print_report(&front.overhead_of(&[&machine]).unwrap_or_else(|e| exit_with_error(&e)));
+        return;
+    }
     
-    return State::new(transitions, num_states);
-}
-
-
-// Find the width of an interval in the Rayleigh distribution,
-// starting at a, with the specified area. Uses a search algorithm
-// because numerical error affects direct calculation significantly.
-fn calc_interval_width(a: f64, max_t: f64, area: f64, scale: f64) -> f64 {
-    let mut b = max_t;
-    let mut increment = (b - a) / 2.0;
+    if cli.has("--dry-run") {
+        if !dry_run(&[("Machine", &machine)]) {
+            process::exit(1);
+        }
+        return;
+    }
     
-    let mut curr_area = rayleigh_cdf(b, scale) - rayleigh_cdf(a, scale);
-    let mut curr_diff = area - curr_area;
+    if rust_output(&cli) {
+        print_rust(&[("Machine", &machine)]);
+        return;
+    }
     
-    while curr_diff.abs() > EPSILON {
-        if curr_diff < 0.0 {
-            b -= increment;
-        } else {
-            b += increment;
-        }
-        increment /= 2.0;
-        
-        curr_area = rayleigh_cdf(b, scale) - rayleigh_cdf(a, scale);
-        curr_diff = area - curr_area;
+    if ffi_output(&cli) {
+        print_ffi(&[&machine]);
+        return;
     }
     
-    return b - a;
-}
-
-
-// Cumulative distribution function of Rayleigh distribution
-fn rayleigh_cdf(t: f64, scale: f64) -> f64 {
-    let exp_num = -t.powi(2);
-    let exp_div = 2.0 * scale.powi(2);
-    let exp = exp_num / exp_div;
+    let annotate = cli.has("--annotate");
+    let encoding = encoding(&cli);
+    let pretty = pretty_output(&cli);
+    if annotate {
+        print_annotation("adapted_front", &[
+            ("padding window", front.padding_window.to_string()),
+            ("padding budget", front.padding_budget.to_string()),
+            ("num states", front.num_states.to_string()),
+            ("packet size", front.packet_size.to_string()),
+            ("max blocking frac", options.budget.max_blocking_frac.to_string()),
+            ("allowed blocked microsec", options.budget.allowed_blocked_microsec.to_string()),
+            ("time scale", options.time_scale.to_string()),
+            ("include small packets", options.include_small_packets.to_string()),
+        ]);
+    }
     
-    return 1.0 - E.powf(exp);
-}
-
-
-// Return the value of t (input to Rayleigh CDF) at which area = 0.9996645373720975, chosen
-// empirically. This is a bit more than 6 standard deviations.
-fn rayleigh_max_t(scale: f64) -> f64 {
-    let a: f64 = -2.0 * scale.powi(2);
-    let b: f64 = 1.0 - 0.9996645373720975;
+    print_machine("Machine", &machine, annotate, pretty, encoding);
     
-    return (a * b.log(E)).sqrt();
+    if cli.has("--hash") {
+        print_hash(&[&machine]);
+    }
 }
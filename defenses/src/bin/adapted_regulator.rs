@@ -7,378 +7,224 @@
 // If you use this code in your work, please include a reference to the paper
 // and the RegulaTor/Maybenot papers, which the code is based on (more details
 // in README.md).
+// See regulator.rs for the approximations this version makes compared to the
+// original RegulaTor schedule.
+//
+// Small packets per state give a relay machine of many states. As for the
+// regulator binary, --max-states <n> rejects parameters whose relay machine
+// would take more than n states, or with --coarsen, increases the packets per
+// state to the smallest whole number for which it fits (reported on stderr).
 
 use std::env;
-use std::f64::INFINITY;
-use std::collections::HashMap;
-
-use maybenot::{
-constants::STATEEND,
-machine::Machine,
-event::Event,
-state::State,
-dist::{Dist, DistType}
+use std::process;
+
+use raising_the_bar::args::{parse_args, exit_with_error, ffi_output, rust_output, encoding, pretty_output, help, explain, GENERATOR_OPTIONS};
+use raising_the_bar::output::{print_annotation, print_machine, print_hash, print_ffi, print_rust, serialize_stable};
+use raising_the_bar::report::print_report;
+use raising_the_bar::validate::{report_unreachable, dry_run};
+use raising_the_bar::constant::trigger_name;
+use raising_the_bar::regulator::{
+FIRST_SEND_STATE_INDEX,
+ADAPTED_SWITCHES,
+ADAPTED_OPTIONS,
+AdaptedRegulator,
+check_send_units
 };
-
-
-// Relay machine states
-const BLOCK_STATE_INDEX: usize = 1;
-const FIRST_SEND_STATE_INDEX: usize = 2;
-
-// Shared constants
-const PACKET_SIZE: f64 = 1500.0;
+use raising_the_bar::rate::decay_horizon_seconds;
+use raising_the_bar::defense::Defense;
+
+
+// SEND states between the progress lines of --progress
+const PROGRESS_STATES: usize = 1000;
+
+
+const HELP: &str = "\
+Usage: adapted_regulator [initial rate] [decay rate] [upload ratio] [packets per state] [client limit] [options]
+
+Arguments:
+  [initial rate]       R, rate at the start of a surge (packets/sec, > 0,
+                       default 500)
+  [decay rate]         D, decay of the rate per second (0 < D < 1, default 0.75)
+  [upload ratio]       U, packets received per packet sent by the client (> 0,
+                       default 4)
+  [packets per state]  padding packets per relay SEND state (packets, > 0,
+                       default 20)
+  [client limit]       received packets counted per client COUNT state
+                       (packets, > 0, default 2)
+
+Options:
+  --loop-surge         restart the surge at the first relay SEND state once the
+                       rate has decayed, instead of ending the relay machine
+  --trigger <dir>      real traffic of the relay that starts its machine: sent,
+                       recv, or both (default both, see README.md)
+  --verbose-states     print the rate of each relay SEND state and the decay
+                       horizon to stderr
+  --progress           print the number of relay SEND states to stderr every
+                       1000 states while generating, and a summary of the relay
+                       machine (states, serialized length, decay horizon) once
+                       it is done
+  --count-only         only print the number of states of the relay and client
+                       machines, without generating them (for fast parameter
+                       sweeps)
+  --units-check        check that the first relay SEND state pads every
+                       1e6 / R usec (R in packets/sec), instead of printing the
+                       machines
+  --max-states <n>     cap on the states of the relay machine (>= 3): larger
+                       machines are rejected, unless --coarsen is given
+  --coarsen            with --max-states, increase the packets per state to the
+                       smallest whole number that fits the relay machine in the
+                       cap, reported on stderr
+
+The defaults are the parameters of the paper (see defaults.rs), which also
+started the relay machine with --trigger sent.
+";
+
+
+const EXPLAIN: &str = "\
+RegulaTor pads the download in surges: once real traffic is seen, the relay
+machine blocks outgoing traffic and sends at an initial rate R, decaying as R *
+D^t over the t seconds since the surge started, with real packets sent in place
+of padding. A burst of real traffic while the rate is low restarts the surge.
+The client machine sends a packet for every U packets received, hiding the
+upload. The decay curve is approximated by a chain of relay SEND states, each
+sending a fixed number of packets at a fixed rate.
+
+Parameters:
+  initial rate       R, rate at the start of a surge (packets/sec): how fast
+                     the relay pads when a burst starts.
+  decay rate         D, decay of the rate per second: closer to 1, the surge
+                     lasts longer and sends more padding.
+  upload ratio       U, packets received per packet the client sends.
+  packets per state  padding packets per relay SEND state: how finely the decay
+                     curve is approximated. Fewer packets per state follow it
+                     more closely, but take more states.
+  client limit       received packets counted per client COUNT state.
+
+In the paper: the Adapted RegulaTor defense, one of the four defenses described
+in the paper (see README.md), with the paper's parameters as the defaults (see
+defaults.rs). It is based on RegulaTor (Holland and Hopper, PoPETs 2022, [5] in
+README.md); the paper's machines also start the relay machine with --trigger
+sent.
+";
 
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    assert!(args.len() == 5, "Usage: {} <initial rate> <decay rate> <upload ratio> <packets per state>", &args[0]);
-    
-    let initial_rate:      f64 = args[1].parse().expect("Invalid initial rate");      // RegulaTor param = R, initial surge rate (packets / sec)
-    let decay_rate:        f64 = args[2].parse().expect("Invalid decay rate");        // RegulaTor param = D, decay rate
-    let upload_ratio:      f64 = args[3].parse().expect("Invalid upload ratio");      // RegulaTor param = U, upload ratio
-    let packets_per_state: f64 = args[4].parse().expect("Invalid packets per state"); // number of packets per state (approximation granularity)
-    
-    let relay_machine = generate_relay_machine(packets_per_state, initial_rate, decay_rate);
-    println!("Relay machine: {} ({})\n", relay_machine, relay_machine.len());
-
-    let client_machine = generate_client_machine(upload_ratio);
-    println!("Client machine: {} ({})\n", client_machine, client_machine.len());
-}
-
-
-// Generate an Adapted RegulaTor client-side machine.
-fn generate_client_machine(upload_ratio: f64) -> String {
-    // Set up state vector
-    let num_states = (upload_ratio as usize) + 1;
-    let prob_last_trans = 1.0 - upload_ratio.fract();
-    
-    let mut states: Vec<State> = Vec::with_capacity(num_states);
+    let cli = parse_args(env::args().collect(), ADAPTED_SWITCHES, ADAPTED_OPTIONS);
+    help(&cli, &[HELP, GENERATOR_OPTIONS]);
+    explain(&cli, EXPLAIN);
+    let regulator = AdaptedRegulator::from_args(&cli).unwrap_or_else(|e| exit_with_error(&e));
+    if let Some(packets_per_state) = regulator.coarsened_from {
+        eprintln!("Coarsened packets per state from {} to {}, for a relay machine of at most {} states", packets_per_state, regulator.packets_per_state, regulator.max_states.unwrap());
+    }
+    let options = regulator.options;
     
-    // COUNTER states
-    for i in 1..num_states {
-        let mut prob_trans = 1.0;
-        if i == num_states - 1 {
-            prob_trans = prob_last_trans;
+    if cli.has("--count-only") {
+        let needs_machines = ["--warn-unreachable", "--dry-run", "--annotate", "--hash", "--verbose-states", "--progress", "--report", "--ffi", "--emit-rust", "--units-check", "--pretty", "--base64"];
+        if needs_machines.iter().any(|flag| cli.has(flag)) {
+            exit_with_error("--count-only cannot be combined with options that need the machines");
         }
         
-        states.push(generate_client_count_state(i - 1, i, num_states, prob_trans));
+        let (relay_states, client_states) = regulator.count_states();
+        println!("Relay states: {}", relay_states);
+        println!("Client states: {}", client_states);
+        return;
     }
     
-    // SEND state
-    states.push(generate_client_send_state(num_states));
-    
-    // Machine
-    let machine = Machine {
-        allowed_padding_bytes: 0,
-        max_padding_frac: 0.0,
-        allowed_blocked_microsec: 0,
-        max_blocking_frac: 0.0,
-        states: states,
-        include_small_packets: false,
-    };
-    
-    return machine.serialize();
-}
-
-
-// Generate the SEND state for a client-side machine.
-fn generate_client_send_state(num_states: usize) -> State {
-    // PaddingSent --> COUNT_0 (100%)
-    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
-    padding_sent.insert(0, 1.0);
-    
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::PaddingSent, padding_sent);
-    
-    // SEND state
-    let mut state = State::new(transitions, num_states);
-    state.bypass = true;
-    state.replace = true;
-    
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: 0.0,
-        param2: 0.0,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: PACKET_SIZE,
-        param2: PACKET_SIZE,
-        start: 0.0,
-        max: 0.0,
-    };
+    // With --verbose-states, the parameters of each SEND state are printed to
+    // stderr as they are computed, followed by the decay horizon. With
+    // --progress, the number of SEND states is printed every PROGRESS_STATES
+    // states, and a summary of the relay machine once it is done.
+    let verbose = cli.has("--verbose-states");
+    let progress = cli.has("--progress");
+    let packets_per_state = regulator.packets_per_state;
+    let machines = regulator.named_machines_with_progress(|count| {
+        if progress && count % PROGRESS_STATES == 0 {
+            eprintln!("Progress: {} SEND states", count);
+        }
+    }, |curr_idx, (t1, t2, rate), timeout| {
+        if !verbose {
+            return;
+        }
+        if curr_idx == FIRST_SEND_STATE_INDEX {
+            eprintln!("{:>5}  {:>25}  {:>10}  {:>10}  {:>12}  {:>8}", "State", "Interval (sec)", "Middle", "Rate", "Timeout (us)", "Padding");
+        }
+        let middle = t1 + ((t2 - t1) / 2.0);
+        eprintln!("{:>5}  [{:>10.6}, {:>10.6})  {:>10.6}  {:>10.3}  {:>12.3}  {:>8}", curr_idx, t1, t2, middle, rate, timeout, packets_per_state);
+    }).unwrap_or_else(|e| exit_with_error(&e));
+    let (relay_machine, client_machine) = (&machines[0].1, &machines[1].1);
     
-    return state;
-}
-
-
-// Generate a COUNT state for a client-side machine.
-fn generate_client_count_state(curr_index: usize, next_index: usize, num_states: usize, prob_trans: f64) -> State {
-    // PaddingRecv --> COUNT_[i+1] (prob_trans)
-    let mut padding_recv: HashMap<usize, f64> = HashMap::new();
-    padding_recv.insert(next_index, prob_trans);
-    if prob_trans < 1.0 {
-        padding_recv.insert(curr_index, 1.0 - prob_trans);
+    if verbose {
+        eprintln!("Decay horizon: {:.3} sec until 1 packet/sec ({} SEND states)", decay_horizon_seconds(regulator.initial_rate, regulator.decay), relay_machine.states.len() - FIRST_SEND_STATE_INDEX);
     }
-    
-    // NonPaddingRecv --> COUNT_[i+1] (prob_trans)
-    let mut nonpadding_recv: HashMap<usize, f64> = HashMap::new();
-    nonpadding_recv.insert(next_index, prob_trans);
-    if prob_trans < 1.0 {
-        nonpadding_recv.insert(curr_index, 1.0 - prob_trans);
+    if progress {
+        eprintln!("Relay machine: {} states, serialized length {}, decay horizon {:.3} sec",
+            relay_machine.states.len(), serialize_stable(relay_machine).len(), decay_horizon_seconds(regulator.initial_rate, regulator.decay));
     }
     
-    // LimitReached --> COUNT_[i+1] (100%)
-    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
-    limit_reached.insert(next_index, 1.0);
-    
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::PaddingRecv, padding_recv);
-    transitions.insert(Event::NonPaddingRecv, nonpadding_recv);
-    if prob_trans < 1.0 {
-        transitions.insert(Event::LimitReached, limit_reached);
+    if cli.has("--warn-unreachable") {
+        report_unreachable("Relay machine", relay_machine);
+        report_unreachable("Client machine", client_machine);
     }
     
-    // COUNT_i state
-    let mut state = State::new(transitions, num_states);
-    state.action_is_block = true;
-    state.bypass = true;
-    state.replace = true;
-    
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: 0.0,
-        param2: 0.0,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: INFINITY,
-        param2: INFINITY,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    state.limit = Dist {
-        dist: DistType::Uniform,
-        param1: 2.0,
-        param2: 2.0,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    return state;
-}
-
-
-// Generate an Adapted RegulaTor relay-side machine.
-fn generate_relay_machine(packets_per_state: f64, initial_rate: f64, decay: f64) -> String {
-    let mut t1 = 0.0;
-    let mut keep_going = true;
-    let mut num_send_states = 0;
+    if cli.has("--report") {
+        print_report(&regulator.overhead_of(&[relay_machine, client_machine]).unwrap_or_else(|e| exit_with_error(&e)));
+        return;
+    }
     
-    // Calculate number of send states
-    while keep_going {
-        let width = calc_interval_width(t1, packets_per_state, initial_rate, decay);
-        let middle = t1 + (width / 2.0);
-        let t2 = t1 + width;
-        
-        if width == INFINITY || calculate_rate(middle, initial_rate, decay) < 1.0 {
-            keep_going = false;
+    if cli.has("--dry-run") {
+        if !dry_run(&[("Relay machine", relay_machine), ("Client machine", client_machine)]) {
+            process::exit(1);
         }
-        
-        t1 = t2;
-        num_send_states += 1;
+        return;
     }
     
-    // Set up state vector
-    let num_states = num_send_states + 2;
-    let mut states: Vec<State> = Vec::with_capacity(num_states);
-    
-    // START and BLOCK states
-    states.push(generate_relay_start_state(num_states));
-    states.push(generate_relay_block_state(num_states));
-    
-    // SEND states
-    t1 = 0.0;
-    
-    for i in 0..num_send_states {
-        let width = calc_interval_width(t1, packets_per_state, initial_rate, decay);
-        let middle = t1 + (width / 2.0);
-        let t2 = t1 + width;
-        
-        let mut rate = calculate_rate(middle, initial_rate, decay);
-        let mut next_idx = i + FIRST_SEND_STATE_INDEX + 1;
-        let curr_idx = i + FIRST_SEND_STATE_INDEX;
-        
-        if width == INFINITY || rate < 1.0 {
-            rate = 1.0;
-            next_idx = STATEEND;
+    // A self-test of the units of the relay's SEND states instead of the machines
+    if cli.has("--units-check") {
+        match check_send_units(relay_machine, regulator.initial_rate, regulator.decay, options.time_scale) {
+            Ok((timeout, expected)) => println!("Units check: the first SEND state pads every {:.3} usec, about 1e6 / initial rate (times the time scale) = {} usec: OK", timeout, expected),
+            Err(e) => {
+                eprintln!("Units check failed: {}", e);
+                process::exit(1);
+            }
         }
-        
-        states.push(generate_relay_send_state(curr_idx, next_idx, num_states, packets_per_state, 1000000.0 / rate, rate));
-        
-        t1 = t2;
+        return;
     }
     
-    // Machine
-    let machine = Machine {
-        allowed_padding_bytes: 0,
-        max_padding_frac: 0.0,
-        allowed_blocked_microsec: 0,
-        max_blocking_frac: 0.0,
-        states: states,
-        include_small_packets: false,
-    };
-    
-    return machine.serialize();
-}
-
-
-// Generate a SEND state for a relay-side machine.
-fn generate_relay_send_state(curr_index: usize, next_index: usize, num_states: usize, padding_count: f64, timeout: f64, rate: f64) -> State {
-    // PaddingSent --> SEND_i (100%)
-    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
-    padding_sent.insert(curr_index, 1.0);
-    
-    // LimitReached --> SEND_[i+1] or loop around (100%)
-    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
-    limit_reached.insert(next_index, 1.0);
-    
-    // NonPaddingSent --> SEND_0 (100%) if rate < 200.0
-    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
-    nonpadding_sent.insert(FIRST_SEND_STATE_INDEX, 1.0);
-
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::PaddingSent, padding_sent);
-    transitions.insert(Event::LimitReached, limit_reached);
-    if rate < 200.0 {
-        transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+    if rust_output(&cli) {
+        print_rust(&[("Relay machine", relay_machine), ("Client machine", client_machine)]);
+        return;
     }
     
-    // SEND_i state
-    let mut state = State::new(transitions, num_states);
-    state.bypass = true;
-    state.replace = true;
-    
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: timeout,
-        param2: timeout,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: PACKET_SIZE,
-        param2: PACKET_SIZE,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    state.limit = Dist {
-        dist: DistType::Uniform,
-        param1: padding_count,
-        param2: padding_count,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    return state;
-}
-
-
-// Generate the BLOCK state for a relay-side machine.
-fn generate_relay_block_state(num_states: usize) -> State {
-    // BlockingBegin --> SEND_0 (100%)
-    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
-    blocking_begin.insert(FIRST_SEND_STATE_INDEX, 1.0);
-    
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::BlockingBegin, blocking_begin);
-    
-    // BLOCK state
-    let mut state = State::new(transitions, num_states);
-    state.action_is_block = true;
-    state.bypass = true;
-    state.replace = true;
-    
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: 0.0,
-        param2: 0.0,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: INFINITY,
-        param2: INFINITY,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    return state;
-}
-
-
-// Generate the START state for a relay-side machine.
-fn generate_relay_start_state(num_states: usize) -> State {
-    // NonPaddingSent --> BLOCK (100%)
-    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
-    nonpadding_sent.insert(BLOCK_STATE_INDEX, 1.0);
-    
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+    if ffi_output(&cli) {
+        print_ffi(&[relay_machine, client_machine]);
+        return;
+    }
     
-    return State::new(transitions, num_states);
-}
-
-
-// Find the width of an interval of the function RD^t, from a, with the specified packet count.
-fn calc_interval_width(a: f64, count: f64, rate: f64, decay: f64) -> f64 {
-    let mut mid = a;
-    let mut step: f64 = 0.5;
-    let mut decreasing = false;
+    let annotate = cli.has("--annotate");
+    let encoding = encoding(&cli);
+    let pretty = pretty_output(&cli);
+    if annotate {
+        print_annotation("adapted_regulator", &[
+            ("initial rate", regulator.initial_rate.to_string()),
+            ("decay rate", regulator.decay.to_string()),
+            ("upload ratio", regulator.upload_ratio.to_string()),
+            ("packets per state", regulator.packets_per_state.to_string()),
+            ("client limit", regulator.client_limit.to_string()),
+            ("loop surge", regulator.loop_surge.to_string()),
+            ("trigger", trigger_name(regulator.trigger).to_string()),
+            ("max states", regulator.max_states.map_or(String::from("none"), |n| n.to_string())),
+            ("packet size", regulator.packet_size.to_string()),
+            ("max blocking frac", options.budget.max_blocking_frac.to_string()),
+            ("allowed blocked microsec", options.budget.allowed_blocked_microsec.to_string()),
+            ("time scale", options.time_scale.to_string()),
+            ("include small packets", options.include_small_packets.to_string()),
+        ]);
+    }
     
-    let mut curr_count = 0.0;
-    let mut curr_diff = count - curr_count;
+    print_machine("Relay machine", relay_machine, annotate, pretty, encoding);
+    print_machine("Client machine", client_machine, annotate, pretty, encoding);
     
-    while curr_diff.abs() > 0.00001 {
-        if curr_diff < 0.0 {
-            mid -= step;
-            decreasing = true;
-        } else {
-            mid += step;
-        }
-        
-        if decreasing {
-            step /= 2.0;
-        } else {
-            step *= 2.0;
-        }
-        
-        curr_count = calculate_rate(mid, rate, decay) * (mid - a) * 2.0;
-        curr_diff = count - curr_count;
+    if cli.has("--hash") {
+        print_hash(&[relay_machine, client_machine]);
     }
-    
-    return (mid - a) * 2.0;
-}
-
-
-// RD^t
-fn calculate_rate(t: f64, initial_rate: f64, decay: f64) -> f64 {
-    return initial_rate * decay.powf(t);
 }
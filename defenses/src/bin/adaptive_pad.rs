@@ -0,0 +1,134 @@
+// Adaptive Padding -- a WTF-PAD style defense, where padding fills statistically
+// unlikely gaps between real packets.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper
+// and the WTF-PAD/Maybenot papers, which the code is based on.
+//
+// WTF-PAD samples the inter-packet gaps it pads from histograms. Here, each
+// histogram is replaced by a distribution (normal by default, see --dist) with
+// the given parameters, sampled in microseconds:
+//  - IDLE: no padding until real traffic is sent.
+//  - BURST: a real packet arms a timer from the burst distribution. If another
+//    real packet is sent first, the timer is re-armed; otherwise a padding
+//    packet is sent and the machine switches to GAP.
+//  - GAP: padding packets are sent with timeouts from the gap distribution,
+//    until real traffic is sent (back to BURST) or up to <max gap padding>
+//    packets have been sent (back to IDLE).
+
+use std::env;
+use std::process;
+
+use raising_the_bar::args::{parse_args, exit_with_error, ffi_output, rust_output, encoding, pretty_output, help, explain, GENERATOR_OPTIONS};
+use raising_the_bar::output::{print_annotation, print_machine, print_hash, print_ffi, print_rust};
+use raising_the_bar::report::print_report;
+use raising_the_bar::validate::{report_unreachable, dry_run};
+use raising_the_bar::adaptive_pad::{AdaptivePad, SWITCHES, OPTIONS};
+use raising_the_bar::defense::Defense;
+
+const HELP: &str = "\
+Usage: adaptive_pad <burst param1> <burst param2> <gap param1> <gap param2> <max gap padding> [options]
+
+Arguments:
+  <burst param1>     first parameter of the BURST timeout distribution (usec
+                     for normal: mean)
+  <burst param2>     second parameter of the BURST timeout distribution (usec
+                     for normal: standard deviation)
+  <gap param1>       first parameter of the GAP timeout distribution (usec for
+                     normal: mean)
+  <gap param2>       second parameter of the GAP timeout distribution (usec for
+                     normal: standard deviation)
+  <max gap padding>  maximum padding packets per gap (packets, >= 1)
+
+Options:
+  --dist <name>      timeout distribution: normal (default), uniform,
+                     lognormal, binomial, geometric, pareto, poisson, weibull,
+                     gamma, or beta
+";
+
+
+const EXPLAIN: &str = "\
+Adaptive Padding fills statistically unlikely gaps between real packets with
+padding, without delaying real traffic. After a real packet, a timer is drawn
+from the burst distribution: if it expires before the next real packet, a
+padding packet is sent, and padding continues with timeouts from the gap
+distribution until real traffic is sent or the gap holds the maximum number of
+padding packets. The distributions replace the histograms of WTF-PAD (normal by
+default, see --dist).
+
+Parameters:
+  burst param1     first parameter of the BURST timeout distribution (usec, for
+                   normal the mean): how long a silence must last to be padded.
+  burst param2     second parameter of the BURST timeout distribution (for
+                   normal the standard deviation).
+  gap param1       first parameter of the GAP timeout distribution (usec, for
+                   normal the mean): the time between padding packets of a gap.
+  gap param2       second parameter of the GAP timeout distribution.
+  max gap padding  maximum padding packets per gap.
+
+In the paper: a comparison baseline, not one of the paper's four defenses,
+based on WTF-PAD (Juarez et al., ESORICS 2016).
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), SWITCHES, OPTIONS);
+    help(&cli, &[HELP, GENERATOR_OPTIONS]);
+    explain(&cli, EXPLAIN);
+    let pad = AdaptivePad::from_args(&cli).unwrap_or_else(|e| exit_with_error(&e));
+    let machine = pad.machine().unwrap_or_else(|e| exit_with_error(&e));
+    let options = pad.options;
+
+    if cli.has("--warn-unreachable") {
+        report_unreachable("Machine", &machine);
+    }
+
+    if cli.has("--report") {
+        print_report(&pad.overhead_of(&[&machine]).unwrap_or_else(|e| exit_with_error(&e)));
+        return;
+    }
+
+    if cli.has("--dry-run") {
+        if !dry_run(&[("Machine", &machine)]) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if rust_output(&cli) {
+        print_rust(&[("Machine", &machine)]);
+        return;
+    }
+
+    if ffi_output(&cli) {
+        print_ffi(&[&machine]);
+        return;
+    }
+
+    let annotate = cli.has("--annotate");
+    let encoding = encoding(&cli);
+    let pretty = pretty_output(&cli);
+    if annotate {
+        print_annotation("adaptive_pad", &[
+            ("burst param1", pad.burst.0.to_string()),
+            ("burst param2", pad.burst.1.to_string()),
+            ("gap param1", pad.gap.0.to_string()),
+            ("gap param2", pad.gap.1.to_string()),
+            ("max gap padding", pad.max_gap.to_string()),
+            ("dist", pad.dist.to_string()),
+            ("packet size", pad.packet_size.to_string()),
+            ("max blocking frac", options.budget.max_blocking_frac.to_string()),
+            ("allowed blocked microsec", options.budget.allowed_blocked_microsec.to_string()),
+            ("time scale", options.time_scale.to_string()),
+            ("include small packets", options.include_small_packets.to_string()),
+        ]);
+    }
+    
+    print_machine("Machine", &machine, annotate, pretty, encoding);
+
+    if cli.has("--hash") {
+        print_hash(&[&machine]);
+    }
+}
@@ -0,0 +1,111 @@
+// BuFLO -- constant-rate padding for a minimum duration, based on the BuFLO
+// defense.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper
+// and the BuFLO/Maybenot papers, which the code is based on.
+//
+// Uses the START/BLOCK/CONST structure of the constant defense, but the CONST
+// state only sends ceil(tau / interval) packets, i.e., it keeps the constant
+// rate for at least tau seconds. START then lifts the blocking, and the next
+// real packet starts another round. Padding therefore stops within tau of the
+// end of the real traffic, rather than never.
+
+use std::env;
+use std::process;
+
+use raising_the_bar::args::{parse_args, exit_with_error, ffi_output, rust_output, encoding, pretty_output, help, explain, GENERATOR_OPTIONS};
+use raising_the_bar::output::{print_annotation, print_machine, print_hash, print_ffi, print_rust};
+use raising_the_bar::report::print_report;
+use raising_the_bar::validate::{report_unreachable, dry_run};
+use raising_the_bar::buflo::{Buflo, SWITCHES, OPTIONS};
+use raising_the_bar::defense::Defense;
+
+
+const HELP: &str = "\
+Usage: buflo <tau> <send interval> [options]
+
+Arguments:
+  <tau>            minimum duration of each round of padding (sec, > 0)
+  <send interval>  time between packets (usec, or ms with --ms, > 0)
+
+Options:
+  --ms             give the send interval in milliseconds instead of usec
+";
+
+
+const EXPLAIN: &str = "\
+BuFLO sends at a constant rate, like the constant defense, but only for a
+minimum duration: each round blocks outgoing traffic and sends a packet every
+send interval for at least tau seconds, then lifts the blocking until the next
+real packet starts another round. The padding therefore stops within tau of the
+end of the real traffic, rather than never.
+
+Parameters:
+  tau            minimum duration of each round of padding (sec). Longer rounds
+                 hide more of the traffic's shape, but pad for longer after it
+                 ends.
+  send interval  time between packets of a round, as for the constant defense.
+
+In the paper: a comparison baseline, not one of the paper's four defenses,
+based on BuFLO (Dyer et al., IEEE S&P 2012).
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), SWITCHES, OPTIONS);
+    help(&cli, &[HELP, GENERATOR_OPTIONS]);
+    explain(&cli, EXPLAIN);
+    let buflo = Buflo::from_args(&cli).unwrap_or_else(|e| exit_with_error(&e));
+    let machine = buflo.machine().unwrap_or_else(|e| exit_with_error(&e));
+    let options = buflo.options;
+    
+    if cli.has("--warn-unreachable") {
+        report_unreachable("Machine", &machine);
+    }
+    
+    if cli.has("--report") {
+        print_report(&buflo.overhead_of(&[&machine]).unwrap_or_else(|e| exit_with_error(&e)));
+        return;
+    }
+    
+    if cli.has("--dry-run") {
+        if !dry_run(&[("Machine", &machine)]) {
+            process::exit(1);
+        }
+        return;
+    }
+    
+    if rust_output(&cli) {
+        print_rust(&[("Machine", &machine)]);
+        return;
+    }
+    
+    if ffi_output(&cli) {
+        print_ffi(&[&machine]);
+        return;
+    }
+    
+    let annotate = cli.has("--annotate");
+    let encoding = encoding(&cli);
+    let pretty = pretty_output(&cli);
+    if annotate {
+        print_annotation("buflo", &[
+            ("tau", buflo.tau.to_string()),
+            ("send interval", buflo.interval.to_string()),
+            ("packet size", buflo.packet_size.to_string()),
+            ("max blocking frac", options.budget.max_blocking_frac.to_string()),
+            ("allowed blocked microsec", options.budget.allowed_blocked_microsec.to_string()),
+            ("time scale", options.time_scale.to_string()),
+            ("include small packets", options.include_small_packets.to_string()),
+        ]);
+    }
+    
+    print_machine("Machine", &machine, annotate, pretty, encoding);
+    
+    if cli.has("--hash") {
+        print_hash(&[&machine]);
+    }
+}
@@ -0,0 +1,102 @@
+// Calibrate -- recommends the send interval of the constant defense for a
+// stream, from the throughput of its base trace, and prints the machine.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper.
+//
+// The constant defense hides the stream's bitrate only while it sends faster
+// than the stream: a burst above the padding rate queues behind the blocked
+// padding, and shows in the delays. The recommended interval pads at the peak
+// throughput of the side's packets (the most bytes in any window of --window
+// usec) plus a margin, e.g. 0.1 for 10% above the peak (see
+// overhead::calibrate_interval()). The machine is that of the constant binary
+// with the interval (see constant.rs).
+
+use std::env;
+use std::fs;
+use std::process;
+
+use raising_the_bar::args::{parse_args, exit_with_error, parse_finite, parse_finite_positive, packet_size, help};
+use raising_the_bar::constant::Constant;
+use raising_the_bar::output::{print_machine, Encoding};
+use raising_the_bar::overhead::{bandwidth_bps, overhead_ratio, mean_throughput_bps, peak_throughput_bps, calibrate_interval};
+use raising_the_bar::simulate::{parse_base_trace, Packet};
+
+
+// Default margin over the peak throughput (ratio)
+const MARGIN: f64 = 0.1;
+
+// Default window of the peak throughput (usec)
+const WINDOW: f64 = 1000000.0;
+
+
+const HELP: &str = "\
+Usage: calibrate <base trace> [options]
+
+Arguments:
+  <base trace>          file of undefended packets, one <time (ns)>,<s|r>[,<size>]
+                        per line, as for measure (see README.md)
+
+Options:
+  --side <client|relay> side to pad, whose packets are calibrated for: client
+                        (s) or relay (r, the video download, default)
+  --margin <ratio>      padding bandwidth over the peak throughput of the side
+                        (>= 0, default 0.1 for 10% above the peak)
+  --window <usec>       window of the peak throughput (usec, > 0, default
+                        1000000)
+  --packet-size <bytes> size of padding packets (bytes, default 1500)
+
+Prints the peak and mean throughput of the side, the recommended send interval
+(rounded down to a whole usec), and the constant machine with it.
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), &[], &["--side", "--margin", "--window", "--packet-size"]);
+    help(&cli, &[HELP]);
+    let args = &cli.positional;
+    if args.len() != 2 {
+        exit_with_error(&format!("Usage: {} <base trace> [--side <client|relay>] [--margin <ratio>] [--window <usec>] [--packet-size <bytes>]", &args[0]));
+    }
+
+    let client_side = match cli.value("--side").unwrap_or("relay") {
+        "client" => true,
+        "relay" => false,
+        side => exit_with_error(&format!("Unknown side: {} (expected client or relay)", side)),
+    };
+    let margin: f64 = cli.value("--margin").map_or(MARGIN, |v| parse_finite(v, "margin").unwrap_or_else(|e| exit_with_error(&e)));
+    let window: f64 = cli.value("--window").map_or(WINDOW, |v| parse_finite_positive(v, "window").unwrap_or_else(|e| exit_with_error(&e)));
+    let packet_size = packet_size(&cli).unwrap_or_else(|e| exit_with_error(&e));
+
+    let trace = fs::read_to_string(&args[1]).unwrap_or_else(|e| exit_with_error(&format!("Unable to read base trace: {}", e)));
+    let packets = parse_base_trace(&trace).unwrap_or_else(|e| exit_with_error(&format!("Invalid base trace: {}", e)));
+
+    // The packets the side sends: s by the client, r by the relay
+    let packets: Vec<Packet> = packets.into_iter().filter(|packet| packet.sent == client_side).collect();
+    if packets.is_empty() {
+        exit_with_error(&format!("The base trace has no packets sent by the {}", if client_side { "client" } else { "relay" }));
+    }
+
+    let peak = peak_throughput_bps(&packets, window);
+    let mean = mean_throughput_bps(&packets);
+    let interval = calibrate_interval(peak, margin, packet_size).unwrap_or_else(|e| exit_with_error(&e));
+
+    println!("Peak throughput: {:.0} bits/sec (over {} usec)", peak, window);
+    println!("Mean throughput: {}", mean.map_or(String::from("n/a"), |mean| format!("{:.0} bits/sec", mean)));
+    println!("Send interval: {} usec ({:.0} bits/sec, margin {} over the peak)", interval, bandwidth_bps(interval, packet_size), margin);
+    println!("Overhead over the mean: {}\n", mean.map_or(String::from("n/a"), |mean| format!("{:.4}", overhead_ratio(interval, packet_size, mean))));
+
+    let machines = match (Constant { interval: interval, packet_size: packet_size, ..Constant::default() }).named_machines() {
+        Ok(machines) => machines,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    for (name, machine) in machines {
+        print_machine(&name, &machine, false, false, Encoding::Hex);
+    }
+}
@@ -0,0 +1,63 @@
+// Machine combiner -- joins serialized maybenot machines, generated by this code
+// or not, into one list of machines to run together.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper.
+
+use std::env;
+use std::io::{self, Write};
+use std::process;
+
+use maybenot::machine::Machine;
+
+use raising_the_bar::args::{parse_args, exit_with_error, parse_count, help};
+use raising_the_bar::output::{write_machine_list, parse_machine_list};
+use raising_the_bar::validate::check_composition;
+use raising_the_bar::warmup::with_warmup;
+
+
+const HELP: &str = "\
+Usage: combine --machine <machine string | machine list> [--machine ...] [--warmup <n>]
+
+Options:
+  --machine <machines>  a serialized machine, or a list of machines printed by
+                        an earlier run; repeat to combine several
+  --warmup <n>          let each machine do nothing until n real packets are
+                        sent, through a new state 0 (default 0 = none)
+
+Machines that would block together are reported on stderr (see README.md).
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), &[], &["--machine", "--warmup"]);
+    help(&cli, &[HELP]);
+    let machine_args = cli.values("--machine");
+    if cli.positional.len() != 1 || machine_args.is_empty() {
+        exit_with_error(&format!("Usage: {} --machine <machine string | machine list> [--machine <machine string | machine list> ...] [--warmup <n>]", &cli.positional[0]));
+    }
+    let warmup: usize = cli.value("--warmup").map_or(0, |v| parse_count(v, "warm-up count").unwrap_or_else(|e| exit_with_error(&e)));
+
+    let mut machines: Vec<Machine> = Vec::with_capacity(machine_args.len());
+
+    // Each value is a machine, or a list of machines from an earlier run
+    for (i, machine) in machine_args.iter().enumerate() {
+        match parse_machine_list(machine) {
+            Ok(list) => machines.extend(list.into_iter().map(|machine| with_warmup(machine, warmup))),
+            Err(e) => {
+                eprintln!("Machine {}: error: failed to parse machine: {}", i + 1, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    for warning in check_composition(&machines) {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let machines: Vec<&Machine> = machines.iter().collect();
+    let mut stdout = io::stdout().lock();
+    write_machine_list(&mut stdout, &machines).and_then(|_| writeln!(stdout)).unwrap_or_else(|e| exit_with_error(&format!("Unable to write to stdout: {}", e)));
+}
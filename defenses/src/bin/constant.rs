@@ -6,149 +6,230 @@
 // If you use this code in your work, please include a reference to the paper.
 
 use std::env;
-use std::f64::INFINITY;
-use std::collections::HashMap;
+use std::process;
 
 use maybenot::{
 machine::Machine,
-event::Event,
-state::State,
-dist::{Dist, DistType}
+dist::Dist
 };
 
-
-const NUM_STATES: usize = 3;
-
-const BLOCK_STATE_INDEX: usize = 1;
-const CONST_STATE_INDEX: usize = 2;
-
-const SEND_INTERVAL: f64 = 4000.0; // 3 Mbps (250 packets/sec)
-const PACKET_SIZE: f64 = 1500.0;
+use raising_the_bar::args::{parse_args, exit_with_error, parse_interval, ffi_output, rust_output, encoding, pretty_output, help, explain, BlockingBudget, GENERATOR_OPTIONS};
+use raising_the_bar::output::{print_annotation, print_machine, print_machines_from_stdin, print_hash, print_ffi, print_rust};
+use raising_the_bar::report::{print_report, send_rate};
+use raising_the_bar::validate::{report_unreachable, dry_run};
+use raising_the_bar::constant::{trigger_name, Constant, Trigger, SWITCHES, OPTIONS};
+use raising_the_bar::dist_helpers::scale_dist;
+use raising_the_bar::defense::Defense;
+
+
+const HELP: &str = "\
+Usage: constant [send interval] [options]
+
+Arguments:
+  [send interval]  time between packets (usec, or ms with --ms, > 0, default
+                   4000 usec, i.e., 3 Mbps with 1500-byte packets)
+
+Options:
+  --ms             give the send interval in milliseconds instead of usec
+                   (also with --stdin)
+  --dist <name>    distribution of the time between packets, with the send
+                   interval as its mean: uniform (fixed, default), exponential,
+                   or pareto
+  --shape <value>  shape of the Pareto distribution (> 1, default 2)
+  --jitter-frac <f>
+                   with --dist uniform, draw the time between packets from
+                   [interval * (1 - f), interval * (1 + f)] instead (0 to 1,
+                   default 0 for a fixed interval), keeping the mean rate
+  --trigger <dir>  real traffic that starts the padding: sent, recv, or both
+                   (default both)
+  --pad-only       add the padding on top of the real traffic, without
+                   blocking it (weaker protection, for where blocking is not
+                   possible)
+  --asymmetric     generate a client machine and a relay machine, padding
+                   each direction at its own interval
+  --both-sides     the same as --asymmetric, for a two-sided deployment
+  --up-interval <usec>
+                   send interval of the client machine with --asymmetric
+                   (usec, or ms with --ms, > 0, default: send interval)
+  --down-interval <usec>
+                   send interval of the relay machine with --asymmetric
+                   (usec, or ms with --ms, > 0, default: send interval)
+  --target-overhead <ratio>
+                   instead of a send interval, solve for the interval whose
+                   padding adds this bandwidth overhead (ratio, >= 0, e.g.,
+                   0.25 for 25%) over --baseline-mbps, and print it before the
+                   machine
+  --baseline-mbps <m>
+                   bandwidth of the undefended stream for --target-overhead
+                   (Mbps, > 0)
+  --maybenot-v2    print the machine for maybenot v2 instead (requires
+                   building with --features maybenot-v2)
+  --stdin          read one send interval per line from stdin, and print one
+                   machine per line (see README.md); the other options apply
+                   to every line
+  --minimal        instead of a send interval, print the smallest valid machine
+                   (that of constant::minimal(): 3 states, padding every
+                   1 usec with 1500-byte packets, on real traffic either way),
+                   e.g., for tests
+";
+
+
+const EXPLAIN: &str = "\
+Constant pads the traffic into a stream of packets at a fixed rate. Once real
+traffic is seen, the machine blocks all outgoing traffic and sends a packet
+every send interval: a queued real packet if there is one, padding otherwise.
+The timing and volume of the traffic are hidden behind the constant stream, at
+the cost of bandwidth whenever the real traffic is slower, and of delay
+whenever it is faster.
+
+Parameters:
+  send interval  time between packets; with 1500-byte packets, the default 4000
+                 usec is 3 Mbps. A shorter interval sends faster, so fewer real
+                 packets are held back, but more padding is sent.
+
+In the paper: the constant-rate defense, one of the four defenses described in
+the paper (see README.md), with the paper's parameters as the defaults (see
+defaults.rs).
+";
 
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    assert!(args.len() == 1 || args.len() == 2, "Usage: {} [send interval = 4000.0]", &args[0]);
+    let cli = parse_args(env::args().collect(), SWITCHES, OPTIONS);
+    help(&cli, &[HELP, GENERATOR_OPTIONS]);
+    explain(&cli, EXPLAIN);
+    let constant = Constant::from_args(&cli).unwrap_or_else(|e| exit_with_error(&e));
+    let options = constant.options;
+    
+    if cli.has("--stdin") {
+        if cli.positional.len() != 1 {
+            exit_with_error("No send interval can be given with --stdin, it is read from each line");
+        }
+        if cli.has("--target-overhead") {
+            exit_with_error("--stdin cannot be combined with --target-overhead");
+        }
+        if cli.has("--dry-run") || cli.has("--annotate") || cli.has("--ffi") || cli.has("--emit-rust") || cli.has("--maybenot-v2") || cli.has("--report") || cli.has("--pretty") {
+            exit_with_error("--stdin cannot be combined with --dry-run, --annotate, --ffi, --emit-rust, --maybenot-v2, --report, or --pretty");
+        }
+        
+        print_machines_from_stdin(encoding(&cli), |params| {
+            if params.len() != 1 {
+                return Err(format!("expected 1 parameter (send interval), got {}", params.len()));
+            }
+            
+            let interval: f64 = match parse_interval(&cli, params[0]) {
+                Some(interval) => interval,
+                None => return Err(format!("invalid send interval \"{}\"", params[0])),
+            };
+            if interval.is_nan() || interval <= 0.0 {
+                return Err(format!("send interval must be positive, got {}", interval));
+            }
+            
+            let line = Constant { interval: interval, ..constant.clone() };
+            return Ok(line.named_machines()?.remove(0).1);
+        });
+        return;
+    }
     
-    let interval: f64;
+    let interval = constant.interval;
+    let machines = constant.named_machines().unwrap_or_else(|e| exit_with_error(&e));
     
-    if args.len() == 1 {
-        interval = SEND_INTERVAL; // default
-    } else {
-        interval = args[1].parse().expect("Invalid send interval");
+    if cli.has("--warn-unreachable") {
+        for (name, machine) in &machines {
+            report_unreachable(name, machine);
+        }
     }
     
-    let machine = generate_machine(interval);
-    println!("Machine: {} ({})\n", machine, machine.len());
-}
-
-
-// Generate a constant-rate machine.
-fn generate_machine(interval: f64) -> String {
-    // States
-    let mut states: Vec<State> = Vec::with_capacity(NUM_STATES);
-    states.push(generate_start_state());
-    states.push(generate_block_state());
-    states.push(generate_const_state(interval));
-
-    // Machine
-    let machine = Machine {
-        allowed_padding_bytes: 0,
-        max_padding_frac: 0.0,
-        allowed_blocked_microsec: 0,
-        max_blocking_frac: 0.0,
-        states: states,
-        include_small_packets: false,
-    };
+    if cli.has("--report") {
+        let intervals = constant.scaled_intervals();
+        if constant.asymmetric {
+            println!("Send rate: up {}, down {}", send_rate(intervals[0]), send_rate(intervals[1]));
+        } else {
+            println!("Send rate: {}", send_rate(intervals[0]));
+        }
+        
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_report(&constant.overhead_of(&machines).unwrap_or_else(|e| exit_with_error(&e)));
+        return;
+    }
     
-    return machine.serialize();
-}
-
-
-// Generate the START state for a machine.
-fn generate_start_state() -> State {
-    // NonPaddingSent/NonPaddingRecv --> BLOCK (100%)
-    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
-    nonpadding_sent.insert(BLOCK_STATE_INDEX, 1.0);
+    if cli.has("--dry-run") {
+        let machines: Vec<(&str, &Machine)> = machines.iter().map(|(name, machine)| (name.as_str(), machine)).collect();
+        if !dry_run(&machines) {
+            process::exit(1);
+        }
+        return;
+    }
     
-    let mut nonpadding_recv: HashMap<usize, f64> = HashMap::new();
-    nonpadding_recv.insert(BLOCK_STATE_INDEX, 1.0);
-
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
-    transitions.insert(Event::NonPaddingRecv, nonpadding_recv);
+    if rust_output(&cli) {
+        let machines: Vec<(&str, &Machine)> = machines.iter().map(|(name, machine)| (name.as_str(), machine)).collect();
+        print_rust(&machines);
+        return;
+    }
     
-    return State::new(transitions, NUM_STATES);
-}
-
-
-// Generate the BLOCK state for a machine.
-fn generate_block_state() -> State {
-    // BlockingBegin --> CONST (100%)
-    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
-    blocking_begin.insert(CONST_STATE_INDEX, 1.0);
+    if ffi_output(&cli) {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_ffi(&machines);
+        return;
+    }
     
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::BlockingBegin, blocking_begin);
+    let annotate = cli.has("--annotate");
+    let encoding = encoding(&cli);
+    let pretty = pretty_output(&cli);
+    if annotate {
+        print_annotation("constant", &[
+            ("send interval", interval.to_string()),
+            ("dist", constant.dist.clone()),
+            ("shape", constant.shape.to_string()),
+            ("jitter frac", constant.jitter.to_string()),
+            ("trigger", trigger_name(constant.trigger).to_string()),
+            ("pad only", constant.pad_only.to_string()),
+            ("asymmetric", constant.asymmetric.to_string()),
+            ("up interval", constant.up_interval.to_string()),
+            ("down interval", constant.down_interval.to_string()),
+            ("packet size", constant.packet_size.to_string()),
+            ("max blocking frac", options.budget.max_blocking_frac.to_string()),
+            ("allowed blocked microsec", options.budget.allowed_blocked_microsec.to_string()),
+            ("time scale", options.time_scale.to_string()),
+            ("include small packets", options.include_small_packets.to_string()),
+        ]);
+    }
     
-    // BLOCK state
-    let mut state = State::new(transitions, NUM_STATES);
-    state.action_is_block = true;
-    state.bypass = true;
-    state.replace = true;
+    // The solved interval, followed by its machine
+    if cli.has("--target-overhead") {
+        println!("Send interval: {} usec\n", interval);
+    }
     
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: 0.0,
-        param2: 0.0,
-        start: 0.0,
-        max: 0.0,
-    };
+    if cli.has("--maybenot-v2") {
+        let timeout = constant.timeout().and_then(|timeout| scale_dist(timeout, options.time_scale)).unwrap_or_else(|e| exit_with_error(&e));
+        print_machine_v2(&timeout, constant.trigger, options.budget, annotate);
+        return;
+    }
     
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: INFINITY,
-        param2: INFINITY,
-        start: 0.0,
-        max: 0.0,
-    };
+    for (name, machine) in &machines {
+        print_machine(name, machine, annotate, pretty, encoding);
+    }
     
-    return state;
+    if cli.has("--hash") {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_hash(&machines);
+    }
 }
 
 
-// Generate the CONST state for a machine.
-fn generate_const_state(interval: f64) -> State {
-    // PaddingSent --> CONST (100%)
-    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
-    padding_sent.insert(CONST_STATE_INDEX, 1.0);
-
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::PaddingSent, padding_sent);
-    
-    // CONST state
-    let mut state = State::new(transitions, NUM_STATES);
-    state.bypass = true;
-    state.replace = true;
-
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: interval,
-        param2: interval,
-        start: 0.0,
-        max: 0.0,
-    };
-
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: PACKET_SIZE,
-        param2: PACKET_SIZE,
-        start: 0.0,
-        max: 0.0,
-    };
+// Print the equivalent maybenot v2 machine (see src/v2.rs).
+#[cfg(feature = "maybenot-v2")]
+fn print_machine_v2(timeout: &Dist, trigger: Trigger, budget: BlockingBudget, annotate: bool) {
+    use raising_the_bar::v2;
     
-    return state;
+    let timeout = v2::convert_dist(timeout).unwrap_or_else(|e| exit_with_error(&format!("Invalid send interval distribution: {}", e)));
+    let mut machine = v2::constant::generate_machine(timeout, trigger);
+    machine.max_blocking_frac = budget.max_blocking_frac;
+    machine.allowed_blocked_microsec = budget.allowed_blocked_microsec;
+    v2::print_machine("Machine", &machine, annotate);
+}
+
+
+#[cfg(not(feature = "maybenot-v2"))]
+fn print_machine_v2(_timeout: &Dist, _trigger: Trigger, _budget: BlockingBudget, _annotate: bool) {
+    exit_with_error("--maybenot-v2 requires building with --features maybenot-v2");
 }
@@ -0,0 +1,132 @@
+// CS-BuFLO -- a BuFLO-style defense whose send rate adapts to the traffic,
+// stepping between rate levels by halving or doubling.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper
+// and the CS-BuFLO/Maybenot papers, which the code is based on.
+//
+// CS-BuFLO sends at a constant rate while blocking real traffic, like BuFLO,
+// but adjusts the rate to the throughput it sees. Here, the rates are levels
+// from <max rate> down to <min rate>, each the previous one divided by
+// <factor> (see rate::rate_levels()), with the START and BLOCK states of the
+// RegulaTor relay machine:
+//  - START: no padding until a real packet is sent.
+//  - BLOCK: blocks all outgoing traffic, and moves on to SEND_0.
+//  - SEND_i: sends a packet at rate level i, replaced by a real packet when
+//    one is queued. A real packet sent steps up to the next faster level
+//    (demand is above the rate), and <window> padding packets in a row step
+//    down to the next slower one (demand is below it).
+// The machine starts at the max rate, as the first real packet usually starts
+// a burst, and the slowest level has no limit: it pads at the min rate until a
+// real packet is sent again. (A LimitReached back to itself, as in the last
+// SEND state of the original RegulaTor, would leave its limit at 0, and stop
+// the padding while blocked traffic waits for it.)
+
+use std::env;
+use std::process;
+
+use raising_the_bar::args::{parse_args, exit_with_error, ffi_output, rust_output, encoding, pretty_output, help, explain, GENERATOR_OPTIONS};
+use raising_the_bar::output::{print_annotation, print_machine, print_hash, print_ffi, print_rust};
+use raising_the_bar::report::print_report;
+use raising_the_bar::validate::{report_unreachable, dry_run};
+use raising_the_bar::cs_buflo::{CsBuflo, SWITCHES, OPTIONS};
+use raising_the_bar::defense::Defense;
+
+const HELP: &str = "\
+Usage: cs_buflo <min rate> <max rate> <factor> [options]
+
+Arguments:
+  <min rate>            slowest send rate (packets/sec, > 0)
+  <max rate>            fastest send rate, at which the machine starts
+                        (packets/sec, >= min rate)
+  <factor>              ratio between consecutive rate levels (> 1, e.g. 2 to
+                        halve and double the rate)
+
+Options:
+  --window <packets>    padding packets in a row before stepping down to the
+                        next slower rate (packets, >= 1, default 20)
+
+The rates are <max rate> divided by powers of <factor>, down to <min rate>,
+one SEND state each, and the machine holds 2 + (levels) states, at most 1000.
+";
+
+
+const EXPLAIN: &str = "\
+CS-BuFLO sends at a constant rate while blocking real traffic, like BuFLO, but
+adapts the rate to the traffic: the rates are levels from the max rate down to
+the min rate, each the previous one divided by the factor. The machine starts
+at the max rate, steps up a level each time a real packet is sent in place of
+padding (demand is above the rate), and steps down a level after a window of
+padding packets in a row (demand is below it).
+
+Parameters:
+  min rate  slowest send rate (packets/sec), at which the machine pads while
+            the traffic is idle.
+  max rate  fastest send rate (packets/sec), at which the machine starts.
+  factor    ratio between consecutive rate levels: a smaller factor gives more
+            levels, and follows the traffic more finely.
+
+In the paper: a comparison baseline, not one of the paper's four defenses,
+based on CS-BuFLO (Cai et al., WPES 2014).
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), SWITCHES, OPTIONS);
+    help(&cli, &[HELP, GENERATOR_OPTIONS]);
+    explain(&cli, EXPLAIN);
+    let cs_buflo = CsBuflo::from_args(&cli).unwrap_or_else(|e| exit_with_error(&e));
+    let machine = cs_buflo.machine().unwrap_or_else(|e| exit_with_error(&e));
+    let options = cs_buflo.options;
+
+    if cli.has("--warn-unreachable") {
+        report_unreachable("Machine", &machine);
+    }
+
+    if cli.has("--report") {
+        print_report(&cs_buflo.overhead_of(&[&machine]).unwrap_or_else(|e| exit_with_error(&e)));
+        return;
+    }
+
+    if cli.has("--dry-run") {
+        if !dry_run(&[("Machine", &machine)]) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if rust_output(&cli) {
+        print_rust(&[("Machine", &machine)]);
+        return;
+    }
+
+    if ffi_output(&cli) {
+        print_ffi(&[&machine]);
+        return;
+    }
+
+    let annotate = cli.has("--annotate");
+    let encoding = encoding(&cli);
+    let pretty = pretty_output(&cli);
+    if annotate {
+        print_annotation("cs_buflo", &[
+            ("min rate", cs_buflo.min_rate.to_string()),
+            ("max rate", cs_buflo.max_rate.to_string()),
+            ("factor", cs_buflo.factor.to_string()),
+            ("window", cs_buflo.window.to_string()),
+            ("packet size", cs_buflo.packet_size.to_string()),
+            ("max blocking frac", options.budget.max_blocking_frac.to_string()),
+            ("allowed blocked microsec", options.budget.allowed_blocked_microsec.to_string()),
+            ("time scale", options.time_scale.to_string()),
+            ("include small packets", options.include_small_packets.to_string()),
+        ]);
+    }
+
+    print_machine("Machine", &machine, annotate, pretty, encoding);
+
+    if cli.has("--hash") {
+        print_hash(&[&machine]);
+    }
+}
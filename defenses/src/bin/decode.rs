@@ -0,0 +1,68 @@
+// Machine decoder -- lists the fields of serialized maybenot machines.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use raising_the_bar::args::{parse_args, exit_with_error, encoding, help};
+use raising_the_bar::output::parse_encoded_machine_list;
+use raising_the_bar::describe::describe_machine;
+
+
+const HELP: &str = "\
+Usage: decode <machine string | machine file> [options]
+
+Arguments:
+  <machine string | machine file>  a serialized machine, or a file holding one,
+                                   or several one per line (as printed by --ffi)
+
+Options:
+  --base64                         the machine(s) are in base64, as printed by
+                                   the generators with --base64
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), &["--base64"], &[]);
+    help(&cli, &[HELP]);
+    let args = &cli.positional;
+    if args.len() != 2 {
+        exit_with_error(&format!("Usage: {} <machine string | machine file> [--base64]", &args[0]));
+    }
+
+    // Accept either a file containing the machine(s) or the machine itself
+    let input: String;
+
+    if Path::new(&args[1]).is_file() {
+        input = fs::read_to_string(&args[1]).unwrap_or_else(|e| exit_with_error(&format!("Unable to read machine file: {}", e)));
+    } else {
+        input = args[1].clone();
+    }
+
+    let machines = match parse_encoded_machine_list(&input, encoding(&cli)) {
+        Ok(machines) if !machines.is_empty() => machines,
+        Ok(_) => {
+            eprintln!("No machine given");
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Failed to parse machine: {}", e);
+            process::exit(1);
+        }
+    };
+
+    // Machines are numbered when there are several, in the order given
+    for (i, machine) in machines.iter().enumerate() {
+        let name = if machines.len() == 1 { String::from("Machine") } else { format!("Machine {}", i + 1) };
+        if i > 0 {
+            println!();
+        }
+        print!("{}", describe_machine(&name, machine));
+    }
+}
@@ -0,0 +1,109 @@
+// Machine diff -- lists what changed between two serialized maybenot machines.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use maybenot::machine::Machine;
+
+use raising_the_bar::args::{parse_args, exit_with_error, help};
+use raising_the_bar::output::parse_machine_list;
+use raising_the_bar::describe::describe_machine;
+use raising_the_bar::diff::diff_machines;
+
+
+const HELP: &str = "\
+Usage: diff <old machines> <new machines>
+
+Arguments:
+  <old machines>  a serialized machine, or a file holding one, or several one
+                  per line (as printed by --ffi)
+  <new machines>  the same for the machine(s) to compare with
+
+Prints nothing and exits with status 0 if the machines are the same, lists the
+differences and exits with status 1 if they differ, and exits with status 2 if
+a machine cannot be read.
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), &[], &[]);
+    help(&cli, &[HELP]);
+    let args = &cli.positional;
+    if args.len() != 3 {
+        exit_with_error(&format!("Usage: {} <old machines> <new machines>", &args[0]));
+    }
+
+    let old = read_machines(&args[1]);
+    let new = read_machines(&args[2]);
+
+    // Machines are compared in the order given, and numbered when there are
+    // several
+    let mut lines: Vec<String> = Vec::new();
+    for i in 0..old.len().max(new.len()) {
+        let name = if old.len() == 1 && new.len() == 1 { String::from("Machine") } else { format!("Machine {}", i + 1) };
+        let machine_lines = match (old.get(i), new.get(i)) {
+            (Some(old_machine), Some(new_machine)) => diff_machines(&name, old_machine, new_machine),
+            (Some(old_machine), None) => listed_machine(&name, "removed", old_machine),
+            (None, Some(new_machine)) => listed_machine(&name, "added", new_machine),
+            (None, None) => Vec::new(),
+        };
+
+        if !machine_lines.is_empty() {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.extend(machine_lines);
+        }
+    }
+
+    if !lines.is_empty() {
+        println!("{}", lines.join("\n"));
+        process::exit(1);
+    }
+}
+
+
+// Read a machine string or file, as the decoder does.
+fn read_machines(arg: &str) -> Vec<Machine> {
+    let input: String;
+
+    if Path::new(arg).is_file() {
+        input = match fs::read_to_string(arg) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("Unable to read machine file: {}", e);
+                process::exit(2);
+            }
+        };
+    } else {
+        input = arg.to_string();
+    }
+
+    return match parse_machine_list(&input) {
+        Ok(machines) if !machines.is_empty() => machines,
+        Ok(_) => {
+            eprintln!("No machine given");
+            process::exit(2);
+        }
+        Err(e) => {
+            eprintln!("Failed to parse machine: {}", e);
+            process::exit(2);
+        }
+    };
+}
+
+
+// A machine of only one of the lists, with all its fields.
+fn listed_machine(name: &str, change: &str, machine: &Machine) -> Vec<String> {
+    let description = describe_machine(name, machine);
+    let mut lines: Vec<String> = description.lines().map(String::from).collect();
+    lines[0] = format!("{}: {} ({} states)", name, change, machine.states.len());
+    return lines;
+}
@@ -0,0 +1,139 @@
+// DynaFlow -- a constant-rate style defense whose send interval adapts to the
+// traffic, stepping through a small set of candidate intervals.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper
+// and the DynaFlow/Maybenot papers, which the code is based on.
+//
+// DynaFlow sends a packet every interval, picking the interval from a small
+// set by how busy the traffic is. Each side gets one machine, which pads in
+// blocks of <block size> padding packets (replaced by real packets when there
+// are any), like the SEND states of the RegulaTor relay machine:
+//  - START: no padding until a real packet is sent.
+//  - SEND_i_k: padding every interval i of the <intervals>, for block k of
+//    the interval. Each block that ends (LimitReached) moves on to the next,
+//    and after <switch threshold> consecutive blocks the machine steps to the
+//    next interval. A real packet sent returns to the first interval, so only
+//    stretches without real packets step through the intervals.
+// The last interval is kept until a real packet is sent, so the intervals are
+// typically given in increasing order.
+
+use std::env;
+use std::process;
+
+use maybenot::machine::Machine;
+
+use raising_the_bar::args::{parse_args, exit_with_error, ffi_output, rust_output, encoding, pretty_output, help, explain, GENERATOR_OPTIONS};
+use raising_the_bar::output::{print_annotation, print_machine, print_hash, print_ffi, print_rust};
+use raising_the_bar::report::print_report;
+use raising_the_bar::validate::{report_unreachable, dry_run};
+use raising_the_bar::dynaflow::{DynaFlow, SWITCHES, OPTIONS};
+use raising_the_bar::defense::Defense;
+
+const HELP: &str = "\
+Usage: dynaflow <intervals> <switch threshold> [options]
+
+Arguments:
+  <intervals>           candidate send intervals, comma-separated, in the order
+                        they are stepped through while idle (usec, or ms with
+                        --ms, > 0, e.g. 1000,2000,4000)
+  <switch threshold>    consecutive idle blocks before stepping to the next
+                        interval (>= 1)
+
+Options:
+  --block-size <packets>
+                        padding packets per block (packets, >= 1, default 20)
+  --ms                  give the intervals in milliseconds instead of usec
+
+The machines hold 1 + (intervals) * (switch threshold) states, at most 1000.
+";
+
+
+const EXPLAIN: &str = "\
+DynaFlow sends at a constant rate, with a send interval picked from a small set
+by how busy the traffic is. Each side pads in blocks of padding packets,
+replaced by real packets when there are any, starting at the first interval.
+After a number of consecutive blocks without a real packet sent, the machine
+steps to the next, typically slower, interval, and a real packet sent returns
+to the first one, so only idle stretches slow the padding down.
+
+Parameters:
+  intervals         candidate send intervals, in the order they are stepped
+                    through while idle.
+  switch threshold  consecutive idle blocks before stepping to the next
+                    interval: a higher threshold keeps the faster intervals for
+                    longer.
+
+In the paper: a comparison baseline, not one of the paper's four defenses,
+based on DynaFlow (Lu et al., WPES 2018).
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), SWITCHES, OPTIONS);
+    help(&cli, &[HELP, GENERATOR_OPTIONS]);
+    explain(&cli, EXPLAIN);
+    let dynaflow = DynaFlow::from_args(&cli).unwrap_or_else(|e| exit_with_error(&e));
+    let machines = dynaflow.named_machines().unwrap_or_else(|e| exit_with_error(&e));
+    let options = dynaflow.options;
+
+    if cli.has("--warn-unreachable") {
+        for (name, machine) in &machines {
+            report_unreachable(name, machine);
+        }
+    }
+
+    if cli.has("--report") {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_report(&dynaflow.overhead_of(&machines).unwrap_or_else(|e| exit_with_error(&e)));
+        return;
+    }
+
+    if cli.has("--dry-run") {
+        let machines: Vec<(&str, &Machine)> = machines.iter().map(|(name, machine)| (name.as_str(), machine)).collect();
+        if !dry_run(&machines) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if rust_output(&cli) {
+        let machines: Vec<(&str, &Machine)> = machines.iter().map(|(name, machine)| (name.as_str(), machine)).collect();
+        print_rust(&machines);
+        return;
+    }
+
+    if ffi_output(&cli) {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_ffi(&machines);
+        return;
+    }
+
+    let annotate = cli.has("--annotate");
+    let encoding = encoding(&cli);
+    let pretty = pretty_output(&cli);
+    if annotate {
+        let intervals: Vec<String> = dynaflow.intervals.iter().map(|interval| interval.to_string()).collect();
+        print_annotation("dynaflow", &[
+            ("intervals", intervals.join(",")),
+            ("switch threshold", dynaflow.threshold.to_string()),
+            ("block size", dynaflow.block_size.to_string()),
+            ("packet size", dynaflow.packet_size.to_string()),
+            ("max blocking frac", options.budget.max_blocking_frac.to_string()),
+            ("allowed blocked microsec", options.budget.allowed_blocked_microsec.to_string()),
+            ("time scale", options.time_scale.to_string()),
+            ("include small packets", options.include_small_packets.to_string()),
+        ]);
+    }
+
+    for (name, machine) in &machines {
+        print_machine(name, machine, annotate, pretty, encoding);
+    }
+
+    if cli.has("--hash") {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_hash(&machines);
+    }
+}
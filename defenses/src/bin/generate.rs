@@ -0,0 +1,93 @@
+// Generate -- generates the machines of any registered defense by name, or
+// lists the defenses.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper.
+
+use std::env;
+use std::process;
+
+use raising_the_bar::args::{parse_args, exit_with_error, parse_packet_sizes, help};
+use raising_the_bar::generate::{available_defenses, parse_defense, generate_per_packet_size};
+use raising_the_bar::output::{print_machine, Encoding};
+
+
+const HELP: &str = "\
+Usage: generate [--packet-sizes <list>] <defense> [defense arguments]
+       generate --list
+
+Arguments:
+  <defense>            defense to generate the machines of (see --list)
+  [defense arguments]  arguments of the defense, as given to its binary
+
+Options:
+  --list               print the names of the defenses, one per line
+  --packet-sizes <list>
+                       comma-separated padding packet sizes (bytes), e.g. for
+                       paths with different MTUs: the machines are generated
+                       once per size, with --packet-size set to it, and their
+                       names end in \"(packet size <bytes>)\"
+
+Prints the machines as the defense's binary does, without its comments (e.g.,
+of --annotate). See <defense> --help for the arguments of a defense.
+";
+
+
+fn main() {
+    // The defense arguments, after the defense, are passed on as they are
+    let argv: Vec<String> = env::args().collect();
+    let mut split = 1;
+    while split < argv.len() && argv[split].starts_with("--") {
+        split += if argv[split] == "--packet-sizes" { 2 } else { 1 };
+    }
+    let split = (split + 1).min(argv.len());
+    let cli = parse_args(argv[..split].to_vec(), &["--list"], &["--packet-sizes"]);
+    help(&cli, &[HELP]);
+    let args = &cli.positional;
+
+    if cli.has("--list") {
+        if args.len() != 1 {
+            exit_with_error("--list takes no defense");
+        }
+        for name in available_defenses() {
+            println!("{}", name);
+        }
+        return;
+    }
+
+    if args.len() != 2 {
+        exit_with_error(&format!("Usage: {} [--packet-sizes <list>] <defense> [defense arguments]", &args[0]));
+    }
+
+    if let Some(sizes) = cli.value("--packet-sizes") {
+        let sizes = parse_packet_sizes(sizes).unwrap_or_else(|e| exit_with_error(&e));
+        let bundles = match generate_per_packet_size(&args[1], &argv[split..], &sizes) {
+            Ok(bundles) => bundles,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        };
+
+        for (size, machines) in bundles {
+            for (name, machine) in machines {
+                print_machine(&format!("{} (packet size {})", name, size), &machine, false, false, Encoding::Hex);
+            }
+        }
+        return;
+    }
+
+    let machines = match parse_defense(&args[1], &argv[split..]).and_then(|defense| defense.named_machines()) {
+        Ok(machines) => machines,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    for (name, machine) in machines {
+        print_machine(&name, &machine, false, false, Encoding::Hex);
+    }
+}
@@ -0,0 +1,146 @@
+// Interspace -- a randomized padding defense, where both the time between
+// padding packets and their size are sampled, for comparison with the other
+// defenses.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper
+// and the Interspace/Maybenot papers, which the code is based on.
+//
+// Interspace pads with samples from distributions learned from traffic. Here,
+// the inter-arrival times of the padding come from a distribution (normal by
+// default, see --dist) with the given parameters, sampled in microseconds, and
+// the padding sizes are drawn uniformly from [<min size>, <max size>]:
+//  - START: no padding until a real packet is sent.
+//  - PAD: padding packets are sent with timeouts from the distribution. Real
+//    packets neither replace padding nor re-arm the timer, so the padding is
+//    interspersed with the real traffic rather than shaped by it.
+//
+// The size range is the action of the PAD state, Uniform [<min size>, <max
+// size>]: maybenot rounds each sample to the nearest byte, and clamps it to
+// [1, MTU] (e.g., with a max size above the MTU, the sizes above it are all
+// sent as full packets).
+//
+// To match a captured distribution of packet sizes, --size-hist <file> draws
+// the padding sizes from a histogram instead, with one PAD state per bucket
+// (see interspace.rs for the file format). The sizes of the histogram must be
+// within [<min size>, <max size>].
+
+use std::env;
+use std::process;
+
+use raising_the_bar::args::{parse_args, exit_with_error, ffi_output, rust_output, encoding, pretty_output, help, explain, GENERATOR_OPTIONS};
+use raising_the_bar::output::{print_annotation, print_machine, print_hash, print_ffi, print_rust};
+use raising_the_bar::report::print_report;
+use raising_the_bar::validate::{report_unreachable, dry_run};
+use raising_the_bar::interspace::{Interspace, SWITCHES, OPTIONS};
+use raising_the_bar::defense::Defense;
+
+
+const HELP: &str = "\
+Usage: interspace <param1> <param2> <min size> <max size> [options]
+
+Arguments:
+  <param1>      first parameter of the padding timeout distribution (usec for
+                normal: mean)
+  <param2>      second parameter of the padding timeout distribution (usec for
+                normal: standard deviation)
+  <min size>    smallest padding packet (bytes, >= 1)
+  <max size>    largest padding packet (bytes, >= min size, sizes above the MTU
+                are sent as full packets)
+
+Options:
+  --dist <name> timeout distribution: normal (default), uniform, lognormal,
+                binomial, geometric, pareto, poisson, weibull, gamma, or beta
+  --size-hist <file>
+                histogram of padding sizes to draw from, one
+                <min size>,<max size>,<count> or <size>,<count> per line
+                (sizes within [min size, max size])
+
+The padding sizes are drawn uniformly from [min size, max size], or from the
+histogram of --size-hist, so --packet-size is not accepted.
+";
+
+
+const EXPLAIN: &str = "\
+Interspace adds randomized padding without delaying real traffic: after the
+first real packet sent, it sends padding packets with timeouts sampled from a
+distribution (normal by default, see --dist), and with sizes drawn uniformly
+from a range (or from a histogram, see --size-hist). Real packets neither
+replace the padding nor re-arm its timer, so the padding is interspersed with
+the traffic rather than shaped by it.
+
+Parameters:
+  param1    first parameter of the padding timeout distribution (usec, for
+            normal the mean): shorter timeouts pad faster.
+  param2    second parameter of the padding timeout distribution (for normal
+            the standard deviation): larger values randomize the timing more.
+  min size  smallest padding packet (bytes).
+  max size  largest padding packet (bytes): a wider size range randomizes the
+            sizes more.
+
+In the paper: a comparison baseline, not one of the paper's four defenses,
+based on Interspace (Pulls, 2020).
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), SWITCHES, OPTIONS);
+    help(&cli, &[HELP, GENERATOR_OPTIONS]);
+    explain(&cli, EXPLAIN);
+    let interspace = Interspace::from_args(&cli).unwrap_or_else(|e| exit_with_error(&e));
+    let machine = interspace.machine().unwrap_or_else(|e| exit_with_error(&e));
+    let options = interspace.options;
+
+    if cli.has("--warn-unreachable") {
+        report_unreachable("Machine", &machine);
+    }
+
+    if cli.has("--report") {
+        print_report(&interspace.overhead_of(&[&machine]).unwrap_or_else(|e| exit_with_error(&e)));
+        return;
+    }
+
+    if cli.has("--dry-run") {
+        if !dry_run(&[("Machine", &machine)]) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if rust_output(&cli) {
+        print_rust(&[("Machine", &machine)]);
+        return;
+    }
+
+    if ffi_output(&cli) {
+        print_ffi(&[&machine]);
+        return;
+    }
+
+    let annotate = cli.has("--annotate");
+    let encoding = encoding(&cli);
+    let pretty = pretty_output(&cli);
+    if annotate {
+        print_annotation("interspace", &[
+            ("param1", interspace.params.0.to_string()),
+            ("param2", interspace.params.1.to_string()),
+            ("min size", interspace.min_size.to_string()),
+            ("max size", interspace.max_size.to_string()),
+            ("dist", interspace.dist.to_string()),
+            ("size hist", cli.value("--size-hist").unwrap_or("none").to_string()),
+            ("max blocking frac", options.budget.max_blocking_frac.to_string()),
+            ("allowed blocked microsec", options.budget.allowed_blocked_microsec.to_string()),
+            ("time scale", options.time_scale.to_string()),
+            ("include small packets", options.include_small_packets.to_string()),
+        ]);
+    }
+
+    print_machine("Machine", &machine, annotate, pretty, encoding);
+
+    if cli.has("--hash") {
+        print_hash(&[&machine]);
+    }
+}
+
@@ -0,0 +1,64 @@
+// Machine linter -- reports structural issues in a serialized maybenot machine.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+
+use maybenot::machine::Machine;
+
+use raising_the_bar::args::{parse_args, exit_with_error, help};
+use raising_the_bar::validate::{validate_machine, has_errors, Level};
+
+
+const HELP: &str = "\
+Usage: lint <machine string | machine file>
+
+Arguments:
+  <machine string | machine file>  a serialized machine, or a file holding one
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), &[], &[]);
+    help(&cli, &[HELP]);
+    let args = &cli.positional;
+    if args.len() != 2 {
+        exit_with_error(&format!("Usage: {} <machine string | machine file>", &args[0]));
+    }
+
+    // Accept either a file containing the machine or the machine itself
+    let input: String;
+
+    if Path::new(&args[1]).is_file() {
+        input = fs::read_to_string(&args[1]).unwrap_or_else(|e| exit_with_error(&format!("Unable to read machine file: {}", e)));
+    } else {
+        input = args[1].clone();
+    }
+
+    let machine = match Machine::from_str(input.trim()) {
+        Ok(machine) => machine,
+        Err(e) => {
+            println!("Machine: error: failed to parse machine: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let issues = validate_machine(&machine);
+    for issue in &issues {
+        println!("{}", issue);
+    }
+
+    let num_errors = issues.iter().filter(|issue| issue.level == Level::Error).count();
+    println!("{} states, {} error(s), {} warning(s)", machine.states.len(), num_errors, issues.len() - num_errors);
+
+    if has_errors(&issues) {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,137 @@
+// Measure -- runs a base trace through the maybenot simulator with the
+// machines of a defense, and reports the padding overhead and the delay added
+// to real packets.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper.
+//
+// The machines come from running the defense's binary (see generate.rs) with
+// the arguments after "--". Machines named "Relay ..." run on the relay, and
+// the others on the side given by --side.
+
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Duration;
+
+use maybenot::machine::Machine;
+
+use raising_the_bar::args::{parse_args, exit_with_error, parse_count, help};
+use raising_the_bar::generate::generate_named;
+use raising_the_bar::simulate::{parse_base_trace, measure, mean, percentile, Side};
+
+
+// Default network delay between the client and the relay (usec)
+const DELAY: u64 = 10000;
+
+// Default cap on the number of packets recorded by the simulator
+const MAX_EVENTS: usize = 1000000;
+
+
+const HELP: &str = "\
+Usage: measure <base trace> [options] -- <defense> [defense arguments]
+
+Arguments:
+  <base trace>          file of undefended packets, one <time (ns)>,<s|r>[,<size>]
+                        per line (see README.md)
+  <defense>             defense to generate the machines with (see schema)
+  [defense arguments]   arguments of the defense, as given to its binary
+
+Options:
+  --side <client|relay> side to run machines not named \"Relay ...\" on (default
+                        client)
+  --delay <usec>        network delay between the client and the relay (usec,
+                        default 10000)
+  --max-events <n>      cap on the number of packets recorded by the simulator
+                        (>= 1, default 1000000)
+";
+
+
+fn main() {
+    // The defense arguments are passed on as they are
+    let argv: Vec<String> = env::args().collect();
+    let split = argv.iter().position(|arg| arg == "--").unwrap_or(argv.len());
+    let cli = parse_args(argv[..split].to_vec(), &[], &["--side", "--delay", "--max-events"]);
+    help(&cli, &[HELP]);
+    let args = &cli.positional;
+    let defense_args = argv.get(split + 1..).unwrap_or(&[]);
+    if args.len() != 2 || defense_args.is_empty() {
+        exit_with_error(&format!("Usage: {} <base trace> [--side <client|relay>] [--delay <usec>] [--max-events <n>] -- <defense> [defense arguments]", &args[0]));
+    }
+
+    let client_side = match cli.value("--side").unwrap_or("client") {
+        "client" => true,
+        "relay" => false,
+        side => exit_with_error(&format!("Unknown side: {} (expected client or relay)", side)),
+    };
+    let delay: u64 = cli.value("--delay").map_or(DELAY, |v| parse_count(v, "delay").unwrap_or_else(|e| exit_with_error(&e)));
+    let max_events: usize = cli.value("--max-events").map_or(MAX_EVENTS, |v| parse_count(v, "max events").unwrap_or_else(|e| exit_with_error(&e)));
+    if max_events < 1 {
+        exit_with_error("Max events must be at least 1");
+    }
+
+    let trace = fs::read_to_string(&args[1]).unwrap_or_else(|e| exit_with_error(&format!("Unable to read base trace: {}", e)));
+    let packets = parse_base_trace(&trace).unwrap_or_else(|e| exit_with_error(&format!("Invalid base trace: {}", e)));
+
+    let (client_machines, relay_machines) = generate_machines(&defense_args[0], &defense_args[1..], client_side);
+    let measurement = match measure(&packets, &client_machines, &relay_machines, Duration::from_micros(delay), max_events) {
+        Ok(measurement) => measurement,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    println!("Client: {}", describe_side(&measurement.client, client_machines.len()));
+    println!("Relay: {}", describe_side(&measurement.relay, relay_machines.len()));
+    println!("Total overhead: {}", format_overhead(measurement.overhead()));
+}
+
+
+// Generate the machines of the defense, split into those of the client and
+// those of the relay.
+fn generate_machines(defense: &str, defense_args: &[String], client_side: bool) -> (Vec<Machine>, Vec<Machine>) {
+    let machines = match generate_named(defense, defense_args) {
+        Ok(machines) => machines,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let mut client_machines: Vec<Machine> = Vec::new();
+    let mut relay_machines: Vec<Machine> = Vec::new();
+
+    for (name, machine) in machines {
+        if name.starts_with("Relay") || !client_side {
+            relay_machines.push(machine);
+        } else {
+            client_machines.push(machine);
+        }
+    }
+
+    return (client_machines, relay_machines);
+}
+
+
+// One line of what a side sent: real and padding packets, overhead, and the
+// delay of real packets.
+fn describe_side(side: &Side, num_machines: usize) -> String {
+    let delays = match (mean(&side.delays), percentile(&side.delays, 95.0)) {
+        (Some(mean), Some(p95)) => format!("delay mean {:.1} usec, p95 {:.1} usec", mean, p95),
+        _ => String::from("delay n/a"),
+    };
+
+    return format!("{} machine(s), {} real packets ({} bytes), {} padding packets ({} bytes), overhead {}, {}",
+        num_machines, side.real_packets, side.real_bytes, side.padding_packets, side.padding_bytes, format_overhead(side.overhead()), delays);
+}
+
+
+fn format_overhead(overhead: Option<f64>) -> String {
+    return match overhead {
+        Some(overhead) => format!("{:.4}", overhead),
+        None => String::from("n/a"),
+    };
+}
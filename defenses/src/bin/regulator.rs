@@ -0,0 +1,183 @@
+// RegulaTor -- the surge-and-decay schedule of the original RegulaTor defense,
+// without the video-specific simplifications of adapted_regulator.rs.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper
+// and the RegulaTor/Maybenot papers, which the code is based on (more details
+// in README.md).
+//
+// Differences from the adapted version, which otherwise shares all of its
+// states with this one (see src/regulator.rs):
+//  - Surge restart: the adapted version lets real traffic restart the surge in
+//    any SEND state below a fixed 200 packets/sec. Here the surge threshold T
+//    is used instead. A machine cannot observe the real packet rate, so it is
+//    taken to be the initial rate R: real traffic restarts the surge in SEND
+//    states where T times the sending rate is below R.
+//  - State rates: the adapted version finds each interval with a search that
+//    treats the rate at its midpoint as constant. Here the interval holds
+//    exactly <packets per state> packets of the RD^t curve (closed form), and
+//    the state sends at the mean rate of the interval.
+//  - Tail: the adapted version ends the machine (STATEEND) once the rate drops
+//    below 1 packet/sec. Here the last SEND state keeps padding at 1 packet/sec
+//    until real traffic restarts the surge, as RegulaTor never stops padding.
+// Neither version implements the padding budget N or the delay cap C.
+//
+// Small packets per state give a relay machine of many states. For a fixed
+// budget of states, --max-states <n> rejects parameters whose relay machine
+// would take more than n states, or with --coarsen, increases the packets per
+// state to the smallest whole number for which it fits (reported on stderr).
+
+use std::env;
+use std::process;
+
+use raising_the_bar::args::{parse_args, exit_with_error, ffi_output, rust_output, encoding, pretty_output, help, explain, GENERATOR_OPTIONS};
+use raising_the_bar::output::{print_annotation, print_machine, print_hash, print_ffi, print_rust};
+use raising_the_bar::report::print_report;
+use raising_the_bar::validate::{report_unreachable, dry_run};
+use raising_the_bar::constant::trigger_name;
+use raising_the_bar::regulator::{
+CLIENT_LIMIT,
+SWITCHES,
+OPTIONS,
+Regulator,
+check_send_units
+};
+use raising_the_bar::defense::Defense;
+
+
+const HELP: &str = "\
+Usage: regulator <initial rate> <decay rate> <surge threshold> <upload ratio> <packets per state> [options]
+
+Arguments:
+  <initial rate>       R, rate at the start of a surge (packets/sec, > 0)
+  <decay rate>         D, decay of the rate per second (0 < D < 1)
+  <surge threshold>    T, real traffic restarts the surge in SEND states where
+                       T times the padding rate is below R (> 0)
+  <upload ratio>       U, packets received per packet sent by the client (> 0)
+  <packets per state>  padding packets per relay SEND state (packets, > 0)
+
+Options:
+  --trigger <dir>      real traffic of the relay that starts its machine: sent,
+                       recv, or both (default both, see README.md)
+  --units-check        check that the first relay SEND state pads every
+                       1e6 / R usec (R in packets/sec), instead of printing the
+                       machines
+  --max-states <n>     cap on the states of the relay machine (>= 3): larger
+                       machines are rejected, unless --coarsen is given
+  --coarsen            with --max-states, increase the packets per state to the
+                       smallest whole number that fits the relay machine in the
+                       cap, reported on stderr
+";
+
+
+const EXPLAIN: &str = "\
+RegulaTor pads the download in surges: once real traffic is seen, the relay
+machine blocks outgoing traffic and sends at an initial rate R, decaying as R *
+D^t over the t seconds since the surge started, with real packets sent in place
+of padding. A burst of real traffic while the rate is low restarts the surge.
+The client machine sends a packet for every U packets received, hiding the
+upload. The decay curve is approximated by a chain of relay SEND states, each
+sending a fixed number of packets at a fixed rate.
+
+This generator follows the original RegulaTor schedule more closely than
+adapted_regulator, for comparison (the differences are listed at the top of
+regulator.rs).
+
+Parameters:
+  initial rate       R, rate at the start of a surge (packets/sec).
+  decay rate         D, decay of the rate per second.
+  surge threshold    T, real traffic restarts the surge in SEND states where T
+                     times the padding rate is below R: a higher threshold
+                     restarts it sooner.
+  upload ratio       U, packets received per packet the client sends.
+  packets per state  padding packets per relay SEND state, as for
+                     adapted_regulator.
+
+In the paper: a comparison baseline for the Adapted RegulaTor, one of the four
+defenses described in the paper, following RegulaTor (Holland and Hopper,
+PoPETs 2022, [5] in README.md).
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), SWITCHES, OPTIONS);
+    help(&cli, &[HELP, GENERATOR_OPTIONS]);
+    explain(&cli, EXPLAIN);
+    let regulator = Regulator::from_args(&cli).unwrap_or_else(|e| exit_with_error(&e));
+    let options = regulator.options;
+    if let Some(packets_per_state) = regulator.coarsened_from {
+        eprintln!("Coarsened packets per state from {} to {}, for a relay machine of at most {} states", packets_per_state, regulator.packets_per_state, regulator.max_states.unwrap());
+    }
+
+    let machines = regulator.named_machines().unwrap_or_else(|e| exit_with_error(&e));
+    let (relay_machine, client_machine) = (&machines[0].1, &machines[1].1);
+
+    if cli.has("--warn-unreachable") {
+        report_unreachable("Relay machine", relay_machine);
+        report_unreachable("Client machine", client_machine);
+    }
+
+    if cli.has("--report") {
+        print_report(&regulator.overhead_of(&[relay_machine, client_machine]).unwrap_or_else(|e| exit_with_error(&e)));
+        return;
+    }
+
+    if cli.has("--dry-run") {
+        if !dry_run(&[("Relay machine", relay_machine), ("Client machine", client_machine)]) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    // A self-test of the units of the relay's SEND states instead of the machines
+    if cli.has("--units-check") {
+        match check_send_units(relay_machine, regulator.initial_rate, regulator.decay, options.time_scale) {
+            Ok((timeout, expected)) => println!("Units check: the first SEND state pads every {:.3} usec, about 1e6 / initial rate (times the time scale) = {} usec: OK", timeout, expected),
+            Err(e) => {
+                eprintln!("Units check failed: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if rust_output(&cli) {
+        print_rust(&[("Relay machine", relay_machine), ("Client machine", client_machine)]);
+        return;
+    }
+
+    if ffi_output(&cli) {
+        print_ffi(&[relay_machine, client_machine]);
+        return;
+    }
+
+    let annotate = cli.has("--annotate");
+    let encoding = encoding(&cli);
+    let pretty = pretty_output(&cli);
+    if annotate {
+        print_annotation("regulator", &[
+            ("initial rate", regulator.initial_rate.to_string()),
+            ("decay rate", regulator.decay.to_string()),
+            ("surge threshold", regulator.threshold.to_string()),
+            ("trigger", trigger_name(regulator.trigger).to_string()),
+            ("upload ratio", regulator.upload_ratio.to_string()),
+            ("packets per state", regulator.packets_per_state.to_string()),
+            ("max states", regulator.max_states.map_or(String::from("none"), |n| n.to_string())),
+            ("client limit", CLIENT_LIMIT.to_string()),
+            ("packet size", regulator.packet_size.to_string()),
+            ("max blocking frac", options.budget.max_blocking_frac.to_string()),
+            ("allowed blocked microsec", options.budget.allowed_blocked_microsec.to_string()),
+            ("time scale", options.time_scale.to_string()),
+            ("include small packets", options.include_small_packets.to_string()),
+        ]);
+    }
+
+    print_machine("Relay machine", relay_machine, annotate, pretty, encoding);
+    print_machine("Client machine", client_machine, annotate, pretty, encoding);
+
+    if cli.has("--hash") {
+        print_hash(&[relay_machine, client_machine]);
+    }
+}
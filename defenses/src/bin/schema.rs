@@ -0,0 +1,46 @@
+// Parameter schema -- prints machine-readable descriptions of the parameters
+// of the defense generators, as JSON (see src/schema.rs).
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper.
+
+use std::env;
+
+use serde_json::Value;
+
+use raising_the_bar::args::{parse_args, exit_with_error, help};
+use raising_the_bar::schema::{find_defense, parameter_schema, DEFENSES};
+
+
+const HELP: &str = "\
+Usage: schema [defense]
+
+Arguments:
+  [defense]  generator to describe, e.g., constant or scrambler (default: a
+             list of every generator)
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), &[], &[]);
+    help(&cli, &[HELP]);
+    let args = &cli.positional;
+    if args.len() != 1 && args.len() != 2 {
+        exit_with_error(&format!("Usage: {} [defense]", &args[0]));
+    }
+
+    let schema: Value;
+
+    if args.len() == 2 {
+        if find_defense(&args[1]).is_none() {
+            exit_with_error(&format!("Unknown defense {}", &args[1]));
+        }
+        schema = parameter_schema(&args[1]);
+    } else {
+        schema = Value::Array(DEFENSES.iter().map(|defense| parameter_schema(defense.name)).collect());
+    }
+
+    println!("{}", serde_json::to_string_pretty(&schema).unwrap_or_else(|e| exit_with_error(&format!("Unable to serialize schema: {}", e))));
+}
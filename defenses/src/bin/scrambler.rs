@@ -7,480 +7,301 @@
 // If you use this code in your work, please include a reference to the paper.
 
 use std::env;
-use std::f64::INFINITY;
-use std::collections::HashMap;
-
-use maybenot::{
-machine::Machine,
-event::Event,
-state::State,
-dist::{Dist, DistType}
-};
-
-
-// Machine #1 states
-const NUM_STATES_M1: usize = 7;
-
-const START_STATE_INDEX: usize = 0;
-const BLOCK_STATE_INDEX: usize = 1;
-const MIN_STATE_INDEX:   usize = 2;
-const LEFT_STATE_INDEX:  usize = 3; // index of L_1
-const RIGHT_STATE_INDEX: usize = 4; // index of R_1
-
-// Machine #2 states
-const NUM_STATES_M2: usize = 3;
-
-const COUNT_LEFT_INDEX:  usize = 0;
-const COUNT_RIGHT_INDEX: usize = 1;
-const SIGNAL_INDEX:      usize = 2;
-
-// Shared constants
-const PACKET_SIZE: f64 = 1500.0;
+use std::process;
+
+use maybenot::machine::Machine;
+
+use raising_the_bar::args::{parse_args, exit_with_error, BlockingBudget, ffi_output, rust_output, encoding, pretty_output, help, explain, GENERATOR_OPTIONS};
+use raising_the_bar::output::{print_annotation, print_machine, print_hash, print_ffi, print_rust, encode_base64, Encoding};
+use raising_the_bar::report::{print_report, send_rate};
+use raising_the_bar::validate::{report_unreachable, dry_run};
+use raising_the_bar::scrambler::{signal_limit, PairTrails, Scrambler, TrailDist, SWITCHES, OPTIONS};
+use raising_the_bar::defense::Defense;
+
+
+const HELP: &str = "\
+Usage: scrambler [send interval] [minimum count] [min trail] [max trail] [options]
+
+Arguments:
+  [send interval]  time between packets in the L and R states (usec, > 0,
+                   default 160)
+  [minimum count]  packets in the MIN state of each segment (packets, >= 1,
+                   rounded to an integer, default 500)
+  [min trail]      minimum packets in an L or R state (packets, >= 0, default
+                   400)
+  [max trail]      maximum packets in an L or R state (packets, >= min trail,
+                   default 1000)
+
+Options:
+  --ms                       give the send interval and --min-interval in
+                             milliseconds instead of usec
+  --no-block                 generate Machine #1 alone, without blocking
+  --single-pair              generate Machine #1 alone, without the second L/R
+                             pair (5 states), as with --pairs 1
+  --report                   print the segment sizes the parameters imply
+                             before the overhead report (see below)
+  --only <1|2>               print only Machine #1 or Machine #2, as its
+                             serialized string alone (in base64 with
+                             --base64)
+  --min-interval <usec>      time between packets in the MIN state (usec, > 0,
+                             default: send interval)
+  --pairs <n>                L/R pairs of Machine #1, each used after a signal
+                             of Machine #2 (1 to 498, default 2)
+  --pair-reduction <factor>  divisor of the trailing packets of each L/R pair
+                             over the previous one (> 0, default 4)
+  --trail-dist <name>        distribution of the trailing packets of the L and R
+                             states: uniform over the range (default), or
+                             geometric with the mean of the range
+  --direction <up|down>      real traffic the machines follow: that their side
+                             sends (up, default), or that it receives (down,
+                             e.g., the download on the client)
+  --left-trail-min <count>   minimum packets in the L state (default: min trail)
+  --left-trail-max <count>   maximum packets in the L state (default: max trail)
+  --right-trail-min <count>  minimum packets in the R state (default: min trail)
+  --right-trail-max <count>  maximum packets in the R state (default: max trail)
+  --counters                 print Machine #1 alone with one L/R pair for
+                             maybenot v2, its segments sized by a counter: one
+                             trailing count per segment, drawn from the R range,
+                             shared by L and R (requires building with
+                             --features counters, see README.md)
+
+The defaults are the parameters of the paper (see defaults.rs).
+";
+
+
+const EXPLAIN: &str = "\
+Scrambler regularizes the timing of the traffic within segments, and randomizes
+the segments' sizes, so that the bursts of a video (e.g., its segments) no
+longer show. Machine #1 blocks outgoing traffic and sends a packet every send
+interval, real or padding: first a MIN state of a fixed number of packets, then
+L and R states that alternate on each real packet, each ending after a random
+trailing count of packets. The segment ends once an L or R state sends its
+trailing count without a real packet. Machine #2 counts real packets and
+signals Machine #1 to switch to a second L/R pair with shorter trailing counts.
+
+Parameters:
+  send interval  time between packets in the L and R states (usec): shorter
+                 intervals send real packets sooner, but pad more.
+  minimum count  packets of the MIN state that starts each segment: every
+                 segment is at least this long.
+  min trail      minimum trailing count of packets of an L or R state.
+  max trail      maximum trailing count of an L or R state: the trailing counts
+                 are drawn from [min trail, max trail], so a wider range
+                 randomizes the segment sizes more, and a larger max trail pads
+                 for longer after the real traffic.
+
+In the paper: the Scrambler, one of the four defenses described in the paper
+(see README.md), with the paper's parameters as the defaults (see defaults.rs).
+";
 
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    assert!(args.len() == 5, "Usage: {} <send interval> <minimum count> <min trail> <max trail>", &args[0]);
-    
-    let interval: f64 = args[1].parse().expect("Invalid send interval");
-    let min_count: f64 = args[2].parse().expect("Invalid minimum segment size");
-    let min_trail: f64 = args[3].parse().expect("Invalid minimum trailing count");
-    let max_trail: f64 = args[4].parse().expect("Invalid maximum trailing count");
-    
-    let machine1 = generate_machine_one(interval, min_count, min_trail, max_trail);
-    println!("Machine 1: {} ({})\n", machine1, machine1.len());
-
-    let machine2 = generate_machine_two(min_count);
-    println!("Machine 2: {} ({})\n", machine2, machine2.len());
-}
-
-
-// Generate Machine #1 with the specified parameters.
-fn generate_machine_one(interval: f64, min_count: f64, min_trail: f64, max_trail: f64) -> String {
-    // States
-    let mut states: Vec<State> = Vec::with_capacity(NUM_STATES_M1);
-    states.push(generate_start_state());
-    states.push(generate_block_state());
-
-    states.push(generate_min_state(interval, min_count));
-
-    states.push(generate_left_state(0, interval, min_trail, max_trail));
-    states.push(generate_right_state(0, interval, min_trail, max_trail));
-
-    states.push(generate_left_state(1, interval, min_trail / 4.0, max_trail / 4.0));
-    states.push(generate_right_state(1, interval, min_trail / 4.0, max_trail / 4.0));
-
-    // Machine
-    let machine = Machine {
-        allowed_padding_bytes: 0,
-        max_padding_frac: 0.0,
-        allowed_blocked_microsec: 0,
-        max_blocking_frac: 0.0,
-        states: states,
-        include_small_packets: false,
-    };
-    
-    return machine.serialize();
-}
-
-// Generate the START state for Machine #1.
-fn generate_start_state() -> State {
-    // NonPaddingSent --> BLOCK (100%)
-    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
-    nonpadding_sent.insert(BLOCK_STATE_INDEX, 1.0);
-    
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
-    
-    // START state
-    let mut state = State::new(transitions, NUM_STATES_M1);
-    state.action_is_block = true;
-    state.bypass = true;
-    state.replace = true;
+    let cli = parse_args(env::args().collect(), SWITCHES, OPTIONS);
+    help(&cli, &[HELP, GENERATOR_OPTIONS]);
+    explain(&cli, EXPLAIN);
+    let scrambler = Scrambler::from_args(&cli).unwrap_or_else(|e| exit_with_error(&e));
+    if let Some(min_count) = scrambler.rounded_from {
+        eprintln!("Warning: minimum count {} rounded to {}", min_count, scrambler.min_count);
+    }
+    let options = scrambler.options;
+    let (interval, min_interval, min_count) = (scrambler.interval, scrambler.min_interval(), scrambler.min_count);
+    let (left_trail, right_trail) = scrambler.trails();
+    let pairs = scrambler.pairs();
+    
+    // Machine #1 for maybenot v2, whose L and R states share the trailing count
+    if cli.has("--counters") {
+        for flag in ["--no-block", "--pairs", "--pair-reduction", "--left-trail-min", "--left-trail-max", "--only", "--report", "--dry-run", "--warn-unreachable", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--include-small-packets", "--direction"] {
+            if cli.has(flag) {
+                exit_with_error(&format!("--counters cannot be combined with {}", flag));
+            }
+        }
+        print_machine_counters(interval * options.time_scale, min_interval * options.time_scale, min_count, right_trail, scrambler.trail_dist, options.budget, cli.has("--annotate"));
+        return;
+    }
     
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: 0.0,
-        param2: 0.0,
-        start: 0.0,
-        max: 0.0,
-    };
+    let machines = scrambler.named_machines().unwrap_or_else(|e| exit_with_error(&e));
     
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: 0.0,
-        param2: 0.0,
-        start: 0.0,
-        max: 0.0,
-    };
+    if cli.has("--warn-unreachable") {
+        for (name, machine) in &machines {
+            report_unreachable(name, machine);
+        }
+    }
     
-    return state;
-}
-
-
-// Generate the BLOCK state for Machine #1.
-fn generate_block_state() -> State {
-    // BlockingBegin --> MIN (100%)
-    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
-    blocking_begin.insert(MIN_STATE_INDEX, 1.0);
+    // A single machine, e.g., to pipe it into another tool
+    if let Some(only) = cli.value("--only") {
+        if cli.has("--report") || cli.has("--annotate") || cli.has("--hash") || cli.has("--pretty") {
+            exit_with_error("--only cannot be combined with --report, --annotate, --hash, or --pretty");
+        }
+        let index = match only {
+            "1" => 0,
+            "2" => 1,
+            _ => exit_with_error(&format!("Unknown machine {}, expected 1 or 2", only)),
+        };
+        let (name, machine) = machines.get(index).unwrap_or_else(|| exit_with_error("Machine 2 is only generated with blocking and at least 2 L/R pairs"));
+        
+        // --dry-run checks the machine alone
+        if cli.has("--dry-run") {
+            if !dry_run(&[(name, machine)]) {
+                process::exit(1);
+            }
+            return;
+        }
+
+        if encoding(&cli) == Encoding::Base64 {
+            println!("{}", encode_base64(machine));
+        } else {
+            print_ffi(&[machine]);
+        }
+        return;
+    }
     
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::BlockingBegin, blocking_begin);
+    if cli.has("--report") {
+        // The option that leaves out the second L/R pair, if any
+        let single_pair = match ["--no-block", "--single-pair"].into_iter().find(|flag| cli.has(flag)) {
+            Some(flag) => Some(flag.to_string()),
+            None if scrambler.num_pairs == 1 => Some(String::from("--pairs 1")),
+            None => None,
+        };
+        print_segments(min_count, min_interval, &pairs, scrambler.trail_dist, single_pair);
+        
+        let (interval, min_interval) = (interval * options.time_scale, min_interval * options.time_scale);
+        println!("Send rate: MIN {}, L/R {}", send_rate(min_interval), send_rate(interval));
+        
+        // A burst filling a segment waits for the longest trailing count of
+        // any L/R pair, as in the max added delay of the overhead report
+        if cli.has("--no-block") {
+            println!("Worst-case added latency: 0 usec (--no-block)");
+        } else {
+            println!("Worst-case added latency: {} usec (a burst of {} packets, one every {} usec)", scrambler.max_delay(), min_count + scrambler.longest_trail(), scrambler.slowest_interval());
+        }
+        
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_report(&scrambler.overhead_of(&machines).unwrap_or_else(|e| exit_with_error(&e)));
+        return;
+    }
     
-    // BLOCK state
-    let mut state = State::new(transitions, NUM_STATES_M1);
-    state.action_is_block = true;
-    state.bypass = true;
-    state.replace = true;
+    if cli.has("--dry-run") {
+        let machines: Vec<(&str, &Machine)> = machines.iter().map(|(name, machine)| (name.as_str(), machine)).collect();
+        if !dry_run(&machines) {
+            process::exit(1);
+        }
+        return;
+    }
     
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: 0.0,
-        param2: 0.0,
-        start: 0.0,
-        max: 0.0,
-    };
+    if rust_output(&cli) {
+        let machines: Vec<(&str, &Machine)> = machines.iter().map(|(name, machine)| (name.as_str(), machine)).collect();
+        print_rust(&machines);
+        return;
+    }
     
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: INFINITY,
-        param2: INFINITY,
-        start: 0.0,
-        max: 0.0,
-    };
+    if ffi_output(&cli) {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_ffi(&machines);
+        return;
+    }
     
-    return state;
-}
-
-
-// Generate the MIN state for Machine #1.
-fn generate_min_state(interval: f64, min_count: f64) -> State {
-    // PaddingSent --> MIN (100%)
-    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
-    padding_sent.insert(MIN_STATE_INDEX, 1.0);
-
-    // LimitReached --> R_1 (100%)
-    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
-    limit_reached.insert(RIGHT_STATE_INDEX, 1.0);
-
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::PaddingSent, padding_sent);
-    transitions.insert(Event::LimitReached, limit_reached);
+    let annotate = cli.has("--annotate");
+    let encoding = encoding(&cli);
+    let pretty = pretty_output(&cli);
+    if annotate {
+        print_annotation("scrambler", &[
+            ("send interval", interval.to_string()),
+            ("minimum count", min_count.to_string()),
+            ("min trail", scrambler.min_trail.to_string()),
+            ("max trail", scrambler.max_trail.to_string()),
+            ("min interval", min_interval.to_string()),
+            ("left trail", format!("{} - {}", left_trail.0, left_trail.1)),
+            ("right trail", format!("{} - {}", right_trail.0, right_trail.1)),
+            ("trail dist", cli.value("--trail-dist").unwrap_or("uniform").to_string()),
+            ("direction", cli.value("--direction").unwrap_or("up").to_string()),
+            ("no block", cli.has("--no-block").to_string()),
+            ("single pair", cli.has("--single-pair").to_string()),
+            ("pairs", scrambler.num_pairs.to_string()),
+            ("pair reduction", scrambler.reduction.to_string()),
+            ("packet size", scrambler.packet_size.to_string()),
+            ("max blocking frac", options.budget.max_blocking_frac.to_string()),
+            ("allowed blocked microsec", options.budget.allowed_blocked_microsec.to_string()),
+            ("time scale", options.time_scale.to_string()),
+            ("include small packets", options.include_small_packets.to_string()),
+        ]);
+    }
     
-    // MIN state
-    let mut state = State::new(transitions, NUM_STATES_M1);
-    state.bypass = true;
-    state.replace = true;
-
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: interval,
-        param2: interval,
-        start: 0.0,
-        max: 0.0,
-    };
-
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: PACKET_SIZE,
-        param2: PACKET_SIZE,
-        start: 0.0,
-        max: 0.0,
-    };
-
-    state.limit = Dist {
-        dist: DistType::Uniform,
-        param1: min_count,
-        param2: min_count,
-        start: 0.0,
-        max: 0.0,
-    };
+    for (name, machine) in &machines {
+        print_machine(name, machine, annotate, pretty, encoding);
+    }
     
-    return state;
+    if cli.has("--hash") {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_hash(&machines);
+    }
 }
 
 
-// Generate an L state for Machine #1.
-fn generate_left_state(index: usize, interval: f64, min_trail: f64, max_trail: f64) -> State {
-    // PaddingSent --> L_{index} (100%)
-    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
-    padding_sent.insert(LEFT_STATE_INDEX + 2 * index, 1.0);
-
-    // NonPaddingSent --> R_{index} (100%)
-    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
-    nonpadding_sent.insert(RIGHT_STATE_INDEX + 2 * index, 1.0);
-
-    // LimitReached --> START (100%)
-    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
-    limit_reached.insert(START_STATE_INDEX, 1.0);
-
-    // BlockingBegin --> L_2 (if L_1)
-    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
-    blocking_begin.insert(LEFT_STATE_INDEX + 2, 1.0);
-
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::PaddingSent, padding_sent);
-    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
-    transitions.insert(Event::LimitReached, limit_reached);
-    if index == 0 {
-        transitions.insert(Event::BlockingBegin, blocking_begin);
+// Print the quantities that determine the segments of the Scrambler: the
+// packets of each MIN state, the trailing packets of the L/R pairs, and the
+// count at which Machine #2 signals the switch to the next pair. single_pair
+// is the option that leaves out the second pair, if any.
+fn print_segments(min_count: f64, min_interval: f64, pairs: &[PairTrails], trail_dist: TrailDist, single_pair: Option<String>) {
+    println!("Minimum packets per segment: {} (MIN state, every {} usec)", min_count, min_interval);
+    for (k, &(left_trail, right_trail)) in pairs.iter().enumerate() {
+        println!("Trailing packets, {} L/R pair: L {}, R {}", ordinal(k + 1), describe_trail(left_trail, trail_dist), describe_trail(right_trail, trail_dist));
     }
     
-    // L_{index} state
-    let mut state = State::new(transitions, NUM_STATES_M1);
-    state.bypass = true;
-    state.replace = true;
-
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: interval,
-        param2: interval,
-        start: 0.0,
-        max: 0.0,
-    };
-
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: PACKET_SIZE,
-        param2: PACKET_SIZE,
-        start: 0.0,
-        max: 0.0,
-    };
-
-    state.limit = Dist {
-        dist: DistType::Uniform,
-        param1: min_trail,
-        param2: max_trail,
-        start: 0.0,
-        max: 0.0,
-    };
+    if let Some(flag) = single_pair {
+        println!("Trailing packets, second L/R pair: not used ({})", flag);
+        println!("Machine 2 signaling limit: not used ({})", flag);
+        return;
+    }
     
-    return state;
+    println!("Machine 2 signaling limit: {} packets", signal_limit(min_count));
 }
 
-// Generate an R state for Machine #1.
-fn generate_right_state(index: usize, interval: f64, min_trail: f64, max_trail: f64) -> State {
-    // PaddingSent --> R_{index} (100%)
-    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
-    padding_sent.insert(RIGHT_STATE_INDEX + 2 * index, 1.0);
-
-    // NonPaddingSent --> L_{index} (100%)
-    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
-    nonpadding_sent.insert(LEFT_STATE_INDEX + 2 * index, 1.0);
-
-    // LimitReached --> START (100%)
-    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
-    limit_reached.insert(START_STATE_INDEX, 1.0);
-
-    // BlockingBegin --> R_2 (if R_1)
-    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
-    blocking_begin.insert(RIGHT_STATE_INDEX + 2, 1.0);
 
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::PaddingSent, padding_sent);
-    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
-    transitions.insert(Event::LimitReached, limit_reached);
-    if index == 0 {
-        transitions.insert(Event::BlockingBegin, blocking_begin);
-    }
+// "first", "second", ... for the report, as digits past the tenth (11th,
+// 21st, ...).
+fn ordinal(n: usize) -> String {
+    const WORDS: [&str; 10] = ["first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth", "tenth"];
     
-    // R_{index} state
-    let mut state = State::new(transitions, NUM_STATES_M1);
-    state.bypass = true;
-    state.replace = true;
-
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: interval,
-        param2: interval,
-        start: 0.0,
-        max: 0.0,
-    };
-
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: PACKET_SIZE,
-        param2: PACKET_SIZE,
-        start: 0.0,
-        max: 0.0,
-    };
-
-    state.limit = Dist {
-        dist: DistType::Uniform,
-        param1: min_trail,
-        param2: max_trail,
-        start: 0.0,
-        max: 0.0,
-    };
+    if let Some(word) = WORDS.get(n.wrapping_sub(1)) {
+        return word.to_string();
+    }
     
-    return state;
-}
-
-
-// Generate Machine #2 with the specified parameters.
-fn generate_machine_two(min_count: f64) -> String {
-    // States
-    let mut states: Vec<State> = Vec::with_capacity(NUM_STATES_M2);
-    states.push(generate_count_left_state(min_count));
-    states.push(generate_count_right_state(min_count));
-    states.push(generate_signal_state());
-
-    // Machine
-    let machine = Machine {
-        allowed_padding_bytes: 0,
-        max_padding_frac: 0.0,
-        allowed_blocked_microsec: 0,
-        max_blocking_frac: 0.0,
-        states: states,
-        include_small_packets: false,
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
     };
     
-    return machine.serialize();
+    return format!("{}{}", n, suffix);
 }
 
 
-// Generate the L state for Machine #2.
-fn generate_count_left_state(count: f64) -> State {
-    // NonPaddingSent --> L (100%)
-    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
-    nonpadding_sent.insert(COUNT_LEFT_INDEX, 1.0);
-
-    // BlockingBegin --> R (100%)
-    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
-    blocking_begin.insert(COUNT_RIGHT_INDEX, 1.0);
-
-    // LimitReached --> SIGNAL (100%)
-    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
-    limit_reached.insert(SIGNAL_INDEX, 1.0);
-    
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
-    transitions.insert(Event::BlockingBegin, blocking_begin);
-    transitions.insert(Event::LimitReached, limit_reached);
-
-    // L state
-    let mut state = State::new(transitions, NUM_STATES_M2);
-    state.action_is_block = true;
-    state.bypass = true;
-    
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: 0.0,
-        param2: 0.0,
-        start: 0.0,
-        max: 0.0,
+// Trailing packets of a state for the report: the range, and with a Geometric
+// distribution, its mean (see scrambler::trail_limit()).
+fn describe_trail(trail: (f64, f64), trail_dist: TrailDist) -> String {
+    return match trail_dist {
+        TrailDist::Uniform => format!("{} - {}", trail.0, trail.1),
+        TrailDist::Geometric => format!("geometric, mean {} ({} - {})", (trail.0 + trail.1) / 2.0, trail.0, trail.1),
     };
-    
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: 0.0,
-        param2: 0.0,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    state.limit = Dist {
-        dist: DistType::Uniform,
-        param1: count * 1.25,
-        param2: count * 1.25,
-        start: 0.0,
-        max: 0.0,
-    };
-
-    return state;
 }
 
 
-// Generate the R state for Machine #2.
-fn generate_count_right_state(count: f64) -> State {
-    // NonPaddingSent --> R (100%)
-    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
-    nonpadding_sent.insert(COUNT_RIGHT_INDEX, 1.0);
-
-    // BlockingBegin --> L (100%)
-    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
-    blocking_begin.insert(COUNT_LEFT_INDEX, 1.0);
-
-    // LimitReached --> SIGNAL (100%)
-    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
-    limit_reached.insert(SIGNAL_INDEX, 1.0);
-    
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
-    transitions.insert(Event::BlockingBegin, blocking_begin);
-    transitions.insert(Event::LimitReached, limit_reached);
-    
-    // R state
-    let mut state = State::new(transitions, NUM_STATES_M2);
-    state.action_is_block = true;
-    state.bypass = true;
-    
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: 0.0,
-        param2: 0.0,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: 0.0,
-        param2: 0.0,
-        start: 0.0,
-        max: 0.0,
-    };
-
-    state.limit = Dist {
-        dist: DistType::Uniform,
-        param1: count * 1.25,
-        param2: count * 1.25,
-        start: 0.0,
-        max: 0.0,
-    };
+// Print Machine #1 with segments sized by a counter, for maybenot v2 (see
+// src/v2/scrambler.rs). The timeouts are fixed, so they are scaled directly.
+#[cfg(feature = "counters")]
+fn print_machine_counters(interval: f64, min_interval: f64, min_count: f64, trail: (f64, f64), trail_dist: TrailDist, budget: BlockingBudget, annotate: bool) {
+    use raising_the_bar::v2;
     
-    return state;
+    let mut machine = v2::scrambler::generate_machine_one_counter(interval, min_interval, min_count, trail, trail_dist);
+    machine.max_blocking_frac = budget.max_blocking_frac;
+    machine.allowed_blocked_microsec = budget.allowed_blocked_microsec;
+    v2::print_machine("Machine 1", &machine, annotate);
 }
 
 
-// Generate the SIGNAL for Machine #2.
-fn generate_signal_state() -> State {
-    // BlockingBegin --> R (100%)
-    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
-    blocking_begin.insert(COUNT_RIGHT_INDEX, 1.0);
-    
-    // Transitions
-    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
-    transitions.insert(Event::BlockingBegin, blocking_begin);
-
-    // SIGNAL state
-    let mut state = State::new(transitions, NUM_STATES_M2);
-    state.action_is_block = true;
-    state.bypass = true;
-    state.replace = true;
-    
-    state.timeout = Dist {
-        dist: DistType::Uniform,
-        param1: 0.0,
-        param2: 0.0,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    state.action = Dist {
-        dist: DistType::Uniform,
-        param1: INFINITY,
-        param2: INFINITY,
-        start: 0.0,
-        max: 0.0,
-    };
-    
-    return state;
+#[cfg(not(feature = "counters"))]
+fn print_machine_counters(_interval: f64, _min_interval: f64, _min_count: f64, _trail: (f64, f64), _trail_dist: TrailDist, _budget: BlockingBudget, _annotate: bool) {
+    exit_with_error("--counters requires building with --features counters");
 }
@@ -0,0 +1,201 @@
+// Sizes -- sweeps one parameter of a defense over a range, and prints how the
+// number of states and the serialized length of its machines grow with it,
+// e.g., to pick the packets per state of RegulaTor.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper.
+//
+// The defense is generated once per value (see generate.rs), with the
+// arguments after "--" and the parameter set to the value (see schema.rs for
+// the names of the parameters). With --count-only, only the states are
+// counted, without generating the machines where the defense can count them
+// (the Adapted RegulaTor, see AdaptedRegulator::count_states()). Values the
+// defense rejects are reported on stderr and skipped.
+
+use std::env;
+use std::process;
+
+use maybenot::machine::Machine;
+
+use raising_the_bar::args::{parse_args, exit_with_error, try_parse_args, parse_finite, parse_finite_positive, help};
+use raising_the_bar::generate::parse_defense;
+use raising_the_bar::regulator::{AdaptedRegulator, ADAPTED_SWITCHES, ADAPTED_OPTIONS};
+use raising_the_bar::schema::{find_defense, Defense, Kind, Param};
+
+
+// Defenses that count their states without generating machines
+const COUNT_ONLY_DEFENSES: &[&str] = &["adapted_regulator"];
+
+// Tolerance on the last value of the range, for steps that are not exact in
+// binary (e.g., 0.1)
+const STOP_TOLERANCE: f64 = 0.000000001;
+
+// Cap on the number of values of the range, each of which generates the
+// defense
+const MAX_VALUES: f64 = 10000.0;
+
+
+const HELP: &str = "\
+Usage: sizes <parameter> <start> <stop> <step> [options] -- <defense> [defense arguments]
+
+Arguments:
+  <parameter>           name of the numeric parameter to sweep, as in the schema
+                        of the defense (e.g., \"packets per state\")
+  <start>               first value of the parameter
+  <stop>                last value of the parameter (>= start)
+  <step>                difference between consecutive values (> 0)
+  <defense>             defense to size (see schema)
+  [defense arguments]   arguments of the defense, as given to its binary: a
+                        positional parameter is replaced by each value, and an
+                        option is set to it
+
+Options:
+  --count-only          only print the number of states, skipping the machines
+                        where the defense supports it
+
+Prints one CSV row per value: the value, the number of states, and the
+serialized length, summed over the machines of the defense.
+";
+
+
+fn main() {
+    // The defense arguments are passed on as they are
+    let argv: Vec<String> = env::args().collect();
+    let split = argv.iter().position(|arg| arg == "--").unwrap_or(argv.len());
+    let cli = parse_args(argv[..split].to_vec(), &["--count-only"], &[]);
+    help(&cli, &[HELP]);
+    let args = &cli.positional;
+    let defense_args = argv.get(split + 1..).unwrap_or(&[]);
+    if args.len() != 5 || defense_args.is_empty() {
+        exit_with_error(&format!("Usage: {} <parameter> <start> <stop> <step> [--count-only] -- <defense> [defense arguments]", &args[0]));
+    }
+
+    let start: f64 = parse_finite(&args[2], "start").unwrap_or_else(|e| exit_with_error(&e));
+    let stop:  f64 = parse_finite(&args[3], "stop").unwrap_or_else(|e| exit_with_error(&e));
+    let step:  f64 = parse_finite_positive(&args[4], "step").unwrap_or_else(|e| exit_with_error(&e));
+    if stop < start {
+        exit_with_error("Stop must be at least the start");
+    }
+    let num_values = ((stop - start) / step + STOP_TOLERANCE).floor() + 1.0;
+    if num_values > MAX_VALUES {
+        exit_with_error(&format!("The range holds {} values, more than the cap of {} (give a larger step)", num_values, MAX_VALUES));
+    }
+
+    let name = &defense_args[0];
+    let defense = find_defense(name).unwrap_or_else(|| exit_with_error(&format!("Unknown defense {}", name)));
+    let param = defense.params.iter().find(|param| param.name == args[1]).unwrap_or_else(|| exit_with_error(&format!("Unknown parameter {} of {}", args[1], name)));
+    if param.kind != Kind::Number && param.kind != Kind::Integer {
+        exit_with_error(&format!("Parameter {} is not a number", param.name));
+    }
+
+    let count_only = cli.has("--count-only");
+    let fast_count = count_only && COUNT_ONLY_DEFENSES.contains(&name.as_str());
+
+    if count_only {
+        println!("{},states", param.name);
+    } else {
+        println!("{},states,serialized_len", param.name);
+    }
+
+    let mut num_values = 0;
+    let mut num_ok = 0;
+    let mut k = 0;
+
+    loop {
+        let value = start + k as f64 * step;
+        if value > stop + STOP_TOLERANCE * step {
+            break;
+        }
+        k += 1;
+        num_values += 1;
+
+        let result = with_value(defense, param, &defense_args[1..], &value.to_string())
+            .and_then(|run_args| if fast_count { count_states(&run_args).map(|states| (states, None)) } else { machine_sizes(name, &run_args) });
+
+        match result {
+            Ok((states, Some(serialized_len))) if !count_only => println!("{},{},{}", value, states, serialized_len),
+            Ok((states, _)) => println!("{},{}", value, states),
+            Err(e) => {
+                eprintln!("{} = {}: {}", param.name, value, e);
+                continue;
+            }
+        }
+        num_ok += 1;
+    }
+
+    eprintln!("{} of {} values succeeded", num_ok, num_values);
+    if num_ok == 0 {
+        process::exit(1);
+    }
+}
+
+
+// The arguments of the defense with the parameter set to value: the positional
+// parameter at its position (appended if the arguments stop just before it),
+// or the option, given once.
+fn with_value(defense: &Defense, param: &Param, args: &[String], value: &str) -> Result<Vec<String>, String> {
+    // Options that take a value, to tell their values from positional arguments
+    let options: Vec<&str> = defense.params.iter().filter(|p| p.kind != Kind::Switch).filter_map(|p| p.flag).collect();
+
+    if let Some(flag) = param.flag {
+        let mut result: Vec<String> = Vec::with_capacity(args.len() + 2);
+        let mut i = 0;
+        while i < args.len() {
+            if args[i] == flag {
+                i += 2;
+                continue;
+            }
+            result.push(args[i].clone());
+            i += 1;
+        }
+        result.push(flag.to_string());
+        result.push(value.to_string());
+        return Ok(result);
+    }
+
+    let position = defense.params.iter().filter(|p| p.flag.is_none()).position(|p| p.name == param.name).unwrap();
+    let mut result: Vec<String> = args.to_vec();
+    let mut seen = 0;
+    let mut i = 0;
+    while i < result.len() {
+        if options.contains(&result[i].as_str()) {
+            i += 2;
+            continue;
+        }
+        if !result[i].starts_with("--") {
+            if seen == position {
+                result[i] = value.to_string();
+                return Ok(result);
+            }
+            seen += 1;
+        }
+        i += 1;
+    }
+
+    if seen == position {
+        result.push(value.to_string());
+        return Ok(result);
+    }
+    return Err(format!("{} is positional argument {}, but only {} are given", param.name, position + 1, seen));
+}
+
+
+// States and serialized length of the machines of a defense, as in its
+// overhead report (see defense.rs).
+fn machine_sizes(name: &str, args: &[String]) -> Result<(usize, Option<usize>), String> {
+    let defense = parse_defense(name, args)?;
+    let machines = defense.named_machines()?;
+    let report = defense.overhead_of(&machines.iter().map(|(_, machine)| machine).collect::<Vec<&Machine>>())?;
+    return Ok((report.states, Some(report.serialized_len)));
+}
+
+
+// States of the Adapted RegulaTor machines, summed, counted without generating
+// them.
+fn count_states(args: &[String]) -> Result<usize, String> {
+    let cli = try_parse_args([&[String::from("adapted_regulator")], args].concat(), ADAPTED_SWITCHES, ADAPTED_OPTIONS)?;
+    let (relay_states, client_states) = AdaptedRegulator::from_args(&cli)?.count_states();
+    return Ok(relay_states + client_states);
+}
@@ -0,0 +1,149 @@
+// Surakav -- a baseline of the Surakav defense, where padding follows the
+// bursts of a reference trace at a controllable rate.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper
+// and the Surakav/Maybenot papers, which the code is based on.
+//
+// Surakav schedules bursts from reference traces generated by a GAN. Here, the
+// schedule comes from a given reference trace instead, and is discretized the
+// same way the Adapted RegulaTor discretizes its decay curve (see
+// regulator.rs): each direction's packets are split into intervals of
+// <packets per state> packets, one relay SEND state per interval, sending at
+// the mean rate of the interval times <rate>. As in the relay-side RegulaTor
+// machine, outgoing traffic is blocked once the machine starts, so real packets
+// only go out in place of padding packets, and the last SEND state ends the
+// machine. The client machine follows the packets sent in the reference trace,
+// and the relay machine the packets received.
+//
+// The reference trace holds one packet per line, in the format of the
+// LongEnough traces and the maybenot simulator: <time>,<direction>[,<size>]
+// with the time in nanoseconds, and the direction s (sent by the client) or r
+// (received), optionally followed by +p (padding). Sizes are ignored. Blank
+// lines are skipped.
+
+use std::env;
+use std::process;
+
+use maybenot::machine::Machine;
+
+use raising_the_bar::args::{parse_args, exit_with_error, ffi_output, rust_output, encoding, pretty_output, help, explain, GENERATOR_OPTIONS};
+use raising_the_bar::output::{print_annotation, print_machine, print_hash, print_ffi, print_rust};
+use raising_the_bar::report::print_report;
+use raising_the_bar::validate::{report_unreachable, dry_run};
+use raising_the_bar::surakav::{Surakav, SWITCHES, OPTIONS};
+use raising_the_bar::defense::Defense;
+
+const HELP: &str = "\
+Usage: surakav <reference trace> <rate> <packets per state> [options]
+
+Arguments:
+  <reference trace>    file of packets to follow, one <time (ns)>,<s|r>[,<size>]
+                       per line (see README.md)
+  <rate>               multiplier of the reference trace's sending rate (> 0,
+                       1 = as in the trace)
+  <packets per state>  reference packets per SEND state (packets, >= 1)
+
+Options:
+  --max-states <n>     cap on the number of SEND states per machine, later
+                       packets of the trace are not followed (>= 1, default 250)
+";
+
+
+const EXPLAIN: &str = "\
+Surakav shapes the traffic after a reference trace: each side blocks its
+outgoing traffic once its machine starts, and sends packets at the rates of the
+reference trace, real packets in place of padding. Surakav generates its
+reference traces with a GAN; here, a reference trace is given instead. Its
+packets are split into intervals, one SEND state each, sending at the mean rate
+of the interval, and the last SEND state ends the machine. The client machine
+follows the packets sent in the trace, and the relay machine the packets
+received.
+
+Parameters:
+  reference trace    file of the packets to follow, one <time>,<s|r>[,<size>]
+                     per line, with the time in nanoseconds.
+  rate               multiplier of the reference trace's rates: above 1, real
+                     packets wait less, but more padding is sent.
+  packets per state  reference packets per SEND state: how finely the trace is
+                     followed. Fewer packets per state follow it more closely,
+                     but take more states.
+
+In the paper: a comparison baseline, not one of the paper's four defenses,
+based on Surakav (Gong et al., IEEE S&P 2022).
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), SWITCHES, OPTIONS);
+    help(&cli, &[HELP, GENERATOR_OPTIONS]);
+    explain(&cli, EXPLAIN);
+    let surakav = Surakav::from_args(&cli).unwrap_or_else(|e| exit_with_error(&e));
+    for needed in surakav.send_states() {
+        if needed > surakav.max_states {
+            eprintln!("Warning: reference trace needs {} SEND states, following only the first {} (see --max-states)", needed, surakav.max_states);
+        }
+    }
+    let machines = surakav.named_machines().unwrap_or_else(|e| exit_with_error(&e));
+    let options = surakav.options;
+
+    if cli.has("--warn-unreachable") {
+        for (name, machine) in &machines {
+            report_unreachable(name, machine);
+        }
+    }
+
+    if cli.has("--report") {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_report(&surakav.overhead_of(&machines).unwrap_or_else(|e| exit_with_error(&e)));
+        return;
+    }
+
+    if cli.has("--dry-run") {
+        let machines: Vec<(&str, &Machine)> = machines.iter().map(|(name, machine)| (name.as_str(), machine)).collect();
+        if !dry_run(&machines) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if rust_output(&cli) {
+        let machines: Vec<(&str, &Machine)> = machines.iter().map(|(name, machine)| (name.as_str(), machine)).collect();
+        print_rust(&machines);
+        return;
+    }
+
+    if ffi_output(&cli) {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_ffi(&machines);
+        return;
+    }
+
+    let annotate = cli.has("--annotate");
+    let encoding = encoding(&cli);
+    let pretty = pretty_output(&cli);
+    if annotate {
+        print_annotation("surakav", &[
+            ("reference trace", surakav.trace.to_string()),
+            ("rate", surakav.rate.to_string()),
+            ("packets per state", surakav.packets_per_state.to_string()),
+            ("max states", surakav.max_states.to_string()),
+            ("packet size", surakav.packet_size.to_string()),
+            ("max blocking frac", options.budget.max_blocking_frac.to_string()),
+            ("allowed blocked microsec", options.budget.allowed_blocked_microsec.to_string()),
+            ("time scale", options.time_scale.to_string()),
+            ("include small packets", options.include_small_packets.to_string()),
+        ]);
+    }
+
+    for (name, machine) in &machines {
+        print_machine(name, machine, annotate, pretty, encoding);
+    }
+
+    if cli.has("--hash") {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_hash(&machines);
+    }
+}
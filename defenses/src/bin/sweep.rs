@@ -0,0 +1,124 @@
+// Sweep -- generates Scrambler machines for every row of a CSV file of
+// parameters, for batch generation in parameter sweeps.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper.
+//
+// Each input row holds the positional arguments of the scrambler binary:
+//   <send interval>,<minimum count>,<min trail>,<max trail>
+// An optional header row is copied to the output. Each output row holds the
+// input columns followed by machine1,machine1_len,machine2,machine2_len.
+// Malformed rows are reported on stderr with their line number and skipped.
+
+use std::env;
+use std::fs;
+
+use raising_the_bar::args::{parse_args, exit_with_error, packet_size, help};
+use raising_the_bar::output::serialize_stable;
+use raising_the_bar::validate::{validate_machine, has_errors};
+use raising_the_bar::scrambler::{generate_machine_one, generate_machine_two, round_min_count, TrailDist, Direction};
+
+
+const NUM_COLUMNS: usize = 4;
+
+
+const HELP: &str = "\
+Usage: sweep <input csv> [output csv] [options]
+
+Arguments:
+  <input csv>   rows of Scrambler parameters: send interval (usec), minimum
+                count (packets), min trail (packets), max trail (packets)
+  [output csv]  file to write the rows with their machines to (default: stdout)
+
+Options:
+  --packet-size <bytes>  size of padding packets (bytes, default 1500)
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), &[], &["--packet-size"]);
+    help(&cli, &[HELP]);
+    let args = &cli.positional;
+    if args.len() != 2 && args.len() != 3 {
+        exit_with_error(&format!("Usage: {} <input csv> [output csv] [--packet-size <bytes>]", &args[0]));
+    }
+
+    let input = fs::read_to_string(&args[1]).unwrap_or_else(|e| exit_with_error(&format!("Unable to read input file: {}", e)));
+    let packet_size = packet_size(&cli).unwrap_or_else(|e| exit_with_error(&e));
+
+    let mut output = String::new();
+    let mut num_rows = 0;
+    let mut num_ok = 0;
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        // Header row, if any
+        if i == 0 && parse_row(line).is_err() {
+            output.push_str(&format!("{},machine1,machine1_len,machine2,machine2_len\n", line));
+            continue;
+        }
+
+        num_rows += 1;
+        match generate_row(line, packet_size) {
+            Ok(row) => {
+                output.push_str(&row);
+                num_ok += 1;
+            },
+            Err(e) => eprintln!("Line {}: {}", i + 1, e),
+        }
+    }
+
+    if args.len() == 3 {
+        fs::write(&args[2], output).unwrap_or_else(|e| exit_with_error(&format!("Unable to write output file: {}", e)));
+    } else {
+        print!("{}", output);
+    }
+
+    eprintln!("{} of {} rows succeeded", num_ok, num_rows);
+}
+
+
+// Generate the output row for an input row.
+fn generate_row(line: &str, packet_size: f64) -> Result<String, String> {
+    let params = parse_row(line)?;
+    let (interval, min_count, min_trail, max_trail) = (params[0], params[1], params[2], params[3]);
+    let min_count = round_min_count(min_count)?;
+
+    let machine1 = generate_machine_one(interval, interval, min_count, (min_trail, max_trail), (min_trail, max_trail), TrailDist::Uniform, packet_size);
+    let machine2 = generate_machine_two(min_count, Direction::Up);
+
+    if has_errors(&validate_machine(&machine1)) || has_errors(&validate_machine(&machine2)) {
+        return Err(String::from("parameters produce an invalid machine"));
+    }
+
+    let machine1 = serialize_stable(&machine1);
+    let machine2 = serialize_stable(&machine2);
+
+    return Ok(format!("{},{},{},{},{}\n", line, machine1, machine1.len(), machine2, machine2.len()));
+}
+
+
+// Parse the parameters of an input row.
+fn parse_row(line: &str) -> Result<Vec<f64>, String> {
+    let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+    if fields.len() != NUM_COLUMNS {
+        return Err(format!("expected {} columns, got {}", NUM_COLUMNS, fields.len()));
+    }
+
+    let mut params: Vec<f64> = Vec::with_capacity(NUM_COLUMNS);
+    for field in fields {
+        match field.parse::<f64>() {
+            Ok(value) if value.is_finite() => params.push(value),
+            Ok(_) => return Err(format!("invalid number \"{}\", must be finite", field)),
+            Err(_) => return Err(format!("invalid number \"{}\"", field)),
+        }
+    }
+
+    return Ok(params);
+}
@@ -0,0 +1,113 @@
+// Tune -- searches Scrambler parameters for a target overhead on a base trace,
+// measuring each configuration in the maybenot simulator.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper.
+//
+// The search starts from the given parameters (by default those of the paper)
+// and hill-climbs on the send interval, the minimum count, and the trailing
+// count range (see tune.rs for the search space and when it stops). It prints
+// the configuration measured closest to the target, as the arguments of the
+// scrambler binary, with its measured overhead and its overhead report (see
+// report.rs).
+
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Duration;
+
+use raising_the_bar::args::{parse_args, exit_with_error, parse_finite, parse_finite_positive, parse_count, packet_size, help};
+use raising_the_bar::defense::{Defense, Scrambler};
+use raising_the_bar::defaults::{SCRAMBLER_SEND_INTERVAL, SCRAMBLER_MIN_COUNT, SCRAMBLER_MIN_TRAIL, SCRAMBLER_MAX_TRAIL};
+use raising_the_bar::report::print_report;
+use raising_the_bar::simulate::parse_base_trace;
+use raising_the_bar::tune::{hill_climb, measured_overhead, Search, TOLERANCE, MAX_MEASUREMENTS};
+
+
+// Default network delay between the client and the relay (usec), as for measure
+const DELAY: u64 = 10000;
+
+// Default cap on the number of packets recorded by each simulation
+const MAX_EVENTS: usize = 1000000;
+
+
+const HELP: &str = "\
+Usage: tune <base trace> <target overhead> [send interval] [minimum count] [min trail] [max trail] [options]
+
+Arguments:
+  <base trace>        file of undefended packets, one <time (ns)>,<s|r>[,<size>]
+                      per line, as for measure (see README.md)
+  <target overhead>   padding bytes over real bytes to aim for (ratio, > 0)
+  [send interval]     send interval to start from (usec, default 160)
+  [minimum count]     minimum count to start from (packets, default 500)
+  [min trail]         trailing count range to start from (packets, default
+  [max trail]         400 to 1000)
+
+Options:
+  --tolerance <ratio>     stop within this fraction of the target (>= 0,
+                          default 0.01)
+  --max-measurements <n>  cap on the configurations measured (>= 1, default
+                          100)
+  --delay <usec>          network delay between the client and the relay
+                          (usec, default 10000)
+  --max-events <n>        cap on the number of packets recorded by each
+                          simulation (>= 1, default 1000000)
+  --packet-size <bytes>   size of padding packets (bytes, default 1500)
+
+Prints the configuration measured closest to the target, as the arguments of
+the scrambler binary, its measured overhead, and its overhead report.
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), &[], &["--tolerance", "--max-measurements", "--delay", "--max-events", "--packet-size"]);
+    help(&cli, &[HELP]);
+    let args = &cli.positional;
+    if args.len() < 3 || args.len() > 7 {
+        exit_with_error(&format!("Usage: {} <base trace> <target overhead> [send interval] [minimum count] [min trail] [max trail] [--tolerance <ratio>] [--max-measurements <n>] [--delay <usec>] [--max-events <n>] [--packet-size <bytes>]", &args[0]));
+    }
+
+    let target: f64 = parse_finite_positive(&args[2], "target overhead").unwrap_or_else(|e| exit_with_error(&e));
+    let start_param = |i: usize, default: f64, name: &str| args.get(i).map_or(default, |v| parse_finite_positive(v, name).unwrap_or_else(|e| exit_with_error(&e)));
+    let start = Scrambler {
+        interval: start_param(3, SCRAMBLER_SEND_INTERVAL, "send interval"),
+        min_count: start_param(4, SCRAMBLER_MIN_COUNT, "minimum count"),
+        min_trail: start_param(5, SCRAMBLER_MIN_TRAIL, "min trail"),
+        max_trail: start_param(6, SCRAMBLER_MAX_TRAIL, "max trail"),
+        packet_size: packet_size(&cli).unwrap_or_else(|e| exit_with_error(&e)),
+        ..Scrambler::default()
+    };
+    if start.min_trail > start.max_trail {
+        exit_with_error("Min trail must not exceed max trail");
+    }
+
+    let search = Search {
+        target: target,
+        tolerance: cli.value("--tolerance").map_or(TOLERANCE, |v| parse_finite(v, "tolerance").unwrap_or_else(|e| exit_with_error(&e))),
+        max_measurements: cli.value("--max-measurements").map_or(MAX_MEASUREMENTS, |v| parse_count(v, "max measurements").unwrap_or_else(|e| exit_with_error(&e))),
+    };
+    let delay: u64 = cli.value("--delay").map_or(DELAY, |v| parse_count(v, "delay").unwrap_or_else(|e| exit_with_error(&e)));
+    let max_events: usize = cli.value("--max-events").map_or(MAX_EVENTS, |v| parse_count(v, "max events").unwrap_or_else(|e| exit_with_error(&e)));
+    if max_events < 1 {
+        exit_with_error("Max events must be at least 1");
+    }
+
+    let trace = fs::read_to_string(&args[1]).unwrap_or_else(|e| exit_with_error(&format!("Unable to read base trace: {}", e)));
+    let packets = parse_base_trace(&trace).unwrap_or_else(|e| exit_with_error(&format!("Invalid base trace: {}", e)));
+
+    let tuned = match hill_climb(&start, &search, |params| measured_overhead(params, &packets, Duration::from_micros(delay), max_events)) {
+        Ok(tuned) => tuned,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let params = &tuned.params;
+    println!("Scrambler: {} {} {} {}", params.interval, params.min_count, params.min_trail, params.max_trail);
+    println!("Measured overhead: {:.4} (target {}, {})", tuned.overhead, target, if tuned.converged { "within the tolerance" } else { "closest found, not within the tolerance" });
+    println!("Measurements: {}\n", tuned.measurements);
+    print_report(&params.overhead());
+}
@@ -0,0 +1,135 @@
+// Walkie-Talkie -- a half-duplex style defense, where each outgoing burst of
+// traffic is padded to a target size before the other side's burst.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper
+// and the Walkie-Talkie/Maybenot papers, which the code is based on.
+//
+// Walkie-Talkie makes the browser talk half-duplex and pads the resulting
+// bursts. A machine can only pad its own direction, so each side gets one,
+// alternating between bursts the same way as the Scrambler's L/R states:
+//  - RECV: the other side's burst. No padding until a real packet is sent.
+//  - SEND: our burst. Padding packets are sent every <send interval>, replaced
+//    by real packets when there are any, until the burst holds the target
+//    number of packets. Packets received meanwhile do not end the burst.
+//  - SENT: the burst is full. Further real packets are sent as is, and the
+//    next received packet hands the turn to the other side (back to RECV).
+// The client machine pads outgoing bursts to <send burst> packets, and the
+// relay machine pads incoming bursts to <recv burst> packets. Unlike
+// Walkie-Talkie, real traffic is never delayed to make it half-duplex.
+
+use std::env;
+use std::process;
+
+use maybenot::machine::Machine;
+
+use raising_the_bar::args::{parse_args, exit_with_error, ffi_output, rust_output, encoding, pretty_output, help, explain, GENERATOR_OPTIONS};
+use raising_the_bar::output::{print_annotation, print_machine, print_hash, print_ffi, print_rust};
+use raising_the_bar::report::print_report;
+use raising_the_bar::validate::{report_unreachable, dry_run};
+use raising_the_bar::walkie_talkie::{WalkieTalkie, SWITCHES, OPTIONS};
+use raising_the_bar::defense::Defense;
+
+const HELP: &str = "\
+Usage: walkie_talkie <send burst> <recv burst> <send interval> [options]
+
+Arguments:
+  <send burst>     packets per outgoing burst, padded by the client machine
+                   (packets, >= 1)
+  <recv burst>     packets per incoming burst, padded by the relay machine
+                   (packets, >= 1)
+  <send interval>  time between padding packets of a burst (usec, or ms with
+                   --ms, > 0)
+
+Options:
+  --ms             give the send interval in milliseconds instead of usec
+";
+
+
+const EXPLAIN: &str = "\
+Walkie-Talkie pads each burst of traffic to a fixed size before the other
+side's burst, so that the bursts no longer reveal their sizes. A machine can
+only pad its own direction, so the client machine pads outgoing bursts and the
+relay machine incoming ones: during its burst, a machine sends padding every
+send interval, replaced by real packets when there are any, until the burst
+holds its target number of packets, and the next received packet hands the turn
+to the other side. Unlike Walkie-Talkie, real traffic is never delayed to make
+it half-duplex.
+
+Parameters:
+  send burst     packets per outgoing burst, padded by the client machine.
+  recv burst     packets per incoming burst, padded by the relay machine.
+  send interval  time between padding packets of a burst.
+
+In the paper: a comparison baseline, not one of the paper's four defenses,
+based on Walkie-Talkie (Wang and Goldberg, USENIX Security 2017).
+";
+
+
+fn main() {
+    let cli = parse_args(env::args().collect(), SWITCHES, OPTIONS);
+    help(&cli, &[HELP, GENERATOR_OPTIONS]);
+    explain(&cli, EXPLAIN);
+    let talkie = WalkieTalkie::from_args(&cli).unwrap_or_else(|e| exit_with_error(&e));
+    let machines = talkie.named_machines().unwrap_or_else(|e| exit_with_error(&e));
+    let options = talkie.options;
+
+    if cli.has("--warn-unreachable") {
+        for (name, machine) in &machines {
+            report_unreachable(name, machine);
+        }
+    }
+
+    if cli.has("--report") {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_report(&talkie.overhead_of(&machines).unwrap_or_else(|e| exit_with_error(&e)));
+        return;
+    }
+
+    if cli.has("--dry-run") {
+        let machines: Vec<(&str, &Machine)> = machines.iter().map(|(name, machine)| (name.as_str(), machine)).collect();
+        if !dry_run(&machines) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if rust_output(&cli) {
+        let machines: Vec<(&str, &Machine)> = machines.iter().map(|(name, machine)| (name.as_str(), machine)).collect();
+        print_rust(&machines);
+        return;
+    }
+
+    if ffi_output(&cli) {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_ffi(&machines);
+        return;
+    }
+
+    let annotate = cli.has("--annotate");
+    let encoding = encoding(&cli);
+    let pretty = pretty_output(&cli);
+    if annotate {
+        print_annotation("walkie_talkie", &[
+            ("send burst", talkie.send_burst.to_string()),
+            ("recv burst", talkie.recv_burst.to_string()),
+            ("send interval", talkie.interval.to_string()),
+            ("packet size", talkie.packet_size.to_string()),
+            ("max blocking frac", options.budget.max_blocking_frac.to_string()),
+            ("allowed blocked microsec", options.budget.allowed_blocked_microsec.to_string()),
+            ("time scale", options.time_scale.to_string()),
+            ("include small packets", options.include_small_packets.to_string()),
+        ]);
+    }
+
+    for (name, machine) in &machines {
+        print_machine(name, machine, annotate, pretty, encoding);
+    }
+
+    if cli.has("--hash") {
+        let machines: Vec<&Machine> = machines.iter().map(|(_, machine)| machine).collect();
+        print_hash(&machines);
+    }
+}
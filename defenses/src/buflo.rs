@@ -0,0 +1,151 @@
+// Machine generation for BuFLO (see bin/buflo.rs), out of the states of the
+// constant defense (see constant.rs). Buflo holds the parameters of the buflo
+// binary, parsed from its arguments by Buflo::from_args().
+
+use std::collections::HashMap;
+
+use maybenot::{
+machine::Machine,
+event::Event,
+state::State
+};
+
+use crate::args::{try_parse_args, parse_finite_positive, packet_size, parse_interval, machine_options, Args, MachineOptions};
+use crate::constant::{
+NUM_STATES,
+BLOCK_STATE_INDEX,
+generate_block_state,
+generate_const_state
+};
+use crate::dist_helpers::fixed;
+use crate::validate::check_has_states;
+use crate::logging::debug;
+
+
+// Switches and options of the buflo binary
+pub const SWITCHES: &[&str] = &["--warn-unreachable", "--explain", "--dry-run", "--annotate", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--report", "--include-small-packets", "--strict", "--ms"];
+pub const OPTIONS: &[&str] = &["--packet-size", "--max-blocking-frac", "--allowed-blocked-microsec", "--time-scale"];
+
+
+// Parameters of the buflo binary
+#[derive(Debug, Clone, PartialEq)]
+pub struct Buflo {
+    // Minimum duration of each round of padding (sec)
+    pub tau: f64,
+    // Send interval (usec)
+    pub interval: f64,
+    // Size of padding packets (bytes)
+    pub packet_size: f64,
+    pub options: MachineOptions,
+}
+
+
+impl Buflo {
+    // Parse the arguments of the buflo binary (see parse_args()).
+    pub fn from_args(cli: &Args) -> Result<Buflo, String> {
+        let args = &cli.positional;
+        if args.len() != 3 {
+            return Err(format!("Usage: {} <tau> <send interval> [--warn-unreachable] [--explain] [--dry-run] [--annotate] [--hash] [--ffi] [--emit-rust] [--base64] [--report] [--include-small-packets] [--strict] [--ms] [--packet-size <bytes>] [--max-blocking-frac <fraction>] [--allowed-blocked-microsec <usec>] [--time-scale <factor>]", args[0]));
+        }
+
+        let tau:      f64 = parse_finite_positive(&args[1], "tau")?; // BuFLO param = tau, minimum duration (sec)
+        let interval: f64 = parse_interval(cli, &args[2]).ok_or("Invalid send interval")?; // BuFLO param = rho, time between packets (usec)
+        if interval <= 0.0 {
+            return Err(String::from("Send interval must be positive"));
+        }
+
+        return Ok(Buflo {
+            tau: tau,
+            interval: interval,
+            packet_size: packet_size(cli)?,
+            options: machine_options(cli)?,
+        });
+    }
+
+
+    // The machine, with the options applied.
+    pub fn machine(&self) -> Result<Machine, String> {
+        let mut machine = generate_machine(self.tau * 1000000.0, self.interval, self.packet_size);
+        self.options.apply(&mut machine)?;
+        check_has_states("Machine", &machine)?;
+        return Ok(machine);
+    }
+
+
+    // The machine, with the name the binary prints.
+    pub fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return Ok(vec![(String::from("Machine"), self.machine()?)]);
+    }
+}
+
+
+// Parse the arguments of the buflo binary (without the program name) into
+// its parameters.
+pub fn parse(args: &[String]) -> Result<Buflo, String> {
+    let cli = try_parse_args([&[String::from("buflo")], args].concat(), SWITCHES, OPTIONS)?;
+    return Buflo::from_args(&cli);
+}
+
+
+// Generate the machines of the buflo binary for its arguments (without the
+// program name), e.g., generate(&["5", "4000"]).
+pub fn generate(args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse(args)?.named_machines();
+}
+
+
+// Generate a BuFLO machine, padding for at least tau (usec) per round.
+pub fn generate_machine(tau: f64, interval: f64, packet_size: f64) -> Machine {
+    let min_packets = (tau / interval).ceil().max(1.0);
+    debug(|| format!("Round of {} packets, every {} usec", min_packets, interval));
+
+    let timeout = fixed(interval);
+
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(NUM_STATES);
+    states.push(generate_start_state());
+    states.push(generate_block_state());
+    states.push(generate_const_state(timeout, Some(min_packets), packet_size));
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+
+    return machine;
+}
+
+
+// Generate the START state for a machine. Unlike the constant defense, START
+// is also entered at the end of each round, so its (zero-length) blocking
+// action replaces the blocking of the BLOCK state.
+pub fn generate_start_state() -> State {
+    // NonPaddingSent/NonPaddingRecv --> BLOCK (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(BLOCK_STATE_INDEX, 1.0);
+
+    let mut nonpadding_recv: HashMap<usize, f64> = HashMap::new();
+    nonpadding_recv.insert(BLOCK_STATE_INDEX, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+    transitions.insert(Event::NonPaddingRecv, nonpadding_recv);
+
+    // START state
+    let mut state = State::new(transitions, NUM_STATES);
+    state.action_is_block = true;
+    state.bypass = true;
+    state.replace = true;
+
+    state.timeout = fixed(0.0);
+
+    state.action = fixed(0.0);
+
+    return state;
+}
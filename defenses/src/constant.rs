@@ -0,0 +1,486 @@
+// Machine generation for the constant-rate defense (see bin/constant.rs): a
+// START state, a BLOCK state that blocks all outgoing traffic once traffic is
+// seen, and a CONST state that sends padding (replaced by real packets) at a
+// fixed rate. Shared by the constant and buflo binaries.
+//
+// Without blocking (--pad-only), the machine is only a START state and a CONST
+// state: padding is added on top of the real traffic, which is sent as it
+// comes. This is weaker protection, as the bursts of the real traffic still
+// show over the constant stream.
+//
+// Constant holds the parameters of the constant binary, parsed from its
+// arguments by Constant::from_args(), and generates its machines.
+
+use std::collections::HashMap;
+
+use maybenot::{
+machine::Machine,
+event::Event,
+state::State,
+dist::{Dist, DistType}
+};
+
+use crate::PACKET_SIZE;
+use crate::args::{try_parse_args, parse_finite, parse_finite_positive, packet_size, parse_interval, machine_options, Args, MachineOptions};
+use crate::defaults::CONSTANT_SEND_INTERVAL;
+use crate::dist_helpers::{fixed, uniform_range, infinite};
+use crate::overhead::solve_interval;
+use crate::validate::check_has_states;
+use crate::logging::debug;
+
+
+pub const NUM_STATES: usize = 3;
+
+pub const START_STATE_INDEX: usize = 0;
+pub const BLOCK_STATE_INDEX: usize = 1;
+pub const CONST_STATE_INDEX: usize = 2;
+
+// States of the machine without blocking, START followed by CONST
+pub const PAD_ONLY_NUM_STATES: usize = 2;
+pub const PAD_ONLY_CONST_STATE_INDEX: usize = 1;
+
+// Send interval of minimal() (usec)
+pub const MINIMAL_SEND_INTERVAL: f64 = 1.0;
+
+// Shape of the Pareto distribution of --dist pareto, without --shape
+pub const PARETO_SHAPE: f64 = 2.0;
+
+// Switches and options of the constant binary
+pub const SWITCHES: &[&str] = &["--warn-unreachable", "--explain", "--dry-run", "--annotate", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--report", "--include-small-packets", "--strict", "--ms", "--maybenot-v2", "--stdin", "--pad-only", "--asymmetric", "--both-sides", "--minimal"];
+pub const OPTIONS: &[&str] = &["--packet-size", "--max-blocking-frac", "--allowed-blocked-microsec", "--time-scale", "--dist", "--shape", "--jitter-frac", "--trigger", "--target-overhead", "--baseline-mbps", "--up-interval", "--down-interval"];
+
+
+// Real traffic that starts the defense (START --> BLOCK)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    Sent,
+    Recv,
+    Both,
+}
+
+
+// Trigger for the --trigger option of the generators: sent, recv, or both.
+pub fn parse_trigger(name: &str) -> Result<Trigger, String> {
+    return match name {
+        "sent" => Ok(Trigger::Sent),
+        "recv" => Ok(Trigger::Recv),
+        "both" => Ok(Trigger::Both),
+        _ => Err(format!("Unknown trigger {}", name)),
+    };
+}
+
+
+// Name of a trigger, as parse_trigger() takes it (e.g., for --annotate).
+pub fn trigger_name(trigger: Trigger) -> &'static str {
+    return match trigger {
+        Trigger::Sent => "sent",
+        Trigger::Recv => "recv",
+        Trigger::Both => "both",
+    };
+}
+
+
+// Generate a constant-rate machine, sending padding with the given timeout once
+// real traffic in the trigger direction(s) is seen.
+pub fn generate_machine(timeout: Dist, trigger: Trigger, packet_size: f64) -> Machine {
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(NUM_STATES);
+    states.push(generate_start_state(trigger));
+    states.push(generate_block_state());
+    states.push(generate_const_state(timeout, None, packet_size));
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+    
+    return machine;
+}
+
+
+// Timeout of the CONST state for a send interval (usec) jittered by a fraction
+// of it, for --jitter-frac: Uniform over [interval * (1 - jitter_frac),
+// interval * (1 + jitter_frac)]. Its mean is still the interval, so the padding
+// rate is kept while the gaps between padding packets vary, and a jitter of 0
+// is the fixed interval. The fraction must be between 0 and 1.
+pub fn jittered_timeout(interval: f64, jitter_frac: f64) -> Result<Dist, String> {
+    if !(0.0..=1.0).contains(&jitter_frac) {
+        return Err(format!("jitter fraction must be between 0 and 1, got {}", jitter_frac));
+    }
+    
+    return Ok(uniform_range(interval * (1.0 - jitter_frac), interval * (1.0 + jitter_frac)));
+}
+
+
+// The smallest valid constant-rate machine, e.g., for tests that need a
+// machine to parse or lint without computing a send interval: the NUM_STATES
+// states of generate_machine(), padding every MINIMAL_SEND_INTERVAL usec with
+// PACKET_SIZE-byte packets once real traffic in either direction is seen.
+pub fn minimal() -> Machine {
+    return generate_machine(fixed(MINIMAL_SEND_INTERVAL), Trigger::Both, PACKET_SIZE);
+}
+
+
+// Generate a constant-rate machine without blocking, which adds padding with
+// the given timeout on top of the real traffic once it is seen.
+pub fn generate_pad_only_machine(timeout: Dist, trigger: Trigger, packet_size: f64) -> Machine {
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(PAD_ONLY_NUM_STATES);
+    states.push(start_state(trigger, PAD_ONLY_CONST_STATE_INDEX, PAD_ONLY_NUM_STATES));
+    states.push(const_state(timeout, None, packet_size, PAD_ONLY_CONST_STATE_INDEX, PAD_ONLY_NUM_STATES));
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+    
+    return machine;
+}
+
+
+// Generate the START state for a machine, leaving it on the trigger event(s).
+pub fn generate_start_state(trigger: Trigger) -> State {
+    return start_state(trigger, BLOCK_STATE_INDEX, NUM_STATES);
+}
+
+
+// START state leaving for next_index, of a machine with num_states states.
+fn start_state(trigger: Trigger, next_index: usize, num_states: usize) -> State {
+    // NonPaddingSent/NonPaddingRecv --> BLOCK, or CONST without blocking (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(next_index, 1.0);
+    
+    let mut nonpadding_recv: HashMap<usize, f64> = HashMap::new();
+    nonpadding_recv.insert(next_index, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    if trigger != Trigger::Recv {
+        transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+    }
+    if trigger != Trigger::Sent {
+        transitions.insert(Event::NonPaddingRecv, nonpadding_recv);
+    }
+    
+    return State::new(transitions, num_states);
+}
+
+
+// Generate the BLOCK state for a machine.
+pub fn generate_block_state() -> State {
+    // BlockingBegin --> CONST (100%)
+    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
+    blocking_begin.insert(CONST_STATE_INDEX, 1.0);
+    
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::BlockingBegin, blocking_begin);
+    
+    // BLOCK state
+    let mut state = State::new(transitions, NUM_STATES);
+    state.action_is_block = true;
+    state.bypass = true;
+    state.replace = true;
+    
+    state.timeout = fixed(0.0);
+    
+    state.action = infinite();
+    
+    return state;
+}
+
+
+// Generate the CONST state for a machine. With a limit, the state sends at
+// most that many packets before returning to START.
+pub fn generate_const_state(timeout: Dist, limit: Option<f64>, packet_size: f64) -> State {
+    return const_state(timeout, limit, packet_size, CONST_STATE_INDEX, NUM_STATES);
+}
+
+
+// CONST state at curr_index, of a machine with num_states states.
+fn const_state(timeout: Dist, limit: Option<f64>, packet_size: f64, curr_index: usize, num_states: usize) -> State {
+    // PaddingSent --> CONST (100%)
+    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
+    padding_sent.insert(curr_index, 1.0);
+
+    // LimitReached --> START (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(START_STATE_INDEX, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingSent, padding_sent);
+    if limit.is_some() {
+        transitions.insert(Event::LimitReached, limit_reached);
+    }
+    
+    // CONST state
+    let mut state = State::new(transitions, num_states);
+    state.bypass = true;
+    state.replace = true;
+
+    state.timeout = timeout;
+
+    state.action = fixed(packet_size);
+    
+    if let Some(limit) = limit {
+        state.limit = fixed(limit);
+    }
+    
+    return state;
+}
+
+
+// Parameters of the constant binary (see bin/constant.rs)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constant {
+    // Send interval (usec), solved for with --target-overhead
+    pub interval: f64,
+    // Distribution of the send interval (--dist: uniform, exponential, or
+    // pareto), the shape of the Pareto distribution, and the jitter fraction
+    pub dist: String,
+    pub shape: f64,
+    pub jitter: f64,
+    pub trigger: Trigger,
+    pub pad_only: bool,
+    // A client machine and a relay machine, at the up and down intervals
+    // (usec), instead of a single machine at the send interval
+    pub asymmetric: bool,
+    pub up_interval: f64,
+    pub down_interval: f64,
+    // minimal() instead, with the options of every generator
+    pub minimal: bool,
+    // Size of padding packets (bytes)
+    pub packet_size: f64,
+    pub options: MachineOptions,
+}
+
+
+impl Default for Constant {
+    // The paper's configuration, starting on real traffic in both directions
+    // (the default of --trigger).
+    fn default() -> Constant {
+        return Constant {
+            interval: CONSTANT_SEND_INTERVAL,
+            dist: String::from("uniform"),
+            shape: PARETO_SHAPE,
+            jitter: 0.0,
+            trigger: Trigger::Both,
+            pad_only: false,
+            asymmetric: false,
+            up_interval: CONSTANT_SEND_INTERVAL,
+            down_interval: CONSTANT_SEND_INTERVAL,
+            minimal: false,
+            packet_size: PACKET_SIZE,
+            options: MachineOptions::default(),
+        };
+    }
+}
+
+
+impl Constant {
+    // Parse the arguments of the constant binary (see parse_args()). With
+    // --stdin, the send interval is left at the default, as each line gives
+    // its own.
+    pub fn from_args(cli: &Args) -> Result<Constant, String> {
+        let args = &cli.positional;
+        if args.len() != 1 && args.len() != 2 {
+            return Err(format!("Usage: {} [send interval = 4000.0] [--warn-unreachable] [--explain] [--dry-run] [--annotate] [--hash] [--ffi] [--emit-rust] [--base64] [--report] [--include-small-packets] [--strict] [--ms] [--packet-size <bytes>] [--max-blocking-frac <fraction>] [--allowed-blocked-microsec <usec>] [--time-scale <factor>] [--dist <uniform|exponential|pareto>] [--shape <pareto shape>] [--jitter-frac <f>] [--trigger <sent|recv|both>] [--pad-only] [--asymmetric|--both-sides [--up-interval <usec>] [--down-interval <usec>]] [--target-overhead <ratio> --baseline-mbps <m>] [--maybenot-v2] [--stdin] [--minimal]", args[0]));
+        }
+        if (cli.has("--hash") || cli.has("--ffi") || cli.has("--emit-rust") || cli.has("--base64") || cli.has("--pretty")) && cli.has("--maybenot-v2") {
+            return Err(String::from("--hash, --ffi, --emit-rust, --base64, and --pretty cannot be combined with --maybenot-v2"));
+        }
+        if cli.has("--pad-only") && cli.has("--maybenot-v2") {
+            return Err(String::from("--pad-only cannot be combined with --maybenot-v2"));
+        }
+        if cli.has("--include-small-packets") && cli.has("--maybenot-v2") {
+            return Err(String::from("--include-small-packets cannot be combined with --maybenot-v2, whose machines have no such field"));
+        }
+        // --both-sides names the same machines as --asymmetric, a client
+        // machine and a relay machine, for when both sides pad
+        let asymmetric = cli.has("--asymmetric") || cli.has("--both-sides");
+        if !asymmetric && (cli.has("--up-interval") || cli.has("--down-interval")) {
+            return Err(String::from("--up-interval and --down-interval require --asymmetric or --both-sides"));
+        }
+        if asymmetric && (cli.has("--stdin") || cli.has("--target-overhead") || cli.has("--maybenot-v2")) {
+            return Err(String::from("--asymmetric and --both-sides cannot be combined with --stdin, --target-overhead, or --maybenot-v2"));
+        }
+        if cli.has("--time-scale") && cli.has("--target-overhead") {
+            return Err(String::from("--time-scale cannot be combined with --target-overhead, whose send interval is solved for"));
+        }
+        // --minimal fixes the machine, leaving only the options of every
+        // generator
+        let minimal = cli.has("--minimal");
+        if minimal {
+            if args.len() != 1 {
+                return Err(String::from("No send interval can be given with --minimal"));
+            }
+            for flag in ["--target-overhead", "--baseline-mbps", "--stdin", "--maybenot-v2", "--dist", "--shape", "--jitter-frac", "--trigger", "--pad-only", "--asymmetric", "--both-sides", "--packet-size", "--ms"] {
+                if cli.has(flag) {
+                    return Err(format!("--minimal cannot be combined with {}", flag));
+                }
+            }
+        }
+
+        // Distribution of the send interval, with interval as its mean
+        let dist = cli.value("--dist").unwrap_or("uniform");
+        let shape: f64 = cli.value("--shape").map_or(Ok(PARETO_SHAPE), |v| parse_finite_positive(v, "shape"))?;
+        let jitter: f64 = cli.value("--jitter-frac").map_or(Ok(0.0), |v| parse_finite(v, "jitter fraction"))?;
+        jittered_timeout(CONSTANT_SEND_INTERVAL, jitter)?;
+        if jitter != 0.0 && dist != "uniform" {
+            return Err(String::from("--jitter-frac requires --dist uniform"));
+        }
+
+        // Direction(s) of real traffic that start the defense
+        let trigger = parse_trigger(cli.value("--trigger").unwrap_or("both"))?;
+
+        let packet_size = packet_size(cli)?;
+        let options = machine_options(cli)?;
+
+        let interval: f64;
+
+        if cli.has("--target-overhead") || cli.has("--baseline-mbps") {
+            if args.len() != 1 {
+                return Err(String::from("No send interval can be given with --target-overhead, it is solved for"));
+            }
+            let target: f64 = parse_finite(cli.value("--target-overhead").ok_or("--baseline-mbps requires --target-overhead")?, "target overhead")?;
+            let baseline: f64 = parse_finite_positive(cli.value("--baseline-mbps").ok_or("--target-overhead requires --baseline-mbps")?, "baseline bandwidth")?;
+
+            interval = solve_interval(target, baseline * 1_000_000.0, packet_size)?;
+        } else if minimal {
+            interval = MINIMAL_SEND_INTERVAL;
+        } else if args.len() == 1 {
+            interval = CONSTANT_SEND_INTERVAL; // default
+        } else {
+            interval = parse_interval(cli, &args[1]).ok_or("Invalid send interval")?;
+        }
+        if interval <= 0.0 {
+            return Err(String::from("Send interval must be positive"));
+        }
+
+        // With --asymmetric, the client machine pads the upload and the relay
+        // machine the download, each at its own interval
+        let up_interval = cli.value("--up-interval").map_or(Some(interval), |v| parse_interval(cli, v)).ok_or("Invalid up interval")?;
+        let down_interval = cli.value("--down-interval").map_or(Some(interval), |v| parse_interval(cli, v)).ok_or("Invalid down interval")?;
+        if up_interval <= 0.0 || down_interval <= 0.0 {
+            return Err(String::from("Send intervals must be positive"));
+        }
+
+        let constant = Constant {
+            interval: interval,
+            dist: dist.to_string(),
+            shape: shape,
+            jitter: jitter,
+            trigger: trigger,
+            pad_only: cli.has("--pad-only"),
+            asymmetric: asymmetric,
+            up_interval: up_interval,
+            down_interval: down_interval,
+            minimal: minimal,
+            packet_size: packet_size,
+            options: options,
+        };
+        // Check --dist and --shape up front, also for --stdin
+        constant.timeout()?;
+
+        return Ok(constant);
+    }
+
+
+    // Timeout of the CONST state, for the send interval.
+    pub fn timeout(&self) -> Result<Dist, String> {
+        return interval_dist(&self.dist, self.interval, self.shape, self.jitter);
+    }
+
+
+    // Send intervals (usec) scaled by the time scale: those of the client and
+    // relay machines with --asymmetric, or the send interval.
+    pub fn scaled_intervals(&self) -> Vec<f64> {
+        let intervals = if self.asymmetric { vec![self.up_interval, self.down_interval] } else { vec![self.interval] };
+        return intervals.iter().map(|interval| interval * self.options.time_scale).collect();
+    }
+
+
+    // The machines, with the names the binary prints: "Machine", or with
+    // --asymmetric, "Client machine" and "Relay machine".
+    pub fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        let generate = if self.pad_only { generate_pad_only_machine } else { generate_machine };
+        let timeout = self.timeout()?;
+        debug(|| format!("Send interval {} usec, timeout {}", self.interval, timeout));
+        let mut machines: Vec<(&str, Machine)>;
+
+        if self.asymmetric {
+            machines = vec![
+                ("Client machine", generate(interval_dist(&self.dist, self.up_interval, self.shape, self.jitter)?, self.trigger, self.packet_size)),
+                ("Relay machine", generate(interval_dist(&self.dist, self.down_interval, self.shape, self.jitter)?, self.trigger, self.packet_size)),
+            ];
+        } else if self.minimal {
+            machines = vec![("Machine", minimal())];
+        } else {
+            machines = vec![("Machine", generate(timeout, self.trigger, self.packet_size))];
+        }
+
+        for (name, machine) in machines.iter_mut() {
+            self.options.apply(machine)?;
+            check_has_states(name, machine)?;
+        }
+
+        return Ok(machines.into_iter().map(|(name, machine)| (name.to_string(), machine)).collect());
+    }
+}
+
+
+// Parse the arguments of the constant binary (without the program name) into
+// its parameters.
+pub fn parse(args: &[String]) -> Result<Constant, String> {
+    let cli = try_parse_args([&[String::from("constant")], args].concat(), SWITCHES, OPTIONS)?;
+    return Constant::from_args(&cli);
+}
+
+
+// Generate the machines of the constant binary for its arguments (without the
+// program name), e.g., generate(&["4000"]).
+pub fn generate(args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse(args)?.named_machines();
+}
+
+
+// Distribution of the CONST state timeout for the --dist option, with mean
+// interval: constant (uniform, jittered by --jitter-frac), exponential (a
+// Weibull distribution with shape 1), or Pareto with the given shape (scale
+// interval * (shape - 1) / shape).
+fn interval_dist(name: &str, interval: f64, shape: f64, jitter: f64) -> Result<Dist, String> {
+    return match name {
+        "uniform" => jittered_timeout(interval, jitter),
+        "exponential" => Ok(Dist {
+            dist: DistType::Weibull,
+            param1: interval,
+            param2: 1.0,
+            start: 0.0,
+            max: 0.0,
+        }),
+        "pareto" => {
+            if shape <= 1.0 {
+                return Err(String::from("Pareto shape must be greater than 1 for the mean to exist"));
+            }
+            Ok(Dist {
+                dist: DistType::Pareto,
+                param1: interval * (shape - 1.0) / shape,
+                param2: shape,
+                start: 0.0,
+                max: 0.0,
+            })
+        },
+        _ => Err(format!("Unknown distribution {}", name)),
+    };
+}
@@ -0,0 +1,204 @@
+// Machine generation for CS-BuFLO (see bin/cs_buflo.rs): the relay START and
+// BLOCK states (see regulator.rs), followed by a SEND state per rate level.
+// CsBuflo holds the parameters of the cs_buflo binary, parsed from its
+// arguments by CsBuflo::from_args().
+
+use std::collections::HashMap;
+
+use maybenot::{
+machine::Machine,
+event::Event,
+state::State
+};
+
+use crate::args::{try_parse_args, parse_finite_positive, packet_size, strict_count, machine_options, Args, MachineOptions};
+use crate::validate::{check_has_states, validate_send_chain};
+use crate::constant::Trigger;
+use crate::regulator::{generate_relay_start_state, generate_relay_block_state, FIRST_SEND_STATE_INDEX};
+use crate::rate::{rate_levels, count_rate_levels, send_interval_usec};
+use crate::dist_helpers::fixed;
+use crate::logging::debug;
+
+
+// Default padding packets in a row before stepping down a level (packets)
+pub const WINDOW: f64 = 20.0;
+
+// Cap on the number of states, which grows as the factor gets closer to 1
+pub const MAX_STATES: usize = crate::MAX_STATES;
+
+// Switches and options of the cs_buflo binary
+pub const SWITCHES: &[&str] = &["--warn-unreachable", "--explain", "--dry-run", "--annotate", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--report", "--include-small-packets", "--strict"];
+pub const OPTIONS: &[&str] = &["--packet-size", "--max-blocking-frac", "--allowed-blocked-microsec", "--time-scale", "--window"];
+
+
+// Parameters of the cs_buflo binary
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsBuflo {
+    // Slowest and fastest send rates (packets/sec)
+    pub min_rate: f64,
+    pub max_rate: f64,
+    // Ratio between consecutive rate levels
+    pub factor: f64,
+    // Padding packets in a row before stepping down a level
+    pub window: f64,
+    // Size of padding packets (bytes)
+    pub packet_size: f64,
+    pub options: MachineOptions,
+}
+
+
+impl CsBuflo {
+    // Parse the arguments of the cs_buflo binary (see parse_args()).
+    pub fn from_args(cli: &Args) -> Result<CsBuflo, String> {
+        let args = &cli.positional;
+        if args.len() != 4 {
+            return Err(format!("Usage: {} <min rate> <max rate> <factor> [--warn-unreachable] [--explain] [--dry-run] [--annotate] [--hash] [--ffi] [--emit-rust] [--base64] [--report] [--include-small-packets] [--strict] [--packet-size <bytes>] [--max-blocking-frac <fraction>] [--allowed-blocked-microsec <usec>] [--time-scale <factor>] [--window <packets>]", args[0]));
+        }
+
+        let min_rate: f64 = parse_finite_positive(&args[1], "min rate")?; // packets/sec
+        let max_rate: f64 = parse_finite_positive(&args[2], "max rate")?; // packets/sec
+        let factor:   f64 = parse_finite_positive(&args[3], "factor")?; // between levels
+        let window:   f64 = match cli.value("--window") {
+            Some(v) => parse_finite_positive(v, "window").and_then(|count| strict_count(cli, count, "window"))?,
+            None => WINDOW,
+        };
+
+        if max_rate < min_rate {
+            return Err(String::from("Max rate must be at least the min rate"));
+        }
+        if factor <= 1.0 {
+            return Err(String::from("Factor must be above 1"));
+        }
+        if window < 1.0 {
+            return Err(String::from("Window must be at least 1 packet"));
+        }
+
+        let num_states = count_rate_levels(max_rate, min_rate, factor).saturating_add(FIRST_SEND_STATE_INDEX);
+        if num_states > MAX_STATES {
+            return Err(format!("CS-BuFLO would take {} states, more than the cap of {} (give a larger factor or closer rate bounds)", num_states, MAX_STATES));
+        }
+
+        return Ok(CsBuflo {
+            min_rate: min_rate,
+            max_rate: max_rate,
+            factor: factor,
+            window: window,
+            packet_size: packet_size(cli)?,
+            options: machine_options(cli)?,
+        });
+    }
+
+
+    // The machine, with the options applied.
+    pub fn machine(&self) -> Result<Machine, String> {
+        let rates = rate_levels(self.max_rate, self.min_rate, self.factor);
+        debug(|| format!("Rate levels {:?} packets/sec, window of {} packets", rates, self.window));
+        let mut machine = generate_machine(&rates, self.window, self.packet_size);
+
+        // Each level steps down to the next on LimitReached
+        let issues = validate_send_chain(&machine, FIRST_SEND_STATE_INDEX, false);
+        if !issues.is_empty() {
+            return Err(format!("Broken chain of SEND states: {}", issues.iter().map(|issue| issue.to_string()).collect::<Vec<String>>().join("; ")));
+        }
+
+        self.options.apply(&mut machine)?;
+        check_has_states("Machine", &machine)?;
+        return Ok(machine);
+    }
+
+
+    // The machine, with the name the binary prints.
+    pub fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return Ok(vec![(String::from("Machine"), self.machine()?)]);
+    }
+}
+
+
+// Parse the arguments of the cs_buflo binary (without the program name) into
+// its parameters.
+pub fn parse(args: &[String]) -> Result<CsBuflo, String> {
+    let cli = try_parse_args([&[String::from("cs_buflo")], args].concat(), SWITCHES, OPTIONS)?;
+    return CsBuflo::from_args(&cli);
+}
+
+
+// Generate the machines of the cs_buflo binary for its arguments (without the
+// program name), e.g., generate(&["10", "1000", "2"]).
+pub fn generate(args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse(args)?.named_machines();
+}
+
+
+// Generate a CS-BuFLO machine, with a SEND state per rate (packets / sec), from
+// the fastest.
+pub fn generate_machine(rates: &[f64], window: f64, packet_size: f64) -> Machine {
+    let num_states = FIRST_SEND_STATE_INDEX + rates.len();
+
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(num_states);
+    states.push(generate_relay_start_state(num_states, Trigger::Sent));
+    states.push(generate_relay_block_state(num_states));
+
+    for (i, &rate) in rates.iter().enumerate() {
+        let curr_index = FIRST_SEND_STATE_INDEX + i;
+
+        // The fastest level has none faster, and the slowest none slower
+        let faster_index = if i == 0 { None } else { Some(curr_index - 1) };
+        let slower_index = if i == rates.len() - 1 { None } else { Some(curr_index + 1) };
+        states.push(generate_send_state(curr_index, faster_index, slower_index, num_states, send_interval_usec(rate), window, packet_size));
+    }
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+
+    return machine;
+}
+
+
+// Generate a SEND state for a machine, sending every timeout (usec).
+pub fn generate_send_state(curr_index: usize, faster_index: Option<usize>, slower_index: Option<usize>, num_states: usize, timeout: f64, window: f64, packet_size: f64) -> State {
+    // PaddingSent --> SEND_i (100%)
+    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
+    padding_sent.insert(curr_index, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingSent, padding_sent);
+
+    // LimitReached --> SEND_[i+1] (100%)
+    if let Some(slower_index) = slower_index {
+        let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+        limit_reached.insert(slower_index, 1.0);
+        transitions.insert(Event::LimitReached, limit_reached);
+    }
+
+    // NonPaddingSent --> SEND_[i-1] (100%)
+    if let Some(faster_index) = faster_index {
+        let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+        nonpadding_sent.insert(faster_index, 1.0);
+        transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+    }
+
+    // SEND_i state
+    let mut state = State::new(transitions, num_states);
+    state.bypass = true;
+    state.replace = true;
+
+    state.timeout = fixed(timeout);
+
+    state.action = fixed(packet_size);
+
+    // The slowest level has no limit (see above)
+    if slower_index.is_some() {
+        state.limit = fixed(window);
+    }
+
+    return state;
+}
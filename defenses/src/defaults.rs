@@ -0,0 +1,37 @@
+// Default parameters of the defenses, as evaluated in the paper (the first
+// configuration of each defense in tests/test_defenses.sh). The generators fall
+// back to these when their positional arguments are omitted, so e.g.
+// `adapted_front` alone prints the paper's Adapted FRONT machine.
+//
+// The baselines (BuFLO, Adaptive Padding, ...) were not evaluated with a single
+// configuration, and have no defaults.
+
+use crate::constant::Trigger;
+use crate::regulator;
+
+
+// Constant-rate padding: 3 Mbps (250 packets/sec)
+pub const CONSTANT_SEND_INTERVAL: f64 = 4000.0; // usec
+
+// Adapted FRONT
+pub const ADAPTED_FRONT_PADDING_WINDOW: f64 = 12.0; // sec
+pub const ADAPTED_FRONT_PADDING_BUDGET: u32 = 4000; // packets
+pub const ADAPTED_FRONT_NUM_STATES: u32 = 30;
+
+// Adapted RegulaTor
+pub const ADAPTED_REGULATOR_INITIAL_RATE: f64 = 500.0; // packets/sec
+pub const ADAPTED_REGULATOR_DECAY_RATE: f64 = 0.75;
+pub const ADAPTED_REGULATOR_UPLOAD_RATIO: f64 = 4.0;
+pub const ADAPTED_REGULATOR_PACKETS_PER_STATE: f64 = 20.0; // packets
+pub const ADAPTED_REGULATOR_CLIENT_LIMIT: f64 = regulator::CLIENT_LIMIT; // packets
+
+// Default of --trigger for the RegulaTor binaries: the relay machines start on
+// real traffic either way (the paper's start on sent traffic only, --trigger
+// sent)
+pub const ADAPTED_REGULATOR_TRIGGER: Trigger = Trigger::Both;
+
+// Scrambler
+pub const SCRAMBLER_SEND_INTERVAL: f64 = 160.0; // usec
+pub const SCRAMBLER_MIN_COUNT: f64 = 500.0; // packets
+pub const SCRAMBLER_MIN_TRAIL: f64 = 400.0; // packets
+pub const SCRAMBLER_MAX_TRAIL: f64 = 1000.0; // packets
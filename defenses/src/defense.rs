@@ -0,0 +1,283 @@
+// The Defense trait, for drivers that treat defenses uniformly (e.g., a
+// benchmark over several configurations, or the registry of generate.rs): the
+// name of a defense, its machines, and the overhead report of its --report
+// option (see report.rs). The parameter structs of the defenses implement it,
+// with the parameters of their binaries and the paper's defaults where the
+// paper has them (see defaults.rs). The machines are those the binary of the
+// defense prints with the same parameters, and the binaries print their
+// --report with overhead_of().
+
+use maybenot::machine::Machine;
+
+use crate::dist_helpers::scale_timeouts;
+use crate::interspace::{generate_machine as generate_interspace_machine, mean_size, SizeBucket};
+use crate::overhead::bandwidth_bps;
+use crate::regulator::overhead_report;
+use crate::report::{OverheadReport, peak_padding_bps, longest_padding_timeout};
+
+pub use crate::adapted_front::AdaptedFront;
+pub use crate::adaptive_pad::AdaptivePad;
+pub use crate::buflo::Buflo;
+pub use crate::constant::Constant;
+pub use crate::cs_buflo::CsBuflo;
+pub use crate::dynaflow::DynaFlow;
+pub use crate::interspace::Interspace;
+pub use crate::regulator::{AdaptedRegulator, Regulator};
+pub use crate::scrambler::Scrambler;
+pub use crate::surakav::Surakav;
+pub use crate::walkie_talkie::WalkieTalkie;
+
+
+pub trait Defense {
+    // Name of the defense, that of its binary and of schema::DEFENSES
+    fn name(&self) -> &str;
+
+    // Machines of the defense with the names its binary prints, in its order,
+    // or the error the binary exits with
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String>;
+
+    // Overhead report of the machines of named_machines(), as printed by
+    // --report
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String>;
+
+    // Machines of the defense, in the order its binary prints them. Panics on
+    // parameters the binary rejects, see named_machines() otherwise.
+    fn machines(&self) -> Vec<Machine> {
+        return self.named_machines().unwrap_or_else(|e| panic!("{}", e)).into_iter().map(|(_, machine)| machine).collect();
+    }
+
+    // Overhead report of the machines, as printed by --report. Panics like
+    // machines().
+    fn overhead(&self) -> OverheadReport {
+        let machines = self.machines();
+        return self.overhead_of(&machines.iter().collect::<Vec<&Machine>>()).unwrap_or_else(|e| panic!("{}", e));
+    }
+}
+
+
+// No blocking, each machine pads its own direction
+fn unblocked_overhead(machines: &[&Machine]) -> OverheadReport {
+    let padding_bps = machines.iter().map(|machine| peak_padding_bps(machine)).sum();
+    return OverheadReport::new(machines, padding_bps, 0.0);
+}
+
+
+impl Defense for Constant {
+    fn name(&self) -> &str {
+        return "constant";
+    }
+
+
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return Constant::named_machines(self);
+    }
+
+
+    // A real packet waits for the next padding packet of its direction, at
+    // most the longest jittered interval
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String> {
+        let intervals = self.scaled_intervals();
+        let padding_bps = intervals.iter().map(|&interval| bandwidth_bps(interval, self.packet_size)).sum();
+        let delay = if self.pad_only { 0.0 } else { intervals.iter().cloned().fold(0.0, f64::max) * (1.0 + self.jitter) };
+        return Ok(OverheadReport::new(machines, padding_bps, delay));
+    }
+}
+
+
+impl Defense for Buflo {
+    fn name(&self) -> &str {
+        return "buflo";
+    }
+
+
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return Buflo::named_machines(self);
+    }
+
+
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String> {
+        let interval = self.interval * self.options.time_scale;
+        return Ok(OverheadReport::new(machines, bandwidth_bps(interval, self.packet_size), interval));
+    }
+}
+
+
+impl Defense for AdaptedFront {
+    fn name(&self) -> &str {
+        return "adapted_front";
+    }
+
+
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return AdaptedFront::named_machines(self);
+    }
+
+
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String> {
+        return Ok(unblocked_overhead(machines));
+    }
+}
+
+
+impl Defense for AdaptedRegulator {
+    fn name(&self) -> &str {
+        return "adapted_regulator";
+    }
+
+
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return AdaptedRegulator::named_machines(self);
+    }
+
+
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String> {
+        return Ok(overhead_report(machines[0], machines[1], self.upload_ratio));
+    }
+}
+
+
+impl Defense for Regulator {
+    fn name(&self) -> &str {
+        return "regulator";
+    }
+
+
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return Regulator::named_machines(self);
+    }
+
+
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String> {
+        return Ok(overhead_report(machines[0], machines[1], self.upload_ratio));
+    }
+}
+
+
+impl Defense for Scrambler {
+    fn name(&self) -> &str {
+        return "scrambler";
+    }
+
+
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return Scrambler::named_machines(self);
+    }
+
+
+    // Real packets filling a segment are held back for up to its packets
+    // times the slower of the intervals, unless Machine #1 runs without
+    // blocking
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String> {
+        let (interval, min_interval) = (self.interval * self.options.time_scale, self.min_interval() * self.options.time_scale);
+        return Ok(OverheadReport::new(machines, bandwidth_bps(interval.min(min_interval), self.packet_size), self.max_delay()));
+    }
+}
+
+
+impl Defense for AdaptivePad {
+    fn name(&self) -> &str {
+        return "adaptive_pad";
+    }
+
+
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return AdaptivePad::named_machines(self);
+    }
+
+
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String> {
+        return Ok(unblocked_overhead(machines));
+    }
+}
+
+
+impl Defense for WalkieTalkie {
+    fn name(&self) -> &str {
+        return "walkie_talkie";
+    }
+
+
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return WalkieTalkie::named_machines(self);
+    }
+
+
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String> {
+        return Ok(unblocked_overhead(machines));
+    }
+}
+
+
+impl Defense for Surakav {
+    fn name(&self) -> &str {
+        return "surakav";
+    }
+
+
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return Surakav::named_machines(self);
+    }
+
+
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String> {
+        return Ok(unblocked_overhead(machines));
+    }
+}
+
+
+impl Defense for DynaFlow {
+    fn name(&self) -> &str {
+        return "dynaflow";
+    }
+
+
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return DynaFlow::named_machines(self);
+    }
+
+
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String> {
+        return Ok(unblocked_overhead(machines));
+    }
+}
+
+
+impl Defense for Interspace {
+    fn name(&self) -> &str {
+        return "interspace";
+    }
+
+
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return Interspace::named_machines(self);
+    }
+
+
+    // The bandwidth of a single PAD state of the mean size, as every PAD
+    // state has the same timeouts
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String> {
+        let size = mean_size(&self.buckets);
+        let mut mean_machine = generate_interspace_machine(self.dist, self.params, &[SizeBucket { min: size, max: size, weight: 1.0 }]);
+        scale_timeouts(&mut mean_machine, self.options.time_scale)?;
+        return Ok(OverheadReport::new(machines, peak_padding_bps(&mean_machine), 0.0));
+    }
+}
+
+
+impl Defense for CsBuflo {
+    fn name(&self) -> &str {
+        return "cs_buflo";
+    }
+
+
+    fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return CsBuflo::named_machines(self);
+    }
+
+
+    // A real packet waits for the next packet of the current level, at most
+    // that of the slowest
+    fn overhead_of(&self, machines: &[&Machine]) -> Result<OverheadReport, String> {
+        return Ok(OverheadReport::new(machines, peak_padding_bps(machines[0]), longest_padding_timeout(machines[0])));
+    }
+}
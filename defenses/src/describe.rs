@@ -0,0 +1,111 @@
+// Human-readable listings of maybenot machines, for reading machines without
+// the context of the generator that made them: every field that serialize()
+// writes, the machine's first and then each state's. The fields are also
+// available as (name, value) pairs, which diff.rs compares.
+
+use maybenot::{
+machine::Machine,
+state::State,
+event::Event
+};
+
+
+// List the fields of a machine, then of each of its states, indented under
+// the given name (e.g., "Machine").
+pub fn describe_machine(name: &str, machine: &Machine) -> String {
+    let mut lines: Vec<String> = Vec::new();
+
+    lines.push(format!("{}: {} states", name, machine.states.len()));
+    lines.extend(machine_fields(machine).into_iter().map(|(field, value)| format!("  {}: {}", field, value)));
+
+    for (index, state) in machine.states.iter().enumerate() {
+        lines.push(format!("  State {}:", index));
+        lines.extend(describe_state(state, machine.states.len()).into_iter().map(|line| format!("    {}", line)));
+    }
+
+    return lines.join("\n") + "\n";
+}
+
+
+// List the flags, distributions, and transitions of a state. The action
+// distribution samples the block duration (usec) of a blocking state, and the
+// padding packet size (bytes) otherwise.
+pub fn describe_state(state: &State, num_states: usize) -> Vec<String> {
+    let mut lines: Vec<String> = state_fields(state).into_iter().map(|(field, value)| format!("{}: {}", field, value)).collect();
+
+    let transitions = state_transitions(state, num_states);
+    if transitions.is_empty() {
+        lines.push(String::from("transitions: none"));
+    } else {
+        lines.push(String::from("transitions:"));
+        lines.extend(transitions.into_iter().map(|(event, targets)| format!("  {} -> {}", event, targets)));
+    }
+
+    return lines;
+}
+
+
+// The fields of a machine other than its states, as (name, value).
+pub fn machine_fields(machine: &Machine) -> Vec<(&'static str, String)> {
+    return vec![
+        ("allowed_padding_bytes", machine.allowed_padding_bytes.to_string()),
+        ("max_padding_frac", machine.max_padding_frac.to_string()),
+        ("allowed_blocked_microsec", machine.allowed_blocked_microsec.to_string()),
+        ("max_blocking_frac", machine.max_blocking_frac.to_string()),
+        ("include_small_packets", machine.include_small_packets.to_string()),
+    ];
+}
+
+
+// The flags and distributions of a state, as (name, value).
+pub fn state_fields(state: &State) -> Vec<(&'static str, String)> {
+    let action = if state.action_is_block { "action (block duration)" } else { "action (padding size)" };
+
+    return vec![
+        ("action_is_block", state.action_is_block.to_string()),
+        ("bypass", state.bypass.to_string()),
+        ("replace", state.replace.to_string()),
+        ("limit_includes_nonpadding", state.limit_includes_nonpadding.to_string()),
+        ("timeout", state.timeout.to_string()),
+        (action, state.action.to_string()),
+        ("limit", state.limit.to_string()),
+    ];
+}
+
+
+// The transitions of a state on each event, in maybenot's event order, as
+// (event, "<target> (<probability>), ..."). Only targets with a nonzero
+// probability are listed, and events without any are left out.
+pub fn state_transitions(state: &State, num_states: usize) -> Vec<(Event, String)> {
+    let mut transitions: Vec<(Event, String)> = Vec::new();
+
+    for event in Event::iterator() {
+        let probs = match state.next_state.get(event) {
+            Some(probs) => probs,
+            None => continue,
+        };
+
+        let targets: Vec<String> = probs.iter().enumerate()
+            .filter(|(_, &prob)| prob != 0.0)
+            .map(|(position, prob)| format!("{} ({})", target_name(position, num_states), prob))
+            .collect();
+
+        if !targets.is_empty() {
+            transitions.push((*event, targets.join(", ")));
+        }
+    }
+
+    return transitions;
+}
+
+
+// Name of a position in a next_state vector: real states are listed by index,
+// followed by STATECANCEL and STATEEND (see validate.rs).
+fn target_name(position: usize, num_states: usize) -> String {
+    return match position.checked_sub(num_states) {
+        None => position.to_string(),
+        Some(0) => String::from("CANCEL"),
+        Some(1) => String::from("END"),
+        Some(_) => format!("{} (out of range)", position),
+    };
+}
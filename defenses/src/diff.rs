@@ -0,0 +1,84 @@
+// Structured differences between maybenot machines, for seeing what a change
+// to a generator changed in its output: the fields of the machines and of the
+// states at each index, as listed by describe.rs, compared one by one.
+
+use maybenot::{
+machine::Machine,
+state::State,
+event::Event
+};
+
+use crate::describe::{describe_state, machine_fields, state_fields, state_transitions};
+
+
+// List the differences from the old machine to the new one, indented under the
+// given name (e.g., "Machine"), or nothing if they are the same. States are
+// compared by index: a changed field is listed as "<field>: <old> -> <new>",
+// with the transitions of each event as their targets (or "none"), and states
+// of only one machine are listed in full as added or removed.
+pub fn diff_machines(name: &str, old: &Machine, new: &Machine) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    if old.states.len() != new.states.len() {
+        lines.push(format!("  states: {} -> {}", old.states.len(), new.states.len()));
+    }
+    lines.extend(diff_fields(machine_fields(old), machine_fields(new)).into_iter().map(|line| format!("  {}", line)));
+
+    for index in 0..old.states.len().max(new.states.len()) {
+        let (header, state_lines) = match (old.states.get(index), new.states.get(index)) {
+            (Some(old_state), Some(new_state)) => (format!("State {}:", index), diff_states(old_state, old.states.len(), new_state, new.states.len())),
+            (Some(old_state), None) => (format!("State {}: removed", index), describe_state(old_state, old.states.len())),
+            (None, Some(new_state)) => (format!("State {}: added", index), describe_state(new_state, new.states.len())),
+            (None, None) => continue,
+        };
+
+        if !state_lines.is_empty() {
+            lines.push(format!("  {}", header));
+            lines.extend(state_lines.into_iter().map(|line| format!("    {}", line)));
+        }
+    }
+
+    if !lines.is_empty() {
+        lines.insert(0, format!("{}:", name));
+    }
+
+    return lines;
+}
+
+
+// Changed fields and transitions of a state.
+fn diff_states(old: &State, old_num_states: usize, new: &State, new_num_states: usize) -> Vec<String> {
+    let mut lines = diff_fields(state_fields(old), state_fields(new));
+
+    let old_transitions = state_transitions(old, old_num_states);
+    let new_transitions = state_transitions(new, new_num_states);
+    for event in Event::iterator() {
+        let old_targets = targets_on(&old_transitions, *event);
+        let new_targets = targets_on(&new_transitions, *event);
+        if old_targets != new_targets {
+            lines.push(format!("transitions on {}: {} -> {}", event, old_targets, new_targets));
+        }
+    }
+
+    return lines;
+}
+
+
+// "<field>: <old> -> <new>" for each field that changed, in order. An action
+// that changed between padding and blocking is compared by position, under
+// its new name.
+fn diff_fields(old: Vec<(&str, String)>, new: Vec<(&str, String)>) -> Vec<String> {
+    return old.into_iter().zip(new)
+        .filter(|((_, old_value), (_, new_value))| old_value != new_value)
+        .map(|((_, old_value), (field, new_value))| format!("{}: {} -> {}", field, old_value, new_value))
+        .collect();
+}
+
+
+fn targets_on(transitions: &[(Event, String)], event: Event) -> String {
+    return match transitions.iter().find(|(other, _)| *other == event) {
+        Some((_, targets)) => targets.clone(),
+        None => String::from("none"),
+    };
+}
+
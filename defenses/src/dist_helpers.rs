@@ -0,0 +1,75 @@
+// Constructors for the Dist values that machine states use most: fixed values
+// (e.g., a timeout, a padding size, or a limit), uniform ranges, and infinite
+// actions (e.g., the block duration of BLOCK states). Also scales the timeouts
+// of built machines, for the --time-scale option of the generators.
+
+use std::f64::INFINITY;
+
+use maybenot::{
+machine::Machine,
+dist::{Dist, DistType}
+};
+
+
+// Always value: Uniform over [value, value].
+pub fn fixed(value: f64) -> Dist {
+    return uniform_range(value, value);
+}
+
+
+// Uniform over [low, high].
+pub fn uniform_range(low: f64, high: f64) -> Dist {
+    return Dist {
+        dist: DistType::Uniform,
+        param1: low,
+        param2: high,
+        start: 0.0,
+        max: 0.0,
+    };
+}
+
+
+// Always infinite, e.g., blocking until the machine leaves the state.
+pub fn infinite() -> Dist {
+    return fixed(INFINITY);
+}
+
+
+// Scale a timeout distribution, so that its samples are multiplied by factor:
+// start and max are multiplied, as are the location and scale parameters
+// (both parameters of Uniform and Normal, the scale of Pareto, Weibull, and
+// Gamma), and ln(factor) is added to mu of LogNormal. The distributions of
+// counts (Binomial, Geometric, Poisson) and Beta have no scale parameter, so
+// they can only be scaled by 1.0.
+pub fn scale_dist(dist: Dist, factor: f64) -> Result<Dist, String> {
+    if factor == 1.0 || dist.dist == DistType::None {
+        return Ok(dist);
+    }
+
+    let (param1, param2) = match dist.dist {
+        DistType::Uniform | DistType::Normal => (dist.param1 * factor, dist.param2 * factor),
+        DistType::Pareto | DistType::Weibull | DistType::Gamma => (dist.param1 * factor, dist.param2),
+        DistType::LogNormal => (dist.param1 + factor.ln(), dist.param2),
+        _ => return Err(format!("{} timeouts cannot be scaled", dist.dist)),
+    };
+
+    return Ok(Dist {
+        dist: dist.dist,
+        param1: param1,
+        param2: param2,
+        start: dist.start * factor,
+        max: dist.max * factor,
+    });
+}
+
+
+// Scale the timeouts of every state of a machine by factor (see
+// scale_dist()), leaving the limits and actions (padding sizes and block
+// durations) as they are.
+pub fn scale_timeouts(machine: &mut Machine, factor: f64) -> Result<(), String> {
+    for (index, state) in machine.states.iter_mut().enumerate() {
+        state.timeout = scale_dist(state.timeout, factor).map_err(|e| format!("state {}: {}", index, e))?;
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,210 @@
+// Machine generation for DynaFlow (see bin/dynaflow.rs): a START state and
+// the SEND states of each interval, stepped through while idle. DynaFlow holds
+// the parameters of the dynaflow binary, parsed from its arguments by
+// DynaFlow::from_args().
+
+use std::collections::HashMap;
+
+use maybenot::{
+machine::Machine,
+event::Event,
+state::State
+};
+
+use crate::args::{try_parse_args, parse_finite_positive, parse_count, packet_size, parse_interval, strict_count, machine_options, Args, MachineOptions};
+use crate::validate::check_has_states;
+use crate::dist_helpers::fixed;
+use crate::logging::debug;
+
+
+// START is state 0, followed by the SEND states of each interval
+pub const FIRST_SEND_STATE_INDEX: usize = 1;
+
+// Default padding packets per block (packets)
+pub const BLOCK_SIZE: f64 = 20.0;
+
+// Cap on the number of states per machine, which grows with the number of
+// intervals times the switch threshold
+pub const MAX_STATES: usize = crate::MAX_STATES;
+
+// Switches and options of the dynaflow binary
+pub const SWITCHES: &[&str] = &["--warn-unreachable", "--explain", "--dry-run", "--annotate", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--report", "--include-small-packets", "--strict", "--ms"];
+pub const OPTIONS: &[&str] = &["--packet-size", "--max-blocking-frac", "--allowed-blocked-microsec", "--time-scale", "--block-size"];
+
+
+// Parameters of the dynaflow binary
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynaFlow {
+    // Candidate send intervals (usec), in the order they are stepped through
+    pub intervals: Vec<f64>,
+    // Consecutive idle blocks before stepping to the next interval
+    pub threshold: usize,
+    // Padding packets per block
+    pub block_size: f64,
+    // Size of padding packets (bytes)
+    pub packet_size: f64,
+    pub options: MachineOptions,
+}
+
+
+impl DynaFlow {
+    // Parse the arguments of the dynaflow binary (see parse_args()).
+    pub fn from_args(cli: &Args) -> Result<DynaFlow, String> {
+        let args = &cli.positional;
+        if args.len() != 3 {
+            return Err(format!("Usage: {} <intervals> <switch threshold> [--warn-unreachable] [--explain] [--dry-run] [--annotate] [--hash] [--ffi] [--emit-rust] [--base64] [--report] [--include-small-packets] [--strict] [--ms] [--packet-size <bytes>] [--max-blocking-frac <fraction>] [--allowed-blocked-microsec <usec>] [--time-scale <factor>] [--block-size <packets>]", args[0]));
+        }
+
+        let mut intervals: Vec<f64> = Vec::new();
+        for interval in args[1].split(',') {
+            intervals.push(parse_interval(cli, interval.trim()).ok_or("Invalid send interval")?);
+        }
+        let threshold: usize = parse_count(&args[2], "switch threshold")?;
+        let block_size: f64 = match cli.value("--block-size") {
+            Some(v) => parse_finite_positive(v, "block size").and_then(|count| strict_count(cli, count, "block size"))?,
+            None => BLOCK_SIZE,
+        };
+
+        if intervals.iter().any(|&interval| interval <= 0.0) {
+            return Err(String::from("Send intervals must be positive"));
+        }
+        if threshold < 1 {
+            return Err(String::from("Switch threshold must be at least 1"));
+        }
+        if block_size < 1.0 {
+            return Err(String::from("Block size must be at least 1 packet"));
+        }
+
+        let num_states = intervals.len().saturating_mul(threshold).saturating_add(FIRST_SEND_STATE_INDEX);
+        debug(|| format!("Intervals {:?} usec, {} blocks each of {} packets", intervals, threshold, block_size));
+        if num_states > MAX_STATES {
+            return Err(format!("DynaFlow would take {} states, more than the cap of {} (give fewer intervals or a lower switch threshold)", num_states, MAX_STATES));
+        }
+
+        return Ok(DynaFlow {
+            intervals: intervals,
+            threshold: threshold,
+            block_size: block_size,
+            packet_size: packet_size(cli)?,
+            options: machine_options(cli)?,
+        });
+    }
+
+
+    // The client and relay machines, the same DynaFlow machine on each side,
+    // with the names the binary prints.
+    pub fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        let mut machines = [
+            ("Client machine", generate_machine(&self.intervals, self.threshold, self.block_size, self.packet_size)),
+            ("Relay machine", generate_machine(&self.intervals, self.threshold, self.block_size, self.packet_size)),
+        ];
+        for (name, machine) in machines.iter_mut() {
+            self.options.apply(machine)?;
+            check_has_states(name, machine)?;
+        }
+
+        return Ok(machines.into_iter().map(|(name, machine)| (name.to_string(), machine)).collect());
+    }
+}
+
+
+// Parse the arguments of the dynaflow binary (without the program name) into
+// its parameters.
+pub fn parse(args: &[String]) -> Result<DynaFlow, String> {
+    let cli = try_parse_args([&[String::from("dynaflow")], args].concat(), SWITCHES, OPTIONS)?;
+    return DynaFlow::from_args(&cli);
+}
+
+
+// Generate the machines of the dynaflow binary for its arguments (without the
+// program name), e.g., generate(&["1000,2000,4000", "3"]).
+pub fn generate(args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse(args)?.named_machines();
+}
+
+
+// Generate a DynaFlow machine, with threshold SEND states per interval.
+pub fn generate_machine(intervals: &[f64], threshold: usize, block_size: f64, packet_size: f64) -> Machine {
+    let num_states = intervals.len().saturating_mul(threshold).saturating_add(FIRST_SEND_STATE_INDEX);
+    let last_index = num_states - 1;
+
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(num_states);
+    states.push(generate_start_state(num_states));
+
+    for (i, &interval) in intervals.iter().enumerate() {
+        for k in 0..threshold {
+            let curr_index = FIRST_SEND_STATE_INDEX + i * threshold + k;
+
+            // The last SEND state has no limit, so it sends at the last
+            // interval until a real packet is sent
+            let limit = if curr_index == last_index { None } else { Some(block_size) };
+            states.push(generate_send_state(curr_index, curr_index + 1, num_states, interval, limit, packet_size));
+        }
+    }
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+
+    return machine;
+}
+
+
+// Generate the START state for a machine.
+pub fn generate_start_state(num_states: usize) -> State {
+    // NonPaddingSent --> SEND_0_0 (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(FIRST_SEND_STATE_INDEX, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+
+    return State::new(transitions, num_states);
+}
+
+
+// Generate a SEND state for a machine. Without a limit, the state never moves
+// on while idle.
+pub fn generate_send_state(curr_index: usize, next_index: usize, num_states: usize, interval: f64, limit: Option<f64>, packet_size: f64) -> State {
+    // PaddingSent --> SEND_i_k (100%)
+    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
+    padding_sent.insert(curr_index, 1.0);
+
+    // NonPaddingSent --> SEND_0_0 (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(FIRST_SEND_STATE_INDEX, 1.0);
+
+    // LimitReached --> next SEND state (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(next_index, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingSent, padding_sent);
+    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+    if limit.is_some() {
+        transitions.insert(Event::LimitReached, limit_reached);
+    }
+
+    // SEND_i_k state
+    let mut state = State::new(transitions, num_states);
+    state.replace = true;
+
+    state.timeout = fixed(interval);
+
+    state.action = fixed(packet_size);
+
+    if let Some(limit) = limit {
+        state.limit = fixed(limit);
+    }
+
+    return state;
+}
@@ -0,0 +1,103 @@
+// Registry of the defenses, and dispatch to them by name. Each defense of
+// schema::DEFENSES registers the parser of its module (e.g.,
+// constant::parse()), which takes the arguments of the binary of the same name
+// into the parameters of the defense, a Defense (see defense.rs). Registering
+// a defense takes its schema entry (src/schema.rs), its binary
+// (src/bin/<name>.rs), and its entry in PARSERS below. The machines are
+// generated in-process through the Defense trait, with the names the binary
+// prints them with.
+
+use maybenot::machine::Machine;
+
+use crate::defense::Defense;
+use crate::schema::DEFENSES;
+use crate::{adapted_front, adaptive_pad, buflo, constant, cs_buflo, dynaflow, interspace, regulator, scrambler, surakav, walkie_talkie};
+
+
+// Parser of a defense: its parameters for the arguments of its binary, without
+// the program name
+pub type Parser = fn(&[String]) -> Result<Box<dyn Defense>, String>;
+
+// Machines generated for one padding packet size (bytes), with their names
+pub type SizedMachines = (f64, Vec<(String, Machine)>);
+
+
+// Parser of each defense of schema::DEFENSES
+const PARSERS: &[(&str, Parser)] = &[
+    ("constant", |args| boxed(constant::parse(args))),
+    ("buflo", |args| boxed(buflo::parse(args))),
+    ("adapted_front", |args| boxed(adapted_front::parse(args))),
+    ("adapted_regulator", |args| boxed(regulator::parse_adapted(args))),
+    ("regulator", |args| boxed(regulator::parse(args))),
+    ("scrambler", |args| boxed(scrambler::parse(args))),
+    ("adaptive_pad", |args| boxed(adaptive_pad::parse(args))),
+    ("walkie_talkie", |args| boxed(walkie_talkie::parse(args))),
+    ("surakav", |args| boxed(surakav::parse(args))),
+    ("dynaflow", |args| boxed(dynaflow::parse(args))),
+    ("interspace", |args| boxed(interspace::parse(args))),
+    ("cs_buflo", |args| boxed(cs_buflo::parse(args))),
+];
+
+// Flags of the binaries that print something other than their machines
+// (e.g., a report, the machines of several intervals, or one machine only), so
+// they are only taken by the binaries
+const NON_MACHINE_FLAGS: &[&str] = &["--help", "--explain", "--report", "--dry-run", "--ffi", "--emit-rust", "--stdin", "--maybenot-v2", "--counters", "--count-only", "--only"];
+
+
+// Names of the registered defenses, in the order of schema::DEFENSES.
+pub fn available_defenses() -> Vec<&'static str> {
+    return DEFENSES.iter().map(|defense| defense.name).collect();
+}
+
+
+// Parse the command-line arguments of a defense into its parameters, e.g.,
+// parse_defense("constant", &["4000"]).
+pub fn parse_defense(name: &str, args: &[String]) -> Result<Box<dyn Defense>, String> {
+    let parser = match PARSERS.iter().find(|(parser_name, _)| *parser_name == name) {
+        Some((_, parser)) => parser,
+        None => return Err(format!("unknown defense {} (expected one of {})", name, available_defenses().join(", "))),
+    };
+    if let Some(flag) = args.iter().find(|arg| NON_MACHINE_FLAGS.contains(&arg.as_str())) {
+        return Err(format!("{} does not print the machines of {}, and is only taken by its binary", flag, name));
+    }
+
+    return parser(args);
+}
+
+
+// Box the parameters of a defense, for PARSERS.
+fn boxed<D: Defense + 'static>(defense: Result<D, String>) -> Result<Box<dyn Defense>, String> {
+    return Ok(Box::new(defense?));
+}
+
+
+// Generate the machines of a defense with its command-line arguments, e.g.,
+// generate("constant", &["4000"]).
+pub fn generate(name: &str, args: &[String]) -> Result<Vec<Machine>, String> {
+    return Ok(generate_named(name, args)?.into_iter().map(|(_, machine)| machine).collect());
+}
+
+
+// generate(), with the name the defense prints for each machine (e.g., "Relay
+// machine" or "Machine 2").
+pub fn generate_named(name: &str, args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse_defense(name, args)?.named_machines();
+}
+
+
+// generate_named() once per padding packet size (bytes), with --packet-size
+// set to the size, e.g., for paths with different MTUs. Returns the machines
+// of each size along with it, in the order of sizes.
+pub fn generate_per_packet_size(name: &str, args: &[String], sizes: &[f64]) -> Result<Vec<SizedMachines>, String> {
+    if args.iter().any(|arg| arg == "--packet-size") {
+        return Err(String::from("--packet-size is set to each of the packet sizes, and cannot be given too"));
+    }
+
+    let mut bundles: Vec<SizedMachines> = Vec::with_capacity(sizes.len());
+    for &size in sizes {
+        let sized_args = [args, &[String::from("--packet-size"), size.to_string()]].concat();
+        bundles.push((size, generate_named(name, &sized_args)?));
+    }
+
+    return Ok(bundles);
+}
@@ -0,0 +1,295 @@
+// Machine generation for Interspace (see bin/interspace.rs): a START state,
+// and PAD states that send padding with sampled timeouts and sizes. The
+// padding sizes come from a histogram of size buckets, with one PAD state per
+// bucket: every padding packet sent moves to a PAD state picked with the
+// weight of its bucket, whose action is Uniform over the bucket, so the sizes
+// follow the histogram. The size range of the binary is a histogram of a
+// single bucket, a machine of START and a single PAD state.
+//
+// A histogram file (--size-hist) has one bucket per line, as
+// <min size>,<max size>,<count> or <size>,<count> for a bucket of a single
+// size, with the sizes in bytes. The counts may be frequencies rather than
+// whole counts, as only their ratios matter. Blank lines are skipped, and
+// buckets with a count of 0 are left out.
+
+use std::collections::HashMap;
+use std::fs;
+
+use maybenot::{
+machine::Machine,
+event::Event,
+state::State,
+dist::{Dist, DistType}
+};
+
+use crate::args::{try_parse_args, parse_finite, parse_finite_positive, strict_count, dist_type, machine_options, Args, MachineOptions};
+use crate::validate::check_has_states;
+use crate::dist_helpers::uniform_range;
+use crate::logging::debug;
+
+
+pub const START_STATE_INDEX: usize = 0;
+pub const FIRST_PAD_STATE_INDEX: usize = 1;
+
+// Cap on the buckets of a histogram, for a machine of at most 1000 states
+pub const MAX_BUCKETS: usize = 999;
+
+// Switches and options of the interspace binary
+pub const SWITCHES: &[&str] = &["--warn-unreachable", "--explain", "--dry-run", "--annotate", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--report", "--include-small-packets", "--strict"];
+pub const OPTIONS: &[&str] = &["--max-blocking-frac", "--allowed-blocked-microsec", "--time-scale", "--dist", "--size-hist"];
+
+
+// A bucket of padding sizes (bytes), sent with a probability proportional to
+// its weight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizeBucket {
+    pub min: f64,
+    pub max: f64,
+    pub weight: f64,
+}
+
+
+// Parameters of the interspace binary
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interspace {
+    // Parameters of the padding timeout distribution (usec)
+    pub params: (f64, f64),
+    pub dist: DistType,
+    // Range of the padding sizes (bytes)
+    pub min_size: f64,
+    pub max_size: f64,
+    // Buckets of the padding sizes: the size range as a single bucket, or the
+    // buckets of --size-hist
+    pub buckets: Vec<SizeBucket>,
+    pub options: MachineOptions,
+}
+
+
+impl Interspace {
+    // Parse the arguments of the interspace binary (see parse_args()).
+    pub fn from_args(cli: &Args) -> Result<Interspace, String> {
+        let args = &cli.positional;
+        if args.len() != 5 {
+            return Err(format!("Usage: {} <param1> <param2> <min size> <max size> [--warn-unreachable] [--explain] [--dry-run] [--annotate] [--hash] [--ffi] [--emit-rust] [--base64] [--report] [--include-small-packets] [--strict] [--max-blocking-frac <fraction>] [--allowed-blocked-microsec <usec>] [--time-scale <factor>] [--dist <distribution>] [--size-hist <file>]", args[0]));
+        }
+
+        let param1:   f64 = parse_finite(&args[1], "timeout parameter 1")?; // e.g. mean (usec)
+        let param2:   f64 = parse_finite(&args[2], "timeout parameter 2")?; // e.g. stdev (usec)
+        let min_size: f64 = parse_finite_positive(&args[3], "min size").and_then(|size| strict_count(cli, size, "min size"))?; // bytes
+        let max_size: f64 = parse_finite_positive(&args[4], "max size").and_then(|size| strict_count(cli, size, "max size"))?; // bytes
+
+        if min_size < 1.0 {
+            return Err(String::from("Min size must be at least 1 byte"));
+        }
+        if max_size < min_size {
+            return Err(String::from("Max size must be at least the min size"));
+        }
+
+        let buckets = match cli.value("--size-hist") {
+            Some(path) => {
+                let hist = fs::read_to_string(path).map_err(|e| format!("Unable to read size histogram: {}", e))?;
+                let buckets = parse_size_hist(&hist).map_err(|e| format!("Invalid size histogram: {}", e))?;
+                for bucket in &buckets {
+                    if bucket.min < min_size || bucket.max > max_size {
+                        return Err(format!("Size histogram bucket [{}, {}] is outside [{}, {}]", bucket.min, bucket.max, min_size, max_size));
+                    }
+                }
+                buckets
+            },
+            None => vec![SizeBucket { min: min_size, max: max_size, weight: 1.0 }],
+        };
+
+        let dist = dist_type(cli, DistType::Normal)?;
+        debug(|| format!("Padding timeouts {} ({}, {}), sizes [{}, {}] bytes in {} bucket(s)", dist, param1, param2, min_size, max_size, buckets.len()));
+
+        return Ok(Interspace {
+            params: (param1, param2),
+            dist: dist,
+            min_size: min_size,
+            max_size: max_size,
+            buckets: buckets,
+            options: machine_options(cli)?,
+        });
+    }
+
+
+    // The machine, with the options applied.
+    pub fn machine(&self) -> Result<Machine, String> {
+        let mut machine = generate_machine(self.dist, self.params, &self.buckets);
+        self.options.apply(&mut machine)?;
+        check_has_states("Machine", &machine)?;
+        return Ok(machine);
+    }
+
+
+    // The machine, with the name the binary prints.
+    pub fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return Ok(vec![(String::from("Machine"), self.machine()?)]);
+    }
+}
+
+
+// Parse the arguments of the interspace binary (without the program name) into
+// its parameters.
+pub fn parse(args: &[String]) -> Result<Interspace, String> {
+    let cli = try_parse_args([&[String::from("interspace")], args].concat(), SWITCHES, OPTIONS)?;
+    return Interspace::from_args(&cli);
+}
+
+
+// Generate the machines of the interspace binary for its arguments (without
+// the program name), e.g., generate(&["2000", "500", "100", "1500"]).
+pub fn generate(args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse(args)?.named_machines();
+}
+
+
+// Parse a histogram file of padding sizes (see the top of this file).
+pub fn parse_size_hist(hist: &str) -> Result<Vec<SizeBucket>, String> {
+    let mut buckets: Vec<SizeBucket> = Vec::new();
+
+    for (i, line) in hist.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+        if fields.len() < 2 || fields.len() > 3 {
+            return Err(format!("line {}: expected <min size>,<max size>,<count> or <size>,<count>, got \"{}\"", i + 1, line));
+        }
+
+        let number = |field: &str, name: &str| match field.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(value),
+            _ => Err(format!("line {}: invalid {} \"{}\"", i + 1, name, field)),
+        };
+        let min = number(fields[0], "size")?;
+        let max = if fields.len() == 3 { number(fields[1], "size")? } else { min };
+        let weight = number(fields[fields.len() - 1], "count")?;
+
+        if min < 1.0 {
+            return Err(format!("line {}: sizes must be at least 1 byte, got {}", i + 1, min));
+        }
+        if max < min {
+            return Err(format!("line {}: max size {} is below the min size {}", i + 1, max, min));
+        }
+        if weight < 0.0 {
+            return Err(format!("line {}: count must be at least 0, got {}", i + 1, weight));
+        }
+
+        if weight > 0.0 {
+            buckets.push(SizeBucket { min: min, max: max, weight: weight });
+        }
+    }
+
+    if buckets.is_empty() {
+        return Err(String::from("the histogram has no bucket with a count above 0"));
+    }
+    if buckets.len() > MAX_BUCKETS {
+        return Err(format!("the histogram has {} buckets with a count above 0, at most {} are supported", buckets.len(), MAX_BUCKETS));
+    }
+
+    return Ok(buckets);
+}
+
+
+// Mean padding size (bytes) of a histogram, with sizes uniform over each
+// bucket, ignoring the rounding and clamping of the sizes.
+pub fn mean_size(buckets: &[SizeBucket]) -> f64 {
+    let total: f64 = buckets.iter().map(|bucket| bucket.weight).sum();
+    return buckets.iter().map(|bucket| bucket.weight * (bucket.min + bucket.max) / 2.0).sum::<f64>() / total;
+}
+
+
+// Probabilities of the buckets of a histogram, their weights over the total.
+// Rounding may make them sum to just above 1.0, which maybenot rejects, so
+// they are then scaled down until they do not.
+fn bucket_probabilities(buckets: &[SizeBucket]) -> Vec<f64> {
+    let total: f64 = buckets.iter().map(|bucket| bucket.weight).sum();
+    let mut probabilities: Vec<f64> = buckets.iter().map(|bucket| bucket.weight / total).collect();
+
+    while probabilities.iter().sum::<f64>() > 1.0 {
+        for p in probabilities.iter_mut() {
+            *p *= 1.0 - f64::EPSILON;
+        }
+    }
+
+    return probabilities;
+}
+
+
+// Generate an Interspace machine. The padding timeouts are (param1, param2) of
+// a distribution of type dist, and the padding sizes follow the histogram of
+// size buckets, with one PAD state per bucket.
+pub fn generate_machine(dist: DistType, timeout: (f64, f64), buckets: &[SizeBucket]) -> Machine {
+    let num_states = FIRST_PAD_STATE_INDEX + buckets.len();
+    let probabilities = bucket_probabilities(buckets);
+
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(num_states);
+    states.push(generate_start_state(&probabilities, num_states));
+    for bucket in buckets {
+        states.push(generate_pad_state(dist, timeout, bucket, &probabilities, num_states));
+    }
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: u64::MAX,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+
+    return machine;
+}
+
+
+// Transitions to the PAD states, with the probabilities of their buckets.
+fn to_pad_states(probabilities: &[f64]) -> HashMap<usize, f64> {
+    let mut targets: HashMap<usize, f64> = HashMap::new();
+    for (i, p) in probabilities.iter().enumerate() {
+        targets.insert(FIRST_PAD_STATE_INDEX + i, *p);
+    }
+    return targets;
+}
+
+
+// Generate the START state for a machine.
+fn generate_start_state(probabilities: &[f64], num_states: usize) -> State {
+    // NonPaddingSent --> PAD (the probability of each bucket)
+    let nonpadding_sent = to_pad_states(probabilities);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+
+    return State::new(transitions, num_states);
+}
+
+
+// Generate the PAD state of a bucket for a machine.
+fn generate_pad_state(dist: DistType, timeout: (f64, f64), bucket: &SizeBucket, probabilities: &[f64], num_states: usize) -> State {
+    // PaddingSent --> PAD (the probability of each bucket)
+    let padding_sent = to_pad_states(probabilities);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingSent, padding_sent);
+
+    // PAD state
+    let mut state = State::new(transitions, num_states);
+
+    state.timeout = Dist {
+        dist: dist,
+        param1: timeout.0,
+        param2: timeout.1,
+        start: 0.0,
+        max: 0.0,
+    };
+
+    state.action = uniform_range(bucket.min, bucket.max);
+
+    return state;
+}
@@ -0,0 +1,46 @@
+// Shared code for the defense generators and machine tools.
+// Code from the paper: David Hasselquist, Ethan Witwer, August Carlson, Niklas
+// Johansson, and Niklas Carlsson. "Raising the Bar: Improved Fingerprinting
+// Attacks and Defenses for Video Streaming Traffic". Proceedings on Privacy
+// Enhancing Technologies (PoPETs), volume 4, 2024.
+// If you use this code in your work, please include a reference to the paper.
+
+// Default size of padding packets (bytes), see args::packet_size()
+pub const PACKET_SIZE: f64 = 1500.0;
+
+// Cap on the states of a generated machine, shared by the generators that
+// have no cap of their own: each state holds transitions to every state, so
+// the memory of a machine grows with the square of its states
+pub const MAX_STATES: usize = 1000;
+
+pub mod adapted_front;
+pub mod adaptive_pad;
+pub mod args;
+pub mod buflo;
+pub mod constant;
+pub mod cs_buflo;
+pub mod defense;
+pub mod defaults;
+pub mod describe;
+pub mod diff;
+pub mod dist_helpers;
+pub mod dynaflow;
+pub mod generate;
+pub mod interspace;
+pub mod logging;
+pub mod output;
+pub mod overhead;
+pub mod rate;
+pub mod regulator;
+pub mod report;
+pub mod schema;
+pub mod scrambler;
+pub mod simulate;
+pub mod surakav;
+pub mod tune;
+pub mod validate;
+pub mod walkie_talkie;
+pub mod warmup;
+
+#[cfg(feature = "maybenot-v2")]
+pub mod v2;
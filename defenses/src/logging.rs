@@ -0,0 +1,29 @@
+// Debug traces of the generators, for following generation over many
+// configurations: interval widths, rates per state, state counts, and
+// validation results. With the logging feature, the traces are written to
+// stderr by env_logger and controlled by RUST_LOG (e.g., RUST_LOG=debug), so
+// stdout still holds only the machines. Without it, tracing is a no-op.
+
+
+// Set up the logger from RUST_LOG, once per process (see args::parse_args()).
+#[cfg(feature = "logging")]
+pub fn init() {
+    let _ = env_logger::Builder::from_default_env().try_init();
+}
+
+
+#[cfg(not(feature = "logging"))]
+pub fn init() {}
+
+
+// Trace a debug-level message, built only if debug traces are enabled.
+#[cfg(feature = "logging")]
+pub fn debug<F>(message: F) where F: FnOnce() -> String {
+    if log::log_enabled!(target: "raising_the_bar", log::Level::Debug) {
+        log::debug!(target: "raising_the_bar", "{}", message());
+    }
+}
+
+
+#[cfg(not(feature = "logging"))]
+pub fn debug<F>(_message: F) where F: FnOnce() -> String {}
@@ -0,0 +1,355 @@
+// Printing of generated machines, shared by the generators. Each machine is
+// printed as "<name>: <machine> (<length>)". With --annotate, the output also
+// carries its provenance in comment lines starting with "#", which can be
+// stripped (e.g., with grep -v '^#') to get the plain output back. Machines
+// that run together can also be serialized as a single list, and generators
+// can stream machines for parameters read from stdin. The machines are written
+// to stdout as they are serialized (see write_machine()), which other tools
+// can also write to their own files or sockets. With --base64, the bytes of
+// the serialized machines (which maybenot prints as hex) are printed in base64
+// instead, for channels that take machines as text (see Encoding). With
+// --pretty, each machine line is followed by its decoded listing in "#"
+// comment lines, for reading generated machines in review. With --emit-rust,
+// the machines are printed as Rust constants instead, for embedding them.
+
+use std::io::{self, BufRead, Write};
+use std::process;
+use std::error::Error;
+use std::str::FromStr;
+
+use maybenot::machine::Machine;
+use sha2::{Digest, Sha256};
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::args::exit_with_error;
+use crate::describe::describe_machine;
+use crate::validate::{validate_machine, has_errors};
+use crate::logging::debug;
+
+
+// Exit on an error writing to stdout, with an error unless the reader closed
+// the pipe early (e.g., | head -n 1): then, there is nothing left to print to,
+// so the output is done.
+fn exit_on_write_error(e: io::Error) -> ! {
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        process::exit(0);
+    }
+    exit_with_error(&format!("Unable to write to stdout: {}", e));
+}
+
+
+// Separator between the machines of a serialized machine list. Serialized
+// machines are hex strings, so they never contain it.
+pub const MACHINE_LIST_SEPARATOR: &str = "\n";
+
+
+// Encoding of printed machines: maybenot's serialization, or its bytes in
+// (standard, padded) base64, which is a third shorter than the hex. Either
+// way, a machine is a single line in the alphabet of the encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Hex,
+    Base64,
+}
+
+
+// Print the --annotate header: the defense, crate version, and every input
+// parameter (including defaults).
+pub fn print_annotation(defense: &str, params: &[(&str, String)]) {
+    println!("# defense: {}", defense);
+    println!("# version: {}", env!("CARGO_PKG_VERSION"));
+    for (name, value) in params {
+        println!("# {}: {}", name, value);
+    }
+}
+
+
+// Print a serialized machine, preceded by its state count if annotating, and
+// followed by its listing (see pretty_listing()) if pretty.
+pub fn print_machine(name: &str, machine: &Machine, annotate: bool, pretty: bool, encoding: Encoding) {
+    if annotate {
+        println!("# {}: {} states", name, machine.states.len());
+    }
+
+    debug(|| format!("{}: {} states", name, machine.states.len()));
+    
+    // The end of the machine line and the rest in one write, as a pipe that
+    // only takes the first line (e.g., head -n 1) may be closed right after
+    let rest = if pretty { format!("\n{}\n", pretty_listing(name, machine)) } else { String::from("\n\n") };
+    let mut stdout = io::stdout().lock();
+    write_named_machine(&mut stdout, name, machine, encoding).and_then(|_| stdout.write_all(rest.as_bytes())).unwrap_or_else(|e| exit_on_write_error(e));
+}
+
+
+// The --pretty listing of a machine: its decoded fields and states (see
+// describe::describe_machine()), each line commented out with "# ". The
+// machine line comes first, unchanged, so grep -v '^#' still gets the plain
+// output back.
+pub fn pretty_listing(name: &str, machine: &Machine) -> String {
+    return describe_machine(name, machine).lines().map(|line| format!("# {}\n", line)).collect();
+}
+
+
+// Write a serialized machine (see serialize_stable()) to w, e.g., a file or a
+// socket, as the generators print it. Only the machine is written, without a
+// newline. maybenot v1 serializes to a String, but nothing is built around it
+// (e.g., the lines of print_machine()), so streaming many machines takes no
+// more memory than the largest of them.
+pub fn write_machine<W: Write>(w: &mut W, machine: &Machine) -> io::Result<()> {
+    return w.write_all(serialize_stable(machine).as_bytes());
+}
+
+
+// Write machines that run together as their list (see
+// serialize_machine_list()), without a newline after the last one.
+pub fn write_machine_list<W: Write>(w: &mut W, machines: &[&Machine]) -> io::Result<()> {
+    for (i, machine) in machines.iter().enumerate() {
+        if i > 0 {
+            w.write_all(MACHINE_LIST_SEPARATOR.as_bytes())?;
+        }
+        write_machine(w, machine)?;
+    }
+
+    return Ok(());
+}
+
+
+// Write "<name>: <machine> (<length>)", without a newline. The length is that
+// of the machine as encoded.
+fn write_named_machine<W: Write>(w: &mut W, name: &str, machine: &Machine, encoding: Encoding) -> io::Result<()> {
+    write!(w, "{}: ", name)?;
+
+    let mut counter = CountingWriter { inner: w, count: 0 };
+    match encoding {
+        Encoding::Hex => write_machine(&mut counter, machine)?,
+        Encoding::Base64 => counter.write_all(encode_base64(machine).as_bytes())?,
+    }
+    let length = counter.count;
+
+    return write!(w, " ({})", length);
+}
+
+
+// Writer that counts the bytes written through it, for the length that
+// follows a machine in write_named_machine().
+struct CountingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    count: usize,
+}
+
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written;
+        return Ok(written);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return self.inner.flush();
+    }
+}
+
+
+// Serialize a machine, the same on every build and platform, and for machines
+// that only differ in how their transitions are held (see
+// normalize_transitions()). The generators serialize through here so that
+// this holds in one place (see tests/output.rs). maybenot v1 already writes
+// the transitions in the order of Event::iterator(), so a machine is only
+// copied to be normalized if it holds transitions to normalize: its
+// transitions grow with the square of its states.
+pub fn serialize_stable(machine: &Machine) -> String {
+    if !has_transitions_to_normalize(machine) {
+        return machine.serialize();
+    }
+
+    let mut normalized = machine.clone();
+    normalize_transitions(&mut normalized);
+    return normalized.serialize();
+}
+
+
+// Normalize the transitions of each state of a machine: without events that
+// have no transition (which maybenot v1 writes as zeros either way), and with
+// -0.0 probabilities written as 0.0.
+fn normalize_transitions(machine: &mut Machine) {
+    for state in machine.states.iter_mut() {
+        state.next_state.retain(|_, probabilities| probabilities.iter().any(|&probability| probability != 0.0));
+        for probabilities in state.next_state.values_mut() {
+            for probability in probabilities.iter_mut().filter(|probability| **probability == 0.0) {
+                *probability = 0.0;
+            }
+        }
+    }
+}
+
+
+// Whether normalize_transitions() would change the machine.
+fn has_transitions_to_normalize(machine: &Machine) -> bool {
+    return machine.states.iter().flat_map(|state| state.next_state.values()).any(|probabilities| {
+        return probabilities.iter().all(|&probability| probability == 0.0) || probabilities.iter().any(|&probability| probability == 0.0 && probability.is_sign_negative());
+    });
+}
+
+
+// Encode a machine in base64, for --base64: the bytes its serialization holds
+// in hex.
+pub fn encode_base64(machine: &Machine) -> String {
+    let serialized = serialize_stable(machine);
+    let bytes: Vec<u8> = (0..serialized.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&serialized[i..i + 2], 16).expect("maybenot serializes machines in hex"))
+        .collect();
+    return STANDARD.encode(bytes);
+}
+
+
+// Decode a machine from encode_base64().
+pub fn decode_base64(encoded: &str) -> Result<Machine, Box<dyn Error + Send + Sync>> {
+    let bytes = STANDARD.decode(encoded.trim()).map_err(|e| format!("invalid base64: {}", e))?;
+    let serialized: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    return Machine::from_str(&serialized);
+}
+
+
+// Serialize machines that are to run together (e.g., both Scrambler machines
+// alongside a constant-rate machine) as one list, one machine per line.
+pub fn serialize_machine_list(machines: &[Machine]) -> String {
+    let serialized: Vec<String> = machines.iter().map(serialize_stable).collect();
+    return serialized.join(MACHINE_LIST_SEPARATOR);
+}
+
+
+// Serialize machines in the format maybenot-ffi expects: maybenot_start()
+// takes a null-terminated UTF-8 string of LF-separated machines, i.e., their
+// machine list (one line per machine, in the order they are to run). The NUL
+// terminator is left to the caller, e.g., when reading the output into a C
+// string.
+pub fn ffi_machines(machines: &[&Machine]) -> String {
+    let serialized: Vec<String> = machines.iter().map(|machine| serialize_stable(machine)).collect();
+    return serialized.join(MACHINE_LIST_SEPARATOR);
+}
+
+
+// Print the --ffi output for the machines of a generator.
+pub fn print_ffi(machines: &[&Machine]) {
+    for (i, machine) in machines.iter().enumerate() {
+        debug(|| format!("Machine {}: {} states", i + 1, machine.states.len()));
+    }
+    
+    let mut stdout = io::stdout().lock();
+    write_machine_list(&mut stdout, machines).and_then(|_| writeln!(stdout)).unwrap_or_else(|e| exit_on_write_error(e));
+}
+
+
+// Name of the Rust constant for a machine: its name in upper case, with
+// anything but letters and digits replaced by "_" (e.g., "Relay machine" is
+// RELAY_MACHINE, and "Machine 1" is MACHINE_1).
+pub fn rust_const_name(name: &str) -> String {
+    return name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+}
+
+
+// Rust source for the machines of a generator (--emit-rust), to paste into a
+// crate that embeds them: a &str constant per machine, holding its
+// serialization. maybenot v1's Machine is built from HashMaps, which cannot be
+// made in a const, so the constant is the string, parsed with
+// Machine::from_str() where the machine is needed.
+pub fn rust_constants(machines: &[(&str, &Machine)]) -> String {
+    let constants: Vec<String> = machines.iter().map(|(name, machine)| {
+        return format!("// {} ({} states), parse with maybenot::machine::Machine::from_str()\npub const {}: &str = {:?};\n", name, machine.states.len(), rust_const_name(name), serialize_stable(machine));
+    }).collect();
+    return constants.join("\n");
+}
+
+
+// Print the --emit-rust output for the machines of a generator.
+pub fn print_rust(machines: &[(&str, &Machine)]) {
+    for (name, machine) in machines {
+        debug(|| format!("{}: {} states", name, machine.states.len()));
+    }
+
+    print!("{}", rust_constants(machines));
+}
+
+
+// SHA-256 of a serialized machine (hex), for tracking generated machines.
+pub fn machine_hash(machine: &Machine) -> String {
+    return machine_list_hash(&[machine]);
+}
+
+
+// SHA-256 of machines that run together (hex), over their serialized list in
+// order. The hash of a single machine is its machine_hash().
+pub fn machine_list_hash(machines: &[&Machine]) -> String {
+    let serialized: Vec<String> = machines.iter().map(|machine| serialize_stable(machine)).collect();
+    let digest = Sha256::digest(serialized.join(MACHINE_LIST_SEPARATOR).as_bytes());
+    return digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+
+// Print the --hash line for the machines printed by a generator.
+pub fn print_hash(machines: &[&Machine]) {
+    println!("SHA-256: {}", machine_list_hash(machines));
+}
+
+
+// Parse a list from serialize_machine_list(), ignoring blank lines.
+pub fn parse_machine_list(list: &str) -> Result<Vec<Machine>, Box<dyn Error + Send + Sync>> {
+    return parse_encoded_machine_list(list, Encoding::Hex);
+}
+
+
+// parse_machine_list(), of machines in the given encoding.
+pub fn parse_encoded_machine_list(list: &str, encoding: Encoding) -> Result<Vec<Machine>, Box<dyn Error + Send + Sync>> {
+    let mut machines: Vec<Machine> = Vec::new();
+    for machine in list.split(MACHINE_LIST_SEPARATOR).map(|line| line.trim()) {
+        if machine.is_empty() {
+            continue;
+        }
+
+        machines.push(match encoding {
+            Encoding::Hex => Machine::from_str(machine)?,
+            Encoding::Base64 => decode_base64(machine)?,
+        });
+    }
+
+    return Ok(machines);
+}
+
+
+// Generate a machine for each line of stdin, which holds the positional
+// parameters of the generator separated by whitespace, until EOF. Each machine
+// is printed on its own line as "Machine: <machine> (<length>)" in the given
+// encoding, flushed right away so a pipeline can consume it. Blank lines are
+// skipped, and a line that fails is reported in place of its machine as
+// "Machine: error: <message>" (and on stderr with its line number) without
+// ending the stream.
+pub fn print_machines_from_stdin<F>(encoding: Encoding, generate: F) where F: Fn(&[&str]) -> Result<Machine, String> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for (i, line) in stdin.lock().lines().enumerate() {
+        let line = line.unwrap_or_else(|e| exit_with_error(&format!("Unable to read from stdin: {}", e)));
+        let params: Vec<&str> = line.split_whitespace().collect();
+        if params.is_empty() {
+            continue;
+        }
+
+        let result = generate(&params).and_then(|machine| {
+            if has_errors(&validate_machine(&machine)) {
+                return Err(String::from("parameters produce an invalid machine"));
+            }
+            return Ok(machine);
+        });
+
+        match result {
+            Ok(machine) => write_named_machine(&mut stdout, "Machine", &machine, encoding).and_then(|_| writeln!(stdout)),
+            Err(e) => {
+                eprintln!("Line {}: {}", i + 1, e);
+                writeln!(stdout, "Machine: error: {}", e)
+            },
+        }.unwrap_or_else(|e| exit_on_write_error(e));
+
+        stdout.flush().unwrap_or_else(|e| exit_on_write_error(e));
+    }
+}
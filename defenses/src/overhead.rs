@@ -0,0 +1,101 @@
+// Bandwidth and overhead of constant-rate padding. A machine sending one packet
+// every interval (usec) has a constant bandwidth, and real packets replace
+// padding packets, so against a baseline stream of lower bandwidth the padding
+// adds (bandwidth - baseline) on top of it. The overhead ratio is that added
+// bandwidth relative to the baseline.
+//
+// The throughput of a base trace (see simulate::parse_base_trace()) gives the
+// baseline to calibrate the interval against: padding at a bandwidth above the
+// peak of the stream leaves no burst of real packets waiting on it.
+
+use crate::simulate::Packet;
+
+
+// Bandwidth (bits / sec) of one packet of packet_size bytes every interval (usec).
+pub fn bandwidth_bps(interval: f64, packet_size: f64) -> f64 {
+    return packet_size * 8.0 * 1_000_000.0 / interval;
+}
+
+
+// Packets per second of one packet every interval (usec), e.g. 250 for 4000.
+pub fn pps_from_interval(interval: f64) -> f64 {
+    return 1_000_000.0 / interval;
+}
+
+
+// Overhead ratio of sending one packet every interval over a baseline (bits / sec).
+pub fn overhead_ratio(interval: f64, packet_size: f64, baseline_bps: f64) -> f64 {
+    return (bandwidth_bps(interval, packet_size) - baseline_bps) / baseline_bps;
+}
+
+
+// Mean throughput (bits / sec) of the packets, from the first to the last: the
+// bytes of all but the last packet, each sent in the gap up to the next one,
+// over the time between the two. None for packets that span no time (e.g., a
+// single packet).
+pub fn mean_throughput_bps(packets: &[Packet]) -> Option<f64> {
+    let (first, last) = match (packets.first(), packets.last()) {
+        (Some(first), Some(last)) if last.time > first.time => (first, last),
+        _ => return None,
+    };
+
+    let bytes: u64 = packets[..packets.len() - 1].iter().map(|packet| packet.size as u64).sum();
+    return Some(bytes as f64 * 8.0 * 1_000_000_000.0 / (last.time - first.time) as f64);
+}
+
+
+// Peak throughput (bits / sec) of the packets, over windows of window usec:
+// the most bytes in any window starting at a packet, over the window. The
+// packets must be in time order, as parse_base_trace() checks.
+pub fn peak_throughput_bps(packets: &[Packet], window: f64) -> f64 {
+    let window_ns = window * 1000.0;
+    let mut peak_bytes: u64 = 0;
+    let mut bytes: u64 = 0;
+    let mut end = 0;
+
+    for (start, packet) in packets.iter().enumerate() {
+        while end < packets.len() && ((packets[end].time - packet.time) as f64) < window_ns {
+            bytes += packets[end].size as u64;
+            end += 1;
+        }
+        peak_bytes = peak_bytes.max(bytes);
+        bytes -= packets[start].size as u64;
+    }
+
+    return peak_bytes as f64 * 8.0 * 1_000_000.0 / window;
+}
+
+
+// Send interval (usec) of constant-rate padding that dominates a stream of the
+// given peak throughput (bits / sec) by margin (e.g., 0.1 for 10% above the
+// peak), rounded down to a whole usec so that the rate stays above it.
+pub fn calibrate_interval(peak_bps: f64, margin: f64, packet_size: f64) -> Result<f64, String> {
+    if !margin.is_finite() || margin < 0.0 {
+        return Err(format!("margin must be a finite ratio of at least 0, got {}", margin));
+    }
+    if !peak_bps.is_finite() || peak_bps <= 0.0 {
+        return Err(format!("peak throughput must be positive, got {} bits/sec", peak_bps));
+    }
+
+    let interval = solve_interval(margin, peak_bps, packet_size)?;
+    if interval < 1.0 {
+        return Err(format!("the peak of {:.0} bits/sec needs a send interval below 1 usec", peak_bps));
+    }
+
+    return Ok(interval.floor());
+}
+
+
+// Solve for the send interval (usec) with the target overhead ratio over a
+// baseline (bits / sec), i.e., a bandwidth of baseline * (1 + target).
+pub fn solve_interval(target_overhead: f64, baseline_bps: f64, packet_size: f64) -> Result<f64, String> {
+    if !target_overhead.is_finite() || target_overhead < 0.0 {
+        return Err(format!("target overhead must be a finite ratio of at least 0, got {}", target_overhead));
+    }
+    if !baseline_bps.is_finite() || baseline_bps <= 0.0 {
+        return Err(format!("baseline bandwidth must be positive, got {} bits/sec", baseline_bps));
+    }
+
+    // bandwidth_bps(interval) = baseline * (1 + target), solved for interval
+    return Ok(packet_size * 8.0 * 1_000_000.0 / (baseline_bps * (1.0 + target_overhead)));
+}
@@ -0,0 +1,124 @@
+// The RD^t rate model of RegulaTor: a surge starting at an initial rate R
+// (packets / sec) that decays by a factor D every second. Rate-scheduled
+// defenses split the curve into intervals of a fixed packet count, one state
+// per interval. Rate-adaptive defenses (CS-BuFLO) step through the same curve
+// one level at a time instead (see rate_levels()).
+//
+// Rates are in packets / sec and times on the curve in seconds, while machine
+// timeouts are in usec: send_interval_usec() is the one conversion between
+// them, so the units meet in a single place.
+
+use std::f64::INFINITY;
+
+
+// Largest difference (packets) between the packet count of an interval found by
+// calc_interval_width() and the requested one. The search doubles and then
+// halves its step, so a tighter tolerance only costs a few more iterations.
+pub const WIDTH_TOLERANCE: f64 = 0.00001;
+
+
+// Find the width of an interval of the function RD^t, from a, with the specified
+// packet count (within tolerance packets, see WIDTH_TOLERANCE). The count of an
+// interval is approximated by its width times the rate at its middle.
+pub fn calc_interval_width(a: f64, count: f64, rate: f64, decay: f64, tolerance: f64) -> f64 {
+    let mut mid = a;
+    let mut step: f64 = 0.5;
+    let mut decreasing = false;
+    
+    let mut curr_count = 0.0;
+    let mut curr_diff = count - curr_count;
+    
+    while curr_diff.abs() > tolerance {
+        if curr_diff < 0.0 {
+            mid -= step;
+            decreasing = true;
+        } else {
+            mid += step;
+        }
+        
+        if decreasing {
+            step /= 2.0;
+        } else {
+            step *= 2.0;
+        }
+        
+        curr_count = calculate_rate(mid, rate, decay) * (mid - a) * 2.0;
+        curr_diff = count - curr_count;
+    }
+    
+    return (mid - a) * 2.0;
+}
+
+
+// Find the width of an interval of the function RD^t, from a, containing exactly
+// the specified packet count. Solves count = R(D^b - D^a) / ln(D) for b, which
+// is infinite if fewer than count packets remain after a.
+pub fn calc_interval_width_exact(a: f64, count: f64, rate: f64, decay: f64) -> f64 {
+    if decay == 1.0 {
+        return count / rate;
+    }
+    
+    let decay_b = decay.powf(a) + count * decay.ln() / rate;
+    if decay_b <= 0.0 {
+        return INFINITY;
+    }
+    
+    return decay_b.ln() / decay.ln() - a;
+}
+
+
+// Time (sec) until RD^t drops to 1 packet/sec, i.e. roughly how long the relay
+// keeps padding after a surge: ln(1/R) / ln(D). Zero if R is at most 1, and
+// infinite if D is at least 1 (no decay).
+pub fn decay_horizon_seconds(initial_rate: f64, decay: f64) -> f64 {
+    if initial_rate <= 1.0 {
+        return 0.0;
+    } else if decay >= 1.0 {
+        return INFINITY;
+    }
+    
+    return (1.0 / initial_rate).ln() / decay.ln();
+}
+
+
+// Interval (usec) between the packets of a rate (packets / sec), e.g., the
+// timeout of a state padding at that rate. Panics unless the rate is positive
+// and finite, as a timeout of 0 or infinity is never what was meant.
+pub fn send_interval_usec(rate: f64) -> f64 {
+    assert!(rate.is_finite() && rate > 0.0, "Rate must be a positive number of packets/sec, got {}", rate);
+    return 1000000.0 / rate;
+}
+
+
+// RD^t, the rate t seconds into the surge: R at t = 0, which the first SEND
+// state's rate (taken at the middle of its interval) builds on, and R at every
+// t for D = 1. Far into the surge, D^t underflows, through subnormals that
+// would make the interval search crawl, so rates below the smallest normal
+// f64 are 0.0, as is a NaN (e.g., from a negative D), never a negative rate.
+pub fn calculate_rate(t: f64, initial_rate: f64, decay: f64) -> f64 {
+    let rate = initial_rate * decay.powf(t);
+    if rate.is_nan() || rate < f64::MIN_POSITIVE {
+        return 0.0;
+    }
+    return rate;
+}
+
+
+// Number of levels of rate_levels(): ln(max / min) / ln(factor) + 1, rounded
+// down, with some slack for max / min being an exact power of the factor.
+// Saturates at usize::MAX, e.g. for a min rate too small for max / min to be
+// finite.
+pub fn count_rate_levels(max_rate: f64, min_rate: f64, factor: f64) -> usize {
+    return (((max_rate / min_rate).ln() / factor.ln() + 1e-9).floor() as usize).saturating_add(1);
+}
+
+
+// Rates (packets / sec) from max_rate down, each the previous one divided by
+// factor (> 1), while at least min_rate: RD^t at t = 0, 1, 2, ... with R =
+// max_rate and D = 1 / factor. A factor of 2 halves the rate at each level,
+// as CS-BuFLO does.
+pub fn rate_levels(max_rate: f64, min_rate: f64, factor: f64) -> Vec<f64> {
+    return (0..count_rate_levels(max_rate, min_rate, factor))
+        .map(|level| calculate_rate(level as f64, max_rate, 1.0 / factor))
+        .collect();
+}
@@ -0,0 +1,881 @@
+// Building blocks shared by the RegulaTor generators (adapted_regulator.rs and
+// regulator.rs): the client-side machine, the fixed relay-side states, and the
+// discretization of the relay's surge into SEND states (see rate.rs for the
+// RD^t rate model itself).
+//
+// AdaptedRegulator and Regulator hold the parameters of the two binaries,
+// parsed from their arguments by from_args(), and generate their machines.
+
+use std::f64::INFINITY;
+use std::collections::HashMap;
+
+use maybenot::{
+constants::STATEEND,
+machine::Machine,
+event::Event,
+state::State
+};
+
+use crate::PACKET_SIZE;
+use crate::args::{try_parse_args, parse_finite, parse_finite_positive, parse_count, packet_size, strict_count, machine_options, Args, MachineOptions};
+use crate::rate::{calc_interval_width, calc_interval_width_exact, calculate_rate, send_interval_usec, WIDTH_TOLERANCE};
+use crate::constant::{parse_trigger, Trigger};
+use crate::defaults::{ADAPTED_REGULATOR_INITIAL_RATE, ADAPTED_REGULATOR_DECAY_RATE, ADAPTED_REGULATOR_UPLOAD_RATIO, ADAPTED_REGULATOR_PACKETS_PER_STATE, ADAPTED_REGULATOR_CLIENT_LIMIT, ADAPTED_REGULATOR_TRIGGER};
+use crate::dist_helpers::{fixed, infinite};
+use crate::validate::check_has_states;
+use crate::logging::debug;
+use crate::report::{OverheadReport, peak_padding_bps, longest_padding_timeout};
+
+
+// Relay machine states
+pub const BLOCK_STATE_INDEX: usize = 1;
+pub const FIRST_SEND_STATE_INDEX: usize = 2;
+
+// Client machine defaults: the limit of the COUNT states, the [client limit]
+// argument of the Adapted RegulaTor (fixed in regulator.rs)
+pub const CLIENT_LIMIT: f64 = 2.0;
+
+// Real traffic restarts the surge of the Adapted RegulaTor in SEND states below
+// this rate (packets / sec)
+pub const BURST_RESET_RATE: f64 = 200.0;
+
+// Cap on the states of each RegulaTor machine. Each event of a maybenot v1
+// state holds a Vec<f64> of a probability per state (and STATECANCEL and
+// STATEEND), so a machine of n states takes about n^2 * 8 bytes per event it
+// uses, and serializes all of its 8 events: about 64 * n^2 bytes before
+// compression. The cap fits the largest machine of the paper (1854 states) in
+// 256 MB, where 10000 states would take 6.4 GB.
+pub const MAX_STATES: usize = 2000;
+
+// Cap on the SEND intervals of the relay's surge (see send_state_intervals()),
+// which take time and memory linear in their number: enough to count or list
+// those of a machine far past MAX_STATES, but not to loop for hours on e.g. an
+// initial rate of 1e15.
+pub const MAX_SEND_INTERVALS: usize = 1000000;
+
+// Relative slack of check_send_units() on the bounds of the timeout, for the
+// rounding of the interval widths
+const UNITS_SLACK: f64 = 0.001;
+
+// Lowest sending rate of the RegulaTor relay (packets / sec)
+const MIN_RATE: f64 = 1.0;
+
+// Switches and options of the adapted_regulator binary
+pub const ADAPTED_SWITCHES: &[&str] = &["--warn-unreachable", "--explain", "--dry-run", "--annotate", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--report", "--include-small-packets", "--strict", "--units-check", "--loop-surge", "--verbose-states", "--progress", "--count-only", "--coarsen"];
+pub const ADAPTED_OPTIONS: &[&str] = &["--packet-size", "--max-blocking-frac", "--allowed-blocked-microsec", "--time-scale", "--trigger", "--max-states"];
+
+// Switches and options of the regulator binary
+pub const SWITCHES: &[&str] = &["--warn-unreachable", "--explain", "--dry-run", "--annotate", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--report", "--include-small-packets", "--strict", "--units-check", "--coarsen"];
+pub const OPTIONS: &[&str] = &["--packet-size", "--max-blocking-frac", "--allowed-blocked-microsec", "--time-scale", "--trigger", "--max-states"];
+
+
+// Generate a RegulaTor client-side machine. The client sends one packet per
+// upload_ratio packets received: a chain of COUNT states for ratios of 1.0 and
+// above, or a single COUNT state followed by a SEND state that repeats with
+// probability 1.0 - upload_ratio for ratios below 1.0 (see check_upload_ratio()
+// for the ratios accepted).
+pub fn generate_client_machine(upload_ratio: f64, limit: f64, packet_size: f64) -> Machine {
+    if let Err(e) = check_upload_ratio(upload_ratio) {
+        panic!("Invalid upload ratio: {}", e);
+    }
+    
+    debug(|| format!("Client machine: upload ratio {}, {} states", upload_ratio, count_client_states(upload_ratio)));
+    
+    if upload_ratio < 1.0 {
+        let states = vec![
+            generate_client_count_state(0, 1, 2, 1.0, limit),
+            generate_client_send_state(2, upload_ratio, packet_size),
+        ];
+        
+        return client_machine(states);
+    }
+    
+    // Set up state vector
+    let num_states = (upload_ratio as usize) + 1;
+    let prob_last_trans = 1.0 - upload_ratio.fract();
+    
+    let mut states: Vec<State> = Vec::with_capacity(num_states);
+    
+    // COUNTER states
+    for i in 1..num_states {
+        let mut prob_trans = 1.0;
+        if i == num_states - 1 {
+            prob_trans = prob_last_trans;
+        }
+        
+        states.push(generate_client_count_state(i - 1, i, num_states, prob_trans, limit));
+    }
+    
+    // SEND state
+    states.push(generate_client_send_state(num_states, 1.0, packet_size));
+    
+    return client_machine(states);
+}
+
+
+// Check that an upload ratio is positive and finite. A ratio of 0.0 is
+// rejected rather than read as "send only": the SEND state would repeat with
+// probability 1.0, padding without end and never counting received packets.
+// An infinite ratio would take infinitely many COUNT states, and one of
+// MAX_STATES or more, more states than the cap.
+pub fn check_upload_ratio(upload_ratio: f64) -> Result<(), String> {
+    if !upload_ratio.is_finite() || upload_ratio <= 0.0 {
+        return Err(format!("upload ratio must be a positive finite number, got {}", upload_ratio));
+    }
+    
+    if upload_ratio >= MAX_STATES as f64 {
+        return Err(format!("upload ratio {} would take more than the cap of {} client states", upload_ratio, MAX_STATES));
+    }
+    
+    return Ok(());
+}
+
+
+// Check that a packet count per relay SEND state is positive and finite. At 0
+// or below, calc_interval_width() looks for an interval of no (or negative)
+// packets, whose width collapses to 0, so the surge would never end.
+pub fn check_packets_per_state(packets_per_state: f64) -> Result<(), String> {
+    if !packets_per_state.is_finite() || packets_per_state <= 0.0 {
+        return Err(format!("packets per state must be a positive finite number, got {}", packets_per_state));
+    }
+    
+    return Ok(());
+}
+
+
+// Check that the relay's surge takes at most max_states SEND states, e.g.
+// MAX_STATES for a machine, before it is split: the surge holds about (R - 1) /
+// ln(1 / D) packets down to 1 packet/sec, packets_per_state per state. A huge
+// initial rate or a tiny packets per state would otherwise loop over as many
+// intervals (or take forever building them). The decay rate must be between 0
+// and 1.
+pub fn check_send_state_count(packets_per_state: f64, initial_rate: f64, decay: f64, max_states: usize) -> Result<(), String> {
+    if !(decay > 0.0 && decay < 1.0) {
+        return Err(format!("decay rate must be between 0 and 1, got {}", decay));
+    }
+    
+    let num_states = (initial_rate - 1.0).max(0.0) / (1.0 / decay).ln() / packets_per_state;
+    if num_states.is_nan() || num_states > max_states as f64 {
+        return Err(format!("the surge would take about {:.0} SEND states, more than the cap of {} (give more packets per state, or a lower initial rate or decay rate)", num_states, max_states));
+    }
+    
+    return Ok(());
+}
+
+
+// Number of states in the client-side machine for upload_ratio, without
+// generating it (see generate_client_machine()).
+pub fn count_client_states(upload_ratio: f64) -> usize {
+    if let Err(e) = check_upload_ratio(upload_ratio) {
+        panic!("Invalid upload ratio: {}", e);
+    }
+    
+    if upload_ratio < 1.0 {
+        return 2;
+    }
+    
+    return (upload_ratio as usize) + 1;
+}
+
+
+// Overhead report of RegulaTor machines. The client sends one packet per
+// upload_ratio received at most, so it pads at the rate of the relay divided
+// by the ratio. Real packets wait in the relay for the next packet of the
+// slowest SEND state, and in the client for upload_ratio packets from it.
+pub fn overhead_report(relay_machine: &Machine, client_machine: &Machine, upload_ratio: f64) -> OverheadReport {
+    let relay_bps = peak_padding_bps(relay_machine);
+    let delay = longest_padding_timeout(relay_machine) * upload_ratio.max(1.0);
+    
+    return OverheadReport::new(&[relay_machine, client_machine], relay_bps + relay_bps / upload_ratio, delay);
+}
+
+
+// Wrap the states of a client-side machine.
+fn client_machine(states: Vec<State>) -> Machine {
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+    
+    return machine;
+}
+
+
+// Generate the SEND state for a client-side machine.
+fn generate_client_send_state(num_states: usize, prob_trans: f64, packet_size: f64) -> State {
+    // PaddingSent --> COUNT_0 (prob_trans)
+    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
+    padding_sent.insert(0, prob_trans);
+    if prob_trans < 1.0 {
+        padding_sent.insert(num_states - 1, 1.0 - prob_trans);
+    }
+    
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingSent, padding_sent);
+    
+    // SEND state
+    let mut state = State::new(transitions, num_states);
+    state.bypass = true;
+    state.replace = true;
+    
+    state.timeout = fixed(0.0);
+    
+    state.action = fixed(packet_size);
+    
+    return state;
+}
+
+
+// Generate a COUNT state for a client-side machine.
+fn generate_client_count_state(curr_index: usize, next_index: usize, num_states: usize, prob_trans: f64, limit: f64) -> State {
+    // PaddingRecv --> COUNT_[i+1] (prob_trans)
+    let mut padding_recv: HashMap<usize, f64> = HashMap::new();
+    padding_recv.insert(next_index, prob_trans);
+    if prob_trans < 1.0 {
+        padding_recv.insert(curr_index, 1.0 - prob_trans);
+    }
+    
+    // NonPaddingRecv --> COUNT_[i+1] (prob_trans)
+    let mut nonpadding_recv: HashMap<usize, f64> = HashMap::new();
+    nonpadding_recv.insert(next_index, prob_trans);
+    if prob_trans < 1.0 {
+        nonpadding_recv.insert(curr_index, 1.0 - prob_trans);
+    }
+    
+    // LimitReached --> COUNT_[i+1] (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(next_index, 1.0);
+    
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingRecv, padding_recv);
+    transitions.insert(Event::NonPaddingRecv, nonpadding_recv);
+    if prob_trans < 1.0 {
+        transitions.insert(Event::LimitReached, limit_reached);
+    }
+    
+    // COUNT_i state
+    let mut state = State::new(transitions, num_states);
+    state.action_is_block = true;
+    state.bypass = true;
+    state.replace = true;
+    
+    state.timeout = fixed(0.0);
+    
+    state.action = infinite();
+    
+    state.limit = fixed(limit);
+    
+    return state;
+}
+
+
+// Generate a SEND state for a relay-side machine, padding every timeout_usec
+// (usec, not a rate: see rate::send_interval_usec()).
+pub fn generate_relay_send_state(curr_index: usize, next_index: usize, num_states: usize, padding_count: f64, timeout_usec: f64, surge_reset: bool, packet_size: f64) -> State {
+    assert!(timeout_usec.is_finite() && timeout_usec >= 0.0, "SEND state timeout must be a non-negative number of usec, got {}", timeout_usec);
+    
+    // PaddingSent --> SEND_i (100%)
+    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
+    padding_sent.insert(curr_index, 1.0);
+    
+    // LimitReached --> SEND_[i+1] or loop around (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(next_index, 1.0);
+    
+    // NonPaddingSent --> SEND_0 (100%) if surge_reset
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(FIRST_SEND_STATE_INDEX, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingSent, padding_sent);
+    transitions.insert(Event::LimitReached, limit_reached);
+    if surge_reset {
+        transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+    }
+    
+    // SEND_i state
+    let mut state = State::new(transitions, num_states);
+    state.bypass = true;
+    state.replace = true;
+    
+    state.timeout = fixed(timeout_usec);
+    
+    state.action = fixed(packet_size);
+    
+    state.limit = fixed(padding_count);
+    
+    return state;
+}
+
+
+// Generate the BLOCK state for a relay-side machine.
+pub fn generate_relay_block_state(num_states: usize) -> State {
+    // BlockingBegin --> SEND_0 (100%)
+    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
+    blocking_begin.insert(FIRST_SEND_STATE_INDEX, 1.0);
+    
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::BlockingBegin, blocking_begin);
+    
+    // BLOCK state
+    let mut state = State::new(transitions, num_states);
+    state.action_is_block = true;
+    state.bypass = true;
+    state.replace = true;
+    
+    state.timeout = fixed(0.0);
+    
+    state.action = infinite();
+    
+    return state;
+}
+
+
+// Generate the START state for a relay-side machine, leaving it on the trigger
+// event(s) as the constant defense does. The RegulaTor binaries default to
+// Trigger::Both: the relay machine then starts on the first real packet either
+// way, so a session opened by the client (e.g., the request for a video) has
+// the relay blocking before the response, whose first packet would otherwise
+// leave unshaped. Trigger::Sent waits for the first real packet sent to the
+// client, as in the paper (until then there is nothing to shape), and saves
+// the padding from the request on.
+pub fn generate_relay_start_state(num_states: usize, trigger: Trigger) -> State {
+    // NonPaddingSent/NonPaddingRecv --> BLOCK (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(BLOCK_STATE_INDEX, 1.0);
+    
+    let mut nonpadding_recv: HashMap<usize, f64> = HashMap::new();
+    nonpadding_recv.insert(BLOCK_STATE_INDEX, 1.0);
+    
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    if trigger != Trigger::Recv {
+        transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+    }
+    if trigger != Trigger::Sent {
+        transitions.insert(Event::NonPaddingRecv, nonpadding_recv);
+    }
+    
+    return State::new(transitions, num_states);
+}
+
+
+// Check the units of a relay machine's first SEND state, for --units-check:
+// it pads at the rate of its interval of the RD^t curve, so its timeout (usec)
+// must be about 1e6 / initial_rate (packets / sec), times the time scale. The
+// rate of the interval is at most the initial rate (or 1 packet/sec, the
+// slowest SEND state) and at least the rate where the state's packets run out,
+// which bounds the timeout. A rate or time mixed up with a timeout is off by orders
+// of magnitude. Returns the timeout and its lower bound.
+pub fn check_send_units(relay_machine: &Machine, initial_rate: f64, decay: f64, time_scale: f64) -> Result<(f64, f64), String> {
+    let state = relay_machine.states.get(FIRST_SEND_STATE_INDEX).ok_or("the relay machine has no SEND state")?;
+    let timeout = state.timeout.param1;
+    let width = calc_interval_width_exact(0.0, state.limit.param1, initial_rate, decay);
+
+    let low = send_interval_usec(initial_rate.max(1.0)) * time_scale;
+    let high = 1000000.0 / calculate_rate(width, initial_rate, decay) * time_scale;
+    if !(timeout >= low * (1.0 - UNITS_SLACK) && timeout <= high * (1.0 + UNITS_SLACK)) {
+        return Err(format!("the first SEND state pads every {} usec, expected {} usec (1e6 / initial rate {} packets/sec) up to {} usec", timeout, low, initial_rate, high));
+    }
+
+    return Ok((timeout, low));
+}
+
+
+// Split the RD^t curve into consecutive [t1, t2) intervals of packets_per_state
+// packets each, returned as (t1, t2, rate) with the rate at the middle of the
+// interval. The last interval is the first one that is unbounded or has a rate
+// below 1 packet/sec.
+pub fn send_state_intervals(packets_per_state: f64, initial_rate: f64, decay: f64) -> Vec<(f64, f64, f64)> {
+    return send_state_intervals_with_progress(packets_per_state, initial_rate, decay, |_| ());
+}
+
+
+// send_state_intervals(), calling on_interval with the number of intervals so
+// far after each one (e.g., to report progress on long surges).
+pub fn send_state_intervals_with_progress<F>(packets_per_state: f64, initial_rate: f64, decay: f64, mut on_interval: F) -> Vec<(f64, f64, f64)> where F: FnMut(usize) {
+    if let Err(e) = check_packets_per_state(packets_per_state) {
+        panic!("Invalid packets per state: {}", e);
+    }
+    if let Err(e) = check_send_state_count(packets_per_state, initial_rate, decay, MAX_SEND_INTERVALS) {
+        panic!("Invalid relay machine: {}", e);
+    }
+    
+    let mut intervals: Vec<(f64, f64, f64)> = Vec::new();
+    let mut t1 = 0.0;
+    let mut keep_going = true;
+    
+    while keep_going {
+        let width = calc_interval_width(t1, packets_per_state, initial_rate, decay, WIDTH_TOLERANCE);
+        let middle = t1 + (width / 2.0);
+        let t2 = t1 + width;
+        
+        let rate = calculate_rate(middle, initial_rate, decay);
+        if width == INFINITY || rate < 1.0 {
+            keep_going = false;
+        }
+        
+        debug(|| format!("SEND interval {}: [{}, {}) sec, width {} sec, rate {} packets/sec", intervals.len(), t1, t2, width, rate));
+        
+        intervals.push((t1, t2, rate));
+        t1 = t2;
+        on_interval(intervals.len());
+    }
+    
+    return intervals;
+}
+
+
+// Number of relay SEND states for the parameters, i.e., the number of
+// intervals from send_state_intervals(), without building any states.
+pub fn count_send_states(packets_per_state: f64, initial_rate: f64, decay: f64) -> usize {
+    return send_state_intervals(packets_per_state, initial_rate, decay).len();
+}
+
+
+// Generate an Adapted RegulaTor relay-side machine, starting on the trigger
+// event(s). The last SEND state ends the machine, or restarts the surge at the
+// first SEND state if loop_surge. Fails past MAX_STATES states.
+pub fn generate_adapted_relay_machine(packets_per_state: f64, initial_rate: f64, decay: f64, loop_surge: bool, trigger: Trigger, packet_size: f64) -> Result<Machine, String> {
+    return generate_adapted_relay_machine_with_progress(packets_per_state, initial_rate, decay, loop_surge, trigger, packet_size, |_| (), |_, _, _| ());
+}
+
+
+// generate_adapted_relay_machine(), calling on_interval as
+// send_state_intervals_with_progress() does, then on_send_state with the index
+// of each SEND state, its (t1, t2, rate) interval (the rate as padded, at least
+// 1 packet/sec), and its timeout (usec), e.g., to print the states.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_adapted_relay_machine_with_progress<F, G>(packets_per_state: f64, initial_rate: f64, decay: f64, loop_surge: bool, trigger: Trigger, packet_size: f64, on_interval: F, mut on_send_state: G) -> Result<Machine, String> where F: FnMut(usize), G: FnMut(usize, (f64, f64, f64), f64) {
+    let intervals = send_state_intervals_with_progress(packets_per_state, initial_rate, decay, on_interval);
+    let num_send_states = intervals.len();
+    
+    // Set up state vector
+    let num_states = num_send_states + 2;
+    if num_states > MAX_STATES {
+        return Err(format!("The relay machine would take {} states, more than the cap of {} (give more packets per state, or a lower initial rate or decay rate)", num_states, MAX_STATES));
+    }
+    let mut states: Vec<State> = Vec::with_capacity(num_states);
+    
+    // START and BLOCK states
+    states.push(generate_relay_start_state(num_states, trigger));
+    states.push(generate_relay_block_state(num_states));
+    
+    // SEND states
+    for (i, &(t1, t2, interval_rate)) in intervals.iter().enumerate() {
+        let width = t2 - t1;
+        
+        let mut rate = interval_rate;
+        let mut next_idx = i + FIRST_SEND_STATE_INDEX + 1;
+        let curr_idx = i + FIRST_SEND_STATE_INDEX;
+        
+        if width == INFINITY || rate < 1.0 {
+            rate = 1.0;
+            next_idx = if loop_surge { FIRST_SEND_STATE_INDEX } else { STATEEND };
+        }
+        
+        let timeout = send_interval_usec(rate);
+        on_send_state(curr_idx, (t1, t2, rate), timeout);
+        
+        states.push(generate_relay_send_state(curr_idx, next_idx, num_states, packets_per_state, timeout, rate < BURST_RESET_RATE, packet_size));
+    }
+    
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+    
+    return Ok(machine);
+}
+
+
+// Generate a RegulaTor relay-side machine (see bin/regulator.rs), starting on
+// the trigger event(s). Its SEND states hold exactly packets_per_state packets
+// of the RD^t curve each, sent at their mean rate, and the last keeps padding
+// at MIN_RATE. Real traffic restarts the surge in the SEND states where the
+// surge threshold times the rate is below the initial rate.
+pub fn generate_regulator_relay_machine(packets_per_state: f64, initial_rate: f64, decay: f64, threshold: f64, trigger: Trigger, packet_size: f64) -> Result<Machine, String> {
+    if let Err(e) = check_packets_per_state(packets_per_state) {
+        return Err(format!("Invalid packets per state: {}", e));
+    }
+    if let Err(e) = check_send_state_count(packets_per_state, initial_rate, decay, MAX_STATES) {
+        return Err(format!("Invalid relay machine: {}", e));
+    }
+
+    let num_send_states = count_relay_send_states(packets_per_state, initial_rate, decay, usize::MAX).unwrap();
+
+    // Set up state vector
+    let num_states = num_send_states + 2;
+    let mut states: Vec<State> = Vec::with_capacity(num_states);
+
+    // START and BLOCK states
+    states.push(generate_relay_start_state(num_states, trigger));
+    states.push(generate_relay_block_state(num_states));
+
+    // SEND states
+    let mut t1 = 0.0;
+
+    for i in 0..num_send_states {
+        let width = calc_interval_width_exact(t1, packets_per_state, initial_rate, decay);
+
+        let mut rate = packets_per_state / width;
+        let curr_idx = i + FIRST_SEND_STATE_INDEX;
+        let mut next_idx = curr_idx + 1;
+
+        // Last state loops on itself at the minimum rate
+        if width == INFINITY || rate < MIN_RATE {
+            rate = MIN_RATE;
+            next_idx = curr_idx;
+        }
+
+        let surge_reset = rate * threshold < initial_rate;
+        states.push(generate_relay_send_state(curr_idx, next_idx, num_states, packets_per_state, send_interval_usec(rate), surge_reset, packet_size));
+
+        t1 += width;
+    }
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+
+    return Ok(machine);
+}
+
+
+// Number of SEND states of a RegulaTor relay machine, down to MIN_RATE, or None
+// if it would take more than max_send_states.
+pub fn count_relay_send_states(packets_per_state: f64, initial_rate: f64, decay: f64, max_send_states: usize) -> Option<usize> {
+    let mut t1 = 0.0;
+    let mut keep_going = true;
+    let mut num_send_states = 0;
+
+    while keep_going {
+        if num_send_states == max_send_states {
+            return None;
+        }
+
+        let width = calc_interval_width_exact(t1, packets_per_state, initial_rate, decay);
+
+        if width == INFINITY || packets_per_state / width < MIN_RATE {
+            keep_going = false;
+        }
+
+        t1 += width;
+        num_send_states += 1;
+    }
+
+    return Some(num_send_states);
+}
+
+
+// Smallest whole number of packets per state above packets_per_state for which
+// the RegulaTor relay machine takes at most max_states states (at least 3),
+// found by doubling and then bisection, as fewer SEND states hold more packets
+// each. A single SEND state holds the whole surge, so the doubling ends.
+pub fn coarsen_packets_per_state(packets_per_state: f64, initial_rate: f64, decay: f64, max_states: usize) -> f64 {
+    return coarsen_until(packets_per_state, |packets_per_state| count_relay_send_states(packets_per_state, initial_rate, decay, max_states - FIRST_SEND_STATE_INDEX).is_some());
+}
+
+
+// Smallest whole number of packets per state above packets_per_state that fits
+// (see coarsen_packets_per_state()).
+fn coarsen_until<F>(packets_per_state: f64, fits: F) -> f64 where F: Fn(f64) -> bool {
+    let mut low = packets_per_state.floor();
+    let mut high = low + 1.0;
+    while !fits(high) {
+        low = high;
+        high *= 2.0;
+    }
+
+    while high - low > 1.0 {
+        let mid = ((low + high) / 2.0).floor();
+        if fits(mid) {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+
+    return high;
+}
+
+
+// Packets per state of a relay machine capped by --max-states, given
+// count_send_states(packets per state, cap), the number of its SEND states or
+// None past the cap: the packets per state, coarsened with --coarsen (and the
+// number given if they were), and the cap.
+fn fit_max_states<F>(cli: &Args, packets_per_state: f64, count_send_states: F) -> Result<(f64, Option<f64>, Option<usize>), String> where F: Fn(f64, usize) -> Option<usize> {
+    let max_states: Option<usize> = cli.value("--max-states").map(|v| parse_count(v, "max states")).transpose()?;
+    if cli.has("--coarsen") && max_states.is_none() {
+        return Err(String::from("--coarsen needs --max-states"));
+    }
+    let max_states = match max_states {
+        Some(max_states) => max_states,
+        None => return Ok((packets_per_state, None, None)),
+    };
+    if max_states <= FIRST_SEND_STATE_INDEX {
+        return Err(format!("Max states must be at least {}, for the START, BLOCK, and a SEND state", FIRST_SEND_STATE_INDEX + 1));
+    }
+
+    let max_send_states = max_states - FIRST_SEND_STATE_INDEX;
+    if count_send_states(packets_per_state, max_send_states).is_some() {
+        return Ok((packets_per_state, None, Some(max_states)));
+    }
+    if !cli.has("--coarsen") {
+        let needed = match count_send_states(packets_per_state, MAX_STATES) {
+            Some(num_send_states) => (num_send_states + FIRST_SEND_STATE_INDEX).to_string(),
+            None => format!("more than {}", MAX_STATES),
+        };
+        return Err(format!("The relay machine would take {} states, more than --max-states {} (give more packets per state, or --coarsen to increase them until it fits)", needed, max_states));
+    }
+
+    let coarsened = coarsen_until(packets_per_state, |packets_per_state| count_send_states(packets_per_state, max_send_states).is_some());
+    return Ok((coarsened, Some(packets_per_state), Some(max_states)));
+}
+
+
+// Apply the options to the relay and client machines of a RegulaTor, and name
+// them as the binaries print them.
+fn named_relay_and_client(relay_machine: Machine, client_machine: Machine, options: &MachineOptions) -> Result<Vec<(String, Machine)>, String> {
+    let mut machines = vec![("Relay machine", relay_machine), ("Client machine", client_machine)];
+    for (name, machine) in machines.iter_mut() {
+        options.apply(machine)?;
+        check_has_states(name, machine)?;
+    }
+
+    return Ok(machines.into_iter().map(|(name, machine)| (name.to_string(), machine)).collect());
+}
+
+
+// Parameters of the adapted_regulator binary (see bin/adapted_regulator.rs)
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptedRegulator {
+    // Initial surge rate R (packets / sec) and decay rate D
+    pub initial_rate: f64,
+    pub decay: f64,
+    // Upload ratio U, and the received packets counted per COUNT state
+    pub upload_ratio: f64,
+    pub client_limit: f64,
+    // Packets per SEND state (approximation granularity), once coarsened to
+    // fit --max-states with --coarsen, and the number given if it was
+    pub packets_per_state: f64,
+    pub coarsened_from: Option<f64>,
+    pub max_states: Option<usize>,
+    pub loop_surge: bool,
+    pub trigger: Trigger,
+    // Size of padding packets (bytes)
+    pub packet_size: f64,
+    pub options: MachineOptions,
+}
+
+
+impl Default for AdaptedRegulator {
+    // The paper's configuration, but starting on real traffic in both
+    // directions (the default of --trigger, see
+    // defaults::ADAPTED_REGULATOR_TRIGGER).
+    fn default() -> AdaptedRegulator {
+        return AdaptedRegulator {
+            initial_rate: ADAPTED_REGULATOR_INITIAL_RATE,
+            decay: ADAPTED_REGULATOR_DECAY_RATE,
+            upload_ratio: ADAPTED_REGULATOR_UPLOAD_RATIO,
+            client_limit: ADAPTED_REGULATOR_CLIENT_LIMIT,
+            packets_per_state: ADAPTED_REGULATOR_PACKETS_PER_STATE,
+            coarsened_from: None,
+            max_states: None,
+            loop_surge: false,
+            trigger: ADAPTED_REGULATOR_TRIGGER,
+            packet_size: PACKET_SIZE,
+            options: MachineOptions::default(),
+        };
+    }
+}
+
+
+impl AdaptedRegulator {
+    // Parse the arguments of the adapted_regulator binary (see parse_args()).
+    pub fn from_args(cli: &Args) -> Result<AdaptedRegulator, String> {
+        let args = &cli.positional;
+        if args.len() > 6 {
+            return Err(format!("Usage: {} [initial rate = 500] [decay rate = 0.75] [upload ratio = 4] [packets per state = 20] [client limit = 2.0] [--warn-unreachable] [--explain] [--dry-run] [--annotate] [--hash] [--ffi] [--emit-rust] [--base64] [--report] [--include-small-packets] [--strict] [--units-check] [--packet-size <bytes>] [--max-blocking-frac <fraction>] [--allowed-blocked-microsec <usec>] [--time-scale <factor>] [--loop-surge] [--trigger <sent|recv|both>] [--verbose-states] [--progress] [--count-only] [--max-states <n>] [--coarsen]", args[0]));
+        }
+
+        let initial_rate:      f64 = args.get(1).map_or(Ok(ADAPTED_REGULATOR_INITIAL_RATE), |v| parse_finite_positive(v, "initial rate"))?; // RegulaTor param = R, initial surge rate (packets / sec)
+        let decay_rate:        f64 = args.get(2).map_or(Ok(ADAPTED_REGULATOR_DECAY_RATE), |v| parse_finite(v, "decay rate"))?; // RegulaTor param = D, decay rate
+        let upload_ratio:      f64 = args.get(3).map_or(Ok(ADAPTED_REGULATOR_UPLOAD_RATIO), |v| parse_finite(v, "upload ratio"))?; // RegulaTor param = U, upload ratio
+        let packets_per_state: f64 = args.get(4).map_or(Ok(ADAPTED_REGULATOR_PACKETS_PER_STATE), |v| parse_finite_positive(v, "packets per state"))?; // number of packets per state (approximation granularity)
+        if !(decay_rate > 0.0 && decay_rate < 1.0) {
+            return Err(String::from("Decay rate must be between 0 and 1"));
+        }
+        if let Err(e) = check_upload_ratio(upload_ratio) {
+            return Err(format!("Invalid upload ratio: {}", e));
+        }
+        if let Err(e) = check_send_state_count(packets_per_state, initial_rate, decay_rate, MAX_SEND_INTERVALS) {
+            return Err(format!("Invalid relay machine: {}", e));
+        }
+
+        // The SEND states of the relay machine, counted as it generates them
+        let (packets_per_state, coarsened_from, max_states) = fit_max_states(cli, packets_per_state, |packets_per_state, max_send_states| {
+            return Some(count_send_states(packets_per_state, initial_rate, decay_rate)).filter(|&num_send_states| num_send_states <= max_send_states);
+        })?;
+
+        let client_limit: f64 = args.get(5).map_or(Ok(ADAPTED_REGULATOR_CLIENT_LIMIT), |v| parse_finite_positive(v, "client limit").and_then(|limit| strict_count(cli, limit, "client limit")))?; // received packets counted per COUNT state
+
+        return Ok(AdaptedRegulator {
+            initial_rate: initial_rate,
+            decay: decay_rate,
+            upload_ratio: upload_ratio,
+            client_limit: client_limit,
+            packets_per_state: packets_per_state,
+            coarsened_from: coarsened_from,
+            max_states: max_states,
+            loop_surge: cli.has("--loop-surge"),
+            trigger: cli.value("--trigger").map_or(Ok(ADAPTED_REGULATOR_TRIGGER), parse_trigger)?,
+            packet_size: packet_size(cli)?,
+            options: machine_options(cli)?,
+        });
+    }
+
+
+    // The relay machine and then the client machine, with the names the binary
+    // prints.
+    pub fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        return self.named_machines_with_progress(|_| (), |_, _, _| ());
+    }
+
+
+    // named_machines(), calling on_interval and on_send_state while building
+    // the relay machine (see generate_adapted_relay_machine_with_progress()).
+    pub fn named_machines_with_progress<F, G>(&self, on_interval: F, on_send_state: G) -> Result<Vec<(String, Machine)>, String> where F: FnMut(usize), G: FnMut(usize, (f64, f64, f64), f64) {
+        let relay_machine = generate_adapted_relay_machine_with_progress(self.packets_per_state, self.initial_rate, self.decay, self.loop_surge, self.trigger, self.packet_size, on_interval, on_send_state)?;
+        let client_machine = generate_client_machine(self.upload_ratio, self.client_limit, self.packet_size);
+        return named_relay_and_client(relay_machine, client_machine, &self.options);
+    }
+
+
+    // Number of states of the relay and client machines, without generating
+    // them (for --count-only).
+    pub fn count_states(&self) -> (usize, usize) {
+        // START and BLOCK states, followed by the SEND states
+        return (count_send_states(self.packets_per_state, self.initial_rate, self.decay) + FIRST_SEND_STATE_INDEX, count_client_states(self.upload_ratio));
+    }
+}
+
+
+// Parse the arguments of the adapted_regulator binary (without the program
+// name) into its parameters.
+pub fn parse_adapted(args: &[String]) -> Result<AdaptedRegulator, String> {
+    let cli = try_parse_args([&[String::from("adapted_regulator")], args].concat(), ADAPTED_SWITCHES, ADAPTED_OPTIONS)?;
+    return AdaptedRegulator::from_args(&cli);
+}
+
+
+// Generate the machines of the adapted_regulator binary for its arguments
+// (without the program name), e.g., generate_adapted(&["500", "0.75"]).
+pub fn generate_adapted(args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse_adapted(args)?.named_machines();
+}
+
+
+// Parameters of the regulator binary (see bin/regulator.rs)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regulator {
+    // Initial surge rate R (packets / sec), decay rate D, and surge threshold T
+    pub initial_rate: f64,
+    pub decay: f64,
+    pub threshold: f64,
+    // Upload ratio U
+    pub upload_ratio: f64,
+    // Packets per SEND state, once coarsened to fit --max-states with
+    // --coarsen, and the number given if it was
+    pub packets_per_state: f64,
+    pub coarsened_from: Option<f64>,
+    pub max_states: Option<usize>,
+    pub trigger: Trigger,
+    // Size of padding packets (bytes)
+    pub packet_size: f64,
+    pub options: MachineOptions,
+}
+
+
+impl Regulator {
+    // Parse the arguments of the regulator binary (see parse_args()).
+    pub fn from_args(cli: &Args) -> Result<Regulator, String> {
+        let args = &cli.positional;
+        if args.len() != 6 {
+            return Err(format!("Usage: {} <initial rate> <decay rate> <surge threshold> <upload ratio> <packets per state> [--warn-unreachable] [--explain] [--dry-run] [--annotate] [--hash] [--ffi] [--emit-rust] [--base64] [--report] [--include-small-packets] [--strict] [--units-check] [--packet-size <bytes>] [--max-blocking-frac <fraction>] [--allowed-blocked-microsec <usec>] [--time-scale <factor>] [--trigger <sent|recv|both>] [--max-states <n>] [--coarsen]", args[0]));
+        }
+
+        let initial_rate:      f64 = parse_finite_positive(&args[1], "initial rate")?; // RegulaTor param = R, initial surge rate (packets / sec)
+        let decay_rate:        f64 = parse_finite(&args[2], "decay rate")?; // RegulaTor param = D, decay rate
+        let threshold:         f64 = parse_finite_positive(&args[3], "surge threshold")?; // RegulaTor param = T, surge threshold
+        let upload_ratio:      f64 = parse_finite(&args[4], "upload ratio")?; // RegulaTor param = U, upload ratio
+        let packets_per_state: f64 = parse_finite_positive(&args[5], "packets per state")?; // number of packets per state (approximation granularity)
+        if !(decay_rate > 0.0 && decay_rate < 1.0) {
+            return Err(String::from("Decay rate must be between 0 and 1"));
+        }
+        if let Err(e) = check_upload_ratio(upload_ratio) {
+            return Err(format!("Invalid upload ratio: {}", e));
+        }
+
+        let (packets_per_state, coarsened_from, max_states) = fit_max_states(cli, packets_per_state, |packets_per_state, max_send_states| count_relay_send_states(packets_per_state, initial_rate, decay_rate, max_send_states))?;
+
+        return Ok(Regulator {
+            initial_rate: initial_rate,
+            decay: decay_rate,
+            threshold: threshold,
+            upload_ratio: upload_ratio,
+            packets_per_state: packets_per_state,
+            coarsened_from: coarsened_from,
+            max_states: max_states,
+            // The relay machine starts as that of the Adapted RegulaTor by
+            // default
+            trigger: cli.value("--trigger").map_or(Ok(ADAPTED_REGULATOR_TRIGGER), parse_trigger)?,
+            packet_size: packet_size(cli)?,
+            options: machine_options(cli)?,
+        });
+    }
+
+
+    // The relay machine and then the client machine, with the names the binary
+    // prints.
+    pub fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        let relay_machine = generate_regulator_relay_machine(self.packets_per_state, self.initial_rate, self.decay, self.threshold, self.trigger, self.packet_size)?;
+        let client_machine = generate_client_machine(self.upload_ratio, CLIENT_LIMIT, self.packet_size);
+        return named_relay_and_client(relay_machine, client_machine, &self.options);
+    }
+}
+
+
+// Parse the arguments of the regulator binary (without the program name) into
+// its parameters.
+pub fn parse(args: &[String]) -> Result<Regulator, String> {
+    let cli = try_parse_args([&[String::from("regulator")], args].concat(), SWITCHES, OPTIONS)?;
+    return Regulator::from_args(&cli);
+}
+
+
+// Generate the machines of the regulator binary for its arguments (without the
+// program name), e.g., generate(&["500", "0.75", "2", "4", "20"]).
+pub fn generate(args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse(args)?.named_machines();
+}
@@ -0,0 +1,131 @@
+// Overhead report of generated machines, for the --report option of the
+// generators: the size of the machines, and estimates of the padding
+// bandwidth and the delay they add. The estimates come from the parameters of
+// each defense (e.g., the send interval of the constant defense), or from the
+// padding states of the machines where the parameters do not give them
+// directly (see peak_padding_bps()).
+
+use maybenot::{
+machine::Machine,
+dist::{Dist, DistType}
+};
+
+use crate::overhead::{bandwidth_bps, pps_from_interval};
+use crate::output::serialize_stable;
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverheadReport {
+    // States of all the machines
+    pub states: usize,
+    // Length of the serialized machines, summed
+    pub serialized_len: usize,
+    // Peak bandwidth of the padding, without real traffic to replace it (bits
+    // / sec, summed over the machines, NaN if unknown)
+    pub est_padding_bps: f64,
+    // Longest a real packet is held back by blocking (usec, 0 for machines
+    // that never block)
+    pub est_max_added_delay_us: f64,
+}
+
+
+impl OverheadReport {
+    // Report on the machines of a defense, with its estimates.
+    pub fn new(machines: &[&Machine], est_padding_bps: f64, est_max_added_delay_us: f64) -> OverheadReport {
+        return OverheadReport {
+            states: machines.iter().map(|machine| machine.states.len()).sum(),
+            serialized_len: machines.iter().map(|machine| serialize_stable(machine).len()).sum(),
+            est_padding_bps: est_padding_bps,
+            est_max_added_delay_us: est_max_added_delay_us,
+        };
+    }
+
+
+    // Lines of the --report view.
+    pub fn lines(&self) -> Vec<String> {
+        let bandwidth = if self.est_padding_bps.is_nan() { String::from("unknown") } else { format!("{:.0} bits/sec", self.est_padding_bps) };
+
+        return vec![
+            format!("States: {}", self.states),
+            format!("Serialized length: {}", self.serialized_len),
+            format!("Estimated padding bandwidth: {}", bandwidth),
+            format!("Estimated max added delay: {:.0} usec", self.est_max_added_delay_us),
+        ];
+    }
+}
+
+
+// Print the --report view of a report.
+pub fn print_report(report: &OverheadReport) {
+    for line in report.lines() {
+        println!("{}", line);
+    }
+}
+
+
+// Send rate of one packet every interval (usec), for the --report lines of
+// the generators that take intervals, e.g. "250 packets/sec (every 4000
+// usec)". The rate is rounded to 2 decimals.
+pub fn send_rate(interval: f64) -> String {
+    return format!("{} packets/sec (every {} usec)", (pps_from_interval(interval) * 100.0).round() / 100.0, interval);
+}
+
+
+// Peak bandwidth (bits / sec) of the padding states of a machine, i.e., of its
+// fastest one: one padding packet of the mean size per mean timeout. Only
+// Uniform padding sizes (e.g., fixed) with Uniform or Normal timeouts are
+// known, NaN if any padding state has another distribution.
+pub fn peak_padding_bps(machine: &Machine) -> f64 {
+    let mut peak: f64 = 0.0;
+
+    for state in &machine.states {
+        if state.action_is_block || state.action.dist == DistType::None {
+            continue;
+        }
+
+        let size = match mean_size(&state.action) {
+            Some(size) => size,
+            None => return f64::NAN,
+        };
+        let timeout = match mean_timeout(&state.timeout) {
+            Some(timeout) => timeout,
+            None => return f64::NAN,
+        };
+        peak = peak.max(bandwidth_bps(timeout, size));
+    }
+
+    return peak;
+}
+
+
+// Longest mean timeout (usec) of the padding states of a machine, or 0 if it
+// has none, with the distributions of peak_padding_bps().
+pub fn longest_padding_timeout(machine: &Machine) -> f64 {
+    return machine.states.iter()
+        .filter(|state| !state.action_is_block && state.action.dist != DistType::None)
+        .filter_map(|state| mean_timeout(&state.timeout))
+        .fold(0.0, f64::max);
+}
+
+
+// Mean of a Uniform or Normal timeout, ignoring the clamping of Dist::max.
+fn mean_timeout(timeout: &Dist) -> Option<f64> {
+    let mean = match timeout.dist {
+        DistType::Uniform => (timeout.param1 + timeout.param2) / 2.0,
+        DistType::Normal => timeout.param1,
+        _ => return None,
+    };
+
+    return Some(timeout.start + mean);
+}
+
+
+// Mean of a Uniform padding size, e.g., of a fixed one (see
+// dist_helpers::fixed()), ignoring the rounding and clamping of the size.
+fn mean_size(size: &Dist) -> Option<f64> {
+    if size.dist == DistType::Uniform {
+        return Some(size.start + (size.param1 + size.param2) / 2.0);
+    }
+
+    return None;
+}
@@ -0,0 +1,871 @@
+// Machine-readable descriptions of the parameters of each defense generator,
+// e.g., for a front-end that renders a form per defense and validates input
+// before running the generator. The parameters are listed here once, in the
+// order and with the names, units, defaults, and ranges of the generators'
+// --help text, and tests/schema.rs runs every generator with them to keep the
+// two in sync.
+//
+// parameter_schema() describes each parameter as a JSON Schema for its value
+// ("type", "enum", "default", "minimum", "exclusiveMinimum", ...), along with
+// how it is passed on the command line: a "position" among the positional
+// arguments, or a "flag". Lists of numbers are passed comma-separated, and
+// their ranges apply to each number ("items").
+
+use serde_json::{json, Map, Value};
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Number,
+    Integer,
+    Choice(&'static [&'static str]),
+    Switch,
+    File,
+    NumberList,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Inclusive(f64),
+    Exclusive(f64),
+}
+
+
+// A parameter of a generator. Positional parameters have no flag, and are
+// passed in the order listed. The default (unset if required, or if it depends
+// on other parameters) and the example are given as passed on the command
+// line. Options listed in requires must be given along with this one, and those
+// listed in excludes must not be, at least with their examples (e.g., --dist
+// pareto for the jitter of the uniform dist).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Param {
+    pub name: &'static str,
+    pub flag: Option<&'static str>,
+    pub kind: Kind,
+    pub unit: Option<&'static str>,
+    pub default: Option<&'static str>,
+    pub min: Option<Limit>,
+    pub max: Option<Limit>,
+    pub requires: &'static [&'static str],
+    pub excludes: &'static [&'static str],
+    pub example: &'static str,
+    pub description: &'static str,
+}
+
+
+// The generator binary, and its parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Defense {
+    pub name: &'static str,
+    pub params: &'static [Param],
+}
+
+
+// Base of the parameters below
+const NUMBER: Param = Param {
+    name: "",
+    flag: None,
+    kind: Kind::Number,
+    unit: None,
+    default: None,
+    min: None,
+    max: None,
+    requires: &[],
+    excludes: &[],
+    example: "",
+    description: "",
+};
+
+const SWITCH: Param = Param { kind: Kind::Switch, ..NUMBER };
+
+// Shared by every generator (see args::GENERATOR_OPTIONS)
+const PACKET_SIZE: Param = Param {
+    name: "packet size",
+    flag: Some("--packet-size"),
+    unit: Some("bytes"),
+    default: Some("1500"),
+    min: Some(Limit::Exclusive(0.0)),
+    example: "1200",
+    description: "size of padding packets",
+    ..NUMBER
+};
+
+const MAX_BLOCKING_FRAC: Param = Param {
+    name: "max blocking frac",
+    flag: Some("--max-blocking-frac"),
+    unit: Some("fraction"),
+    default: Some("0"),
+    min: Some(Limit::Inclusive(0.0)),
+    max: Some(Limit::Inclusive(1.0)),
+    example: "0.5",
+    description: "cap on blocking, as a fraction of each machine's lifetime (0 = no cap)",
+    ..NUMBER
+};
+
+const ALLOWED_BLOCKED_MICROSEC: Param = Param {
+    name: "allowed blocked microsec",
+    flag: Some("--allowed-blocked-microsec"),
+    kind: Kind::Integer,
+    unit: Some("usec"),
+    default: Some("0"),
+    min: Some(Limit::Inclusive(0.0)),
+    example: "1000000",
+    description: "blocking each machine may do regardless of max blocking frac",
+    ..NUMBER
+};
+
+const TIME_SCALE: Param = Param {
+    name: "time scale",
+    flag: Some("--time-scale"),
+    unit: Some("factor"),
+    default: Some("1"),
+    min: Some(Limit::Exclusive(0.0)),
+    example: "2",
+    description: "multiplier of every timeout, but not of limits, padding sizes, or block durations",
+    ..NUMBER
+};
+
+const INCLUDE_SMALL_PACKETS: Param = Param {
+    name: "include small packets",
+    flag: Some("--include-small-packets"),
+    description: "let packets of at most 52 bytes trigger transitions too",
+    ..SWITCH
+};
+
+const STRICT: Param = Param {
+    name: "strict",
+    flag: Some("--strict"),
+    description: "reject counts of packets or bytes that are not integers, instead of rounding them",
+    ..SWITCH
+};
+
+const SEND_INTERVAL: Param = Param {
+    name: "send interval",
+    unit: Some("usec"),
+    min: Some(Limit::Exclusive(0.0)),
+    example: "4000",
+    description: "time between padding packets",
+    ..NUMBER
+};
+
+// Of the generators with send intervals, which are then given in milliseconds
+const MS: Param = Param {
+    name: "ms",
+    flag: Some("--ms"),
+    description: "give the send interval in milliseconds instead of usec",
+    ..SWITCH
+};
+
+const INITIAL_RATE: Param = Param {
+    name: "initial rate",
+    unit: Some("packets/sec"),
+    min: Some(Limit::Exclusive(0.0)),
+    example: "500",
+    description: "R, rate at the start of a surge",
+    ..NUMBER
+};
+
+const DECAY_RATE: Param = Param {
+    name: "decay rate",
+    min: Some(Limit::Exclusive(0.0)),
+    max: Some(Limit::Exclusive(1.0)),
+    example: "0.75",
+    description: "D, decay of the rate per second",
+    ..NUMBER
+};
+
+const UPLOAD_RATIO: Param = Param {
+    name: "upload ratio",
+    min: Some(Limit::Exclusive(0.0)),
+    example: "4",
+    description: "U, packets received per packet sent by the client",
+    ..NUMBER
+};
+
+const PACKETS_PER_STATE: Param = Param {
+    name: "packets per state",
+    unit: Some("packets"),
+    min: Some(Limit::Exclusive(0.0)),
+    example: "20",
+    description: "padding packets per relay SEND state",
+    ..NUMBER
+};
+
+// See regulator::generate_relay_start_state()
+const RELAY_TRIGGER: Param = Param {
+    name: "trigger",
+    flag: Some("--trigger"),
+    kind: Kind::Choice(&["sent", "recv", "both"]),
+    default: Some("both"),
+    example: "sent",
+    description: "real traffic of the relay that starts its machine",
+    ..NUMBER
+};
+
+// See regulator::fit_max_states()
+const RELAY_MAX_STATES: Param = Param {
+    name: "max states",
+    flag: Some("--max-states"),
+    kind: Kind::Integer,
+    min: Some(Limit::Inclusive(3.0)),
+    example: "1000",
+    description: "cap on the states of the relay machine (larger machines are rejected, unless --coarsen is given)",
+    ..NUMBER
+};
+
+const COARSEN: Param = Param {
+    name: "coarsen",
+    flag: Some("--coarsen"),
+    requires: &["--max-states"],
+    description: "increase the packets per state to the smallest whole number that fits the relay machine in --max-states",
+    ..SWITCH
+};
+
+const TRAIL: Param = Param {
+    unit: Some("packets"),
+    min: Some(Limit::Inclusive(0.0)),
+    ..NUMBER
+};
+
+
+pub const CONSTANT: Defense = Defense {
+    name: "constant",
+    params: &[
+        Param { default: Some("4000"), ..SEND_INTERVAL },
+        Param {
+            name: "dist",
+            flag: Some("--dist"),
+            kind: Kind::Choice(&["uniform", "exponential", "pareto"]),
+            default: Some("uniform"),
+            example: "pareto",
+            description: "distribution of the time between packets, with the send interval as its mean",
+            ..NUMBER
+        },
+        Param {
+            name: "shape",
+            flag: Some("--shape"),
+            default: Some("2"),
+            min: Some(Limit::Exclusive(1.0)),
+            example: "3",
+            description: "shape of the Pareto distribution",
+            ..NUMBER
+        },
+        Param {
+            name: "jitter frac",
+            flag: Some("--jitter-frac"),
+            default: Some("0"),
+            min: Some(Limit::Inclusive(0.0)),
+            max: Some(Limit::Inclusive(1.0)),
+            excludes: &["--dist"],
+            example: "0.1",
+            description: "with the uniform dist, draw the time between packets from the send interval plus or minus this fraction of it, keeping the mean rate",
+            ..NUMBER
+        },
+        Param {
+            name: "trigger",
+            flag: Some("--trigger"),
+            kind: Kind::Choice(&["sent", "recv", "both"]),
+            default: Some("both"),
+            example: "sent",
+            description: "real traffic that starts the padding",
+            ..NUMBER
+        },
+        Param { name: "pad only", flag: Some("--pad-only"), description: "add the padding on top of the real traffic, without blocking it", ..SWITCH },
+        Param { name: "asymmetric", flag: Some("--asymmetric"), description: "generate a client machine and a relay machine, padding each direction at its own interval", ..SWITCH },
+        Param { name: "both sides", flag: Some("--both-sides"), description: "the same as asymmetric, for a two-sided deployment", ..SWITCH },
+        Param {
+            name: "up interval",
+            flag: Some("--up-interval"),
+            requires: &["--asymmetric"],
+            example: "8000",
+            description: "send interval of the client machine with asymmetric (default: send interval)",
+            ..SEND_INTERVAL
+        },
+        Param {
+            name: "down interval",
+            flag: Some("--down-interval"),
+            requires: &["--asymmetric"],
+            example: "1000",
+            description: "send interval of the relay machine with asymmetric (default: send interval)",
+            ..SEND_INTERVAL
+        },
+        Param {
+            name: "target overhead",
+            flag: Some("--target-overhead"),
+            min: Some(Limit::Inclusive(0.0)),
+            requires: &["--baseline-mbps"],
+            example: "0.25",
+            description: "instead of a send interval, solve for the interval whose padding adds this bandwidth overhead over the baseline",
+            ..NUMBER
+        },
+        Param {
+            name: "baseline mbps",
+            flag: Some("--baseline-mbps"),
+            unit: Some("Mbps"),
+            min: Some(Limit::Exclusive(0.0)),
+            requires: &["--target-overhead"],
+            example: "2.4",
+            description: "bandwidth of the undefended stream for the target overhead",
+            ..NUMBER
+        },
+        MS,
+        PACKET_SIZE,
+        MAX_BLOCKING_FRAC,
+        ALLOWED_BLOCKED_MICROSEC,
+        TIME_SCALE,
+        INCLUDE_SMALL_PACKETS,
+        STRICT,
+    ],
+};
+
+pub const BUFLO: Defense = Defense {
+    name: "buflo",
+    params: &[
+        Param {
+            name: "tau",
+            unit: Some("sec"),
+            min: Some(Limit::Exclusive(0.0)),
+            example: "10",
+            description: "minimum duration of each round of padding",
+            ..NUMBER
+        },
+        SEND_INTERVAL,
+        MS,
+        PACKET_SIZE,
+        MAX_BLOCKING_FRAC,
+        ALLOWED_BLOCKED_MICROSEC,
+        TIME_SCALE,
+        INCLUDE_SMALL_PACKETS,
+        STRICT,
+    ],
+};
+
+pub const ADAPTED_FRONT: Defense = Defense {
+    name: "adapted_front",
+    params: &[
+        Param {
+            name: "padding window",
+            unit: Some("sec"),
+            min: Some(Limit::Exclusive(0.0)),
+            default: Some("12"),
+            example: "12",
+            description: "W_max, upper bound of the window padding is sent in",
+            ..NUMBER
+        },
+        Param {
+            name: "padding budget",
+            kind: Kind::Integer,
+            unit: Some("packets"),
+            min: Some(Limit::Inclusive(1.0)),
+            default: Some("4000"),
+            example: "4000",
+            description: "N, padding packets per window",
+            ..NUMBER
+        },
+        Param {
+            name: "num states",
+            kind: Kind::Integer,
+            min: Some(Limit::Inclusive(1.0)),
+            default: Some("30"),
+            example: "30",
+            description: "PADDING states approximating the Rayleigh distribution",
+            ..NUMBER
+        },
+        PACKET_SIZE,
+        MAX_BLOCKING_FRAC,
+        ALLOWED_BLOCKED_MICROSEC,
+        TIME_SCALE,
+        INCLUDE_SMALL_PACKETS,
+        STRICT,
+    ],
+};
+
+pub const ADAPTED_REGULATOR: Defense = Defense {
+    name: "adapted_regulator",
+    params: &[
+        Param { default: Some("500"), ..INITIAL_RATE },
+        Param { default: Some("0.75"), ..DECAY_RATE },
+        Param { default: Some("4"), ..UPLOAD_RATIO },
+        Param { default: Some("20"), ..PACKETS_PER_STATE },
+        Param {
+            name: "client limit",
+            unit: Some("packets"),
+            default: Some("2"),
+            min: Some(Limit::Exclusive(0.0)),
+            example: "3",
+            description: "received packets counted per client COUNT state",
+            ..NUMBER
+        },
+        Param { name: "loop surge", flag: Some("--loop-surge"), description: "restart the surge once the rate has decayed, instead of ending the relay machine", ..SWITCH },
+        RELAY_TRIGGER,
+        RELAY_MAX_STATES,
+        COARSEN,
+        PACKET_SIZE,
+        MAX_BLOCKING_FRAC,
+        ALLOWED_BLOCKED_MICROSEC,
+        TIME_SCALE,
+        INCLUDE_SMALL_PACKETS,
+        STRICT,
+    ],
+};
+
+pub const REGULATOR: Defense = Defense {
+    name: "regulator",
+    params: &[
+        INITIAL_RATE,
+        DECAY_RATE,
+        Param {
+            name: "surge threshold",
+            min: Some(Limit::Exclusive(0.0)),
+            example: "2",
+            description: "T, real traffic restarts the surge in SEND states where T times the padding rate is below R",
+            ..NUMBER
+        },
+        UPLOAD_RATIO,
+        PACKETS_PER_STATE,
+        RELAY_TRIGGER,
+        RELAY_MAX_STATES,
+        COARSEN,
+        PACKET_SIZE,
+        MAX_BLOCKING_FRAC,
+        ALLOWED_BLOCKED_MICROSEC,
+        TIME_SCALE,
+        INCLUDE_SMALL_PACKETS,
+        STRICT,
+    ],
+};
+
+pub const SCRAMBLER: Defense = Defense {
+    name: "scrambler",
+    params: &[
+        Param { default: Some("160"), example: "160", description: "time between packets in the L and R states", ..SEND_INTERVAL },
+        Param {
+            name: "minimum count",
+            unit: Some("packets"),
+            min: Some(Limit::Inclusive(1.0)),
+            default: Some("500"),
+            example: "500",
+            description: "packets in the MIN state of each segment (rounded to an integer)",
+            ..NUMBER
+        },
+        Param { name: "min trail", default: Some("400"), example: "400", description: "minimum packets in an L or R state", ..TRAIL },
+        Param { name: "max trail", default: Some("1000"), example: "1000", description: "maximum packets in an L or R state (at least the min trail)", ..TRAIL },
+        Param { description: "give the send interval and the min interval in milliseconds instead of usec", ..MS },
+        Param { name: "no block", flag: Some("--no-block"), description: "generate Machine #1 alone, without blocking", ..SWITCH },
+        Param { name: "single pair", flag: Some("--single-pair"), description: "generate Machine #1 alone, without the second L/R pair", ..SWITCH },
+        Param {
+            name: "only",
+            flag: Some("--only"),
+            kind: Kind::Choice(&["1", "2"]),
+            example: "2",
+            description: "print only Machine #1 or Machine #2, as its serialized string alone",
+            ..NUMBER
+        },
+        Param {
+            name: "pairs",
+            flag: Some("--pairs"),
+            kind: Kind::Integer,
+            unit: Some("pairs"),
+            default: Some("2"),
+            min: Some(Limit::Inclusive(1.0)),
+            example: "3",
+            description: "L/R pairs of Machine #1, each used after a signal of Machine #2",
+            ..NUMBER
+        },
+        Param {
+            name: "pair reduction",
+            flag: Some("--pair-reduction"),
+            default: Some("4"),
+            min: Some(Limit::Exclusive(0.0)),
+            example: "2",
+            description: "divisor of the trailing packets of each L/R pair over the previous one",
+            ..NUMBER
+        },
+        Param { name: "min interval", flag: Some("--min-interval"), example: "120", description: "time between packets in the MIN state (default: send interval)", ..SEND_INTERVAL },
+        Param {
+            name: "trail dist",
+            flag: Some("--trail-dist"),
+            kind: Kind::Choice(&["uniform", "geometric"]),
+            default: Some("uniform"),
+            example: "geometric",
+            description: "distribution of the trailing packets of the L and R states: uniform over the range, or geometric with the mean of the range",
+            ..NUMBER
+        },
+        Param {
+            name: "direction",
+            flag: Some("--direction"),
+            kind: Kind::Choice(&["up", "down"]),
+            default: Some("up"),
+            example: "down",
+            description: "real traffic the machines follow: that their side sends, or that it receives (e.g., the download on the client)",
+            ..NUMBER
+        },
+        Param { name: "left trail min", flag: Some("--left-trail-min"), example: "300", description: "minimum packets in the L state (default: min trail)", ..TRAIL },
+        Param { name: "left trail max", flag: Some("--left-trail-max"), example: "1200", description: "maximum packets in the L state (default: max trail)", ..TRAIL },
+        Param { name: "right trail min", flag: Some("--right-trail-min"), example: "300", description: "minimum packets in the R state (default: min trail)", ..TRAIL },
+        Param { name: "right trail max", flag: Some("--right-trail-max"), example: "1200", description: "maximum packets in the R state (default: max trail)", ..TRAIL },
+        PACKET_SIZE,
+        MAX_BLOCKING_FRAC,
+        ALLOWED_BLOCKED_MICROSEC,
+        TIME_SCALE,
+        INCLUDE_SMALL_PACKETS,
+        STRICT,
+    ],
+};
+
+pub const ADAPTIVE_PAD: Defense = Defense {
+    name: "adaptive_pad",
+    params: &[
+        Param { name: "burst param1", unit: Some("usec"), example: "4000", description: "first parameter of the BURST timeout distribution (for normal: mean)", ..NUMBER },
+        Param { name: "burst param2", unit: Some("usec"), example: "1000", description: "second parameter of the BURST timeout distribution (for normal: standard deviation)", ..NUMBER },
+        Param { name: "gap param1", unit: Some("usec"), example: "2000", description: "first parameter of the GAP timeout distribution (for normal: mean)", ..NUMBER },
+        Param { name: "gap param2", unit: Some("usec"), example: "500", description: "second parameter of the GAP timeout distribution (for normal: standard deviation)", ..NUMBER },
+        Param {
+            name: "max gap padding",
+            unit: Some("packets"),
+            min: Some(Limit::Inclusive(1.0)),
+            example: "50",
+            description: "maximum padding packets per gap",
+            ..NUMBER
+        },
+        Param {
+            name: "dist",
+            flag: Some("--dist"),
+            kind: Kind::Choice(&["normal", "uniform", "lognormal", "binomial", "geometric", "pareto", "poisson", "weibull", "gamma", "beta"]),
+            default: Some("normal"),
+            example: "gamma",
+            description: "timeout distribution",
+            ..NUMBER
+        },
+        PACKET_SIZE,
+        MAX_BLOCKING_FRAC,
+        ALLOWED_BLOCKED_MICROSEC,
+        TIME_SCALE,
+        INCLUDE_SMALL_PACKETS,
+        STRICT,
+    ],
+};
+
+pub const WALKIE_TALKIE: Defense = Defense {
+    name: "walkie_talkie",
+    params: &[
+        Param {
+            name: "send burst",
+            unit: Some("packets"),
+            min: Some(Limit::Inclusive(1.0)),
+            example: "8",
+            description: "packets per outgoing burst, padded by the client machine",
+            ..NUMBER
+        },
+        Param {
+            name: "recv burst",
+            unit: Some("packets"),
+            min: Some(Limit::Inclusive(1.0)),
+            example: "20",
+            description: "packets per incoming burst, padded by the relay machine",
+            ..NUMBER
+        },
+        Param { example: "1000", description: "time between padding packets of a burst", ..SEND_INTERVAL },
+        MS,
+        PACKET_SIZE,
+        MAX_BLOCKING_FRAC,
+        ALLOWED_BLOCKED_MICROSEC,
+        TIME_SCALE,
+        INCLUDE_SMALL_PACKETS,
+        STRICT,
+    ],
+};
+
+pub const SURAKAV: Defense = Defense {
+    name: "surakav",
+    params: &[
+        Param {
+            name: "reference trace",
+            kind: Kind::File,
+            example: "reference.csv",
+            description: "file of packets to follow, one <time (ns)>,<s|r>[,<size>] per line",
+            ..NUMBER
+        },
+        Param {
+            name: "rate",
+            min: Some(Limit::Exclusive(0.0)),
+            example: "2",
+            description: "multiplier of the reference trace's sending rate (1 = as in the trace)",
+            ..NUMBER
+        },
+        Param {
+            name: "packets per state",
+            kind: Kind::Integer,
+            unit: Some("packets"),
+            min: Some(Limit::Inclusive(1.0)),
+            example: "5",
+            description: "reference packets per SEND state",
+            ..NUMBER
+        },
+        Param {
+            name: "max states",
+            flag: Some("--max-states"),
+            kind: Kind::Integer,
+            default: Some("250"),
+            min: Some(Limit::Inclusive(1.0)),
+            example: "2",
+            description: "cap on the number of SEND states per machine",
+            ..NUMBER
+        },
+        PACKET_SIZE,
+        MAX_BLOCKING_FRAC,
+        ALLOWED_BLOCKED_MICROSEC,
+        TIME_SCALE,
+        INCLUDE_SMALL_PACKETS,
+        STRICT,
+    ],
+};
+
+pub const DYNAFLOW: Defense = Defense {
+    name: "dynaflow",
+    params: &[
+        Param {
+            name: "intervals",
+            kind: Kind::NumberList,
+            unit: Some("usec"),
+            min: Some(Limit::Exclusive(0.0)),
+            example: "1000,2000,4000",
+            description: "candidate send intervals, in the order they are stepped through while idle",
+            ..NUMBER
+        },
+        Param {
+            name: "switch threshold",
+            kind: Kind::Integer,
+            min: Some(Limit::Inclusive(1.0)),
+            example: "2",
+            description: "consecutive idle blocks before stepping to the next interval",
+            ..NUMBER
+        },
+        Param {
+            name: "block size",
+            flag: Some("--block-size"),
+            unit: Some("packets"),
+            default: Some("20"),
+            min: Some(Limit::Inclusive(1.0)),
+            example: "10",
+            description: "padding packets per block",
+            ..NUMBER
+        },
+        Param { description: "give the intervals in milliseconds instead of usec", ..MS },
+        PACKET_SIZE,
+        MAX_BLOCKING_FRAC,
+        ALLOWED_BLOCKED_MICROSEC,
+        TIME_SCALE,
+        INCLUDE_SMALL_PACKETS,
+        STRICT,
+    ],
+};
+
+pub const INTERSPACE: Defense = Defense {
+    name: "interspace",
+    params: &[
+        Param { name: "param1", unit: Some("usec"), example: "4000", description: "first parameter of the padding timeout distribution (for normal: mean)", ..NUMBER },
+        Param { name: "param2", unit: Some("usec"), example: "1000", description: "second parameter of the padding timeout distribution (for normal: standard deviation)", ..NUMBER },
+        Param {
+            name: "min size",
+            unit: Some("bytes"),
+            min: Some(Limit::Inclusive(1.0)),
+            example: "100",
+            description: "smallest padding packet",
+            ..NUMBER
+        },
+        Param {
+            name: "max size",
+            unit: Some("bytes"),
+            min: Some(Limit::Inclusive(1.0)),
+            example: "1500",
+            description: "largest padding packet (at least min size, sizes above the MTU are sent as full packets)",
+            ..NUMBER
+        },
+        Param {
+            name: "dist",
+            flag: Some("--dist"),
+            kind: Kind::Choice(&["normal", "uniform", "lognormal", "binomial", "geometric", "pareto", "poisson", "weibull", "gamma", "beta"]),
+            default: Some("normal"),
+            example: "gamma",
+            description: "timeout distribution",
+            ..NUMBER
+        },
+        Param {
+            name: "size hist",
+            flag: Some("--size-hist"),
+            kind: Kind::File,
+            example: "sizes.csv",
+            description: "histogram of padding sizes to draw from, one <min size>,<max size>,<count> or <size>,<count> per line (sizes within [min size, max size])",
+            ..NUMBER
+        },
+        MAX_BLOCKING_FRAC,
+        ALLOWED_BLOCKED_MICROSEC,
+        TIME_SCALE,
+        INCLUDE_SMALL_PACKETS,
+        STRICT,
+    ],
+};
+
+pub const CS_BUFLO: Defense = Defense {
+    name: "cs_buflo",
+    params: &[
+        Param {
+            name: "min rate",
+            unit: Some("packets/sec"),
+            min: Some(Limit::Exclusive(0.0)),
+            example: "125",
+            description: "slowest send rate",
+            ..NUMBER
+        },
+        Param {
+            name: "max rate",
+            unit: Some("packets/sec"),
+            min: Some(Limit::Exclusive(0.0)),
+            example: "1000",
+            description: "fastest send rate, at which the machine starts (at least min rate)",
+            ..NUMBER
+        },
+        Param {
+            name: "factor",
+            min: Some(Limit::Exclusive(1.0)),
+            example: "2",
+            description: "ratio between consecutive rate levels",
+            ..NUMBER
+        },
+        Param {
+            name: "window",
+            flag: Some("--window"),
+            unit: Some("packets"),
+            default: Some("20"),
+            min: Some(Limit::Inclusive(1.0)),
+            example: "10",
+            description: "padding packets in a row before stepping down to the next slower rate",
+            ..NUMBER
+        },
+        PACKET_SIZE,
+        MAX_BLOCKING_FRAC,
+        ALLOWED_BLOCKED_MICROSEC,
+        TIME_SCALE,
+        INCLUDE_SMALL_PACKETS,
+        STRICT,
+    ],
+};
+
+// Every defense generator
+pub const DEFENSES: &[Defense] = &[CONSTANT, BUFLO, ADAPTED_FRONT, ADAPTED_REGULATOR, REGULATOR, SCRAMBLER, ADAPTIVE_PAD, WALKIE_TALKIE, SURAKAV, DYNAFLOW, INTERSPACE, CS_BUFLO];
+
+
+// The defense with the given name, if any.
+pub fn find_defense(name: &str) -> Option<&'static Defense> {
+    return DEFENSES.iter().find(|defense| defense.name == name);
+}
+
+
+// Describe the parameters of a defense, see the top of this file. Panics on
+// unknown defenses, which callers check with find_defense().
+pub fn parameter_schema(defense: &str) -> Value {
+    let defense = find_defense(defense).unwrap_or_else(|| panic!("Unknown defense {}", defense));
+
+    let mut position = 0;
+    let mut params: Vec<Value> = Vec::with_capacity(defense.params.len());
+
+    for param in defense.params {
+        let mut schema = Map::new();
+        schema.insert(String::from("name"), json!(param.name));
+
+        match param.flag {
+            Some(flag) => schema.insert(String::from("flag"), json!(flag)),
+            None => {
+                position += 1;
+                schema.insert(String::from("position"), json!(position))
+            },
+        };
+
+        let (value_type, choices) = match param.kind {
+            Kind::Number => ("number", None),
+            Kind::Integer => ("integer", None),
+            Kind::Choice(choices) => ("string", Some(choices)),
+            Kind::Switch => ("boolean", None),
+            Kind::File => ("string", None),
+            Kind::NumberList => ("array", None),
+        };
+        schema.insert(String::from("type"), json!(value_type));
+        if let Some(choices) = choices {
+            schema.insert(String::from("enum"), json!(choices));
+        }
+        if param.kind == Kind::File {
+            schema.insert(String::from("format"), json!("path"));
+        }
+
+        if let Some(unit) = param.unit {
+            schema.insert(String::from("unit"), json!(unit));
+        }
+
+        // Positional parameters without a default must be given, and switches
+        // default to off
+        if let Some(default) = param.default {
+            schema.insert(String::from("default"), value(param, default));
+        } else if param.kind == Kind::Switch {
+            schema.insert(String::from("default"), json!(false));
+        }
+        schema.insert(String::from("required"), json!(param.flag.is_none() && param.default.is_none()));
+
+        let mut items = Map::new();
+        let range = if param.kind == Kind::NumberList {
+            items.insert(String::from("type"), json!("number"));
+            &mut items
+        } else {
+            &mut schema
+        };
+        match param.min {
+            Some(Limit::Inclusive(min)) => range.insert(String::from("minimum"), json!(min)),
+            Some(Limit::Exclusive(min)) => range.insert(String::from("exclusiveMinimum"), json!(min)),
+            None => None,
+        };
+        match param.max {
+            Some(Limit::Inclusive(max)) => range.insert(String::from("maximum"), json!(max)),
+            Some(Limit::Exclusive(max)) => range.insert(String::from("exclusiveMaximum"), json!(max)),
+            None => None,
+        };
+        if param.kind == Kind::NumberList {
+            schema.insert(String::from("items"), Value::Object(items));
+        }
+
+        if !param.requires.is_empty() {
+            schema.insert(String::from("requires"), json!(param.requires));
+        }
+        if !param.excludes.is_empty() {
+            schema.insert(String::from("excludes"), json!(param.excludes));
+        }
+        if param.kind != Kind::Switch {
+            schema.insert(String::from("examples"), json!([value(param, param.example)]));
+        }
+        schema.insert(String::from("description"), json!(param.description));
+
+        params.push(Value::Object(schema));
+    }
+
+    return json!({
+        "defense": defense.name,
+        "parameters": params,
+    });
+}
+
+
+// A command-line value of a parameter as a JSON value of its type.
+fn value(param: &Param, text: &str) -> Value {
+    return match param.kind {
+        Kind::Number | Kind::Integer => match text.parse::<f64>() {
+            Ok(number) if param.kind == Kind::Integer => json!(number as i64),
+            Ok(number) => json!(number),
+            Err(_) => panic!("Invalid number {} for {}", text, param.name),
+        },
+        Kind::NumberList => text.split(',').map(|number| match number.parse::<f64>() {
+            Ok(number) => json!(number),
+            Err(_) => panic!("Invalid number {} for {}", number, param.name),
+        }).collect(),
+        _ => json!(text),
+    };
+}
@@ -0,0 +1,811 @@
+// Machine generation for the Scrambler defense (see bin/scrambler.rs), shared
+// by the scrambler and sweep binaries. Scrambler holds the parameters of the
+// scrambler binary, parsed from its arguments by Scrambler::from_args().
+
+use std::collections::HashMap;
+
+use maybenot::{
+machine::Machine,
+event::Event,
+state::State,
+dist::{Dist, DistType}
+};
+
+use crate::{PACKET_SIZE, MAX_STATES};
+use crate::args::{try_parse_args, parse_finite, parse_finite_positive, parse_count, packet_size, parse_interval, strict_count, machine_options, Args, MachineOptions};
+use crate::defaults::{SCRAMBLER_SEND_INTERVAL, SCRAMBLER_MIN_COUNT, SCRAMBLER_MIN_TRAIL, SCRAMBLER_MAX_TRAIL};
+use crate::dist_helpers::{fixed, infinite};
+use crate::validate::check_has_states;
+
+
+// Machine #1 states, followed by one L/R pair per segment size (see
+// num_states_one())
+pub const START_STATE_INDEX: usize = 0;
+pub const BLOCK_STATE_INDEX: usize = 1;
+pub const MIN_STATE_INDEX:   usize = 2;
+pub const LEFT_STATE_INDEX:  usize = 3; // index of L_1
+pub const RIGHT_STATE_INDEX: usize = 4; // index of R_1
+
+const PAIR_STATES: usize = 2;
+
+// Trailing count ranges of the L and R states of a pair
+pub type PairTrails = ((f64, f64), (f64, f64));
+
+// Machine #1 states without blocking
+const NUM_STATES_NB: usize = 4;
+
+const NB_MIN_STATE_INDEX:   usize = 1;
+const NB_LEFT_STATE_INDEX:  usize = 2;
+const NB_RIGHT_STATE_INDEX: usize = 3;
+
+// Machine #2 states
+const NUM_STATES_M2: usize = 3;
+
+const COUNT_LEFT_INDEX:  usize = 0;
+const COUNT_RIGHT_INDEX: usize = 1;
+const SIGNAL_INDEX:      usize = 2;
+
+// L/R pairs of Machine #1, each with the trailing counts of the previous one
+// divided by SECOND_PAIR_DIVISOR (see pair_trails())
+pub const NUM_PAIRS: usize = 2;
+pub const SECOND_PAIR_DIVISOR: f64 = 4.0;
+
+// Packets counted by Machine #2 before signaling, as a factor of the min count
+pub const SIGNAL_LIMIT_FACTOR: f64 = 1.25;
+
+// Switches and options of the scrambler binary
+pub const SWITCHES: &[&str] = &["--warn-unreachable", "--explain", "--dry-run", "--annotate", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--ms", "--no-block", "--single-pair", "--report", "--include-small-packets", "--strict", "--counters"];
+pub const OPTIONS: &[&str] = &["--packet-size", "--max-blocking-frac", "--allowed-blocked-microsec", "--time-scale", "--min-interval", "--pairs", "--pair-reduction", "--trail-dist", "--direction", "--left-trail-min", "--left-trail-max", "--right-trail-min", "--right-trail-max", "--only"];
+
+
+// Distribution of the trailing counts of the L and R states, see trail_limit()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailDist {
+    Uniform,
+    Geometric,
+}
+
+
+// Direction of the real traffic the machines follow (--direction): that their
+// side sends (Up, the default), or that it receives (Down, e.g., the download,
+// for machines on the client). Machine #1 starts, and switches between its L
+// and R states, on a real packet in the direction, and Machine #2 counts
+// them. The padding of Machine #1 is paced on its own PaddingSent either way:
+// the machines pad their side, whichever traffic they follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+
+impl Direction {
+    // Event of a real packet in the direction
+    pub fn nonpadding_event(self) -> Event {
+        return match self {
+            Direction::Up => Event::NonPaddingSent,
+            Direction::Down => Event::NonPaddingRecv,
+        };
+    }
+}
+
+
+// Direction for the --direction option of the scrambler: up or down.
+pub fn parse_direction(name: &str) -> Result<Direction, String> {
+    return match name {
+        "up" => Ok(Direction::Up),
+        "down" => Ok(Direction::Down),
+        _ => Err(format!("Unknown direction {}", name)),
+    };
+}
+
+
+// Distribution for the --trail-dist option of the scrambler: uniform or
+// geometric.
+pub fn parse_trail_dist(name: &str) -> Result<TrailDist, String> {
+    return match name {
+        "uniform" => Ok(TrailDist::Uniform),
+        "geometric" => Ok(TrailDist::Geometric),
+        _ => Err(format!("Unknown trail distribution {}", name)),
+    };
+}
+
+
+// Check that a minimum count (packets of the MIN state and counted by Machine
+// #2) is at least 1, and round it to the nearest integer (halfway cases away
+// from zero, so 5.5 becomes 6).
+pub fn round_min_count(min_count: f64) -> Result<f64, String> {
+    if min_count.is_nan() || min_count < 1.0 {
+        return Err(format!("minimum count must be at least 1, got {}", min_count));
+    }
+    
+    return Ok(min_count.round());
+}
+
+
+// Trailing count range of the second L/R pair of Machine #1, given the range
+// of the first pair.
+pub fn second_pair_trail(trail: (f64, f64)) -> (f64, f64) {
+    return pair_trail(trail, 1, SECOND_PAIR_DIVISOR);
+}
+
+
+// Trailing count range of L/R pair k (from 0) of Machine #1, given the range
+// of the first pair: the range divided by reduction^k.
+pub fn pair_trail(trail: (f64, f64), k: usize, reduction: f64) -> (f64, f64) {
+    let divisor = reduction.powi(k as i32);
+    return (trail.0 / divisor, trail.1 / divisor);
+}
+
+
+// Trailing count ranges of num_pairs L/R pairs, given the ranges of the first
+// pair (see pair_trail()).
+pub fn pair_trails(left_trail: (f64, f64), right_trail: (f64, f64), num_pairs: usize, reduction: f64) -> Vec<PairTrails> {
+    return (0..num_pairs).map(|k| (pair_trail(left_trail, k, reduction), pair_trail(right_trail, k, reduction))).collect();
+}
+
+
+// Packets counted by each state of Machine #2 before it signals Machine #1 to
+// switch to the second L/R pair.
+pub fn signal_limit(min_count: f64) -> f64 {
+    return min_count * SIGNAL_LIMIT_FACTOR;
+}
+
+
+// Worst-case delay Machine #1 adds to a real packet (usec), when its real
+// traffic comes in bursts of at most a segment. The model: while Machine #1
+// blocks, real packets only leave in place of its padding packets, one every
+// interval (the slower of the send intervals). A segment has slots for
+// min_count packets in the MIN state and up to max_trail more in the L or R
+// state after it (the upper bound of the range, or of the range whose mean a
+// Geometric trail takes), so the last packet of a burst filling the segment,
+// queued at its start, leaves (min_count + max_trail) intervals later. A
+// packet alone waits one interval at most. Larger bursts queue for longer, as
+// real packets keep switching between L and R, drawing a new trailing count
+// each time, so the segment (and the block) outlasts them.
+pub fn max_added_latency_us(interval: f64, min_count: f64, max_trail: f64) -> f64 {
+    return (min_count + max_trail) * interval;
+}
+
+
+// Limit of an L or R state with the given trailing count range: Uniform over
+// the range, or Geometric with the mean of the range. maybenot samples the
+// number of failures before the first success, of mean (1 - p) / p, so the
+// probability is 1 / (mean + 1). The Geometric draw is not clamped to the
+// range, which would shift its mean, so it models a long tail instead.
+pub fn trail_limit(trail: (f64, f64), trail_dist: TrailDist) -> Dist {
+    let (dist, param1, param2) = match trail_dist {
+        TrailDist::Uniform => (DistType::Uniform, trail.0, trail.1),
+        TrailDist::Geometric => (DistType::Geometric, 1.0 / ((trail.0 + trail.1) / 2.0 + 1.0), 0.0),
+    };
+
+    return Dist {
+        dist: dist,
+        param1: param1,
+        param2: param2,
+        start: 0.0,
+        max: 0.0,
+    };
+}
+
+
+// Number of states of Machine #1 with num_pairs L/R pairs.
+pub fn num_states_one(num_pairs: usize) -> usize {
+    return LEFT_STATE_INDEX + num_pairs * PAIR_STATES;
+}
+
+
+// Generate Machine #1 with the specified parameters. The MIN state sends every
+// min_interval, the L and R states every interval. The trailing counts are
+// (min, max) ranges for the L and R states of the first pair, drawn from
+// trail_dist (see trail_limit()). The machine follows the traffic its side
+// sends (see Direction).
+pub fn generate_machine_one(interval: f64, min_interval: f64, min_count: f64, left_trail: (f64, f64), right_trail: (f64, f64), trail_dist: TrailDist, packet_size: f64) -> Machine {
+    let pairs = pair_trails(left_trail, right_trail, NUM_PAIRS, SECOND_PAIR_DIVISOR);
+    return generate_machine_one_pairs(interval, min_interval, min_count, &pairs, trail_dist, Direction::Up, packet_size);
+}
+
+
+// Generate a variant of Machine #1 without the second L/R pair (L_2 and R_2),
+// with the same parameters. The signal of Machine #2 has no pair to switch to,
+// so it is not used.
+pub fn generate_machine_one_single_pair(interval: f64, min_interval: f64, min_count: f64, left_trail: (f64, f64), right_trail: (f64, f64), trail_dist: TrailDist, packet_size: f64) -> Machine {
+    return generate_machine_one_pairs(interval, min_interval, min_count, &[(left_trail, right_trail)], trail_dist, Direction::Up, packet_size);
+}
+
+
+// Generate Machine #1 with the L/R pairs of the given (left, right) trailing
+// count ranges (e.g., from pair_trails()), at least one, following the real
+// traffic in direction. BlockingBegin (the signal of Machine #2) moves each
+// pair on to the next one, if any.
+pub fn generate_machine_one_pairs(interval: f64, min_interval: f64, min_count: f64, pairs: &[PairTrails], trail_dist: TrailDist, direction: Direction, packet_size: f64) -> Machine {
+    assert!(!pairs.is_empty(), "Machine #1 needs at least one L/R pair");
+    let num_states = num_states_one(pairs.len());
+    
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(num_states);
+    states.push(generate_start_state(num_states, direction));
+    states.push(generate_block_state(num_states));
+
+    states.push(generate_min_state(MIN_STATE_INDEX, RIGHT_STATE_INDEX, num_states, min_interval, min_count, packet_size));
+
+    for (i, &(left_trail, right_trail)) in pairs.iter().enumerate() {
+        let left_index = LEFT_STATE_INDEX + i * PAIR_STATES;
+        let right_index = RIGHT_STATE_INDEX + i * PAIR_STATES;
+        
+        // L/R of the next pair, if any
+        let next_left = if i + 1 < pairs.len() { Some(left_index + PAIR_STATES) } else { None };
+        let next_right = if i + 1 < pairs.len() { Some(right_index + PAIR_STATES) } else { None };
+        
+        states.push(generate_left_state(left_index, right_index, next_left, num_states, interval, trail_limit(left_trail, trail_dist), direction, packet_size));
+        states.push(generate_right_state(right_index, left_index, next_right, num_states, interval, trail_limit(right_trail, trail_dist), direction, packet_size));
+    }
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+    
+    return machine;
+}
+
+
+// Generate a variant of Machine #1 that never blocks, for environments that
+// can't block outgoing traffic. Real traffic starts the MIN state directly, and
+// the padding of the MIN, L, and R states is sent alongside real packets rather
+// than in place of blocked ones, so timing is regularized much less. Without
+// blocking there is no signal from Machine #2, so it is not used, and only the
+// first L/R pair remains.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_machine_no_block(interval: f64, min_interval: f64, min_count: f64, left_trail: (f64, f64), right_trail: (f64, f64), trail_dist: TrailDist, direction: Direction, packet_size: f64) -> Machine {
+    // START state: NonPaddingSent (or Recv) --> MIN (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(NB_MIN_STATE_INDEX, 1.0);
+    
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(direction.nonpadding_event(), nonpadding_sent);
+    
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(NUM_STATES_NB);
+    states.push(State::new(transitions, NUM_STATES_NB));
+
+    states.push(generate_min_state(NB_MIN_STATE_INDEX, NB_RIGHT_STATE_INDEX, NUM_STATES_NB, min_interval, min_count, packet_size));
+
+    states.push(generate_left_state(NB_LEFT_STATE_INDEX, NB_RIGHT_STATE_INDEX, None, NUM_STATES_NB, interval, trail_limit(left_trail, trail_dist), direction, packet_size));
+    states.push(generate_right_state(NB_RIGHT_STATE_INDEX, NB_LEFT_STATE_INDEX, None, NUM_STATES_NB, interval, trail_limit(right_trail, trail_dist), direction, packet_size));
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+    
+    return machine;
+}
+
+
+// Generate the START state for Machine #1.
+fn generate_start_state(num_states: usize, direction: Direction) -> State {
+    // NonPaddingSent (or Recv) --> BLOCK (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(BLOCK_STATE_INDEX, 1.0);
+    
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(direction.nonpadding_event(), nonpadding_sent);
+    
+    // START state
+    let mut state = State::new(transitions, num_states);
+    state.action_is_block = true;
+    state.bypass = true;
+    state.replace = true;
+    
+    state.timeout = fixed(0.0);
+    
+    state.action = fixed(0.0);
+    
+    return state;
+}
+
+
+// Generate the BLOCK state for Machine #1.
+fn generate_block_state(num_states: usize) -> State {
+    // BlockingBegin --> MIN (100%)
+    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
+    blocking_begin.insert(MIN_STATE_INDEX, 1.0);
+    
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::BlockingBegin, blocking_begin);
+    
+    // BLOCK state
+    let mut state = State::new(transitions, num_states);
+    state.action_is_block = true;
+    state.bypass = true;
+    state.replace = true;
+    
+    state.timeout = fixed(0.0);
+    
+    state.action = infinite();
+    
+    return state;
+}
+
+
+// Generate the MIN state for Machine #1.
+fn generate_min_state(curr_index: usize, right_index: usize, num_states: usize, interval: f64, min_count: f64, packet_size: f64) -> State {
+    // PaddingSent --> MIN (100%)
+    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
+    padding_sent.insert(curr_index, 1.0);
+
+    // LimitReached --> R_1 (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(right_index, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingSent, padding_sent);
+    transitions.insert(Event::LimitReached, limit_reached);
+    
+    // MIN state
+    let mut state = State::new(transitions, num_states);
+    state.bypass = true;
+    state.replace = true;
+
+    state.timeout = fixed(interval);
+
+    state.action = fixed(packet_size);
+
+    state.limit = fixed(min_count);
+    
+    return state;
+}
+
+
+// Generate an L state for Machine #1, whose partner is the R state at
+// right_index, with the trailing count limit. BlockingBegin moves on to the
+// next L state, if any.
+#[allow(clippy::too_many_arguments)]
+fn generate_left_state(curr_index: usize, right_index: usize, next_index: Option<usize>, num_states: usize, interval: f64, limit: Dist, direction: Direction, packet_size: f64) -> State {
+    // PaddingSent --> L_{index} (100%)
+    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
+    padding_sent.insert(curr_index, 1.0);
+
+    // NonPaddingSent (or Recv) --> R_{index} (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(right_index, 1.0);
+
+    // LimitReached --> START (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(START_STATE_INDEX, 1.0);
+
+    // BlockingBegin --> L of the next pair (if any)
+    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
+    if let Some(next_index) = next_index {
+        blocking_begin.insert(next_index, 1.0);
+    }
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingSent, padding_sent);
+    transitions.insert(direction.nonpadding_event(), nonpadding_sent);
+    transitions.insert(Event::LimitReached, limit_reached);
+    if next_index.is_some() {
+        transitions.insert(Event::BlockingBegin, blocking_begin);
+    }
+    
+    // L_{index} state
+    let mut state = State::new(transitions, num_states);
+    state.bypass = true;
+    state.replace = true;
+
+    state.timeout = fixed(interval);
+
+    state.action = fixed(packet_size);
+
+    state.limit = limit;
+    
+    return state;
+}
+
+// Generate an R state for Machine #1, whose partner is the L state at
+// left_index, with the trailing count limit. BlockingBegin moves on to the
+// next R state, if any.
+#[allow(clippy::too_many_arguments)]
+fn generate_right_state(curr_index: usize, left_index: usize, next_index: Option<usize>, num_states: usize, interval: f64, limit: Dist, direction: Direction, packet_size: f64) -> State {
+    // PaddingSent --> R_{index} (100%)
+    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
+    padding_sent.insert(curr_index, 1.0);
+
+    // NonPaddingSent (or Recv) --> L_{index} (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(left_index, 1.0);
+
+    // LimitReached --> START (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(START_STATE_INDEX, 1.0);
+
+    // BlockingBegin --> R of the next pair (if any)
+    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
+    if let Some(next_index) = next_index {
+        blocking_begin.insert(next_index, 1.0);
+    }
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingSent, padding_sent);
+    transitions.insert(direction.nonpadding_event(), nonpadding_sent);
+    transitions.insert(Event::LimitReached, limit_reached);
+    if next_index.is_some() {
+        transitions.insert(Event::BlockingBegin, blocking_begin);
+    }
+    
+    // R_{index} state
+    let mut state = State::new(transitions, num_states);
+    state.bypass = true;
+    state.replace = true;
+
+    state.timeout = fixed(interval);
+
+    state.action = fixed(packet_size);
+
+    state.limit = limit;
+    
+    return state;
+}
+
+
+// Generate Machine #2 with the specified parameters, counting the real traffic
+// in direction.
+pub fn generate_machine_two(min_count: f64, direction: Direction) -> Machine {
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(NUM_STATES_M2);
+    states.push(generate_count_left_state(min_count, direction));
+    states.push(generate_count_right_state(min_count, direction));
+    states.push(generate_signal_state());
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+    
+    return machine;
+}
+
+
+// Generate the L state for Machine #2.
+fn generate_count_left_state(count: f64, direction: Direction) -> State {
+    // NonPaddingSent (or Recv) --> L (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(COUNT_LEFT_INDEX, 1.0);
+
+    // BlockingBegin --> R (100%)
+    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
+    blocking_begin.insert(COUNT_RIGHT_INDEX, 1.0);
+
+    // LimitReached --> SIGNAL (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(SIGNAL_INDEX, 1.0);
+    
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(direction.nonpadding_event(), nonpadding_sent);
+    transitions.insert(Event::BlockingBegin, blocking_begin);
+    transitions.insert(Event::LimitReached, limit_reached);
+
+    // L state
+    let mut state = State::new(transitions, NUM_STATES_M2);
+    state.action_is_block = true;
+    state.bypass = true;
+    
+    state.timeout = fixed(0.0);
+    
+    state.action = fixed(0.0);
+    
+    state.limit = fixed(signal_limit(count));
+
+    return state;
+}
+
+
+// Generate the R state for Machine #2.
+fn generate_count_right_state(count: f64, direction: Direction) -> State {
+    // NonPaddingSent (or Recv) --> R (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(COUNT_RIGHT_INDEX, 1.0);
+
+    // BlockingBegin --> L (100%)
+    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
+    blocking_begin.insert(COUNT_LEFT_INDEX, 1.0);
+
+    // LimitReached --> SIGNAL (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(SIGNAL_INDEX, 1.0);
+    
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(direction.nonpadding_event(), nonpadding_sent);
+    transitions.insert(Event::BlockingBegin, blocking_begin);
+    transitions.insert(Event::LimitReached, limit_reached);
+    
+    // R state
+    let mut state = State::new(transitions, NUM_STATES_M2);
+    state.action_is_block = true;
+    state.bypass = true;
+    
+    state.timeout = fixed(0.0);
+    
+    state.action = fixed(0.0);
+
+    state.limit = fixed(signal_limit(count));
+    
+    return state;
+}
+
+
+// Generate the SIGNAL for Machine #2.
+fn generate_signal_state() -> State {
+    // BlockingBegin --> R (100%)
+    let mut blocking_begin: HashMap<usize, f64> = HashMap::new();
+    blocking_begin.insert(COUNT_RIGHT_INDEX, 1.0);
+    
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::BlockingBegin, blocking_begin);
+
+    // SIGNAL state
+    let mut state = State::new(transitions, NUM_STATES_M2);
+    state.action_is_block = true;
+    state.bypass = true;
+    state.replace = true;
+    
+    state.timeout = fixed(0.0);
+    
+    state.action = infinite();
+    
+    return state;
+}
+
+
+// Parameters of the scrambler binary
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scrambler {
+    // Send interval (usec) of the L/R pairs, and of the MIN state unless
+    // min_interval is given
+    pub interval: f64,
+    pub min_interval: Option<f64>,
+    // Minimum count (packets), an integer of at least 1 (see
+    // round_min_count()), and the count given if it was rounded
+    pub min_count: f64,
+    pub rounded_from: Option<f64>,
+    // Range of the trailing counts of the first L/R pair (packets), unless
+    // left_trail or right_trail is given for its L or R state
+    pub min_trail: f64,
+    pub max_trail: f64,
+    pub left_trail: Option<(f64, f64)>,
+    pub right_trail: Option<(f64, f64)>,
+    pub trail_dist: TrailDist,
+    pub direction: Direction,
+    // Machine #1 alone and without blocking
+    pub no_block: bool,
+    // L/R pairs, each with the trailing counts of the previous one divided by
+    // the reduction, and Machine #2 if there are at least 2
+    pub num_pairs: usize,
+    pub reduction: f64,
+    // Size of padding packets (bytes)
+    pub packet_size: f64,
+    pub options: MachineOptions,
+}
+
+
+impl Default for Scrambler {
+    // The paper's configuration
+    fn default() -> Scrambler {
+        return Scrambler {
+            interval: SCRAMBLER_SEND_INTERVAL,
+            min_interval: None,
+            min_count: SCRAMBLER_MIN_COUNT,
+            rounded_from: None,
+            min_trail: SCRAMBLER_MIN_TRAIL,
+            max_trail: SCRAMBLER_MAX_TRAIL,
+            left_trail: None,
+            right_trail: None,
+            trail_dist: TrailDist::Uniform,
+            direction: Direction::Up,
+            no_block: false,
+            num_pairs: NUM_PAIRS,
+            reduction: SECOND_PAIR_DIVISOR,
+            packet_size: PACKET_SIZE,
+            options: MachineOptions::default(),
+        };
+    }
+}
+
+
+impl Scrambler {
+    // Parse the arguments of the scrambler binary (see parse_args()).
+    pub fn from_args(cli: &Args) -> Result<Scrambler, String> {
+        let args = &cli.positional;
+        if args.len() > 5 {
+            return Err(format!("Usage: {} [send interval = 160] [minimum count = 500] [min trail = 400] [max trail = 1000] [--warn-unreachable] [--explain] [--dry-run] [--annotate] [--hash] [--ffi] [--emit-rust] [--base64] [--ms] [--no-block] [--single-pair] [--report] [--include-small-packets] [--strict] [--counters] [--only <1|2>] [--packet-size <bytes>] [--max-blocking-frac <fraction>] [--allowed-blocked-microsec <usec>] [--time-scale <factor>] [--min-interval <usec>] [--pairs <n>] [--pair-reduction <factor>] [--trail-dist <uniform|geometric>] [--direction <up|down>] [--left-trail-min <count>] [--left-trail-max <count>] [--right-trail-min <count>] [--right-trail-max <count>]", args[0]));
+        }
+
+        let interval: f64 = args.get(1).map_or(Some(SCRAMBLER_SEND_INTERVAL), |v| parse_interval(cli, v)).ok_or("Invalid send interval")?;
+        let given_min_count: f64 = args.get(2).map_or(Ok(SCRAMBLER_MIN_COUNT), |v| parse_finite(v, "minimum segment size").and_then(|count| strict_count(cli, count, "minimum count")))?;
+        let min_count = match round_min_count(given_min_count) {
+            Ok(rounded) => rounded,
+            Err(e) => return Err(format!("Invalid minimum segment size: {}", e)),
+        };
+        let min_trail: f64 = args.get(3).map_or(Ok(SCRAMBLER_MIN_TRAIL), |v| parse_finite(v, "minimum trailing count").and_then(|count| strict_count(cli, count, "min trail")))?;
+        let max_trail: f64 = args.get(4).map_or(Ok(SCRAMBLER_MAX_TRAIL), |v| parse_finite(v, "maximum trailing count").and_then(|count| strict_count(cli, count, "max trail")))?;
+
+        // The MIN state defaults to the same pacing as the L and R states
+        let min_interval: Option<f64> = cli.value("--min-interval").map(|v| parse_interval(cli, v).ok_or("Invalid MIN send interval")).transpose()?;
+        if interval <= 0.0 || min_interval.is_some_and(|min_interval| min_interval <= 0.0) {
+            return Err(String::from("Send intervals must be positive"));
+        }
+
+        // Trailing counts of the L and R states default to the shared range
+        let trail = |flag: &str, default: f64, name: &str, strict_name: &str| -> Result<f64, String> {
+            return cli.value(flag).map_or(Ok(default), |v| parse_finite(v, name).and_then(|count| strict_count(cli, count, strict_name)));
+        };
+        let left_trail = (
+            trail("--left-trail-min", min_trail, "minimum left trailing count", "left trail min")?,
+            trail("--left-trail-max", max_trail, "maximum left trailing count", "left trail max")?,
+        );
+        let right_trail = (
+            trail("--right-trail-min", min_trail, "minimum right trailing count", "right trail min")?,
+            trail("--right-trail-max", max_trail, "maximum right trailing count", "right trail max")?,
+        );
+        if ![left_trail.0, left_trail.1, right_trail.0, right_trail.1].iter().all(|&count| count >= 0.0) {
+            return Err(String::from("Trailing counts must not be negative"));
+        }
+        for (names, (min, max)) in [(("Min trail", "max trail"), (min_trail, max_trail)), (("Left trail min", "left trail max"), left_trail), (("Right trail min", "right trail max"), right_trail)] {
+            if min > max {
+                return Err(format!("{} must not exceed {}, got {} and {}", names.0, names.1, min, max));
+            }
+        }
+
+        // Number of L/R pairs, and the reduction of their trailing counts
+        let num_pairs: usize = cli.value("--pairs").map_or(Ok(NUM_PAIRS), |v| parse_count(v, "number of pairs"))?;
+        let reduction: f64 = cli.value("--pair-reduction").map_or(Ok(SECOND_PAIR_DIVISOR), |v| parse_finite_positive(v, "pair reduction"))?;
+        if num_pairs < 1 {
+            return Err(String::from("Number of pairs must be at least 1"));
+        }
+        let num_states = LEFT_STATE_INDEX.saturating_add(num_pairs.saturating_mul(PAIR_STATES));
+        if num_states > MAX_STATES {
+            return Err(format!("Machine #1 would take {} states, more than the cap of {} (give fewer pairs)", num_states, MAX_STATES));
+        }
+        for flag in ["--no-block", "--single-pair"] {
+            if cli.has(flag) && (cli.value("--pairs").is_some() || cli.value("--pair-reduction").is_some()) {
+                return Err(format!("{} cannot be combined with --pairs or --pair-reduction", flag));
+            }
+        }
+
+        return Ok(Scrambler {
+            interval: interval,
+            min_interval: min_interval,
+            min_count: min_count,
+            rounded_from: if min_count != given_min_count { Some(given_min_count) } else { None },
+            min_trail: min_trail,
+            max_trail: max_trail,
+            left_trail: if cli.has("--left-trail-min") || cli.has("--left-trail-max") { Some(left_trail) } else { None },
+            right_trail: if cli.has("--right-trail-min") || cli.has("--right-trail-max") { Some(right_trail) } else { None },
+            // Distribution of the trailing counts, over their range
+            trail_dist: parse_trail_dist(cli.value("--trail-dist").unwrap_or("uniform"))?,
+            // Real traffic the machines follow
+            direction: parse_direction(cli.value("--direction").unwrap_or("up"))?,
+            no_block: cli.has("--no-block"),
+            num_pairs: if cli.has("--no-block") || cli.has("--single-pair") { 1 } else { num_pairs },
+            reduction: reduction,
+            packet_size: packet_size(cli)?,
+            options: machine_options(cli)?,
+        });
+    }
+
+
+    // Send interval of the MIN state (usec).
+    pub fn min_interval(&self) -> f64 {
+        return self.min_interval.unwrap_or(self.interval);
+    }
+
+
+    // The slower of the intervals scaled by the time scale (usec), at which
+    // real packets held back by blocking leave.
+    pub fn slowest_interval(&self) -> f64 {
+        return self.interval.max(self.min_interval()) * self.options.time_scale;
+    }
+
+
+    // Longest trailing count of any L/R pair, which a burst filling a segment
+    // waits for.
+    pub fn longest_trail(&self) -> f64 {
+        return self.pairs().iter().map(|(left_trail, right_trail)| left_trail.1.max(right_trail.1)).fold(0.0, f64::max);
+    }
+
+
+    // Longest a real packet is held back (usec): the last packet of a burst
+    // filling a segment (see max_added_latency_us()), or none without
+    // blocking.
+    pub fn max_delay(&self) -> f64 {
+        if self.no_block {
+            return 0.0;
+        }
+        return max_added_latency_us(self.slowest_interval(), self.min_count, self.longest_trail());
+    }
+
+
+    // Trailing count ranges of the L and R states of the first L/R pair.
+    pub fn trails(&self) -> ((f64, f64), (f64, f64)) {
+        let trail = (self.min_trail, self.max_trail);
+        return (self.left_trail.unwrap_or(trail), self.right_trail.unwrap_or(trail));
+    }
+
+
+    // Trailing count ranges of each L/R pair (see pair_trails()).
+    pub fn pairs(&self) -> Vec<PairTrails> {
+        let (left_trail, right_trail) = self.trails();
+        return pair_trails(left_trail, right_trail, self.num_pairs, self.reduction);
+    }
+
+
+    // The machines, with the names the binary prints: Machine #1, and with
+    // blocking and at least 2 L/R pairs, Machine #2.
+    pub fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        let (left_trail, right_trail) = self.trails();
+        let pairs = self.pairs();
+        let mut machines: Vec<(&str, Machine)>;
+
+        // Without blocking or a second L/R pair, Machine #1 runs alone
+        if self.no_block {
+            machines = vec![
+                ("Machine 1", generate_machine_no_block(self.interval, self.min_interval(), self.min_count, left_trail, right_trail, self.trail_dist, self.direction, self.packet_size)),
+            ];
+        } else if self.num_pairs == 1 {
+            machines = vec![
+                ("Machine 1", generate_machine_one_pairs(self.interval, self.min_interval(), self.min_count, &pairs, self.trail_dist, self.direction, self.packet_size)),
+            ];
+        } else {
+            machines = vec![
+                ("Machine 1", generate_machine_one_pairs(self.interval, self.min_interval(), self.min_count, &pairs, self.trail_dist, self.direction, self.packet_size)),
+                ("Machine 2", generate_machine_two(self.min_count, self.direction)),
+            ];
+        }
+
+        for (name, machine) in machines.iter_mut() {
+            self.options.apply(machine)?;
+            check_has_states(name, machine)?;
+        }
+
+        return Ok(machines.into_iter().map(|(name, machine)| (name.to_string(), machine)).collect());
+    }
+}
+
+
+// Parse the arguments of the scrambler binary (without the program name) into
+// its parameters.
+pub fn parse(args: &[String]) -> Result<Scrambler, String> {
+    let cli = try_parse_args([&[String::from("scrambler")], args].concat(), SWITCHES, OPTIONS)?;
+    return Scrambler::from_args(&cli);
+}
+
+
+// Generate the machines of the scrambler binary for its arguments (without the
+// program name), e.g., generate(&["160", "500"]).
+pub fn generate(args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse(args)?.named_machines();
+}
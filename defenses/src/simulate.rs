@@ -0,0 +1,352 @@
+// Measured overhead of machines, from running them in the maybenot simulator
+// against a base trace (see README.md for its format), to check the
+// closed-form estimates (e.g., overhead.rs) against a simulation.
+//
+// The base trace is the undefended stream from the client's point of view:
+// the client sends packets s at their time, and receives packets r at their
+// time, which the relay sent one network delay earlier. The simulated trace
+// holds the same real packets, sent at the same time or later (when the
+// machines block), along with the padding. Real packets leave each side in
+// order, so the n-th real packet a side sends in the simulation is the n-th of
+// the base trace, and its added delay is the difference of the two times.
+
+use std::cmp::Reverse;
+use std::slice;
+use std::time::{Duration, Instant};
+
+use maybenot::{
+framework::TriggerEvent,
+machine::Machine
+};
+use maybenot_simulator::{network::Network, queue::SimQueue, sim_advanced, SimulatorArgs};
+
+use crate::PACKET_SIZE;
+
+
+// MTU of the simulated network (bytes). Padding packets are capped at it.
+pub const MTU: u16 = 1500;
+
+
+// Packets recorded by the first simulation of measure(), per packet of the
+// base trace
+const INITIAL_TRACE_FACTOR: usize = 4;
+
+// Simulator iterations per packet recorded, past which a simulation is taken
+// to have stopped making progress. Most iterations handle events that are not
+// packets (e.g., the PaddingRecv of each packet sent, or BlockingBegin), which
+// are only a few per packet for machines that make progress.
+const SIM_ITERATIONS_FACTOR: usize = 50;
+
+// Network delay of the simulations of behaviorally_equal()
+const EQUALITY_DELAY: Duration = Duration::from_millis(10);
+
+
+// A packet of a base trace: its time (ns, from the start of the trace), its
+// direction (true if sent by the client), and its size (bytes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet {
+    pub time: u64,
+    pub sent: bool,
+    pub size: u16,
+}
+
+
+// What one side (client or relay) sent in a simulation, up to the last real
+// packet of either side. The delays are those of each real packet (usec).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Side {
+    pub real_packets: usize,
+    pub real_bytes: u64,
+    pub padding_packets: usize,
+    pub padding_bytes: u64,
+    pub delays: Vec<f64>,
+}
+
+
+impl Side {
+    // Padding bytes over real bytes sent, None without real bytes.
+    pub fn overhead(&self) -> Option<f64> {
+        if self.real_bytes == 0 {
+            return None;
+        }
+
+        return Some(self.padding_bytes as f64 / self.real_bytes as f64);
+    }
+}
+
+
+// The client and relay sides of a simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub client: Side,
+    pub relay: Side,
+}
+
+
+impl Measurement {
+    // Padding bytes over real bytes sent, over both sides.
+    pub fn overhead(&self) -> Option<f64> {
+        let real_bytes = self.client.real_bytes + self.relay.real_bytes;
+        if real_bytes == 0 {
+            return None;
+        }
+
+        return Some((self.client.padding_bytes + self.relay.padding_bytes) as f64 / real_bytes as f64);
+    }
+}
+
+
+// Parse a base trace: one packet per line as <time>,<direction>[,<size>], with
+// the time in nanoseconds, the direction s (sent by the client) or r
+// (received), and the size in bytes (default PACKET_SIZE). Blank lines are
+// skipped, and the packets must be in time order.
+pub fn parse_base_trace(trace: &str) -> Result<Vec<Packet>, String> {
+    let mut packets: Vec<Packet> = Vec::new();
+
+    for (i, line) in trace.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+        if fields.len() < 2 || fields.len() > 3 {
+            return Err(format!("line {}: expected <time>,<direction>[,<size>], got \"{}\"", i + 1, line));
+        }
+
+        let time: u64 = match fields[0].parse() {
+            Ok(time) => time,
+            Err(_) => return Err(format!("line {}: invalid time \"{}\"", i + 1, fields[0])),
+        };
+
+        let sent = match fields[1] {
+            "s" => true,
+            "r" => false,
+            direction => return Err(format!("line {}: invalid direction \"{}\", expected s or r", i + 1, direction)),
+        };
+
+        let size: u16 = match fields.get(2) {
+            Some(size) => match size.parse() {
+                Ok(size) if size > 0 => size,
+                _ => return Err(format!("line {}: invalid size \"{}\"", i + 1, size)),
+            },
+            None => PACKET_SIZE as u16,
+        };
+
+        if packets.last().is_some_and(|last| time < last.time) {
+            return Err(format!("line {}: packets must be in time order", i + 1));
+        }
+
+        packets.push(Packet { time: time, sent: sent, size: size });
+    }
+
+    if packets.is_empty() {
+        return Err(String::from("no packets"));
+    }
+
+    return Ok(packets);
+}
+
+
+// Simulate the base trace with the client and relay machines, over a network
+// of the given delay. Machines that pad without end (e.g., Constant) keep the
+// simulator going until it has recorded its maximum number of packets, so the
+// simulation starts out recording a few times the number of packets of the
+// trace, and is run again with twice as many until every real packet is sent.
+// Fails if that takes more than max_events packets, or if the simulator stops
+// making progress: machines that keep triggering each other without sending
+// packets (e.g., two machines that block on each other's BlockingBegin, see
+// validate::check_composition()) never advance the simulated time.
+pub fn measure(packets: &[Packet], client_machines: &[Machine], relay_machines: &[Machine], delay: Duration, max_events: usize) -> Result<Measurement, String> {
+    let mut max_trace_length = (packets.len() * INITIAL_TRACE_FACTOR).min(max_events);
+
+    loop {
+        match simulate(packets, client_machines, relay_machines, delay, max_trace_length)? {
+            Some(measurement) => return Ok(measurement),
+            None if max_trace_length >= max_events => return Err(format!("the simulation recorded {} packets before every real packet was sent (raise the maximum number of events)", max_events)),
+            None => max_trace_length = max_trace_length.saturating_mul(2).min(max_events),
+        }
+    }
+}
+
+
+// One simulation of measure(), recording at most max_trace_length packets.
+// None if it stopped at max_trace_length before every real packet was sent.
+fn simulate(packets: &[Packet], client_machines: &[Machine], relay_machines: &[Machine], delay: Duration, max_trace_length: usize) -> Result<Option<Measurement>, String> {
+    // Send times of the real packets of each side, as in parse_trace() of the
+    // simulator: the relay sends a packet a network delay before the client
+    // receives it
+    let start = Instant::now() + delay;
+    let mut queue = SimQueue::new();
+    let mut sent_client: Vec<Instant> = Vec::new();
+    let mut sent_relay: Vec<Instant> = Vec::new();
+
+    for packet in packets {
+        let time = send_time(packet, start, delay);
+        queue.push(TriggerEvent::NonPaddingSent { bytes_sent: packet.size }, packet.sent, time, Duration::ZERO, Reverse(time));
+
+        if packet.sent {
+            sent_client.push(time);
+        } else {
+            sent_relay.push(time);
+        }
+    }
+
+    let network = Network::new(delay);
+    let mut args = SimulatorArgs::new(&network, max_trace_length, true);
+    args.mtu = MTU;
+    args.max_sim_iterations = max_trace_length.saturating_mul(SIM_ITERATIONS_FACTOR);
+    let trace = sim_advanced(client_machines, relay_machines, &mut queue, &args);
+    let num_recorded = trace.len();
+
+    // Packets sent by each side, in order
+    let mut events_client: Vec<(Instant, TriggerEvent)> = Vec::new();
+    let mut events_relay: Vec<(Instant, TriggerEvent)> = Vec::new();
+    for event in trace {
+        match event.event {
+            TriggerEvent::NonPaddingSent { .. } | TriggerEvent::PaddingSent { .. } => {
+                if event.client {
+                    events_client.push((event.time, event.event));
+                } else {
+                    events_relay.push((event.time, event.event));
+                }
+            },
+            _ => (),
+        }
+    }
+
+    let real_client = real_packet_times(&events_client);
+    let real_relay = real_packet_times(&events_relay);
+    if real_client.len() < sent_client.len() || real_relay.len() < sent_relay.len() {
+        if num_recorded >= max_trace_length {
+            return Ok(None);
+        }
+
+        return Err(format!("the simulation stopped making progress after {} of the {} real packets sent by the client and {} of the {} sent by the relay (do the machines keep triggering each other?)",
+            real_client.len(), sent_client.len(), real_relay.len(), sent_relay.len()));
+    }
+
+    let end = real_client.last().max(real_relay.last()).copied().unwrap_or(start);
+
+    return Ok(Some(Measurement {
+        client: measure_side(&events_client, &sent_client, end),
+        relay: measure_side(&events_relay, &sent_relay, end),
+    }));
+}
+
+
+// Send time of a packet of the base trace, started at start.
+fn send_time(packet: &Packet, start: Instant, delay: Duration) -> Instant {
+    let time = start + Duration::from_nanos(packet.time);
+    return if packet.sent { time } else { time - delay };
+}
+
+
+// Check that two machines behave the same on a base trace: each runs on the
+// client in the simulator, over a network of EQUALITY_DELAY, and the first
+// steps events of the client (the padding it sends, its blocking, and its real
+// packets, which blocking delays) must be the same events at the same times.
+// Useful to check that a refactor of a generator does not change its machines.
+//
+// This is a necessary but not sufficient check of equivalence: machines that
+// differ on other traces, after more steps, or only on the relay's side, still
+// pass. maybenot v1 samples from rand's thread_rng, which cannot be seeded, so
+// the check is only deterministic for machines whose distributions are fixed
+// (low == high) and whose transitions have probability 1. Machines sampling
+// real distributions almost never compare equal, even to themselves.
+pub fn behaviorally_equal(a: &Machine, b: &Machine, trace: &[Packet], steps: usize) -> bool {
+    return client_events(a, trace, steps) == client_events(b, trace, steps);
+}
+
+
+// The first steps events of the client (at most) in a simulation of the base
+// trace with the machine on the client, with their time since the start.
+fn client_events(machine: &Machine, trace: &[Packet], steps: usize) -> Vec<(Duration, TriggerEvent)> {
+    let delay = EQUALITY_DELAY;
+    let start = Instant::now() + delay;
+    let mut queue = SimQueue::new();
+    for packet in trace {
+        let time = send_time(packet, start, delay);
+        queue.push(TriggerEvent::NonPaddingSent { bytes_sent: packet.size }, packet.sent, time, Duration::ZERO, Reverse(time));
+    }
+
+    // The simulator also records the relay's events and those the client
+    // receives, a few per step
+    let max_trace_length = steps.saturating_mul(INITIAL_TRACE_FACTOR).max(trace.len());
+    let network = Network::new(delay);
+    let mut args = SimulatorArgs::new(&network, max_trace_length, true);
+    args.mtu = MTU;
+    args.max_sim_iterations = max_trace_length.saturating_mul(SIM_ITERATIONS_FACTOR);
+
+    return sim_advanced(slice::from_ref(machine), &[], &mut queue, &args).into_iter()
+        .filter(|event| event.client && matches!(event.event,
+            TriggerEvent::NonPaddingSent { .. } | TriggerEvent::PaddingSent { .. } | TriggerEvent::BlockingBegin { .. } | TriggerEvent::BlockingEnd))
+        .map(|event| (event.time.saturating_duration_since(start), event.event))
+        .take(steps)
+        .collect();
+}
+
+
+// Times of the real packets a side sent.
+fn real_packet_times(events: &[(Instant, TriggerEvent)]) -> Vec<Instant> {
+    return events.iter()
+        .filter(|(_, event)| matches!(event, TriggerEvent::NonPaddingSent { .. }))
+        .map(|(time, _)| *time)
+        .collect();
+}
+
+
+// What a side sent up to end, with the delays of its real packets over their
+// base send times.
+fn measure_side(events: &[(Instant, TriggerEvent)], base_times: &[Instant], end: Instant) -> Side {
+    let mut side = Side {
+        real_packets: 0,
+        real_bytes: 0,
+        padding_packets: 0,
+        padding_bytes: 0,
+        delays: Vec::with_capacity(base_times.len()),
+    };
+
+    for (time, event) in events.iter().filter(|(time, _)| *time <= end) {
+        match event {
+            TriggerEvent::NonPaddingSent { bytes_sent } => {
+                side.delays.push(time.duration_since(base_times[side.real_packets]).as_secs_f64() * 1_000_000.0);
+                side.real_packets += 1;
+                side.real_bytes += *bytes_sent as u64;
+            },
+            TriggerEvent::PaddingSent { bytes_sent, .. } => {
+                side.padding_packets += 1;
+                side.padding_bytes += *bytes_sent as u64;
+            },
+            _ => (),
+        }
+    }
+
+    return side;
+}
+
+
+// Mean of the values, None if there are none.
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    return Some(values.iter().sum::<f64>() / values.len() as f64);
+}
+
+
+// Percentile (0-100) of the values by the nearest-rank method: the smallest
+// value that is at least the given percent of the values. None if there are
+// none.
+pub fn percentile(values: &[f64], percent: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let rank = ((percent / 100.0) * sorted.len() as f64).ceil() as usize;
+
+    return Some(sorted[rank.clamp(1, sorted.len()) - 1]);
+}
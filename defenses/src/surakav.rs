@@ -0,0 +1,244 @@
+// Machine generation for the Surakav baseline (see bin/surakav.rs): relay
+// START, BLOCK, and SEND states (see regulator.rs) following the bursts of a
+// reference trace. Surakav holds the parameters of the surakav binary, parsed
+// from its arguments by Surakav::from_args().
+
+use std::fs;
+
+use maybenot::{
+constants::STATEEND,
+machine::Machine,
+state::State
+};
+
+use crate::args::{try_parse_args, parse_finite_positive, parse_count, packet_size, machine_options, Args, MachineOptions};
+use crate::validate::check_has_states;
+use crate::constant::Trigger;
+use crate::regulator::{
+FIRST_SEND_STATE_INDEX,
+generate_relay_start_state,
+generate_relay_block_state,
+generate_relay_send_state
+};
+
+
+// Default cap on the number of SEND states per machine, as the serialized
+// size of a machine grows with the square of its number of states
+pub const MAX_SEND_STATES: usize = 250;
+
+// Switches and options of the surakav binary
+pub const SWITCHES: &[&str] = &["--warn-unreachable", "--explain", "--dry-run", "--annotate", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--report", "--include-small-packets", "--strict"];
+pub const OPTIONS: &[&str] = &["--packet-size", "--max-blocking-frac", "--allowed-blocked-microsec", "--time-scale", "--max-states"];
+
+
+// Parameters of the surakav binary
+#[derive(Debug, Clone, PartialEq)]
+pub struct Surakav {
+    // Path of the reference trace, and the times (usec) of its packets sent
+    // and received (see parse_trace())
+    pub trace: String,
+    pub sent: Vec<f64>,
+    pub received: Vec<f64>,
+    // Multiplier of the reference rate
+    pub rate: f64,
+    // Reference packets per SEND state
+    pub packets_per_state: usize,
+    // Cap on the number of SEND states per machine
+    pub max_states: usize,
+    // Size of padding packets (bytes)
+    pub packet_size: f64,
+    pub options: MachineOptions,
+}
+
+
+impl Surakav {
+    // Parse the arguments of the surakav binary (see parse_args()).
+    pub fn from_args(cli: &Args) -> Result<Surakav, String> {
+        let args = &cli.positional;
+        if args.len() != 4 {
+            return Err(format!("Usage: {} <reference trace> <rate> <packets per state> [--warn-unreachable] [--explain] [--dry-run] [--annotate] [--hash] [--ffi] [--emit-rust] [--base64] [--report] [--include-small-packets] [--strict] [--packet-size <bytes>] [--max-blocking-frac <fraction>] [--allowed-blocked-microsec <usec>] [--time-scale <factor>] [--max-states <n>]", args[0]));
+        }
+
+        let trace = fs::read_to_string(&args[1]).map_err(|e| format!("Unable to read reference trace: {}", e))?;
+        let rate:              f64 = parse_finite_positive(&args[2], "rate")?; // multiplier of the reference rate
+        let packets_per_state: f64 = parse_finite_positive(&args[3], "packets per state")?; // number of packets per state (approximation granularity)
+        let max_states: usize = match cli.value("--max-states") {
+            Some(v) => parse_count(v, "max states")?,
+            None => MAX_SEND_STATES,
+        };
+
+        if packets_per_state < 1.0 || packets_per_state.fract() != 0.0 {
+            return Err(String::from("Packets per state must be a whole number of at least 1"));
+        }
+        if max_states < 1 {
+            return Err(String::from("Max states must be at least 1"));
+        }
+
+        let (sent, received) = parse_trace(&trace).map_err(|e| format!("Invalid reference trace: {}", e))?;
+        if sent.len() < 2 || received.len() < 2 {
+            return Err(String::from("The reference trace must hold at least 2 packets in each direction"));
+        }
+
+        // A tiny rate spreads the padding of an interval over an infinite
+        // timeout
+        for times in [&sent, &received] {
+            if trace_intervals(times, packets_per_state as usize).iter().any(|&(t1, t2, count)| !((t2 - t1) / (count as f64) / rate).is_finite()) {
+                return Err(format!("Rate {} is too small for the reference trace", args[2]));
+            }
+        }
+
+        return Ok(Surakav {
+            trace: args[1].to_string(),
+            sent: sent,
+            received: received,
+            rate: rate,
+            packets_per_state: packets_per_state as usize,
+            max_states: max_states,
+            packet_size: packet_size(cli)?,
+            options: machine_options(cli)?,
+        });
+    }
+
+
+    // SEND states each machine needs to follow the whole reference trace,
+    // before the cap of max_states: client, then relay.
+    pub fn send_states(&self) -> [usize; 2] {
+        return [&self.sent, &self.received].map(|times| trace_intervals(times, self.packets_per_state).len());
+    }
+
+
+    // The client machine, following the packets sent in the reference trace,
+    // and the relay machine, following the packets received, with the names
+    // the binary prints.
+    pub fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        let mut machines = [
+            ("Client machine", generate_machine(&self.sent, self.packets_per_state, self.rate, self.max_states, self.packet_size)),
+            ("Relay machine", generate_machine(&self.received, self.packets_per_state, self.rate, self.max_states, self.packet_size)),
+        ];
+        for (name, machine) in machines.iter_mut() {
+            self.options.apply(machine)?;
+            check_has_states(name, machine)?;
+        }
+
+        return Ok(machines.into_iter().map(|(name, machine)| (name.to_string(), machine)).collect());
+    }
+}
+
+
+// Parse the arguments of the surakav binary (without the program name) into
+// its parameters.
+pub fn parse(args: &[String]) -> Result<Surakav, String> {
+    let cli = try_parse_args([&[String::from("surakav")], args].concat(), SWITCHES, OPTIONS)?;
+    return Surakav::from_args(&cli);
+}
+
+
+// Generate the machines of the surakav binary for its arguments (without the
+// program name), e.g., generate(&["trace.csv", "1.0", "5"]).
+pub fn generate(args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse(args)?.named_machines();
+}
+
+
+// Parse a reference trace into the times (usec) of the packets sent and
+// received, each relative to the first packet in that direction.
+pub fn parse_trace(trace: &str) -> Result<(Vec<f64>, Vec<f64>), String> {
+    let mut sent: Vec<f64> = Vec::new();
+    let mut received: Vec<f64> = Vec::new();
+
+    for (i, line) in trace.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+        if fields.len() < 2 {
+            return Err(format!("line {}: expected <time>,<direction>[,<size>], got \"{}\"", i + 1, line));
+        }
+
+        let time: f64 = match fields[0].parse::<u64>() {
+            Ok(time) => time as f64 / 1000.0,
+            Err(_) => return Err(format!("line {}: invalid time \"{}\"", i + 1, fields[0])),
+        };
+
+        match fields[1] {
+            "s" | "s+p" => sent.push(time),
+            "r" | "r+p" => received.push(time),
+            direction => return Err(format!("line {}: invalid direction \"{}\"", i + 1, direction)),
+        }
+    }
+
+    for times in [&mut sent, &mut received] {
+        if times.windows(2).any(|pair| pair[1] < pair[0]) {
+            return Err(String::from("packets must be in time order"));
+        }
+        if let Some(&first) = times.first() {
+            times.iter_mut().for_each(|time| *time -= first);
+        }
+    }
+
+    return Ok((sent, received));
+}
+
+
+// Split the gaps between consecutive packets (times in usec) into consecutive
+// [t1, t2] intervals of packets_per_state gaps each, returned as (t1, t2,
+// count). Every gap is followed by one packet, so an interval holds count
+// packets after t1. The last interval holds the remaining gaps.
+pub fn trace_intervals(times: &[f64], packets_per_state: usize) -> Vec<(f64, f64, usize)> {
+    let mut intervals: Vec<(f64, f64, usize)> = Vec::new();
+    let mut first = 0;
+
+    while first + 1 < times.len() {
+        let last = (first + packets_per_state).min(times.len() - 1);
+        intervals.push((times[first], times[last], last - first));
+        first = last;
+    }
+
+    return intervals;
+}
+
+
+// Generate a machine that sends padding following the packets at times (usec),
+// at rate times their rate, with at most max_states SEND states (see
+// Surakav::send_states() for the SEND states needed without the cap).
+pub fn generate_machine(times: &[f64], packets_per_state: usize, rate: f64, max_states: usize, packet_size: f64) -> Machine {
+    let mut intervals = trace_intervals(times, packets_per_state);
+    intervals.truncate(max_states);
+
+    let num_send_states = intervals.len();
+
+    // Set up state vector
+    let num_states = num_send_states + 2;
+    let mut states: Vec<State> = Vec::with_capacity(num_states);
+
+    // START and BLOCK states
+    states.push(generate_relay_start_state(num_states, Trigger::Sent));
+    states.push(generate_relay_block_state(num_states));
+
+    // SEND states, at the mean rate of each interval
+    for (i, &(t1, t2, count)) in intervals.iter().enumerate() {
+        let curr_idx = i + FIRST_SEND_STATE_INDEX;
+        let mut next_idx = curr_idx + 1;
+
+        if i == num_send_states - 1 {
+            next_idx = STATEEND;
+        }
+
+        let timeout = (t2 - t1) / (count as f64) / rate;
+        states.push(generate_relay_send_state(curr_idx, next_idx, num_states, count as f64, timeout, false, packet_size));
+    }
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+
+    return machine;
+}
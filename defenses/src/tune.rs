@@ -0,0 +1,177 @@
+// Search for Scrambler parameters that hit a target overhead on a base trace
+// (see bin/tune.rs). The overhead of a configuration is measured by running
+// its machine in the simulator (see simulate::measure()), which makes each
+// point of the search expensive, so the search is a hill-climb rather than a
+// grid: from a starting configuration, it tries each parameter multiplied and
+// divided by a step factor, moves to whichever neighbor measures closest to
+// the target, and takes the square root of the step once no neighbor is
+// closer. The parameters searched are the send interval, the minimum count,
+// and the trailing count range, scaled as a whole so that its min and max keep
+// their ratio. The search stops once a configuration is within the tolerance of
+// the target (relative to it), once the step is below MIN_STEP (the closest
+// configuration is then a local optimum), or after the maximum number of
+// measurements.
+//
+// Only Machine #1 is measured, with a single L/R pair (see
+// generate_machine_one_single_pair()): the two machines of the Scrambler
+// signal each other by blocking, which the simulator does not make progress
+// on. The first pair is the one in use until Machine #2 signals the switch.
+
+use std::time::Duration;
+
+use maybenot::machine::Machine;
+
+use crate::defense::Scrambler;
+use crate::scrambler::{generate_machine_one_single_pair, round_min_count, TrailDist};
+use crate::simulate::{measure, Packet};
+
+
+// Step factor of the first moves
+pub const INITIAL_STEP: f64 = 2.0;
+
+// Step factor below which the search stops
+pub const MIN_STEP: f64 = 1.01;
+
+// Default tolerance, relative to the target overhead
+pub const TOLERANCE: f64 = 0.01;
+
+// Default cap on the number of configurations measured
+pub const MAX_MEASUREMENTS: usize = 100;
+
+
+// Settings of a search: the target overhead ratio, the tolerance relative to
+// it, and the cap on the number of configurations measured.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Search {
+    pub target: f64,
+    pub tolerance: f64,
+    pub max_measurements: usize,
+}
+
+
+// Outcome of a search: the configuration measured closest to the target, its
+// overhead, whether that is within the tolerance, and the number of
+// configurations measured.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tuned {
+    pub params: Scrambler,
+    pub overhead: f64,
+    pub converged: bool,
+    pub measurements: usize,
+}
+
+
+// Round a configuration to one the scrambler binary takes as given: a whole
+// send interval of at least 1 usec, and whole counts, with a minimum count and
+// trail of at least 1.
+pub fn round_params(params: &Scrambler) -> Scrambler {
+    let min_trail = params.min_trail.round().max(1.0);
+    return Scrambler {
+        interval: params.interval.round().max(1.0),
+        min_count: round_min_count(params.min_count.max(1.0)).unwrap_or(1.0),
+        min_trail: min_trail,
+        max_trail: params.max_trail.round().max(min_trail),
+        ..params.clone()
+    };
+}
+
+
+// The neighbors of a configuration for a step factor: each of the send
+// interval, the minimum count, and the trailing count range multiplied and
+// divided by it, rounded (see round_params()). Neighbors that round to the
+// configuration itself are left out.
+pub fn neighbors(params: &Scrambler, step: f64) -> Vec<Scrambler> {
+    let mut neighbors: Vec<Scrambler> = Vec::new();
+
+    for factor in [step, 1.0 / step] {
+        for candidate in [
+            Scrambler { interval: params.interval * factor, ..params.clone() },
+            Scrambler { min_count: params.min_count * factor, ..params.clone() },
+            Scrambler { min_trail: params.min_trail * factor, max_trail: params.max_trail * factor, ..params.clone() },
+        ] {
+            let candidate = round_params(&candidate);
+            if candidate != *params && !neighbors.contains(&candidate) {
+                neighbors.push(candidate);
+            }
+        }
+    }
+
+    return neighbors;
+}
+
+
+// Hill-climb from start towards the target overhead, measuring configurations
+// with measure_overhead (e.g., measured_overhead()). Each configuration is
+// measured once. Fails if a measurement does, or on invalid settings.
+pub fn hill_climb<F>(start: &Scrambler, search: &Search, mut measure_overhead: F) -> Result<Tuned, String> where F: FnMut(&Scrambler) -> Result<f64, String> {
+    if !search.target.is_finite() || search.target <= 0.0 {
+        return Err(format!("target overhead must be a positive ratio, got {}", search.target));
+    }
+    if !search.tolerance.is_finite() || search.tolerance < 0.0 {
+        return Err(format!("tolerance must be a finite ratio of at least 0, got {}", search.tolerance));
+    }
+    if search.max_measurements < 1 {
+        return Err(String::from("the search must measure at least 1 configuration"));
+    }
+
+    let distance = |overhead: f64| (overhead - search.target).abs();
+    let within = |overhead: f64| distance(overhead) <= search.tolerance * search.target;
+
+    let mut measured: Vec<(Scrambler, f64)> = Vec::new();
+    let mut current = round_params(start);
+    let mut overhead = measure_overhead(&current)?;
+    measured.push((current.clone(), overhead));
+    let mut step = INITIAL_STEP;
+
+    while !within(overhead) && step >= MIN_STEP && measured.len() < search.max_measurements {
+        let mut best: Option<(Scrambler, f64)> = None;
+
+        for candidate in neighbors(&current, step) {
+            let candidate_overhead = match measured.iter().find(|(params, _)| *params == candidate) {
+                Some((_, candidate_overhead)) => *candidate_overhead,
+                None if measured.len() < search.max_measurements => {
+                    let candidate_overhead = measure_overhead(&candidate)?;
+                    measured.push((candidate.clone(), candidate_overhead));
+                    candidate_overhead
+                },
+                None => continue,
+            };
+
+            if best.as_ref().is_none_or(|(_, best_overhead)| distance(candidate_overhead) < distance(*best_overhead)) {
+                best = Some((candidate, candidate_overhead));
+            }
+        }
+
+        match best {
+            Some((candidate, candidate_overhead)) if distance(candidate_overhead) < distance(overhead) => {
+                current = candidate;
+                overhead = candidate_overhead;
+            },
+            _ => step = step.sqrt(),
+        }
+    }
+
+    return Ok(Tuned {
+        params: current,
+        overhead: overhead,
+        converged: within(overhead),
+        measurements: measured.len(),
+    });
+}
+
+
+// The machine a configuration is measured with: Machine #1 with a single L/R
+// pair, whose L and R states take the same trailing count range.
+pub fn measured_machine(params: &Scrambler) -> Machine {
+    let trail = (params.min_trail, params.max_trail);
+    return generate_machine_one_single_pair(params.interval, params.interval, params.min_count, trail, trail, TrailDist::Uniform, params.packet_size);
+}
+
+
+// Overhead of a configuration on a base trace: that of both sides (see
+// simulate::Measurement::overhead()), with the machine on the relay, which
+// sends the video download.
+pub fn measured_overhead(params: &Scrambler, packets: &[Packet], delay: Duration, max_events: usize) -> Result<f64, String> {
+    let measurement = measure(packets, &[], &[measured_machine(params)], delay, max_events)?;
+    return measurement.overhead().ok_or_else(|| String::from("the base trace has no real bytes to measure the overhead over"));
+}
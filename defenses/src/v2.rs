@@ -0,0 +1,58 @@
+// Builders for maybenot v2 machines (with the maybenot-v2 feature), for users
+// on newer versions of maybenot. Each module constructs the same defense as
+// its v1 counterpart (the Scrambler with one L/R pair, and with the counters
+// feature, a variant on the counters of v2), translated to the v2 API:
+//  - Events: NonPaddingSent/NonPaddingRecv become NormalSent/NormalRecv.
+//  - Actions: the action, timeout, and limit distributions of a state, and its
+//    action_is_block flag, become a single SendPadding or BlockOutgoing
+//    Action. Padding packets have no size in v2, so packet sizes are dropped.
+//  - Machines: the padding budget is counted in packets rather than bytes.
+// Distribution parameters carry over unchanged, see convert_dist().
+
+pub mod constant;
+pub mod scrambler;
+
+use maybenot2::{
+Machine,
+dist::{Dist, DistType}
+};
+
+
+// Convert a v1 distribution to the equivalent v2 distribution. The parameters
+// of each type keep their meaning (e.g., Pareto scale and shape), only their
+// names change. DistType::None has no v2 equivalent: states without an action
+// have no Action in v2.
+pub fn convert_dist(dist: &maybenot::dist::Dist) -> Result<Dist, String> {
+    use maybenot::dist::DistType as V1;
+
+    let (p1, p2) = (dist.param1, dist.param2);
+    let dist_type = match dist.dist {
+        V1::None => return Err(String::from("distribution of type none has no maybenot v2 equivalent")),
+        V1::Uniform => DistType::Uniform { low: p1, high: p2 },
+        V1::Normal => DistType::Normal { mean: p1, stdev: p2 },
+        V1::LogNormal => DistType::LogNormal { mu: p1, sigma: p2 },
+        V1::Binomial => DistType::Binomial { trials: p1 as u64, probability: p2 },
+        V1::Geometric => DistType::Geometric { probability: p1 },
+        V1::Pareto => DistType::Pareto { scale: p1, shape: p2 },
+        V1::Poisson => DistType::Poisson { lambda: p1 },
+        V1::Weibull => DistType::Weibull { scale: p1, shape: p2 },
+        V1::Gamma => DistType::Gamma { scale: p1, shape: p2 },
+        V1::Beta => DistType::Beta { alpha: p1, beta: p2 },
+    };
+
+    let dist = Dist::new(dist_type, dist.start, dist.max);
+    dist.validate().map_err(|e| e.to_string())?;
+
+    return Ok(dist);
+}
+
+
+// Print a serialized v2 machine, in the same format as output::print_machine().
+pub fn print_machine(name: &str, machine: &Machine, annotate: bool) {
+    if annotate {
+        println!("# {}: {} states", name, machine.states.len());
+    }
+
+    let machine = machine.serialize();
+    println!("{}: {} ({})\n", name, machine, machine.len());
+}
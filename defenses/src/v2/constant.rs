@@ -0,0 +1,100 @@
+// Machine generation for the constant-rate defense against the maybenot v2
+// API: the same START, BLOCK, and CONST states as constant.rs.
+
+use enum_map::enum_map;
+
+use maybenot2::{
+Machine,
+constants::MAX_SAMPLED_BLOCK_DURATION,
+event::Event,
+state::{State, Trans},
+action::Action,
+dist::{Dist, DistType}
+};
+
+pub use crate::constant::{NUM_STATES, START_STATE_INDEX, BLOCK_STATE_INDEX, CONST_STATE_INDEX, Trigger};
+
+
+// Generate a constant-rate machine, sending padding with the given timeout once
+// real traffic in the trigger direction(s) is seen.
+pub fn generate_machine(timeout: Dist, trigger: Trigger) -> Machine {
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(NUM_STATES);
+    states.push(generate_start_state(trigger));
+    states.push(generate_block_state());
+    states.push(generate_const_state(timeout, None));
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_packets: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+    };
+
+    return machine;
+}
+
+
+// Generate the START state for a machine, leaving it on the trigger event(s).
+pub fn generate_start_state(trigger: Trigger) -> State {
+    // NormalSent/NormalRecv --> BLOCK (100%)
+    let to_block = vec![Trans(BLOCK_STATE_INDEX, 1.0)];
+    let normal_sent = if trigger != Trigger::Recv { to_block.clone() } else { vec![] };
+    let normal_recv = if trigger != Trigger::Sent { to_block } else { vec![] };
+
+    return State::new(enum_map! {
+        Event::NormalSent => normal_sent.clone(),
+        Event::NormalRecv => normal_recv.clone(),
+        _ => vec![],
+    });
+}
+
+
+// Generate the BLOCK state for a machine. The v1 machine blocks for INFINITY,
+// which v2 distributions reject, so the longest blocking v2 samples is used.
+pub fn generate_block_state() -> State {
+    // BlockingBegin --> CONST (100%)
+    let mut state = State::new(enum_map! {
+        Event::BlockingBegin => vec![Trans(CONST_STATE_INDEX, 1.0)],
+        _ => vec![],
+    });
+
+    state.action = Some(Action::BlockOutgoing {
+        bypass: true,
+        replace: true,
+        timeout: Dist::new(DistType::Uniform { low: 0.0, high: 0.0 }, 0.0, 0.0),
+        duration: Dist::new(DistType::Uniform { low: MAX_SAMPLED_BLOCK_DURATION, high: MAX_SAMPLED_BLOCK_DURATION }, 0.0, 0.0),
+        limit: None,
+    });
+
+    return state;
+}
+
+
+// Generate the CONST state for a machine. With a limit, the state sends at
+// most that many packets before returning to START.
+pub fn generate_const_state(timeout: Dist, limit: Option<f64>) -> State {
+    // PaddingSent --> CONST (100%)
+    // LimitReached --> START (100%)
+    let limit_reached = match limit {
+        Some(_) => vec![Trans(START_STATE_INDEX, 1.0)],
+        None => vec![],
+    };
+
+    let mut state = State::new(enum_map! {
+        Event::PaddingSent => vec![Trans(CONST_STATE_INDEX, 1.0)],
+        Event::LimitReached => limit_reached.clone(),
+        _ => vec![],
+    });
+
+    state.action = Some(Action::SendPadding {
+        bypass: true,
+        replace: true,
+        timeout: timeout,
+        limit: limit.map(|limit| Dist::new(DistType::Uniform { low: limit, high: limit }, 0.0, 0.0)),
+    });
+
+    return state;
+}
@@ -0,0 +1,205 @@
+// Machine #1 of the Scrambler against the maybenot v2 API, with a single L/R
+// pair (as --single-pair, see scrambler.rs): the same START, BLOCK, MIN, L, and
+// R states, with the segment sized by the limits of the states, and with the
+// counters feature, a variant that sizes it with a counter of the machine.
+//
+// With limits, the trailing count of the L and R states is drawn anew on every
+// switch between them, so a real packet sent every so often keeps a segment
+// going for as long as the packets come. The counter variant draws it once per
+// segment instead, when the MIN state ends: counter A holds the packets left in
+// the segment, and every packet sent in the L and R states, real or padding,
+// takes one off, shared by both states. CounterZero ends the segment, so a
+// segment is at most the min count and one trailing count long, however the
+// real packets fall. The trailing count is drawn from the range of R, the
+// state the MIN state moves on to.
+
+use enum_map::enum_map;
+
+use maybenot2::{
+Machine,
+constants::MAX_SAMPLED_BLOCK_DURATION,
+event::Event,
+state::{State, Trans},
+action::Action,
+dist::{Dist, DistType}
+};
+#[cfg(feature = "counters")]
+use maybenot2::counter::{Counter, Operation};
+
+use crate::scrambler::{trail_limit, num_states_one, TrailDist};
+use crate::v2::convert_dist;
+
+pub use crate::scrambler::{START_STATE_INDEX, BLOCK_STATE_INDEX, MIN_STATE_INDEX, LEFT_STATE_INDEX, RIGHT_STATE_INDEX};
+
+
+// Generate Machine #1 with one L/R pair, with the parameters of
+// scrambler::generate_machine_one_single_pair(). Padding packets have no size
+// in v2, so there is no packet size.
+pub fn generate_machine_one_single_pair(interval: f64, min_interval: f64, min_count: f64, left_trail: (f64, f64), right_trail: (f64, f64), trail_dist: TrailDist) -> Machine {
+    let states = vec![
+        generate_start_state(),
+        generate_block_state(),
+        generate_min_state(min_interval, min_count),
+        generate_left_state(interval, Some(trail(left_trail, trail_dist))),
+        generate_right_state(interval, Some(trail(right_trail, trail_dist))),
+    ];
+
+    return machine(states);
+}
+
+
+// Generate the counter variant of generate_machine_one_single_pair(), whose
+// segments have a single trailing count, drawn from trail (see above).
+#[cfg(feature = "counters")]
+pub fn generate_machine_one_counter(interval: f64, min_interval: f64, min_count: f64, trail: (f64, f64), trail_dist: TrailDist) -> Machine {
+    // MIN sets counter A to the trailing count on every packet, so it holds the
+    // last draw when MIN ends. Counters truncate their samples where limits
+    // round them, and entering R takes one off, so 1.5 is added to the draw.
+    let mut count = self::trail(trail, trail_dist);
+    count.start += 1.5;
+
+    let mut min_state = generate_min_state(min_interval, min_count);
+    min_state.counter = (Some(Counter::new_dist(Operation::Set, count)), None);
+
+    // L and R take one off for each packet, and end the segment at zero
+    let mut left_state = generate_left_state(interval, None);
+    let mut right_state = generate_right_state(interval, None);
+    for state in [&mut left_state, &mut right_state] {
+        state.counter = (Some(Counter::new(Operation::Decrement)), None);
+    }
+
+    let states = vec![
+        generate_start_state(),
+        generate_block_state(),
+        min_state,
+        left_state,
+        right_state,
+    ];
+
+    return machine(states);
+}
+
+
+fn machine(states: Vec<State>) -> Machine {
+    debug_assert_eq!(states.len(), num_states_one(1));
+
+    return Machine {
+        allowed_padding_packets: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+    };
+}
+
+
+// The v2 limit of a trailing count range.
+fn trail(trail: (f64, f64), trail_dist: TrailDist) -> Dist {
+    return convert_dist(&trail_limit(trail, trail_dist)).unwrap_or_else(|e| panic!("Invalid trailing count distribution: {}", e));
+}
+
+
+fn fixed(value: f64) -> Dist {
+    return Dist::new(DistType::Uniform { low: value, high: value }, 0.0, 0.0);
+}
+
+
+// Generate the START state, which ends the blocking of a segment.
+fn generate_start_state() -> State {
+    // NormalSent --> BLOCK (100%)
+    let mut state = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(BLOCK_STATE_INDEX, 1.0)],
+        _ => vec![],
+    });
+
+    state.action = Some(Action::BlockOutgoing {
+        bypass: true,
+        replace: true,
+        timeout: fixed(0.0),
+        duration: fixed(0.0),
+        limit: None,
+    });
+
+    return state;
+}
+
+
+// Generate the BLOCK state. The v1 machine blocks for INFINITY, which v2
+// distributions reject, so the longest blocking v2 samples is used.
+fn generate_block_state() -> State {
+    // BlockingBegin --> MIN (100%)
+    let mut state = State::new(enum_map! {
+        Event::BlockingBegin => vec![Trans(MIN_STATE_INDEX, 1.0)],
+        _ => vec![],
+    });
+
+    state.action = Some(Action::BlockOutgoing {
+        bypass: true,
+        replace: true,
+        timeout: fixed(0.0),
+        duration: fixed(MAX_SAMPLED_BLOCK_DURATION),
+        limit: None,
+    });
+
+    return state;
+}
+
+
+// Generate the MIN state, sending min_count packets.
+fn generate_min_state(interval: f64, min_count: f64) -> State {
+    // PaddingSent --> MIN (100%)
+    // LimitReached --> R (100%)
+    let mut state = State::new(enum_map! {
+        Event::PaddingSent => vec![Trans(MIN_STATE_INDEX, 1.0)],
+        Event::LimitReached => vec![Trans(RIGHT_STATE_INDEX, 1.0)],
+        _ => vec![],
+    });
+
+    state.action = Some(Action::SendPadding {
+        bypass: true,
+        replace: true,
+        timeout: fixed(interval),
+        limit: Some(fixed(min_count)),
+    });
+
+    return state;
+}
+
+
+// Generate the L state, with the trailing count limit, or without one for the
+// counter variant, which ends the segment on CounterZero instead.
+fn generate_left_state(interval: f64, limit: Option<Dist>) -> State {
+    return generate_trail_state(LEFT_STATE_INDEX, RIGHT_STATE_INDEX, interval, limit);
+}
+
+
+// Generate the R state, as generate_left_state().
+fn generate_right_state(interval: f64, limit: Option<Dist>) -> State {
+    return generate_trail_state(RIGHT_STATE_INDEX, LEFT_STATE_INDEX, interval, limit);
+}
+
+
+fn generate_trail_state(curr_index: usize, other_index: usize, interval: f64, limit: Option<Dist>) -> State {
+    // PaddingSent --> itself (100%)
+    // NormalSent --> the other state of the pair (100%)
+    // LimitReached or CounterZero --> START (100%)
+    let end = vec![Trans(START_STATE_INDEX, 1.0)];
+    let (limit_reached, counter_zero) = if limit.is_some() { (end, vec![]) } else { (vec![], end) };
+
+    let mut state = State::new(enum_map! {
+        Event::PaddingSent => vec![Trans(curr_index, 1.0)],
+        Event::NormalSent => vec![Trans(other_index, 1.0)],
+        Event::LimitReached => limit_reached.clone(),
+        Event::CounterZero => counter_zero.clone(),
+        _ => vec![],
+    });
+
+    state.action = Some(Action::SendPadding {
+        bypass: true,
+        replace: true,
+        timeout: fixed(interval),
+        limit: limit,
+    });
+
+    return state;
+}
@@ -0,0 +1,438 @@
+// Structural checks for maybenot machines. These work on any machine, not
+// just the ones generated by this crate, and complement the checks done by
+// Machine::validate() (which only rejects machines the framework can't run).
+
+use std::fmt;
+use std::collections::VecDeque;
+
+use maybenot::{
+constants::{STATECANCEL, STATEEND},
+machine::Machine,
+event::Event,
+dist::DistType
+};
+
+use crate::logging::debug;
+
+
+// Tolerance used when comparing probability sums against 1.0
+const PROB_TOLERANCE: f64 = 0.000001;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Warning,
+    Error,
+}
+
+
+// A single problem found in a machine. The state is None for machine-level issues.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub level: Level,
+    pub state: Option<usize>,
+    pub message: String,
+}
+
+
+impl Issue {
+    pub fn warning(state: Option<usize>, message: String) -> Issue {
+        return Issue { level: Level::Warning, state: state, message: message };
+    }
+
+    pub fn error(state: Option<usize>, message: String) -> Issue {
+        return Issue { level: Level::Error, state: state, message: message };
+    }
+}
+
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.level {
+            Level::Warning => "warning",
+            Level::Error => "error",
+        };
+
+        match self.state {
+            Some(index) => write!(f, "State {}: {}: {}", index, level, self.message),
+            None => write!(f, "Machine: {}: {}", level, self.message),
+        }
+    }
+}
+
+
+// Run every check in this module.
+pub fn validate_machine(machine: &Machine) -> Vec<Issue> {
+    let mut issues: Vec<Issue> = Vec::new();
+
+    if let Err(e) = machine.validate() {
+        issues.push(Issue::error(None, e.to_string()));
+    }
+
+    issues.extend(validate_indices(machine));
+    issues.extend(validate_transitions(machine));
+    issues.extend(validate_reachability(machine));
+    issues.extend(validate_infinite_actions(machine));
+    issues.extend(validate_flag_consistency(machine));
+
+    return issues;
+}
+
+
+// Check that every transition vector matches the number of states in the
+// machine, and flag transitions to STATEEND. State::new() silently drops
+// targets that are out of range, so a wrong num_states shows up here as a
+// vector of the wrong length.
+pub fn validate_indices(machine: &Machine) -> Vec<Issue> {
+    let mut issues: Vec<Issue> = Vec::new();
+    let num_states = machine.states.len();
+
+    if num_states == 0 {
+        issues.push(Issue::error(None, "machine has no states".to_string()));
+    }
+
+    for (index, state) in machine.states.iter().enumerate() {
+        for event in Event::iterator() {
+            let probs = match state.next_state.get(event) {
+                Some(probs) => probs,
+                None => continue,
+            };
+
+            if probs.len() != num_states + 2 {
+                issues.push(Issue::error(Some(index), format!("{} transition vector has length {}, expected {}", event, probs.len(), num_states + 2)));
+                continue;
+            }
+
+            if probs[target_to_position(STATEEND, num_states)] > 0.0 {
+                issues.push(Issue::warning(Some(index), format!("{} transitions to STATEEND", event)));
+            }
+        }
+    }
+
+    return issues;
+}
+
+
+// Check transition probabilities and states that can never be left.
+pub fn validate_transitions(machine: &Machine) -> Vec<Issue> {
+    let mut issues: Vec<Issue> = Vec::new();
+
+    for (index, state) in machine.states.iter().enumerate() {
+        if state.next_state.is_empty() {
+            issues.push(Issue::warning(Some(index), "no outgoing transitions".to_string()));
+        }
+
+        for event in Event::iterator() {
+            let probs = match state.next_state.get(event) {
+                Some(probs) => probs,
+                None => continue,
+            };
+
+            let mut total = 0.0;
+            for p in probs {
+                if !(0.0..=1.0).contains(p) {
+                    issues.push(Issue::error(Some(index), format!("{} has probability {} outside [0.0, 1.0]", event, p)));
+                }
+                total += p;
+            }
+
+            if total <= 0.0 || total > 1.0 + PROB_TOLERANCE {
+                issues.push(Issue::error(Some(index), format!("{} probabilities sum to {}, must be (0.0, 1.0]", event, total)));
+            } else if total < 1.0 - PROB_TOLERANCE {
+                issues.push(Issue::warning(Some(index), format!("{} probabilities sum to {}, remainder is a no-op", event, total)));
+            }
+        }
+    }
+
+    return issues;
+}
+
+
+// Flag states that can never be entered.
+pub fn validate_reachability(machine: &Machine) -> Vec<Issue> {
+    let mut issues: Vec<Issue> = Vec::new();
+
+    for index in unreachable_states(machine) {
+        issues.push(Issue::warning(Some(index), "unreachable from state 0".to_string()));
+    }
+
+    return issues;
+}
+
+
+// Flag padding states with an INFINITY action. An infinite action is how the
+// blocking states block until replaced (e.g., BLOCK and SIGNAL), but as a
+// padding size it makes no sense, so it almost certainly belongs to a state
+// that is missing action_is_block.
+pub fn validate_infinite_actions(machine: &Machine) -> Vec<Issue> {
+    let mut issues: Vec<Issue> = Vec::new();
+
+    for (index, state) in machine.states.iter().enumerate() {
+        let action = &state.action;
+        if !state.action_is_block && action.dist != DistType::None && (action.param1.is_infinite() || action.param2.is_infinite()) {
+            issues.push(Issue::error(Some(index), format!("padding action {} is infinite, only blocking actions can be", action)));
+        }
+    }
+
+    return issues;
+}
+
+
+// Flag states whose bypass and replace flags are not a combination the
+// machines of the paper use for their kind of state:
+//  - a state without an action (e.g., START) sets neither, as both only
+//    apply to an action;
+//  - a padding state sets neither (FRONT), replace alone (Walkie-Talkie), or
+//    both (constant), so bypass padding, which gets through blocking, is also
+//    replaced by the real packets queued behind the block rather than sent on
+//    top of them;
+//  - a blocking state sets bypass, with or without replace (the BLOCK and
+//    SIGNAL states), so the machine's own bypass padding gets through it.
+// Other combinations run, but are either meaningless or likely a mistake.
+pub fn validate_flag_consistency(machine: &Machine) -> Vec<Issue> {
+    let mut issues: Vec<Issue> = Vec::new();
+
+    for (index, state) in machine.states.iter().enumerate() {
+        let message = if state.action_is_block {
+            if state.bypass { None } else { Some("blocking action without bypass also holds back the machine's own bypass padding") }
+        } else if state.action.dist == DistType::None {
+            if state.bypass || state.replace { Some("bypass or replace is set without an action, and has no effect") } else { None }
+        } else if state.bypass && !state.replace {
+            Some("padding action with bypass but not replace is sent on top of the real packets it bypasses")
+        } else {
+            None
+        };
+
+        if let Some(message) = message {
+            issues.push(Issue::warning(Some(index), message.to_string()));
+        }
+    }
+
+    return issues;
+}
+
+
+// Check the SEND states of a RegulaTor-style relay machine, from state first
+// to the last state: each one's LimitReached leads to the next with
+// probability 1.0, so the surge steps through them in order, and the last one
+// ends the machine (STATEEND). Only with loop_surge may the last state loop
+// instead, back to first (Adapted RegulaTor with --loop-surge) or to itself
+// (the original RegulaTor, which holds its minimum rate). The last state may
+// also have no limit and no LimitReached, holding its rate for good
+// (CS-BuFLO). The walk stops at the first state that breaks the chain.
+pub fn validate_send_chain(machine: &Machine, first: usize, loop_surge: bool) -> Vec<Issue> {
+    let mut issues: Vec<Issue> = Vec::new();
+    let num_states = machine.states.len();
+
+    if first >= num_states {
+        issues.push(Issue::error(None, format!("first SEND state {} is out of range for {} states", first, num_states)));
+        return issues;
+    }
+
+    let last = num_states - 1;
+    let mut index = first;
+    loop {
+        let targets: Vec<(usize, f64)> = match machine.states[index].next_state.get(&Event::LimitReached) {
+            Some(probs) => probs.iter().enumerate().filter(|(_, p)| **p > 0.0).map(|(position, p)| (position_to_target(position, num_states), *p)).collect(),
+            None => Vec::new(),
+        };
+
+        if index == last && targets.is_empty() && machine.states[index].limit.dist == DistType::None {
+            return issues;
+        }
+
+        let target = match targets[..] {
+            [(target, p)] if (p - 1.0).abs() <= PROB_TOLERANCE => target,
+            _ => {
+                issues.push(Issue::error(Some(index), format!("LimitReached must lead to a single state with probability 1.0, got {}", describe_targets(&targets))));
+                return issues;
+            }
+        };
+
+        if index == last {
+            if loop_surge && target != STATEEND && target != first && target != last {
+                issues.push(Issue::error(Some(index), format!("LimitReached of the last SEND state leads to {}, expected STATEEND (or state {} or {} to loop)", describe_target(target), first, last)));
+            } else if !loop_surge && target != STATEEND {
+                issues.push(Issue::error(Some(index), format!("LimitReached of the last SEND state leads to {}, expected STATEEND (the surge only loops with --loop-surge)", describe_target(target))));
+            }
+            return issues;
+        }
+
+        if target != index + 1 {
+            issues.push(Issue::error(Some(index), format!("LimitReached leads to {}, expected state {}", describe_target(target), index + 1)));
+            return issues;
+        }
+        index = target;
+    }
+}
+
+
+fn describe_targets(targets: &[(usize, f64)]) -> String {
+    if targets.is_empty() {
+        return String::from("no transition");
+    }
+
+    return targets.iter().map(|(target, p)| format!("{} ({})", describe_target(*target), p)).collect::<Vec<String>>().join(", ");
+}
+
+
+fn describe_target(target: usize) -> String {
+    return match target {
+        STATEEND => String::from("STATEEND"),
+        STATECANCEL => String::from("STATECANCEL"),
+        _ => format!("state {}", target),
+    };
+}
+
+
+// Indices of states never visited by a breadth-first search from state 0 over
+// all transitions with non-zero probability. STATEEND is a sink and
+// STATECANCEL stays in the current state, so neither leads anywhere new.
+pub fn unreachable_states(machine: &Machine) -> Vec<usize> {
+    let num_states = machine.states.len();
+    if num_states == 0 {
+        return Vec::new();
+    }
+
+    let mut visited = vec![false; num_states];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    visited[0] = true;
+    queue.push_back(0);
+
+    while let Some(index) = queue.pop_front() {
+        for probs in machine.states[index].next_state.values() {
+            for (target, p) in probs.iter().enumerate().take(num_states) {
+                if *p > 0.0 && !visited[target] {
+                    visited[target] = true;
+                    queue.push_back(target);
+                }
+            }
+        }
+    }
+
+    return (0..num_states).filter(|index| !visited[*index]).collect();
+}
+
+
+// Warn about machines that would block together when run side by side (e.g.,
+// combined into one list). Blocking is global to the framework: the
+// BlockingBegin and BlockingEnd events of a block reach every machine, and a
+// block with replace set cuts short the one in place (without, the longer of
+// the two wins). The Scrambler's machines rely on this to signal each other,
+// but between unrelated machines, it lets one machine's blocks drive the
+// other's states. Machines are numbered from 1, and only blocking states
+// reachable from state 0 are counted.
+pub fn check_composition(machines: &[Machine]) -> Vec<String> {
+    let blocking: Vec<Vec<usize>> = machines.iter().map(reachable_blocking_states).collect();
+    let mut warnings: Vec<String> = Vec::new();
+
+    for i in 0..machines.len() {
+        for j in i + 1..machines.len() {
+            if blocking[i].is_empty() || blocking[j].is_empty() {
+                continue;
+            }
+
+            let replacing = [i, j].iter().any(|&m| blocking[m].iter().any(|&index| machines[m].states[index].replace));
+            warnings.push(format!(
+                "machines {} and {} both block (states {} and {}), and blocking is shared: each machine's BlockingBegin/BlockingEnd transitions also fire on the other's blocks{}",
+                i + 1, j + 1, join_indices(&blocking[i]), join_indices(&blocking[j]),
+                if replacing { ", and a block with replace set cuts short the other's" } else { "" },
+            ));
+        }
+    }
+
+    return warnings;
+}
+
+
+// Indices of the blocking states reachable from state 0.
+fn reachable_blocking_states(machine: &Machine) -> Vec<usize> {
+    let unreachable = unreachable_states(machine);
+    return (0..machine.states.len())
+        .filter(|index| machine.states[*index].action_is_block && !unreachable.contains(index))
+        .collect();
+}
+
+
+fn join_indices(indices: &[usize]) -> String {
+    return indices.iter().map(|index| index.to_string()).collect::<Vec<String>>().join(", ");
+}
+
+
+// Check that a generated machine has a state 0, where maybenot starts it, for
+// the generators to run on every machine before printing it. No parameters
+// the generators accept give a machine without states, but one would
+// otherwise serialize and only fail once loaded.
+pub fn check_has_states(name: &str, machine: &Machine) -> Result<(), String> {
+    if machine.states.is_empty() {
+        return Err(format!("{} has no states, not even state 0 to start in (the parameters give a degenerate machine)", name));
+    }
+
+    return Ok(());
+}
+
+
+// Print a warning to stderr for each unreachable state, for the
+// --warn-unreachable option of the generators.
+pub fn report_unreachable(name: &str, machine: &Machine) {
+    let unreachable = unreachable_states(machine);
+    debug(|| format!("{}: {} unreachable states", name, unreachable.len()));
+    
+    for index in unreachable {
+        eprintln!("Warning: {} state {} is unreachable", name, index);
+    }
+}
+
+
+// Run the full validation suite for the --dry-run option of the generators:
+// print every issue, then "<name>: OK (<n> states)" for each machine without
+// errors. Returns false if any machine has errors.
+pub fn dry_run(machines: &[(&str, &Machine)]) -> bool {
+    let mut ok = true;
+
+    for (name, machine) in machines {
+        let issues = validate_machine(machine);
+        debug(|| format!("{}: {} issues, {} errors", name, issues.len(), issues.iter().filter(|issue| issue.level == Level::Error).count()));
+        
+        for issue in &issues {
+            println!("{}: {}", name, issue);
+        }
+
+        if has_errors(&issues) {
+            ok = false;
+        } else {
+            println!("{}: OK ({} states)", name, machine.states.len());
+        }
+    }
+
+    return ok;
+}
+
+
+// Returns true if any issue is an error.
+pub fn has_errors(issues: &[Issue]) -> bool {
+    return issues.iter().any(|issue| issue.level == Level::Error);
+}
+
+
+// Position of a transition target in a next_state vector: real states come
+// first, followed by STATECANCEL and STATEEND.
+fn target_to_position(target: usize, num_states: usize) -> usize {
+    if target == STATECANCEL {
+        return num_states;
+    } else if target == STATEEND {
+        return num_states + 1;
+    }
+    return target;
+}
+
+
+// Inverse of target_to_position().
+fn position_to_target(position: usize, num_states: usize) -> usize {
+    if position == num_states {
+        return STATECANCEL;
+    } else if position == num_states + 1 {
+        return STATEEND;
+    }
+    return position;
+}
@@ -0,0 +1,188 @@
+// Machine generation for Walkie-Talkie (see bin/walkie_talkie.rs): a machine
+// of RECV, SEND, and SENT states per side, padding the bursts of its
+// direction. WalkieTalkie holds the parameters of the walkie_talkie binary,
+// parsed from its arguments by WalkieTalkie::from_args().
+
+use std::collections::HashMap;
+
+use maybenot::{
+machine::Machine,
+event::Event,
+state::State
+};
+
+use crate::args::{try_parse_args, parse_finite_positive, packet_size, parse_interval, strict_count, machine_options, Args, MachineOptions};
+use crate::validate::check_has_states;
+use crate::dist_helpers::fixed;
+
+
+pub const NUM_STATES: usize = 3;
+
+pub const RECV_STATE_INDEX: usize = 0;
+pub const SEND_STATE_INDEX: usize = 1;
+pub const SENT_STATE_INDEX: usize = 2;
+
+// Switches and options of the walkie_talkie binary
+pub const SWITCHES: &[&str] = &["--warn-unreachable", "--explain", "--dry-run", "--annotate", "--hash", "--ffi", "--emit-rust", "--base64", "--pretty", "--report", "--include-small-packets", "--strict", "--ms"];
+pub const OPTIONS: &[&str] = &["--packet-size", "--max-blocking-frac", "--allowed-blocked-microsec", "--time-scale"];
+
+
+// Parameters of the walkie_talkie binary
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalkieTalkie {
+    // Packets per outgoing and incoming burst, padded by the client and relay
+    // machines
+    pub send_burst: f64,
+    pub recv_burst: f64,
+    // Time between padding packets (usec)
+    pub interval: f64,
+    // Size of padding packets (bytes)
+    pub packet_size: f64,
+    pub options: MachineOptions,
+}
+
+
+impl WalkieTalkie {
+    // Parse the arguments of the walkie_talkie binary (see parse_args()).
+    pub fn from_args(cli: &Args) -> Result<WalkieTalkie, String> {
+        let args = &cli.positional;
+        if args.len() != 4 {
+            return Err(format!("Usage: {} <send burst> <recv burst> <send interval> [--warn-unreachable] [--explain] [--dry-run] [--annotate] [--hash] [--ffi] [--emit-rust] [--base64] [--report] [--include-small-packets] [--strict] [--ms] [--packet-size <bytes>] [--max-blocking-frac <fraction>] [--allowed-blocked-microsec <usec>] [--time-scale <factor>]", args[0]));
+        }
+
+        let send_burst: f64 = parse_finite_positive(&args[1], "send burst").and_then(|count| strict_count(cli, count, "send burst"))?; // packets per outgoing burst
+        let recv_burst: f64 = parse_finite_positive(&args[2], "recv burst").and_then(|count| strict_count(cli, count, "recv burst"))?; // packets per incoming burst
+        let interval:   f64 = parse_interval(cli, &args[3]).ok_or("Invalid send interval")?; // time between padding packets (usec)
+
+        if send_burst < 1.0 || recv_burst < 1.0 {
+            return Err(String::from("Burst sizes must be at least 1 packet"));
+        }
+        if interval <= 0.0 {
+            return Err(String::from("Send interval must be positive"));
+        }
+
+        return Ok(WalkieTalkie {
+            send_burst: send_burst,
+            recv_burst: recv_burst,
+            interval: interval,
+            packet_size: packet_size(cli)?,
+            options: machine_options(cli)?,
+        });
+    }
+
+
+    // The client machine, padding outgoing bursts to the send burst, and the
+    // relay machine, padding incoming bursts to the recv burst, with the names
+    // the binary prints.
+    pub fn named_machines(&self) -> Result<Vec<(String, Machine)>, String> {
+        let mut machines = [
+            ("Client machine", generate_machine(self.send_burst, self.interval, self.packet_size)),
+            ("Relay machine", generate_machine(self.recv_burst, self.interval, self.packet_size)),
+        ];
+        for (name, machine) in machines.iter_mut() {
+            self.options.apply(machine)?;
+            check_has_states(name, machine)?;
+        }
+
+        return Ok(machines.into_iter().map(|(name, machine)| (name.to_string(), machine)).collect());
+    }
+}
+
+
+// Parse the arguments of the walkie_talkie binary (without the program name)
+// into its parameters.
+pub fn parse(args: &[String]) -> Result<WalkieTalkie, String> {
+    let cli = try_parse_args([&[String::from("walkie_talkie")], args].concat(), SWITCHES, OPTIONS)?;
+    return WalkieTalkie::from_args(&cli);
+}
+
+
+// Generate the machines of the walkie_talkie binary for its arguments
+// (without the program name), e.g., generate(&["20", "80", "1000"]).
+pub fn generate(args: &[String]) -> Result<Vec<(String, Machine)>, String> {
+    return parse(args)?.named_machines();
+}
+
+
+// Generate a machine that pads each of its outgoing bursts to burst packets.
+pub fn generate_machine(burst: f64, interval: f64, packet_size: f64) -> Machine {
+    // States
+    let mut states: Vec<State> = Vec::with_capacity(NUM_STATES);
+    states.push(generate_recv_state());
+    states.push(generate_send_state(burst, interval, packet_size));
+    states.push(generate_sent_state());
+
+    // Machine
+    let machine = Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    };
+
+    return machine;
+}
+
+
+// Generate the RECV state for a machine.
+pub fn generate_recv_state() -> State {
+    // NonPaddingSent --> SEND (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(SEND_STATE_INDEX, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+
+    return State::new(transitions, NUM_STATES);
+}
+
+
+// Generate the SEND state for a machine.
+pub fn generate_send_state(burst: f64, interval: f64, packet_size: f64) -> State {
+    // PaddingSent --> SEND (100%)
+    let mut padding_sent: HashMap<usize, f64> = HashMap::new();
+    padding_sent.insert(SEND_STATE_INDEX, 1.0);
+
+    // NonPaddingSent --> SEND (100%)
+    let mut nonpadding_sent: HashMap<usize, f64> = HashMap::new();
+    nonpadding_sent.insert(SEND_STATE_INDEX, 1.0);
+
+    // LimitReached --> SENT (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(SENT_STATE_INDEX, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::PaddingSent, padding_sent);
+    transitions.insert(Event::NonPaddingSent, nonpadding_sent);
+    transitions.insert(Event::LimitReached, limit_reached);
+
+    // SEND state
+    let mut state = State::new(transitions, NUM_STATES);
+    state.replace = true;
+
+    state.timeout = fixed(interval);
+
+    state.action = fixed(packet_size);
+
+    state.limit = fixed(burst);
+
+    return state;
+}
+
+
+// Generate the SENT state for a machine.
+pub fn generate_sent_state() -> State {
+    // NonPaddingRecv --> RECV (100%)
+    let mut nonpadding_recv: HashMap<usize, f64> = HashMap::new();
+    nonpadding_recv.insert(RECV_STATE_INDEX, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::NonPaddingRecv, nonpadding_recv);
+
+    return State::new(transitions, NUM_STATES);
+}
@@ -0,0 +1,67 @@
+// Warm-up states for any machine (see bin/combine.rs --warmup): a machine that
+// should do nothing for the first packets of a connection, so that its
+// padding does not mark the connection setup, gets a new state 0 that lets
+// them pass. The WARMUP state has no action, and its limit counts the real
+// packets sent; once it is reached, the machine moves on to its old start.
+
+use std::collections::HashMap;
+
+use maybenot::{
+machine::Machine,
+event::Event,
+state::State
+};
+
+use crate::dist_helpers::fixed;
+
+
+pub const WARMUP_STATE_INDEX: usize = 0;
+
+
+// Prepend a WARMUP state to a machine that lets count real packets be sent
+// before it moves on to the old start, now state 1. The old states keep their
+// transitions, with every target shifted by 1 (to STATECANCEL and STATEEND
+// included, as they are last). A count of 0 leaves the machine as it is.
+//
+// The old start is then entered on a transition, so unlike at the start of
+// the machine, its action is scheduled: a state 0 with a timeout sends once
+// after the warm-up. The small packets that maybenot ignores still count
+// toward the limit (see README.md).
+pub fn with_warmup(machine: Machine, count: usize) -> Machine {
+    if count == 0 {
+        return machine;
+    }
+
+    let num_states = machine.states.len() + 1;
+    let mut states: Vec<State> = Vec::with_capacity(num_states);
+    states.push(generate_warmup_state(count, num_states));
+
+    for mut state in machine.states {
+        for probabilities in state.next_state.values_mut() {
+            probabilities.insert(WARMUP_STATE_INDEX, 0.0);
+        }
+        states.push(state);
+    }
+
+    return Machine { states: states, ..machine };
+}
+
+
+// Generate the WARMUP state for a machine of num_states states (the old start
+// at index 1).
+fn generate_warmup_state(count: usize, num_states: usize) -> State {
+    // LimitReached --> old start (100%)
+    let mut limit_reached: HashMap<usize, f64> = HashMap::new();
+    limit_reached.insert(WARMUP_STATE_INDEX + 1, 1.0);
+
+    // Transitions
+    let mut transitions: HashMap<Event, HashMap<usize, f64>> = HashMap::new();
+    transitions.insert(Event::LimitReached, limit_reached);
+
+    // WARMUP state
+    let mut state = State::new(transitions, num_states);
+    state.limit = fixed(count as f64);
+    state.limit_includes_nonpadding = true;
+
+    return state;
+}
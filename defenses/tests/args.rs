@@ -0,0 +1,42 @@
+// Tests for the shared parsing of numeric arguments (src/args.rs), and their
+// checks with --strict.
+
+use raising_the_bar::args::{parse_args, parse_finite, parse_finite_positive, strict_count};
+
+
+#[test]
+fn finite_numbers() {
+    assert_eq!(parse_finite("-1.5", "count"), Ok(-1.5));
+    assert_eq!(parse_finite("0", "count"), Ok(0.0));
+    assert_eq!(parse_finite_positive("4000", "interval"), Ok(4000.0));
+    assert_eq!(parse_finite_positive("1e-3", "interval"), Ok(0.001));
+
+    for text in ["nan", "NaN", "inf", "-inf", "infinity", "1e400", "abc", ""] {
+        assert!(parse_finite(text, "count").is_err(), "{} accepted", text);
+        assert!(parse_finite_positive(text, "interval").is_err(), "{} accepted", text);
+    }
+
+    for text in ["0", "-0", "-1"] {
+        assert!(parse_finite_positive(text, "interval").is_err(), "{} accepted", text);
+    }
+}
+
+
+#[test]
+fn errors_name_the_argument() {
+    assert_eq!(parse_finite_positive("nan", "initial rate"), Err(String::from("initial rate must be a positive finite number, got nan")));
+    assert_eq!(parse_finite("inf", "decay rate"), Err(String::from("decay rate must be a finite number, got inf")));
+    assert_eq!(parse_finite("x", "decay rate"), Err(String::from("decay rate must be a number, got x")));
+}
+
+
+#[test]
+fn strict_rejects_fractional_counts() {
+    let strict = parse_args(vec![String::from("scrambler"), String::from("--strict")], &["--strict"], &[]);
+    assert_eq!(strict_count(&strict, 3.0, "minimum count"), Ok(3.0));
+    assert_eq!(strict_count(&strict, 3.5, "minimum count"), Err(String::from("minimum count must be an integer with --strict, got 3.5")));
+
+    // Without --strict, the value is left to be rounded
+    let lenient = parse_args(vec![String::from("scrambler")], &["--strict"], &[]);
+    assert_eq!(strict_count(&lenient, 3.5, "minimum count"), Ok(3.5));
+}
@@ -0,0 +1,235 @@
+// Runs the compiled binaries end to end: every generator prints its machines
+// framed as "<name>: <machine> (<length>)" for representative arguments (those
+// of the paper where there are any), every tool runs on the machines they
+// print, the length each machine is printed with is that of its serialized
+// string, and a wrong number of arguments (or with --strict, a fractional
+// count) makes them exit with an error.
+
+use std::str::FromStr;
+
+use maybenot::machine::Machine;
+
+use raising_the_bar::output::{encode_base64, decode_base64};
+
+mod common;
+
+use common::{generator_args, run, GENERATORS, TRACE};
+
+
+// Scrambler parameters for sweep, one row
+const SWEEP_INPUT: &str = "160,500,400,1000\n";
+
+
+fn write_file(name: &str, contents: &str) -> String {
+    return common::write_file(&format!("cli_{}", name), contents);
+}
+
+
+fn strings(args: &[&str]) -> Vec<String> {
+    return args.iter().map(|arg| arg.to_string()).collect();
+}
+
+
+// The name and machine string of a "<name>: <machine> (<length>)" line,
+// checking the length and that the machine parses.
+fn parse_line(line: &str) -> (String, String) {
+    let (name, rest) = line.split_once(": ").unwrap_or_else(|| panic!("no name in \"{}\"", line));
+    let (machine, length) = rest.rsplit_once(" (").unwrap_or_else(|| panic!("no length in \"{}\"", line));
+    let length: usize = length.strip_suffix(')').and_then(|length| length.parse().ok()).unwrap_or_else(|| panic!("invalid length in \"{}\"", line));
+
+    assert!(!machine.is_empty(), "empty machine in \"{}\"", line);
+    assert_eq!(machine.len(), length, "{}", name);
+    assert!(Machine::from_str(machine).is_ok(), "{} does not parse", name);
+    return (name.to_string(), machine.to_string());
+}
+
+
+// The machines printed by a generator (blank lines apart), checking its exit
+// code and framing.
+fn generated_machines(name: &str, args: &[String]) -> Vec<(String, String)> {
+    let output = run(name, args);
+    assert!(output.status.success(), "{} {:?}: {}", name, args, String::from_utf8_lossy(&output.stderr));
+    return String::from_utf8(output.stdout).unwrap().lines().filter(|line| !line.is_empty()).map(parse_line).collect();
+}
+
+
+#[test]
+fn generators_print_framed_machines() {
+    for (name, args, machine_names) in GENERATORS {
+        let machines = generated_machines(name, &generator_args(args));
+        let names: Vec<&str> = machines.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(&names, machine_names, "{}", name);
+    }
+}
+
+
+// The "(<length>)" after each machine is the number of bytes of the machine
+// string before it, which is the serialization of the machine (or with
+// --base64, its encoding), not e.g. its state count.
+#[test]
+fn printed_lengths_match_serialized_strings() {
+    for (name, args, _) in GENERATORS {
+        for base64 in [false, true] {
+            let mut args = generator_args(args);
+            if base64 {
+                args.push(String::from("--base64"));
+            }
+            let output = run(name, &args);
+            assert!(output.status.success(), "{} {:?}: {}", name, args, String::from_utf8_lossy(&output.stderr));
+
+            for line in String::from_utf8(output.stdout).unwrap().lines().filter(|line| !line.is_empty()) {
+                let (_, rest) = line.split_once(": ").unwrap();
+                let (machine, length) = rest.rsplit_once(" (").unwrap();
+                let length: usize = length.strip_suffix(')').unwrap().parse().unwrap();
+                assert_eq!(length, machine.len(), "{} {:?}: {}", name, args, line);
+
+                let serialized = if base64 { decode_base64(machine).unwrap().serialize() } else { Machine::from_str(machine).unwrap().serialize() };
+                let expected = if base64 { encode_base64(&Machine::from_str(&serialized).unwrap()).len() } else { serialized.len() };
+                assert_eq!(length, expected, "{} {:?}", name, args);
+            }
+        }
+    }
+}
+
+
+#[test]
+fn generators_reject_wrong_argument_counts() {
+    for (name, args, _) in GENERATORS {
+        // One positional argument too many
+        let mut extra = generator_args(args);
+        extra.push(String::from("1"));
+        assert!(!run(name, &extra).status.success(), "{} {:?}", name, extra);
+
+        // The last one left out, unless it has a default (see defaults.rs)
+        let defaults = ["constant", "adapted_front", "adapted_regulator", "scrambler"];
+        if !defaults.contains(name) {
+            let missing = generator_args(&args[..args.len() - 1]);
+            assert!(!run(name, &missing).status.success(), "{} {:?}", name, missing);
+        }
+    }
+}
+
+
+// Parameters that would leave a machine without states (no segment, no send
+// states, a zero cap) are rejected with a message rather than a panic of
+// maybenot or an empty machine.
+#[test]
+fn generators_reject_degenerate_parameters() {
+    let degenerate: &[(&str, &[&str], &str)] = &[
+        ("adapted_front", &["12", "4000", "0"], "Num states must be at least 1"),
+        ("dynaflow", &["4000", "0"], "Switch threshold must be at least 1"),
+        ("scrambler", &["160", "0"], "minimum count must be at least 1"),
+        ("regulator", &["500", "0.75", "2", "4", "0"], "packets per state must be a positive finite number"),
+        ("constant", &["0"], "Send interval must be positive"),
+        ("surakav", &["TRACE", "1000", "5", "--max-states", "0"], "Max states must be at least 1"),
+        ("surakav", &["TRACE", "5e-324", "5"], "Rate 5e-324 is too small for the reference trace"),
+    ];
+
+    for (name, args, message) in degenerate {
+        let output = run(name, &generator_args(args));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.code() == Some(1) && output.stdout.is_empty(), "{} {:?}", name, args);
+        assert!(stderr.contains(message), "{} {:?}: {}", name, args, stderr);
+    }
+}
+
+
+// --strict rejects a fractional minimum count, which is otherwise rounded with
+// a warning, but accepts an integer one written as a decimal.
+#[test]
+fn strict_rejects_fractional_counts() {
+    let args = |min_count: &str, strict: bool| {
+        let mut args = strings(&["160", min_count, "400", "1000"]);
+        if strict {
+            args.push(String::from("--strict"));
+        }
+        return args;
+    };
+
+    assert!(run("scrambler", &args("3.5", false)).status.success());
+    let output = run("scrambler", &args("3.5", true));
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("minimum count must be an integer with --strict, got 3.5"));
+
+    assert_eq!(generated_machines("scrambler", &args("3.0", true)), generated_machines("scrambler", &args("3", false)));
+}
+
+
+// --pretty keeps the plain output as the first line, and only adds "#" lines
+// listing each machine.
+#[test]
+fn pretty_keeps_plain_machine_lines() {
+    for (name, args, _) in GENERATORS {
+        let args = generator_args(args);
+        let plain = String::from_utf8(run(name, &args).stdout).unwrap();
+
+        let mut pretty_args = args.clone();
+        pretty_args.push(String::from("--pretty"));
+        let output = run(name, &pretty_args);
+        assert!(output.status.success(), "{} {:?}: {}", name, pretty_args, String::from_utf8_lossy(&output.stderr));
+        let pretty = String::from_utf8(output.stdout).unwrap();
+
+        assert_eq!(pretty.lines().next(), plain.lines().next(), "{}", name);
+        let stripped: Vec<&str> = pretty.lines().filter(|line| !line.starts_with('#')).collect();
+        assert_eq!(stripped, plain.lines().collect::<Vec<&str>>(), "{}", name);
+        assert!(pretty.lines().any(|line| line.starts_with("#     ")), "{} lists no state", name);
+    }
+}
+
+
+#[test]
+fn tools_run_on_generated_machines() {
+    let machine = generated_machines("constant", &[]).remove(0).1;
+    let other = generated_machines("constant", &strings(&["5000"])).remove(0).1;
+
+    for (tool, args) in [
+        ("lint", vec![machine.clone()]),
+        ("decode", vec![machine.clone()]),
+        ("combine", vec![String::from("--machine"), machine.clone(), String::from("--machine"), other.clone()]),
+        ("generate", strings(&["constant", "4000"])),
+        ("schema", strings(&["constant"])),
+        ("sweep", vec![write_file("sweep.csv", SWEEP_INPUT)]),
+        ("sizes", strings(&["packets per state", "10", "20", "10", "--", "adapted_regulator", "500", "0.75", "4", "20"])),
+        ("measure", vec![write_file("base.csv", TRACE), String::from("--"), String::from("constant"), String::from("4000")]),
+        ("calibrate", vec![write_file("base.csv", TRACE)]),
+        ("tune", vec![write_file("base.csv", TRACE), String::from("2"), String::from("--max-measurements"), String::from("5")]),
+    ] {
+        let output = run(tool, &args);
+        assert!(output.status.success(), "{} {:?}: {}", tool, args, String::from_utf8_lossy(&output.stderr));
+        assert!(!output.stdout.is_empty(), "{} printed nothing", tool);
+    }
+
+    // diff exits with status 1 on differences, which it lists
+    assert!(run("diff", &[machine.clone(), machine.clone()]).status.success());
+    let output = run("diff", &[machine.clone(), other.clone()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!output.stdout.is_empty());
+
+    // generate prints the machines of the defense, framed as its binary does
+    let output = run("generate", &strings(&["constant", "4000"]));
+    let lines: Vec<(String, String)> = String::from_utf8(output.stdout).unwrap().lines().filter(|line| !line.is_empty()).map(parse_line).collect();
+    assert_eq!(lines, [(String::from("Machine"), machine)]);
+}
+
+
+#[test]
+fn tools_reject_wrong_argument_counts() {
+    let machine = generated_machines("constant", &[]).remove(0).1;
+
+    for (tool, args) in [
+        ("lint", vec![]),
+        ("lint", vec![machine.clone(), machine.clone()]),
+        ("decode", vec![]),
+        ("diff", vec![machine.clone()]),
+        ("combine", vec![]),
+        ("generate", vec![]),
+        ("schema", strings(&["constant", "buflo"])),
+        ("sweep", vec![]),
+        ("sizes", strings(&["packets per state", "10", "20", "--", "adapted_regulator"])),
+        ("measure", strings(&["--", "constant"])),
+        ("calibrate", vec![]),
+        ("tune", vec![write_file("base.csv", TRACE)]),
+    ] {
+        assert!(!run(tool, &args).status.success(), "{} {:?}", tool, args);
+    }
+}
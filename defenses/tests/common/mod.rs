@@ -0,0 +1,89 @@
+// Fixtures shared by the tests, included with `mod common;`. Each test crate
+// uses some of them only.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use raising_the_bar::schema::{find_defense, Kind};
+
+
+// A reference trace for Surakav, and a base trace for the tools
+pub const TRACE: &str = "\
+0,s,600
+1000000,s,600
+2000000,s,600
+3000000,s,600
+20000000,r,1500
+20500000,r,1500
+21000000,r,1500
+21500000,r,1500
+";
+
+// Generators, with their representative arguments (those of the paper where
+// there are any, that run quickly) and the names of their machines. Surakav
+// takes the trace written by generator_args().
+pub const GENERATORS: &[(&str, &[&str], &[&str])] = &[
+    ("constant", &["4000"], &["Machine"]),
+    ("buflo", &["10", "4000"], &["Machine"]),
+    ("adapted_front", &["12", "4000", "30"], &["Machine"]),
+    ("adapted_regulator", &["500", "0.75", "4", "20", "2"], &["Relay machine", "Client machine"]),
+    ("scrambler", &["160", "500", "400", "1000"], &["Machine 1", "Machine 2"]),
+    ("adaptive_pad", &["4000", "1000", "2000", "500", "100"], &["Machine"]),
+    ("walkie_talkie", &["10", "20", "4000"], &["Client machine", "Relay machine"]),
+    ("surakav", &["TRACE", "1000", "5"], &["Client machine", "Relay machine"]),
+    ("dynaflow", &["4000,8000", "10"], &["Client machine", "Relay machine"]),
+    ("interspace", &["4000", "1000", "100", "1500"], &["Machine"]),
+    ("cs_buflo", &["125", "1000", "2"], &["Machine"]),
+    ("regulator", &["500", "0.75", "2", "4", "20"], &["Relay machine", "Client machine"]),
+];
+
+
+// Path of a binary of the crate, next to the generate binary that cargo builds
+// for the tests.
+pub fn binary(name: &str) -> PathBuf {
+    return PathBuf::from(env!("CARGO_BIN_EXE_generate")).with_file_name(name);
+}
+
+
+// Run a binary of the crate to completion.
+pub fn run(name: &str, args: &[String]) -> Output {
+    return Command::new(binary(name)).args(args).output().expect("Unable to run binary");
+}
+
+
+// Write a file of the tests to the temporary directory, and return its path.
+pub fn write_file(name: &str, contents: &str) -> String {
+    let path = std::env::temp_dir().join(name);
+    fs::write(&path, contents).unwrap();
+    return path.to_str().unwrap().to_string();
+}
+
+
+// The arguments of a generator of GENERATORS, with the trace written out (to
+// a file of this test process, as the test crates run in parallel).
+pub fn generator_args(args: &[&str]) -> Vec<String> {
+    return args.iter().map(|arg| {
+        if *arg != "TRACE" {
+            return arg.to_string();
+        }
+        return write_file(&format!("generator_trace_{}.csv", std::process::id()), TRACE);
+    }).collect();
+}
+
+
+// The examples of the required positional parameters of a defense (see
+// schema.rs), with TRACE for files.
+pub fn example_args(name: &str) -> Vec<String> {
+    let defense = find_defense(name).unwrap();
+    return defense.params.iter()
+        .filter(|param| param.flag.is_none() && param.default.is_none())
+        .map(|param| {
+            if param.kind != Kind::File {
+                return param.example.to_string();
+            }
+            return write_file(&format!("example_{}_{}", name, param.example), TRACE);
+        })
+        .collect();
+}
@@ -0,0 +1,77 @@
+// Tests for the composition check (validate::check_composition()), with the
+// Scrambler's two machines, which block together by design, as the fixture.
+
+use maybenot::{
+machine::Machine,
+dist::{Dist, DistType}
+};
+
+use raising_the_bar::PACKET_SIZE;
+use raising_the_bar::constant::{self, Trigger};
+use raising_the_bar::scrambler::{generate_machine_one, generate_machine_one_single_pair, generate_machine_no_block, generate_machine_two, TrailDist, Direction};
+use raising_the_bar::validate::check_composition;
+
+
+// Machine #1 generator of the Scrambler, with the parameters of the README
+type MachineOne = fn(f64, f64, f64, (f64, f64), (f64, f64), TrailDist, f64) -> Machine;
+
+fn machine_one(generate: MachineOne) -> Machine {
+    return generate(160.0, 160.0, 500.0, (400.0, 1000.0), (400.0, 1000.0), TrailDist::Uniform, PACKET_SIZE);
+}
+
+
+fn no_block_machine() -> Machine {
+    return generate_machine_no_block(160.0, 160.0, 500.0, (400.0, 1000.0), (400.0, 1000.0), TrailDist::Uniform, Direction::Up, PACKET_SIZE);
+}
+
+
+fn constant_machine() -> Machine {
+    let timeout = Dist { dist: DistType::Uniform, param1: 4000.0, param2: 4000.0, start: 0.0, max: 0.0 };
+    return constant::generate_machine(timeout, Trigger::Both, PACKET_SIZE);
+}
+
+
+#[test]
+fn scrambler_machines_block_together() {
+    let warnings = check_composition(&[machine_one(generate_machine_one), generate_machine_two(500.0, Direction::Up)]);
+    assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    assert!(warnings[0].starts_with("machines 1 and 2 both block"), "{}", warnings[0]);
+    assert!(warnings[0].contains("replace"), "{}", warnings[0]);
+
+    let warnings = check_composition(&[machine_one(generate_machine_one_single_pair), generate_machine_two(500.0, Direction::Up)]);
+    assert_eq!(warnings.len(), 1, "{:?}", warnings);
+}
+
+
+#[test]
+fn single_machines_do_not_conflict() {
+    assert!(check_composition(&[]).is_empty());
+    assert!(check_composition(&[machine_one(generate_machine_one)]).is_empty());
+    assert!(check_composition(&[generate_machine_two(500.0, Direction::Up)]).is_empty());
+}
+
+
+#[test]
+fn machines_without_blocking_do_not_conflict() {
+    let no_block = no_block_machine();
+    assert!(check_composition(&[no_block.clone(), generate_machine_two(500.0, Direction::Up)]).is_empty());
+    assert!(check_composition(&[constant_machine(), no_block.clone(), no_block]).is_empty());
+}
+
+
+#[test]
+fn every_blocking_pair_is_reported() {
+    let machines = [machine_one(generate_machine_one), constant_machine(), no_block_machine(), generate_machine_two(500.0, Direction::Up)];
+    let warnings = check_composition(&machines);
+    let pairs: Vec<&str> = warnings.iter().map(|warning| warning.split(" both").next().unwrap()).collect();
+    assert_eq!(pairs, ["machines 1 and 2", "machines 1 and 4", "machines 2 and 4"]);
+}
+
+
+#[test]
+fn unreachable_blocking_states_are_ignored() {
+    // The constant machine with its BLOCK state cut off from START
+    let mut machine = constant_machine();
+    machine.states[0].next_state.clear();
+    assert!(check_composition(&[machine, generate_machine_two(500.0, Direction::Up)]).is_empty());
+}
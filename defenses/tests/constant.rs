@@ -0,0 +1,138 @@
+// Tests for the constant-rate machines (src/constant.rs), with and without
+// blocking, with a different interval per direction (--asymmetric and
+// --both-sides), with their timeouts scaled (--time-scale), with no arguments
+// (the paper default), jittered (--jitter-frac), and the minimal machine
+// (--minimal).
+
+use std::str::FromStr;
+
+use maybenot::machine::Machine;
+
+use raising_the_bar::PACKET_SIZE;
+use raising_the_bar::defaults;
+use raising_the_bar::constant::{self, Trigger, NUM_STATES, PAD_ONLY_NUM_STATES, PAD_ONLY_CONST_STATE_INDEX};
+use raising_the_bar::dist_helpers::{fixed, uniform_range};
+use raising_the_bar::validate::validate_machine;
+
+
+#[test]
+fn pad_only_machine_never_blocks() {
+    for trigger in [Trigger::Sent, Trigger::Recv, Trigger::Both] {
+        let machine = constant::generate_pad_only_machine(fixed(4000.0), trigger, PACKET_SIZE);
+        assert_eq!(machine.states.len(), PAD_ONLY_NUM_STATES);
+        assert_eq!(PAD_ONLY_NUM_STATES, NUM_STATES - 1);
+        assert!(machine.states.iter().all(|state| !state.action_is_block));
+
+        // The CONST state is the same as with blocking, but for its index
+        let with_blocking = constant::generate_machine(fixed(4000.0), trigger, PACKET_SIZE);
+        let (state, blocking_state) = (&machine.states[PAD_ONLY_CONST_STATE_INDEX], &with_blocking.states[constant::CONST_STATE_INDEX]);
+        assert!(state.bypass && state.replace);
+        assert_eq!((state.timeout, state.action, state.limit), (blocking_state.timeout, blocking_state.action, blocking_state.limit));
+    }
+}
+
+
+#[test]
+fn asymmetric_machines_have_own_intervals() {
+    let args: Vec<String> = ["--asymmetric", "--up-interval", "8000", "--down-interval", "1000"].iter().map(|arg| arg.to_string()).collect();
+    let machines = constant::generate(&args).unwrap();
+
+    let names: Vec<&str> = machines.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["Client machine", "Relay machine"]);
+    for ((_, machine), interval) in machines.iter().zip([8000.0, 1000.0]) {
+        assert_eq!(machine, &constant::generate_machine(fixed(interval), Trigger::Both, PACKET_SIZE));
+        assert_eq!(machine.states[constant::CONST_STATE_INDEX].timeout, fixed(interval));
+    }
+}
+
+
+// --both-sides prints a client and a relay machine, whose CONST states pad at
+// their own intervals, as --asymmetric does.
+#[test]
+fn both_sides_prints_two_machines() {
+    let strings = |args: &[&str]| -> Vec<String> { args.iter().map(|arg| arg.to_string()).collect() };
+    let machines = constant::generate(&strings(&["--both-sides", "--up-interval", "8000", "--down-interval", "1000"])).unwrap();
+
+    let names: Vec<&str> = machines.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["Client machine", "Relay machine"]);
+    let timeouts: Vec<_> = machines.iter().map(|(_, machine)| machine.states[constant::CONST_STATE_INDEX].timeout).collect();
+    assert_eq!(timeouts, [fixed(8000.0), fixed(1000.0)]);
+    assert_ne!(machines[0].1, machines[1].1);
+    for ((_, machine), interval) in machines.iter().zip([8000.0, 1000.0]) {
+        assert_eq!(machine, &constant::generate_machine(fixed(interval), Trigger::Both, PACKET_SIZE));
+    }
+    assert_eq!(machines, constant::generate(&strings(&["--asymmetric", "--up-interval", "8000", "--down-interval", "1000"])).unwrap());
+
+    // Without intervals of their own, both pad at the send interval
+    let machines = constant::generate(&strings(&["5000", "--both-sides"])).unwrap();
+    assert_eq!(machines.len(), 2);
+    assert_eq!(machines[0].1, machines[1].1);
+}
+
+
+#[test]
+fn time_scale_scales_only_timeouts() {
+    let args: Vec<String> = ["4000", "--time-scale", "2.0"].iter().map(|arg| arg.to_string()).collect();
+    let machines = constant::generate(&args).unwrap();
+    let machine = &machines[0].1;
+    let unscaled = constant::generate_machine(fixed(4000.0), Trigger::Both, PACKET_SIZE);
+
+    let (state, unscaled_state) = (&machine.states[constant::CONST_STATE_INDEX], &unscaled.states[constant::CONST_STATE_INDEX]);
+    assert_eq!(state.timeout, fixed(8000.0));
+    assert_eq!(state.action, fixed(PACKET_SIZE));
+    assert_eq!((state.action, state.limit), (unscaled_state.action, unscaled_state.limit));
+
+    // The other states have no timeout to scale
+    for (index, (state, unscaled_state)) in machine.states.iter().zip(&unscaled.states).enumerate() {
+        if index != constant::CONST_STATE_INDEX {
+            assert_eq!(state, unscaled_state, "state {}", index);
+        }
+    }
+}
+
+
+#[test]
+fn zero_argument_machine_is_paper_default() {
+    let machines = constant::generate(&[]).unwrap();
+    assert_eq!(machines, constant::generate(&[defaults::CONSTANT_SEND_INTERVAL.to_string()]).unwrap());
+
+    let names: Vec<&str> = machines.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["Machine"]);
+    assert_eq!(machines[0].1, constant::generate_machine(fixed(defaults::CONSTANT_SEND_INTERVAL), Trigger::Both, PACKET_SIZE));
+}
+
+
+// The minimal machine passes every check of validate.rs, without warnings,
+// survives serialization, and is what --minimal prints
+#[test]
+fn minimal_machine_is_valid() {
+    let machine = constant::minimal();
+    assert_eq!(machine.states.len(), NUM_STATES);
+
+    let issues = validate_machine(&machine);
+    assert!(issues.is_empty(), "{:?}", issues.iter().map(|issue| issue.to_string()).collect::<Vec<String>>());
+    assert_eq!(Machine::from_str(&machine.serialize()).unwrap(), machine);
+
+    let machines = constant::generate(&[String::from("--minimal")]).unwrap();
+    assert_eq!(machines, [(String::from("Machine"), machine)]);
+}
+
+
+// --jitter-frac spreads the CONST timeout evenly around the interval, and 0
+// leaves it fixed
+#[test]
+fn jitter_keeps_interval_as_mean() {
+    let timeout = constant::jittered_timeout(4000.0, 0.1).unwrap();
+    assert_eq!(timeout, uniform_range(3600.0, 4400.0));
+    assert_eq!((timeout.param1 + timeout.param2) / 2.0, 4000.0);
+    assert_eq!(constant::jittered_timeout(4000.0, 0.0), Ok(fixed(4000.0)));
+
+    for jitter in [-0.1, 1.5, f64::NAN] {
+        assert!(constant::jittered_timeout(4000.0, jitter).is_err(), "{}", jitter);
+    }
+
+    let args = |jitter: &str| vec![String::from("4000"), String::from("--jitter-frac"), String::from(jitter)];
+    let machine = &constant::generate(&args("0.1")).unwrap()[0].1;
+    assert_eq!(machine.states[constant::CONST_STATE_INDEX].timeout, timeout);
+    assert_eq!(constant::generate(&args("0")).unwrap(), constant::generate(&[String::from("4000")]).unwrap());
+}
@@ -0,0 +1,79 @@
+// Checks the Scrambler's counter-sized segments (src/v2/scrambler.rs) against
+// the limit-sized ones in the maybenot v2 simulator: after a lone real packet
+// both send the same segment, while on steady real traffic only the counter
+// variant ends its segments (and blocks again for the next one). Only built
+// with the counters feature (cargo test --features counters).
+#![cfg(feature = "counters")]
+
+use std::time::Duration;
+
+use maybenot2::{Machine, TriggerEvent};
+use maybenot_simulator2::{network::Network, parse_trace, sim};
+
+use raising_the_bar::scrambler::TrailDist;
+use raising_the_bar::v2::scrambler::{generate_machine_one_single_pair, generate_machine_one_counter};
+
+
+const DELAY: Duration = Duration::from_millis(10);
+const MAX_TRACE_LENGTH: usize = 5000;
+
+// Send interval (usec), min count and trailing count (packets)
+const INTERVAL: f64 = 1000.0;
+const MIN_COUNT: f64 = 5.0;
+const TRAIL: (f64, f64) = (10.0, 10.0);
+
+
+// What the client did: padding packets sent, and segments (the START state ends
+// the blocking of each one).
+fn simulate(machine: Machine, trace: &str) -> (usize, usize) {
+    let mut queue = parse_trace(trace, Network::new(DELAY, None));
+    let events = sim(&[machine], &[], &mut queue, DELAY, MAX_TRACE_LENGTH, false);
+    let client: Vec<&TriggerEvent> = events.iter().filter(|event| event.client).map(|event| &event.event).collect();
+
+    let padding = client.iter().filter(|event| matches!(event, TriggerEvent::TunnelSent)).count() - client.iter().filter(|event| matches!(event, TriggerEvent::NormalSent)).count();
+    let segments = client.iter().filter(|event| matches!(event, TriggerEvent::BlockingEnd)).count();
+    return (padding, segments);
+}
+
+
+fn limit_machine() -> Machine {
+    return generate_machine_one_single_pair(INTERVAL, INTERVAL, MIN_COUNT, TRAIL, TRAIL, TrailDist::Uniform);
+}
+
+
+fn counter_machine() -> Machine {
+    return generate_machine_one_counter(INTERVAL, INTERVAL, MIN_COUNT, TRAIL, TrailDist::Uniform);
+}
+
+
+// A real packet sent every gap (ns), count times.
+fn steady_trace(gap: u64, count: u64) -> String {
+    return (0..count).map(|i| format!("{},s,600\n", i * gap)).collect();
+}
+
+
+#[test]
+fn lone_packet_segments_match() {
+    let trace = "0,s,600\n";
+    let limits = simulate(limit_machine(), trace);
+    let counter = simulate(counter_machine(), trace);
+    assert_eq!(limits, ((MIN_COUNT + TRAIL.0) as usize, 1));
+    assert_eq!(counter, limits);
+}
+
+
+#[test]
+fn steady_traffic_ends_counter_segments() {
+    // A real packet every 3 intervals, for 200 intervals: each one resets
+    // the trailing count of the limits, which then never run out
+    let num_packets = 67;
+    let trace = steady_trace(3000000, num_packets);
+    let limits = simulate(limit_machine(), &trace);
+    assert_eq!(limits.1, 1);
+
+    // Each counter segment is the real packet that starts it, then at most
+    // the min count and the trailing count, padding or real
+    let counter = simulate(counter_machine(), &trace);
+    assert!(counter.1 > 1);
+    assert!(counter.0 + num_packets as usize <= counter.1 * (1 + (MIN_COUNT + TRAIL.1) as usize), "{:?}", counter);
+}
@@ -0,0 +1,77 @@
+// Tests for the Defense trait (src/defense.rs): each parameter struct names its
+// binary, and gives the machines and --report of the binary with the same
+// parameters, for the structs built in Rust and for those of the registry.
+
+use std::process::Command;
+
+use raising_the_bar::defense::{Defense, Constant, Scrambler, AdaptedRegulator};
+use raising_the_bar::generate::{available_defenses, generate, parse_defense};
+use raising_the_bar::output::serialize_stable;
+
+mod common;
+
+use common::example_args;
+
+
+// The defenses, with the arguments of their binaries for the same parameters
+fn defenses() -> Vec<(Box<dyn Defense>, Vec<String>)> {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect();
+
+    return vec![
+        (Box::new(Constant::default()), args(&[])),
+        (Box::new(Constant { interval: 5000.0, packet_size: 1200.0, ..Constant::default() }), args(&["5000", "--packet-size", "1200"])),
+        (Box::new(Scrambler::default()), args(&[])),
+        (Box::new(Scrambler { interval: 200.0, min_count: 10.0, min_trail: 20.0, max_trail: 40.0, ..Scrambler::default() }), args(&["200", "10", "20", "40"])),
+        (Box::new(AdaptedRegulator::default()), args(&[])),
+        (Box::new(AdaptedRegulator { initial_rate: 1000.0, decay: 0.5, loop_surge: true, ..AdaptedRegulator::default() }), args(&["1000", "0.5", "--loop-surge"])),
+    ];
+}
+
+
+// The machines and the --report of a defense are those of its binary with
+// the arguments.
+fn assert_matches_binary(defense: &dyn Defense, args: &[String]) {
+    let name = defense.name();
+    let expected: Vec<String> = generate(name, args).unwrap().iter().map(serialize_stable).collect();
+    let machines: Vec<String> = defense.machines().iter().map(serialize_stable).collect();
+    assert_eq!(machines, expected, "{} {:?}", name, args);
+
+    // The report of --report, after any lines of the defense's own
+    let output = Command::new(common::binary(name)).args(args).arg("--report").output().unwrap();
+    assert!(output.status.success(), "{} {:?}: {}", name, args, String::from_utf8_lossy(&output.stderr));
+    let report = String::from_utf8_lossy(&output.stdout);
+    let lines = defense.overhead().lines();
+    assert!(report.lines().collect::<Vec<&str>>().ends_with(&lines.iter().map(|line| line.as_str()).collect::<Vec<&str>>()), "{} {:?}: {}", name, args, report);
+}
+
+
+#[test]
+fn defenses_match_their_binaries() {
+    for (defense, args) in defenses() {
+        assert_matches_binary(defense.as_ref(), &args);
+    }
+}
+
+
+// Every registered defense, with the examples of its parameters and a time
+// scale, which the estimates of --report scale with
+#[test]
+fn registered_defenses_match_their_binaries() {
+    for name in available_defenses() {
+        for args in [example_args(name), [example_args(name), vec![String::from("--time-scale"), String::from("2")]].concat()] {
+            let defense = parse_defense(name, &args).unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert_eq!(defense.name(), name);
+            assert_matches_binary(defense.as_ref(), &args);
+        }
+    }
+}
+
+
+// The fallible named_machines() gives the error the binary exits with, where
+// machines() panics.
+#[test]
+fn invalid_parameters_are_errors() {
+    let defense = Constant { dist: String::from("pareto"), shape: 0.5, ..Constant::default() };
+    assert!(defense.named_machines().is_err());
+    assert!(std::panic::catch_unwind(|| defense.machines()).is_err());
+}
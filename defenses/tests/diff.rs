@@ -0,0 +1,57 @@
+// Tests for the machine diff (src/diff.rs), with constant-rate machines.
+
+use maybenot::{
+machine::Machine,
+dist::{Dist, DistType}
+};
+
+use raising_the_bar::PACKET_SIZE;
+use raising_the_bar::constant::{self, Trigger};
+use raising_the_bar::diff::diff_machines;
+
+
+fn constant_machine(interval: f64, trigger: Trigger) -> Machine {
+    let timeout = Dist { dist: DistType::Uniform, param1: interval, param2: interval, start: 0.0, max: 0.0 };
+    return constant::generate_machine(timeout, trigger, PACKET_SIZE);
+}
+
+
+#[test]
+fn same_machines_have_no_diff() {
+    let machine = constant_machine(4000.0, Trigger::Both);
+    assert!(diff_machines("Machine", &machine, &machine.clone()).is_empty());
+}
+
+
+#[test]
+fn changed_fields_are_listed_by_state() {
+    let old = constant_machine(4000.0, Trigger::Both);
+    let mut new = constant_machine(5000.0, Trigger::Sent);
+    new.max_blocking_frac = 0.5;
+
+    assert_eq!(diff_machines("Machine", &old, &new), [
+        "Machine:",
+        "  max_blocking_frac: 0 -> 0.5",
+        "  State 0:",
+        "    transitions on NonPaddingRecv: 1 (1) -> none",
+        "  State 2:",
+        "    timeout: Uniform [4000.0, 4000.0] -> Uniform [5000.0, 5000.0]",
+    ]);
+}
+
+
+#[test]
+fn added_and_removed_states_are_listed_in_full() {
+    let old = constant_machine(4000.0, Trigger::Both);
+    let mut new = old.clone();
+    new.states.pop();
+
+    // Without state 2, the BLOCK state's transition to it names STATECANCEL
+    let diff = diff_machines("Machine", &old, &new);
+    assert_eq!(diff[..5], ["Machine:", "  states: 3 -> 2", "  State 1:", "    transitions on BlockingBegin: 2 (1) -> CANCEL (1)", "  State 2: removed"]);
+    assert!(diff.contains(&String::from("    timeout: Uniform [4000.0, 4000.0]")), "{:?}", diff);
+
+    let diff = diff_machines("Machine", &new, &old);
+    assert!(diff.contains(&String::from("  State 2: added")), "{:?}", diff);
+    assert!(diff.contains(&String::from("    transitions on BlockingBegin: CANCEL (1) -> 2 (1)")), "{:?}", diff);
+}
@@ -0,0 +1,70 @@
+// Checks that the --ffi output (output::ffi_machines()) is accepted as is by
+// maybenot-ffi: maybenot_start() must parse every machine and run them all.
+
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+
+use maybenot::{
+machine::Machine,
+dist::{Dist, DistType}
+};
+use maybenot_ffi::{MaybenotResult, MaybenotFramework, maybenot_start, maybenot_num_machines, maybenot_stop};
+
+use raising_the_bar::output::ffi_machines;
+use raising_the_bar::{constant, regulator, scrambler, PACKET_SIZE};
+
+
+const MTU: u16 = 1500;
+
+
+// Start maybenot-ffi with machines, returning the number of machines running.
+fn start(machines: &str) -> Result<usize, MaybenotResult> {
+    let machines = CString::new(machines).unwrap();
+    let mut framework: MaybeUninit<*mut MaybenotFramework> = MaybeUninit::uninit();
+
+    // SAFETY: machines is null-terminated, and framework is a valid pointer
+    let result = unsafe { maybenot_start(machines.as_ptr(), 0.0, 0.0, MTU, &mut framework) };
+    if !matches!(result, MaybenotResult::Ok) {
+        return Err(result);
+    }
+
+    // SAFETY: maybenot_start() succeeded, so framework points to a running instance
+    unsafe {
+        let framework = framework.assume_init();
+        let num_machines = maybenot_num_machines(framework);
+        maybenot_stop(framework);
+        return Ok(num_machines);
+    }
+}
+
+
+#[test]
+fn ffi_output_starts_maybenot_ffi() {
+    let timeout = Dist {
+        dist: DistType::Uniform,
+        param1: 4000.0,
+        param2: 4000.0,
+        start: 0.0,
+        max: 0.0,
+    };
+
+    let constant = constant::generate_machine(timeout, constant::Trigger::Both, PACKET_SIZE);
+    let relay = scrambler::generate_machine_one(160.0, 160.0, 500.0, (400.0, 1000.0), (400.0, 1000.0), scrambler::TrailDist::Uniform, PACKET_SIZE);
+    let signal = scrambler::generate_machine_two(500.0, scrambler::Direction::Up);
+    let client = regulator::generate_client_machine(4.0, regulator::CLIENT_LIMIT, PACKET_SIZE);
+
+    let lists: [&[&Machine]; 3] = [&[&constant], &[&relay, &signal], &[&relay, &signal, &constant, &client]];
+    for machines in lists {
+        let output = ffi_machines(machines);
+        assert_eq!(start(&output).unwrap(), machines.len());
+
+        // As printed, with a trailing newline
+        assert_eq!(start(&format!("{}\n", output)).unwrap(), machines.len());
+    }
+}
+
+
+#[test]
+fn ffi_rejects_invalid_machines() {
+    assert!(matches!(start("not a machine"), Err(MaybenotResult::InvalidMachineString)));
+}
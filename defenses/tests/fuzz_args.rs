@@ -0,0 +1,119 @@
+// Feeds hostile argument strings (empty, huge, in scientific notation, padded
+// with whitespace, with a decimal comma, ...) to the shared parsers of
+// src/args.rs and simulate.rs, and to every generator in each positional slot
+// and option value: each input must either parse or be rejected with a clean
+// error, never panic (on an unwrap, an overflow, an index out of bounds, or
+// even a failed check of the arguments) or crash by a signal.
+
+use std::panic;
+
+use raising_the_bar::args::{parse_args, parse_finite, parse_finite_positive, parse_count, parse_interval, parse_ms, parse_packet_sizes};
+use raising_the_bar::simulate::parse_base_trace;
+
+mod common;
+
+use common::{generator_args, run, GENERATORS};
+
+
+// Values that none of the parsers and binaries should crash on. None of them
+// makes for a legitimately huge machine (e.g., "1e3" states), which would be
+// slow rather than wrong, but a large count ("20000" L/R pairs of the
+// Scrambler, or states) must hit a cap rather than run out of memory.
+const HOSTILE: &[&str] = &[
+    "", " ", "0", "-0", "-1", "+5", " 4 ", "4 ", "\t4",
+    "1e400", "-1e400", "4e15", "1.79e308", "5e-324", "1e-9",
+    "99999999999999999999999", "-99999999999999999999999", "18446744073709551616",
+    "nan", "inf", "-inf", "0x10", "0,75", "1,5", "4,000", "1.2.3", "4000usec", "abc", "--",
+    "20000",
+];
+
+// What an uncontrolled panic or abort writes to stderr
+const UNCONTROLLED: &[&str] = &["unwrap()", "index out of bounds", "overflow", "{ kind:", "capacity", "allocat", "Os {"];
+
+// Options of each generator of common::GENERATORS that take a value
+const VALUE_OPTIONS: &[(&str, &[&str])] = &[
+    ("constant", &["--packet-size", "--allowed-blocked-microsec", "--max-blocking-frac"]),
+    ("buflo", &["--packet-size"]),
+    ("adapted_front", &["--packet-size"]),
+    ("adapted_regulator", &["--time-scale"]),
+    ("scrambler", &["--pairs"]),
+    ("surakav", &["--max-states"]),
+    ("regulator", &["--time-scale"]),
+];
+
+
+// Check that a binary either succeeded or failed with a clean error, never a
+// panic (whatever its message).
+fn assert_clean(binary: &str, args: &[String]) {
+    let output = run(binary, args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.code().is_some(), "{} {:?} was killed by a signal: {}", binary, args, stderr);
+    assert!(!stderr.contains("panicked at"), "{} {:?} panicked: {}", binary, args, stderr);
+    if !output.status.success() {
+        for marker in UNCONTROLLED {
+            assert!(!stderr.contains(marker), "{} {:?} failed uncontrolled: {}", binary, args, stderr);
+        }
+    }
+}
+
+
+#[test]
+fn parsers_never_panic() {
+    for text in HOSTILE {
+        let result = panic::catch_unwind(|| {
+            let _ = parse_finite(text, "value");
+            let _ = parse_finite_positive(text, "value");
+            let _ = parse_count::<usize>(text, "count");
+            let _ = parse_count::<u64>(text, "count");
+            let _ = parse_ms(text);
+            let _ = parse_packet_sizes(text);
+            let _ = parse_base_trace(&format!("{},s,{}\n", text, text));
+            let _ = parse_interval(&parse_args(vec![String::from("constant")], &[], &[]), text);
+        });
+        assert!(result.is_ok(), "a parser panicked on {:?}", text);
+    }
+}
+
+
+#[test]
+fn counts_reject_hostile_values() {
+    assert_eq!(parse_count::<usize>(" 4 ", "count"), Ok(4));
+    assert_eq!(parse_count::<usize>("+5", "count"), Ok(5));
+    for text in ["", "-1", "4e3", "4.0", "0,75", "99999999999999999999999", "abc"] {
+        assert!(parse_count::<usize>(text, "count").is_err(), "{} accepted", text);
+    }
+    assert_eq!(parse_count::<usize>("1e3", "num states"), Err(String::from("num states must be a whole number, got 1e3")));
+}
+
+
+#[test]
+fn generators_fail_cleanly() {
+    for (name, base, _) in GENERATORS {
+        let base = generator_args(base);
+        let options = VALUE_OPTIONS.iter().find(|(option_name, _)| option_name == name).map_or(&[][..], |(_, options)| *options);
+        for value in HOSTILE {
+            // Each positional argument in turn (not the trace of Surakav)
+            for slot in 0..base.len() {
+                if *name == "surakav" && slot == 0 {
+                    continue;
+                }
+                let mut hostile = base.clone();
+                hostile[slot] = value.to_string();
+                assert_clean(name, &hostile);
+            }
+
+            for option in options {
+                let mut hostile = base.clone();
+                hostile.push(option.to_string());
+                hostile.push(value.to_string());
+                assert_clean(name, &hostile);
+            }
+        }
+
+        // Extra arguments, and none at all
+        let mut extra = base.clone();
+        extra.extend(HOSTILE.iter().map(|value| value.to_string()));
+        assert_clean(name, &extra);
+        assert_clean(name, &[]);
+    }
+}
@@ -0,0 +1,98 @@
+// Tests for the defense registry (src/generate.rs): every registered defense
+// dispatches to its generator with the examples of its parameters.
+
+use raising_the_bar::generate::{available_defenses, generate, generate_named, generate_per_packet_size};
+use raising_the_bar::validate::validate_flag_consistency;
+
+mod common;
+
+use common::example_args;
+
+
+#[test]
+fn every_defense_dispatches() {
+    let names = available_defenses();
+    assert!(names.contains(&"constant") && names.contains(&"scrambler"));
+
+    for name in names {
+        let machines = generate(name, &example_args(name)).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        assert!(!machines.is_empty(), "{} generated no machines", name);
+    }
+}
+
+
+// Every machine of every defense includes small packets with the switch, and
+// only with it.
+#[test]
+fn include_small_packets_sets_every_machine() {
+    for name in available_defenses() {
+        let args = example_args(name);
+        let machines = generate(name, &args).unwrap();
+        assert!(machines.iter().all(|machine| !machine.include_small_packets), "{}", name);
+
+        let machines = generate(name, &[&args[..], &[String::from("--include-small-packets")]].concat()).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        assert!(machines.iter().all(|machine| machine.include_small_packets), "{}", name);
+    }
+}
+
+
+// The bypass and replace flags of every defense, and of its variants without
+// blocking, are combinations of the paper's machines.
+#[test]
+fn every_defense_has_consistent_flags() {
+    let variants = [("constant", "--pad-only"), ("scrambler", "--no-block"), ("scrambler", "--single-pair"), ("adapted_regulator", "--loop-surge")];
+    let runs = available_defenses().into_iter().map(|name| (name, example_args(name)))
+        .chain(variants.iter().map(|(name, flag)| (*name, [example_args(name), vec![flag.to_string()]].concat())));
+
+    for (name, args) in runs {
+        for machine in generate(name, &args).unwrap_or_else(|e| panic!("{}: {}", name, e)) {
+            let issues = validate_flag_consistency(&machine);
+            assert!(issues.is_empty(), "{} {:?}: {:?}", name, args, issues.iter().map(|issue| issue.to_string()).collect::<Vec<String>>());
+        }
+    }
+}
+
+
+#[test]
+fn machine_names() {
+    let machines = generate_named("adapted_regulator", &example_args("adapted_regulator")).unwrap();
+    let names: Vec<&str> = machines.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["Relay machine", "Client machine"]);
+}
+
+
+// One bundle per packet size, whose padding packets are of that size.
+#[test]
+fn per_packet_size_sets_padding_sizes() {
+    let bundles = generate_per_packet_size("constant", &[String::from("4000")], &[1200.0, 1500.0]).unwrap();
+    let sizes: Vec<f64> = bundles.iter().map(|(size, _)| *size).collect();
+    assert_eq!(sizes, [1200.0, 1500.0]);
+
+    for (size, machines) in &bundles {
+        assert_eq!(machines.len(), 1);
+        let (name, machine) = &machines[0];
+        assert_eq!(name, "Machine");
+
+        // The padding states send packets of the size, and none other (the
+        // action of the BLOCK state is its infinite duration)
+        let actions: Vec<(f64, f64)> = machine.states.iter()
+            .filter(|state| state.action.param1 > 0.0 && state.action.param1.is_finite())
+            .map(|state| (state.action.param1, state.action.param2))
+            .collect();
+        assert!(!actions.is_empty());
+        assert!(actions.iter().all(|action| *action == (*size, *size)), "{:?}", actions);
+    }
+    assert_ne!(bundles[0].1[0].1.serialize(), bundles[1].1[0].1.serialize());
+
+    assert!(generate_per_packet_size("constant", &[String::from("4000"), String::from("--packet-size"), String::from("1200")], &[1500.0]).is_err());
+}
+
+
+#[test]
+fn invalid_defenses_fail() {
+    assert!(generate("lint", &[]).is_err());
+    assert!(generate("constant", &[String::from("--dry-run")]).is_err());
+    assert!(generate("constant", &[String::from("--stdin")]).is_err());
+    assert!(generate("scrambler", &[String::from("--report")]).is_err());
+    assert!(generate("constant", &[String::from("fast")]).is_err());
+}
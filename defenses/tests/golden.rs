@@ -0,0 +1,80 @@
+// Reproducibility of the paper artifacts: the defenses must generate exactly
+// the machines checked in under tests/golden/, one file per defense and
+// parameter set, with a "<name>: <machine>" line per machine (as the binary
+// prints it, without the length). The goldens are the machines of the paper's
+// results (tests/compare/results_defenses.txt at the root of the repository).
+// After an intentional change to the output, rewrite the files with
+//
+//   cargo test --test golden -- --ignored regenerate_goldens
+//
+// and review the diff of tests/golden/ along with the change.
+
+use std::fs;
+use std::path::PathBuf;
+
+use raising_the_bar::constant::Trigger;
+use raising_the_bar::defense::{AdaptedRegulator, Constant, Defense, Scrambler};
+use raising_the_bar::output::serialize_stable;
+
+
+// The golden files, with the parameters of the defense that generate them: the
+// paper's configurations
+fn goldens() -> Vec<(&'static str, Box<dyn Defense>)> {
+    return vec![
+        ("constant_defaults.txt", Box::new(Constant::default())),
+        ("scrambler_defaults.txt", Box::new(Scrambler::default())),
+        // Starting on the real traffic the relay sends, as in the paper
+        // (--trigger sent, not the default)
+        ("adapted_regulator_defaults.txt", Box::new(AdaptedRegulator { trigger: Trigger::Sent, ..AdaptedRegulator::default() })),
+    ];
+}
+
+
+fn golden_path(file: &str) -> PathBuf {
+    return PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(file);
+}
+
+
+fn read_golden(file: &str) -> String {
+    return fs::read_to_string(golden_path(file)).unwrap_or_else(|e| panic!("Unable to read golden file {}: {}", file, e));
+}
+
+
+// The contents of a golden file, from the current generator.
+fn generated(defense: &dyn Defense) -> String {
+    return defense.named_machines().unwrap().iter()
+        .map(|(machine_name, machine)| format!("{}: {}\n", machine_name, serialize_stable(machine)))
+        .collect();
+}
+
+
+#[test]
+fn generators_reproduce_goldens() {
+    for (file, defense) in goldens() {
+        assert!(generated(defense.as_ref()) == read_golden(file), "{} no longer generates tests/golden/{}; if the change is intentional, run: cargo test --test golden -- --ignored regenerate_goldens", defense.name(), file);
+    }
+}
+
+
+// Every golden machine is one of the paper's, so the goldens cannot lock in
+// another configuration.
+#[test]
+fn goldens_are_the_paper_machines() {
+    let results = fs::read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..").join("tests").join("compare").join("results_defenses.txt")).unwrap();
+    for (file, _) in goldens() {
+        for line in read_golden(file).lines() {
+            let (name, machine) = line.split_once(": ").unwrap();
+            assert!(results.contains(machine), "{} of tests/golden/{} is not in tests/compare/results_defenses.txt", name, file);
+        }
+    }
+}
+
+
+#[test]
+#[ignore]
+fn regenerate_goldens() {
+    fs::create_dir_all(golden_path("")).unwrap();
+    for (file, defense) in goldens() {
+        fs::write(golden_path(file), generated(defense.as_ref())).unwrap();
+    }
+}
@@ -0,0 +1,70 @@
+// Tests for the Interspace machines (src/interspace.rs): the padding sizes
+// follow a loaded histogram, with one PAD state per bucket, rather than the
+// default packet size, and invalid histograms are rejected.
+
+use maybenot::{event::Event, dist::DistType};
+
+use raising_the_bar::PACKET_SIZE;
+use raising_the_bar::interspace::{generate_machine, parse_size_hist, mean_size, SizeBucket, FIRST_PAD_STATE_INDEX, START_STATE_INDEX};
+use raising_the_bar::dist_helpers::uniform_range;
+use raising_the_bar::validate::validate_machine;
+
+
+const HIST: &str = "\
+100,20
+
+500,600,30
+1400,1500,50
+1200,1300,0
+";
+
+
+#[test]
+fn padding_sizes_follow_the_histogram() {
+    let buckets = parse_size_hist(HIST).unwrap();
+    assert_eq!(buckets, [
+        SizeBucket { min: 100.0, max: 100.0, weight: 20.0 },
+        SizeBucket { min: 500.0, max: 600.0, weight: 30.0 },
+        SizeBucket { min: 1400.0, max: 1500.0, weight: 50.0 },
+    ]);
+    assert_eq!(mean_size(&buckets), 910.0);
+
+    let machine = generate_machine(DistType::Normal, (4000.0, 1000.0), &buckets);
+    assert_eq!(machine.states.len(), FIRST_PAD_STATE_INDEX + 3);
+    assert!(validate_machine(&machine).is_empty());
+
+    // Each PAD state sends the sizes of its bucket, not PACKET_SIZE
+    let actions: Vec<_> = machine.states[FIRST_PAD_STATE_INDEX..].iter().map(|state| state.action).collect();
+    assert_eq!(actions, [uniform_range(100.0, 100.0), uniform_range(500.0, 600.0), uniform_range(1400.0, 1500.0)]);
+    assert!(!actions.contains(&uniform_range(PACKET_SIZE, PACKET_SIZE)));
+
+    // ... and is entered with the weight of its bucket
+    for (index, event) in [(START_STATE_INDEX, Event::NonPaddingSent), (FIRST_PAD_STATE_INDEX, Event::PaddingSent)] {
+        let probabilities = &machine.states[index].next_state[&event];
+        assert_eq!(probabilities[FIRST_PAD_STATE_INDEX..FIRST_PAD_STATE_INDEX + 3], [0.2, 0.3, 0.5]);
+    }
+}
+
+
+#[test]
+fn single_bucket_is_the_size_range() {
+    let machine = generate_machine(DistType::Normal, (4000.0, 1000.0), &[SizeBucket { min: 100.0, max: 1500.0, weight: 1.0 }]);
+    assert_eq!(machine.states.len(), 2);
+    assert_eq!(machine.states[FIRST_PAD_STATE_INDEX].action, uniform_range(100.0, 1500.0));
+    assert_eq!(machine.states[START_STATE_INDEX].next_state[&Event::NonPaddingSent][FIRST_PAD_STATE_INDEX], 1.0);
+
+    // Weights that do not divide evenly still give valid probabilities
+    let buckets: Vec<SizeBucket> = (1..=7).map(|i| SizeBucket { min: i as f64 * 100.0, max: i as f64 * 100.0, weight: 1.0 / 3.0 }).collect();
+    assert!(generate_machine(DistType::Normal, (4000.0, 1000.0), &buckets).validate().is_ok());
+}
+
+
+#[test]
+fn invalid_histograms_are_rejected() {
+    for hist in ["", "1200,0\n", "100\n", "100,200,300,400\n", "0,10\n", "600,500,10\n", "100,-1\n", "100,inf\n", "size,count\n"] {
+        assert!(parse_size_hist(hist).is_err(), "{:?}", hist);
+    }
+
+    let too_many: String = (1..=1000).map(|size| format!("{},1\n", size)).collect();
+    assert!(parse_size_hist(&too_many).is_err());
+}
@@ -0,0 +1,42 @@
+// Tests for the debug traces of the generators (src/logging.rs): RUST_LOG does
+// not change what they print on stdout.
+
+use std::process::{Command, Output};
+
+mod common;
+
+
+// Generator runs with traces, e.g. adapted_regulator traces every SEND interval
+const RUNS: &[&[&str]] = &[
+    &["constant", "4000"],
+    &["constant", "4000", "--dry-run"],
+    &["adapted_front", "12", "4000", "30"],
+    &["adapted_regulator", "1400", "0.95", "4", "20"],
+    &["dynaflow", "1000,2000", "2", "--ffi"],
+];
+
+
+fn run(args: &[&str], rust_log: Option<&str>) -> Output {
+    let mut command = Command::new(common::binary(args[0]));
+    command.args(&args[1..]).env_remove("RUST_LOG");
+    if let Some(rust_log) = rust_log {
+        command.env("RUST_LOG", rust_log);
+    }
+
+    let output = command.output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    return output;
+}
+
+
+#[test]
+fn debug_traces_leave_stdout_unchanged() {
+    for args in RUNS {
+        let traced = run(args, Some("debug"));
+        assert_eq!(traced.stdout, run(args, None).stdout, "{:?}", args);
+
+        // The traces go to stderr, if built with them
+        let stderr = String::from_utf8_lossy(&traced.stderr);
+        assert_eq!(stderr.contains("[DEBUG raising_the_bar]"), cfg!(feature = "logging"), "{:?}: {}", args, stderr);
+    }
+}
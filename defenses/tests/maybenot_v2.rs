@@ -0,0 +1,135 @@
+// Checks that the maybenot v2 builders (src/v2.rs) generate the same defenses
+// as the v1 ones: both machines run against the same trace in the simulator of
+// their maybenot version, and must send the same packets at the same times.
+// Only built with the maybenot-v2 feature (cargo test --features maybenot-v2).
+#![cfg(feature = "maybenot-v2")]
+
+use std::time::{Duration, Instant};
+
+use maybenot::dist::{Dist, DistType};
+
+use raising_the_bar::constant;
+use raising_the_bar::v2;
+
+
+const DELAY: Duration = Duration::from_millis(10);
+const MAX_TRACE_LENGTH: usize = 500;
+
+// A client request, the server's response, and a second request (ns, direction,
+// bytes). The requests fall between padding packets, as the two simulators
+// order simultaneous events differently.
+const TRACE: &str = "\
+0,s,600
+5000000,s,600
+20000000,r,1500
+21000000,r,1500
+22000000,r,1500
+81000000,s,600
+";
+
+
+// Outgoing client packets as (time since the first one, is padding).
+type Packets = Vec<(Duration, bool)>;
+
+
+fn first_packets(packets: Vec<(Instant, bool)>) -> Packets {
+    let start = packets[0].0;
+    return packets.iter().map(|(time, padding)| (*time - start, *padding)).collect();
+}
+
+
+fn simulate_v1(machine: maybenot::machine::Machine) -> Packets {
+    use maybenot::framework::TriggerEvent;
+    use maybenot_simulator::{network::Network, parse_trace, sim};
+
+    let mut queue = parse_trace(TRACE, &Network::new(DELAY));
+    let trace = sim(&[machine], &[], &mut queue, DELAY, MAX_TRACE_LENGTH, true);
+
+    let packets = trace.iter().filter(|event| event.client).filter_map(|event| match event.event {
+        TriggerEvent::NonPaddingSent { .. } => Some((event.time, false)),
+        TriggerEvent::PaddingSent { .. } => Some((event.time, true)),
+        _ => None,
+    }).collect();
+
+    return first_packets(packets);
+}
+
+
+fn simulate_v2(machine: maybenot2::Machine) -> Packets {
+    use maybenot2::TriggerEvent;
+    use maybenot_simulator2::{network::Network, parse_trace, sim};
+
+    let mut queue = parse_trace(TRACE, Network::new(DELAY, None));
+    let trace = sim(&[machine], &[], &mut queue, DELAY, MAX_TRACE_LENGTH, true);
+
+    let packets = trace.iter().filter(|event| event.client).filter_map(|event| match event.event {
+        TriggerEvent::TunnelSent => Some((event.time, event.contains_padding)),
+        _ => None,
+    }).collect();
+
+    return first_packets(packets);
+}
+
+
+#[test]
+fn constant_v1_and_v2_are_equivalent() {
+    for interval in [4000.0, 5000.0] {
+        let timeout = Dist {
+            dist: DistType::Uniform,
+            param1: interval,
+            param2: interval,
+            start: 0.0,
+            max: 0.0,
+        };
+
+        let v1_machine = constant::generate_machine(timeout, constant::Trigger::Both, raising_the_bar::PACKET_SIZE);
+        let v2_machine = v2::constant::generate_machine(v2::convert_dist(&timeout).unwrap(), constant::Trigger::Both);
+
+        let v1_packets = simulate_v1(v1_machine);
+        let v2_packets = simulate_v2(v2_machine);
+
+        let len = v1_packets.len().min(v2_packets.len());
+        assert!(len > 20, "too few packets to compare ({})", len);
+        assert_eq!(v1_packets[..len], v2_packets[..len], "send interval {}", interval);
+    }
+}
+
+
+#[test]
+fn convert_dist_keeps_parameters() {
+    let pareto = Dist {
+        dist: DistType::Pareto,
+        param1: 2000.0,
+        param2: 2.0,
+        start: 0.0,
+        max: 0.0,
+    };
+    assert_eq!(v2::convert_dist(&pareto).unwrap().dist, maybenot2::dist::DistType::Pareto { scale: 2000.0, shape: 2.0 });
+
+    let none = Dist {
+        dist: DistType::None,
+        param1: 0.0,
+        param2: 0.0,
+        start: 0.0,
+        max: 0.0,
+    };
+    assert!(v2::convert_dist(&none).is_err());
+}
+
+
+#[test]
+fn constant_v2_start_state_follows_trigger() {
+    use maybenot2::event::Event;
+
+    let expected = [
+        (constant::Trigger::Sent, vec![Event::NormalSent]),
+        (constant::Trigger::Recv, vec![Event::NormalRecv]),
+        (constant::Trigger::Both, vec![Event::NormalRecv, Event::NormalSent]),
+    ];
+
+    for (trigger, events) in expected {
+        let transitions = v2::constant::generate_start_state(trigger).get_transitions();
+        let triggers: Vec<Event> = transitions.iter().filter(|(_, trans)| !trans.is_empty()).map(|(event, _)| event).collect();
+        assert_eq!(triggers, events, "trigger {:?}", trigger);
+    }
+}
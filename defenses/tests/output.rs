@@ -0,0 +1,146 @@
+// Tests for the serialization of machines (src/output.rs): the output does not
+// depend on the hash order of the transitions, within a process or across
+// processes (whose HashMaps are seeded differently), nor on how they are held
+// (normalized before serializing), is the same when written
+// to a writer, round-trips through base64, and is what the constants of
+// --emit-rust hold.
+
+use std::collections::HashSet;
+use std::process::Command;
+use std::str::FromStr;
+
+use maybenot::{event::Event, machine::Machine};
+
+use raising_the_bar::PACKET_SIZE;
+use raising_the_bar::constant::{self, Trigger};
+use raising_the_bar::dist_helpers::fixed;
+use raising_the_bar::output::{serialize_stable, serialize_machine_list, write_machine, write_machine_list, encode_base64, decode_base64, parse_encoded_machine_list, rust_constants, Encoding};
+use raising_the_bar::scrambler::{generate_machine_one, generate_machine_two, TrailDist, Direction};
+
+mod common;
+
+
+// Times to rebuild each machine, each time with new HashMaps
+const RUNS: usize = 100;
+
+
+#[test]
+fn serialization_is_stable_within_a_process() {
+    let generators: [fn() -> Machine; 3] = [
+        || constant::generate_machine(fixed(4000.0), Trigger::Both, PACKET_SIZE),
+        || generate_machine_one(160.0, 160.0, 500.0, (400.0, 1000.0), (400.0, 1000.0), TrailDist::Uniform, PACKET_SIZE),
+        || generate_machine_two(500.0, Direction::Up),
+    ];
+
+    for generate in generators {
+        let serialized: HashSet<String> = (0..RUNS).map(|_| serialize_stable(&generate())).collect();
+        assert_eq!(serialized.len(), 1);
+
+        // ... also for the machines parsed back
+        let machine = serialized.iter().next().unwrap();
+        for _ in 0..RUNS {
+            assert_eq!(&serialize_stable(&Machine::from_str(machine).unwrap()), machine);
+        }
+    }
+}
+
+
+// Transitions that only differ in how they are held serialize the same: an
+// event without any transition, and -0.0 probabilities
+#[test]
+fn serialization_normalizes_transitions() {
+    let machine = generate_machine_two(500.0, Direction::Up);
+    let mut held = machine.clone();
+    let num_states = held.states.len();
+    held.states[0].next_state.insert(Event::PaddingRecv, vec![0.0; num_states + 2]);
+    for probabilities in held.states[0].next_state.values_mut() {
+        for probability in probabilities.iter_mut().filter(|probability| **probability == 0.0) {
+            *probability = -0.0;
+        }
+    }
+    assert_ne!(held, machine);
+    assert_ne!(held.serialize(), machine.serialize());
+    assert_eq!(serialize_stable(&held), serialize_stable(&machine));
+    assert_eq!(serialize_stable(&machine), machine.serialize());
+}
+
+
+#[test]
+fn serialization_is_stable_across_processes() {
+    let output = |args: &[&str]| Command::new(common::binary("scrambler")).args(args).output().unwrap().stdout;
+
+    let first = output(&["160", "500", "400", "1000"]);
+    for _ in 0..10 {
+        assert_eq!(output(&["160", "500", "400", "1000"]), first);
+    }
+}
+
+
+#[test]
+fn written_machines_match_serialization() {
+    let machines = [
+        constant::generate_machine(fixed(4000.0), Trigger::Both, PACKET_SIZE),
+        generate_machine_one(160.0, 160.0, 500.0, (400.0, 1000.0), (400.0, 1000.0), TrailDist::Uniform, PACKET_SIZE),
+        generate_machine_two(500.0, Direction::Up),
+    ];
+
+    for machine in &machines {
+        let mut written: Vec<u8> = Vec::new();
+        write_machine(&mut written, machine).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), machine.serialize());
+    }
+
+    let mut written: Vec<u8> = Vec::new();
+    write_machine_list(&mut written, &machines.iter().collect::<Vec<&Machine>>()).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), serialize_machine_list(&machines));
+}
+
+
+#[test]
+fn base64_round_trips() {
+    let machines = [
+        constant::generate_machine(fixed(4000.0), Trigger::Both, PACKET_SIZE),
+        generate_machine_one(160.0, 160.0, 500.0, (400.0, 1000.0), (400.0, 1000.0), TrailDist::Uniform, PACKET_SIZE),
+        generate_machine_two(500.0, Direction::Up),
+    ];
+
+    for machine in &machines {
+        let encoded = encode_base64(machine);
+        assert!(encoded.len() < machine.serialize().len());
+        assert_eq!(decode_base64(&encoded).unwrap().serialize(), machine.serialize());
+    }
+
+    // As printed by a generator with --base64, one machine per line
+    let output = Command::new(common::binary("scrambler")).args(["160", "500", "400", "1000", "--base64"]).output().unwrap().stdout;
+    let list: Vec<String> = String::from_utf8(output).unwrap().lines()
+        .filter_map(|line| line.split_once(": ").map(|(_, rest)| rest.split(' ').next().unwrap().to_string()))
+        .collect();
+    let decoded = parse_encoded_machine_list(&list.join("\n"), Encoding::Base64).unwrap();
+    assert_eq!(decoded.iter().map(serialize_stable).collect::<Vec<String>>(), [serialize_stable(&machines[1]), serialize_stable(&machines[2])]);
+
+    assert!(decode_base64("not base64!").is_err());
+    assert!(decode_base64(&encode_base64(&machines[0])[4..]).is_err());
+}
+
+
+#[test]
+fn rust_constants_hold_serialized_machines() {
+    let relay = constant::generate_machine(fixed(4000.0), Trigger::Both, PACKET_SIZE);
+    let client = generate_machine_two(500.0, Direction::Up);
+    let source = rust_constants(&[("Relay machine", &relay), ("Machine 2", &client)]);
+
+    // The string of each constant, between its quotes
+    let constants: Vec<(&str, &str)> = source.lines()
+        .filter_map(|line| line.strip_prefix("pub const "))
+        .map(|line| {
+            let (name, value) = line.split_once(": &str = ").unwrap();
+            return (name, value.strip_prefix('"').unwrap().strip_suffix("\";").unwrap());
+        })
+        .collect();
+    assert_eq!(constants, [("RELAY_MACHINE", serialize_stable(&relay).as_str()), ("MACHINE_2", serialize_stable(&client).as_str())]);
+    assert_eq!(serialize_stable(&Machine::from_str(constants[0].1).unwrap()), serialize_stable(&relay));
+
+    // As printed by a generator with --emit-rust
+    let output = Command::new(common::binary("constant")).args(["4000", "--emit-rust"]).output().unwrap().stdout;
+    assert_eq!(String::from_utf8(output).unwrap(), rust_constants(&[("Machine", &relay)]));
+}
@@ -0,0 +1,76 @@
+// Tests for the RD^t interval search (src/rate.rs), at the default and at a
+// tighter tolerance, for the RD^t rate at its edges, and for the rate levels of
+// CS-BuFLO.
+
+use raising_the_bar::rate::{calc_interval_width, calculate_rate, rate_levels, count_rate_levels, WIDTH_TOLERANCE};
+
+
+// Packet count of an interval as approximated by calc_interval_width(): its
+// width times the rate at its middle.
+fn interval_count(a: f64, width: f64, rate: f64, decay: f64) -> f64 {
+    return calculate_rate(a + width / 2.0, rate, decay) * width;
+}
+
+
+#[test]
+fn rate_at_the_edges() {
+    for (rate, decay) in [(500.0, 0.75), (1900.0, 0.95), (1000.0, 0.5), (1.0, 1e-9)] {
+        assert_eq!(calculate_rate(0.0, rate, decay), rate);
+        assert_eq!(calculate_rate(0.0, rate, 1.0), rate);
+    }
+
+    // No decay keeps the initial rate at every t
+    for t in [0.5, 1.0, 60.0, 1e6, 1e300, f64::INFINITY] {
+        assert_eq!(calculate_rate(t, 500.0, 1.0), 500.0);
+    }
+
+    // Far into the surge the rate underflows to exactly 0.0, not to a
+    // subnormal, a negative rate, or NaN
+    for t in [1e4, 1e6, 1e300, f64::INFINITY] {
+        assert_eq!(calculate_rate(t, 500.0, 0.75), 0.0);
+    }
+    // 500 * 0.75^t drops below the smallest normal f64 near t = 2484
+    assert!(calculate_rate(2480.0, 500.0, 0.75) >= f64::MIN_POSITIVE);
+    assert_eq!(calculate_rate(2490.0, 500.0, 0.75), 0.0);
+    assert_eq!(calculate_rate(0.5, 500.0, -0.75), 0.0);
+}
+
+
+#[test]
+fn interval_count_within_tolerance() {
+    for &(a, count, rate, decay) in &[(0.0, 20.0, 500.0, 0.75), (3.5, 20.0, 500.0, 0.75), (0.0, 4.0, 1900.0, 0.95), (1.0, 7.0, 1000.0, 0.5)] {
+        for tolerance in [WIDTH_TOLERANCE, WIDTH_TOLERANCE / 1000.0] {
+            let width = calc_interval_width(a, count, rate, decay, tolerance);
+            let diff = (interval_count(a, width, rate, decay) - count).abs();
+            assert!(diff <= tolerance, "count {} off by {} (tolerance {})", count, diff, tolerance);
+        }
+    }
+}
+
+
+#[test]
+fn tighter_tolerance_refines_width() {
+    let width = calc_interval_width(0.0, 20.0, 500.0, 0.75, WIDTH_TOLERANCE);
+    let tight = calc_interval_width(0.0, 20.0, 500.0, 0.75, 1e-12);
+
+    // The widths differ by at most the tolerance over the rate at the middle
+    assert!((width - tight).abs() <= 2.0 * WIDTH_TOLERANCE / calculate_rate(tight / 2.0, 500.0, 0.75));
+    assert!((interval_count(0.0, tight, 500.0, 0.75) - 20.0).abs() <= 1e-12);
+}
+
+
+#[test]
+fn rate_levels_halve_down_to_min() {
+    assert_eq!(rate_levels(1000.0, 125.0, 2.0), [1000.0, 500.0, 250.0, 125.0]);
+    assert_eq!(rate_levels(1000.0, 100.0, 2.0), [1000.0, 500.0, 250.0, 125.0]);
+    assert_eq!(rate_levels(1000.0, 1000.0, 2.0), [1000.0]);
+    assert_eq!(rate_levels(4000.0, 10.0, 4.0), [4000.0, 1000.0, 250.0, 62.5, 15.625]);
+
+    // ... with as many levels as counted, all within the bounds
+    for &(max_rate, min_rate, factor) in &[(1000.0, 100.0, 1.5), (2000.0, 1.0, 1.1), (500.0, 499.0, 2.0)] {
+        let levels = rate_levels(max_rate, min_rate, factor);
+        assert_eq!(levels.len(), count_rate_levels(max_rate, min_rate, factor));
+        assert!(levels.iter().all(|&rate| rate <= max_rate && rate >= min_rate), "{:?}", levels);
+        assert!(levels.last().unwrap() / factor < min_rate, "{:?}", levels);
+    }
+}
@@ -0,0 +1,342 @@
+// Tests for the RegulaTor machines (src/regulator.rs): the upload ratios the
+// client-side machine accepts, the events that start the relay machine, how
+// the Adapted RegulaTor relay machine ends, the chain of its SEND states (also
+// used by CS-BuFLO), and the units of their timeouts.
+
+use maybenot::{constants::STATEEND, dist::Dist, event::Event, machine::Machine, state::State};
+
+use raising_the_bar::PACKET_SIZE;
+use raising_the_bar::constant::{trigger_name, Trigger};
+use raising_the_bar::defaults;
+use raising_the_bar::cs_buflo;
+use raising_the_bar::regulator::{self, check_upload_ratio, check_packets_per_state, count_client_states, send_state_intervals_with_progress, generate_client_machine, generate_relay_start_state, generate_relay_send_state, check_send_units, check_send_state_count, BLOCK_STATE_INDEX, CLIENT_LIMIT, FIRST_SEND_STATE_INDEX, MAX_STATES, MAX_SEND_INTERVALS};
+use raising_the_bar::validate::validate_send_chain;
+use raising_the_bar::rate::send_interval_usec;
+use raising_the_bar::dist_helpers::scale_timeouts;
+
+
+// Parameters of the Adapted RegulaTor in the paper (tests/test_defenses.sh)
+const PAPER_PARAMETERS: &[[&str; 4]] = &[
+    ["1400", "0.95", "4", "20"],
+    ["1300", "0.95", "4", "20"],
+    ["1000", "0.95", "4", "20"],
+    ["1500", "0.85", "4", "20"],
+    ["1600", "0.95", "4", "20"],
+    ["1900", "0.95", "4", "20"],
+];
+
+
+fn relay_machine(args: &[&str]) -> Machine {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    let machines = regulator::generate_adapted(&args).unwrap();
+    return machines.into_iter().find(|(name, _)| name == "Relay machine").unwrap().1;
+}
+
+
+// Probability of a transition on the event to STATEEND, which follows the
+// states (and STATECANCEL) in the transition vectors of maybenot v1.
+fn end_probability(state: &State, num_states: usize, event: Event) -> f64 {
+    return state.next_state.get(&event).map_or(0.0, |probs| probs[num_states + 1]);
+}
+
+
+#[test]
+fn upload_ratio_bounds() {
+    for ratio in [0.25, 1.0, 4.0, 4.5] {
+        assert!(check_upload_ratio(ratio).is_ok(), "upload ratio {} rejected", ratio);
+    }
+
+    // 0.0 would pad without end, see check_upload_ratio()
+    for ratio in [0.0, -0.0, -1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        assert!(check_upload_ratio(ratio).is_err(), "upload ratio {} accepted", ratio);
+    }
+
+    // A ratio of MAX_STATES or more takes more client states than the cap
+    assert!(check_upload_ratio(MAX_STATES as f64 - 1.0).is_ok());
+    for ratio in [MAX_STATES as f64, 1e23] {
+        assert!(check_upload_ratio(ratio).is_err(), "upload ratio {} accepted", ratio);
+    }
+}
+
+
+#[test]
+fn client_states_match_count() {
+    for ratio in [0.25, 1.0, 4.0, 4.5] {
+        let machine = generate_client_machine(ratio, CLIENT_LIMIT, PACKET_SIZE);
+        assert_eq!(machine.states.len(), count_client_states(ratio));
+    }
+}
+
+
+#[test]
+#[should_panic(expected = "Invalid upload ratio")]
+fn zero_upload_ratio_panics() {
+    generate_client_machine(0.0, CLIENT_LIMIT, PACKET_SIZE);
+}
+
+
+#[test]
+#[should_panic(expected = "Invalid upload ratio")]
+fn infinite_upload_ratio_panics() {
+    count_client_states(f64::INFINITY);
+}
+
+
+#[test]
+fn packets_per_state_bounds() {
+    assert!(check_packets_per_state(20.0).is_ok());
+    assert!(check_packets_per_state(0.5).is_ok());
+    for packets_per_state in [0.0, -20.0, f64::NAN, f64::INFINITY] {
+        assert!(check_packets_per_state(packets_per_state).is_err(), "{}", packets_per_state);
+    }
+}
+
+
+#[test]
+fn send_state_count_bounds() {
+    assert!(check_send_state_count(20.0, 500.0, 0.75, MAX_STATES).is_ok());
+    assert!(check_send_state_count(1.0, 500.0, 0.75, MAX_STATES).is_ok());
+
+    // 173458 SEND states, too many for a machine but not to count
+    assert!(check_send_state_count(0.01, 500.0, 0.75, MAX_STATES).is_err());
+    assert!(check_send_state_count(0.01, 500.0, 0.75, MAX_SEND_INTERVALS).is_ok());
+
+    // A huge initial rate, or a tiny packets per state, would loop over as
+    // many intervals before the states are counted
+    for (packets_per_state, initial_rate) in [(20.0, 1e15), (20.0, f64::MAX), (1e-9, 500.0)] {
+        assert!(check_send_state_count(packets_per_state, initial_rate, 0.75, MAX_SEND_INTERVALS).is_err(), "{} {}", packets_per_state, initial_rate);
+    }
+    for decay in [0.0, 1.0, -0.5, f64::NAN] {
+        assert!(check_send_state_count(20.0, 500.0, decay, MAX_STATES).is_err(), "decay {} accepted", decay);
+    }
+}
+
+
+// 0 and negative counts are rejected before the first interval is searched for
+#[test]
+fn nonpositive_packets_per_state_panics_up_front() {
+    for packets_per_state in [0.0, -20.0] {
+        let mut intervals = 0;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            send_state_intervals_with_progress(packets_per_state, 500.0, 0.75, |count| intervals = count);
+        }));
+        assert!(result.is_err(), "{}", packets_per_state);
+        assert_eq!(intervals, 0, "{}", packets_per_state);
+    }
+}
+
+
+#[test]
+fn relay_start_state_triggers() {
+    let num_states = FIRST_SEND_STATE_INDEX + 1;
+    for (trigger, events) in [
+        (Trigger::Sent, vec![Event::NonPaddingSent]),
+        (Trigger::Recv, vec![Event::NonPaddingRecv]),
+        (Trigger::Both, vec![Event::NonPaddingSent, Event::NonPaddingRecv]),
+    ] {
+        let state = generate_relay_start_state(num_states, trigger);
+        assert_eq!(state.next_state.len(), events.len(), "{:?}", trigger);
+        for event in events {
+            assert_eq!(state.next_state[&event][BLOCK_STATE_INDEX], 1.0, "{:?}", trigger);
+        }
+    }
+}
+
+
+// Without arguments, the Adapted RegulaTor takes the parameters of its first
+// configuration in the paper (tests/test_defenses.sh), with the relay machine
+// starting on real traffic either way
+#[test]
+fn zero_argument_machines_take_the_paper_parameters() {
+    let machines = regulator::generate_adapted(&[]).unwrap();
+    assert_eq!(machines, regulator::generate_adapted(&["500", "0.75", "4", "20"].map(String::from)).unwrap());
+    assert_eq!(relay_machine(&[]), relay_machine(&["--trigger", trigger_name(defaults::ADAPTED_REGULATOR_TRIGGER)]));
+    assert_eq!(defaults::ADAPTED_REGULATOR_TRIGGER, Trigger::Both);
+}
+
+
+#[test]
+fn relay_trigger_defaults_to_both() {
+    let machine = relay_machine(&["1400", "0.95", "4", "20"]);
+    assert_eq!(machine, relay_machine(&["1400", "0.95", "4", "20", "--trigger", "both"]));
+    assert_eq!(machine.states[0].next_state[&Event::NonPaddingRecv][BLOCK_STATE_INDEX], 1.0);
+    assert_eq!(machine.states[0].next_state[&Event::NonPaddingSent][BLOCK_STATE_INDEX], 1.0);
+
+    // The machine of the paper
+    let machine = relay_machine(&["1400", "0.95", "4", "20", "--trigger", "sent"]);
+    assert!(!machine.states[0].next_state.contains_key(&Event::NonPaddingRecv));
+}
+
+
+// The default relay machine takes 89 states: --max-states below that rejects
+// it, and --coarsen increases the packets per state to the fewest that fit (36
+// give 50 states, 35 give 52).
+#[test]
+fn adapted_max_states_caps_the_relay_machine() {
+    let parse = |args: &[&str]| regulator::parse_adapted(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>());
+    assert_eq!(parse(&["--max-states", "89"]).unwrap().packets_per_state, 20.0);
+    assert_eq!(parse(&["--max-states", "50"]).unwrap_err(), "The relay machine would take 89 states, more than --max-states 50 (give more packets per state, or --coarsen to increase them until it fits)");
+    assert_eq!(parse(&["--coarsen"]).unwrap_err(), "--coarsen needs --max-states");
+    assert!(parse(&["--max-states", "2"]).is_err());
+
+    let coarsened = parse(&["--max-states", "50", "--coarsen"]).unwrap();
+    assert_eq!((coarsened.packets_per_state, coarsened.coarsened_from, coarsened.max_states), (36.0, Some(20.0), Some(50)));
+    assert_eq!(coarsened.count_states().0, 50);
+    assert_eq!(relay_machine(&["--max-states", "50", "--coarsen"]), relay_machine(&["500", "0.75", "4", "36"]));
+    assert!(parse(&["500", "0.75", "4", "35", "--max-states", "50"]).is_err());
+}
+
+
+#[test]
+fn relay_machine_ends_once() {
+    for args in PAPER_PARAMETERS {
+        let machine = relay_machine(args);
+        let num_states = machine.states.len();
+
+        // Only the last SEND state ends the machine, on LimitReached
+        let ending: Vec<usize> = (0..num_states)
+            .filter(|&i| Event::iterator().any(|event| end_probability(&machine.states[i], num_states, *event) > 0.0))
+            .collect();
+        assert_eq!(ending, [num_states - 1], "{:?}", args);
+        assert!(num_states > FIRST_SEND_STATE_INDEX, "{:?}", args);
+
+        // ... at 1 packet/sec, which resets the surge on real traffic as it is
+        // below BURST_RESET_RATE
+        let last = &machine.states[num_states - 1];
+        assert_eq!(end_probability(last, num_states, Event::LimitReached), 1.0, "{:?}", args);
+        assert_eq!((last.timeout.param1, last.timeout.param2), (1_000_000.0, 1_000_000.0), "{:?}", args);
+        assert!(last.next_state.contains_key(&Event::NonPaddingSent), "{:?}", args);
+    }
+}
+
+
+#[test]
+fn loop_surge_never_ends() {
+    let machine = relay_machine(&["1400", "0.95", "4", "20", "--loop-surge"]);
+    let num_states = machine.states.len();
+
+    for state in &machine.states {
+        assert!(Event::iterator().all(|event| end_probability(state, num_states, *event) == 0.0));
+    }
+
+    let last = &machine.states[num_states - 1];
+    assert_eq!(last.next_state[&Event::LimitReached][FIRST_SEND_STATE_INDEX], 1.0);
+}
+
+
+#[test]
+fn relay_send_states_form_a_chain() {
+    // Fewer packets per state than in the paper, for smaller machines
+    for args in [["1400", "0.95", "4", "100"], ["1000", "0.85", "4", "50"]] {
+        let machine = relay_machine(&args);
+        assert_eq!(validate_send_chain(&machine, FIRST_SEND_STATE_INDEX, false), [], "{:?}", args);
+    }
+
+    // Looping back to the first SEND state, or holding the last one in the
+    // original RegulaTor
+    let machine = relay_machine(&["1400", "0.95", "4", "100", "--loop-surge"]);
+    assert_eq!(validate_send_chain(&machine, FIRST_SEND_STATE_INDEX, true), []);
+
+    let args: Vec<String> = ["1000", "0.95", "3.0", "4", "100"].iter().map(|arg| arg.to_string()).collect();
+    let machines = regulator::generate(&args).unwrap();
+    let (_, machine) = machines.iter().find(|(name, _)| name == "Relay machine").unwrap();
+    assert_eq!(validate_send_chain(machine, FIRST_SEND_STATE_INDEX, true), []);
+}
+
+
+#[test]
+fn broken_send_chain_is_rejected() {
+    let machine = relay_machine(&["1400", "0.95", "4", "100"]);
+    let num_states = machine.states.len();
+    let index = FIRST_SEND_STATE_INDEX + 1;
+
+    // Skipping a state, looping back early, and ending early
+    for next_index in [index + 2, FIRST_SEND_STATE_INDEX, STATEEND] {
+        let mut broken = machine.clone();
+        broken.states[index] = generate_relay_send_state(index, next_index, num_states, 100.0, 1000.0, false, PACKET_SIZE);
+
+        let issues = validate_send_chain(&broken, FIRST_SEND_STATE_INDEX, false);
+        assert_eq!(issues.len(), 1, "{}", next_index);
+        assert_eq!(issues[0].state, Some(index), "{}", next_index);
+    }
+
+    // No LimitReached at all
+    let mut broken = machine.clone();
+    broken.states[index].next_state.remove(&Event::LimitReached);
+    assert_eq!(validate_send_chain(&broken, FIRST_SEND_STATE_INDEX, false)[0].state, Some(index));
+
+    // The last state may only end the machine or loop
+    let mut broken = machine.clone();
+    broken.states[num_states - 1] = generate_relay_send_state(num_states - 1, BLOCK_STATE_INDEX, num_states, 100.0, 1000.0, false, PACKET_SIZE);
+    assert_eq!(validate_send_chain(&broken, FIRST_SEND_STATE_INDEX, true)[0].state, Some(num_states - 1));
+
+    // ... and only loop with --loop-surge: back to the first SEND state, or to
+    // itself
+    for next_index in [FIRST_SEND_STATE_INDEX, num_states - 1] {
+        let mut looping = machine.clone();
+        looping.states[num_states - 1] = generate_relay_send_state(num_states - 1, next_index, num_states, 100.0, 1000.0, false, PACKET_SIZE);
+        assert_eq!(validate_send_chain(&looping, FIRST_SEND_STATE_INDEX, true), [], "{}", next_index);
+        assert_eq!(validate_send_chain(&looping, FIRST_SEND_STATE_INDEX, false)[0].state, Some(num_states - 1), "{}", next_index);
+    }
+    let machine = relay_machine(&["1400", "0.95", "4", "100", "--loop-surge"]);
+    assert_eq!(validate_send_chain(&machine, FIRST_SEND_STATE_INDEX, false).len(), 1);
+}
+
+
+#[test]
+fn last_send_state_may_hold_without_limit() {
+    let machine = relay_machine(&["1400", "0.95", "4", "100"]);
+    let last = machine.states.len() - 1;
+
+    // Without a limit, the last state never reaches it
+    let mut holding = machine.clone();
+    holding.states[last].next_state.remove(&Event::LimitReached);
+    holding.states[last].limit = Dist::new();
+    assert_eq!(validate_send_chain(&holding, FIRST_SEND_STATE_INDEX, false), []);
+
+    // With one, it needs a LimitReached
+    let mut broken = machine.clone();
+    broken.states[last].next_state.remove(&Event::LimitReached);
+    assert_eq!(validate_send_chain(&broken, FIRST_SEND_STATE_INDEX, false)[0].state, Some(last));
+
+    let args: Vec<String> = ["125", "1000", "2"].iter().map(|arg| arg.to_string()).collect();
+    let machines = cs_buflo::generate(&args).unwrap();
+    assert_eq!(validate_send_chain(&machines[0].1, FIRST_SEND_STATE_INDEX, false), []);
+}
+
+
+#[test]
+fn send_interval_is_usec_per_packet() {
+    assert_eq!(send_interval_usec(500.0), 2000.0);
+    assert_eq!(send_interval_usec(1.0), 1000000.0);
+    assert!(std::panic::catch_unwind(|| send_interval_usec(0.0)).is_err());
+    assert!(std::panic::catch_unwind(|| send_interval_usec(f64::INFINITY)).is_err());
+}
+
+
+// The first SEND state of both RegulaTor relay machines pads every 1e6 / R
+// usec, about, and a timeout in seconds (1 / R) or in msec (1000 / R) fails
+// the check.
+#[test]
+fn first_send_state_units() {
+    for args in [&PAPER_PARAMETERS[0], &PAPER_PARAMETERS[3]] {
+        let (initial_rate, decay) = (args[0].parse().unwrap(), args[1].parse().unwrap());
+        let mut machine = relay_machine(args);
+        let (timeout, expected) = check_send_units(&machine, initial_rate, decay, 1.0).unwrap();
+        assert_eq!(expected, 1000000.0 / initial_rate);
+        assert!(timeout >= expected && timeout < expected * 1.01, "{:?}: {}", args, timeout);
+
+        scale_timeouts(&mut machine, 2.0).unwrap();
+        assert!(check_send_units(&machine, initial_rate, decay, 1.0).is_err());
+        assert!(check_send_units(&machine, initial_rate, decay, 2.0).is_ok());
+
+        for wrong in [1.0 / initial_rate, 1000.0 / initial_rate] {
+            machine.states[FIRST_SEND_STATE_INDEX].timeout.param1 = wrong;
+            assert!(check_send_units(&machine, initial_rate, decay, 1.0).is_err(), "{:?}: {}", args, wrong);
+        }
+    }
+
+    let machines = regulator::generate(&["500", "0.75", "2", "4", "20"].map(String::from)).unwrap();
+    let relay = &machines.iter().find(|(name, _)| name == "Relay machine").unwrap().1;
+    assert!(check_send_units(relay, 500.0, 0.75, 1.0).is_ok());
+}
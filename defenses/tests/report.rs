@@ -0,0 +1,106 @@
+// Tests for the overhead report (src/report.rs), with constant-rate and
+// RegulaTor machines, for the defaults of the Scrambler and Adapted RegulaTor,
+// and for the send rate of an interval.
+
+use raising_the_bar::PACKET_SIZE;
+use raising_the_bar::constant::{self, Trigger};
+use raising_the_bar::dist_helpers::{fixed, uniform_range};
+use raising_the_bar::overhead::{bandwidth_bps, pps_from_interval};
+use raising_the_bar::regulator::{self, AdaptedRegulator, CLIENT_LIMIT};
+use raising_the_bar::defense::{Defense, Scrambler};
+use raising_the_bar::report::{OverheadReport, peak_padding_bps, longest_padding_timeout, send_rate};
+
+
+#[test]
+fn constant_machine_report() {
+    let machine = constant::generate_machine(fixed(4000.0), Trigger::Both, PACKET_SIZE);
+    assert_eq!(peak_padding_bps(&machine), 3_000_000.0);
+    assert_eq!(longest_padding_timeout(&machine), 4000.0);
+
+    let report = OverheadReport::new(&[&machine], bandwidth_bps(4000.0, PACKET_SIZE), 4000.0);
+    assert_eq!(report, OverheadReport {
+        states: constant::NUM_STATES,
+        serialized_len: machine.serialize().len(),
+        est_padding_bps: 3_000_000.0,
+        est_max_added_delay_us: 4000.0,
+    });
+    assert_eq!(report.lines(), [
+        "States: 3",
+        &format!("Serialized length: {}", machine.serialize().len()),
+        "Estimated padding bandwidth: 3000000 bits/sec",
+        "Estimated max added delay: 4000 usec",
+    ]);
+}
+
+
+#[test]
+fn send_rate_of_interval() {
+    assert_eq!(pps_from_interval(4000.0), 250.0);
+    assert_eq!(pps_from_interval(160.0), 6250.0);
+    assert_eq!(send_rate(4000.0), "250 packets/sec (every 4000 usec)");
+    assert_eq!(send_rate(6000.0), "166.67 packets/sec (every 6000 usec)");
+}
+
+
+#[test]
+fn padding_size_range_uses_its_mean() {
+    let mut machine = constant::generate_pad_only_machine(fixed(4000.0), Trigger::Both, PACKET_SIZE);
+    machine.states[constant::PAD_ONLY_CONST_STATE_INDEX].action = uniform_range(100.0, 1500.0);
+    assert_eq!(peak_padding_bps(&machine), bandwidth_bps(4000.0, 800.0));
+}
+
+
+#[test]
+fn reports_sum_over_machines() {
+    let machine = constant::generate_machine(fixed(4000.0), Trigger::Both, PACKET_SIZE);
+    let pad_only = constant::generate_pad_only_machine(fixed(4000.0), Trigger::Both, PACKET_SIZE);
+
+    let report = OverheadReport::new(&[&machine, &pad_only], f64::NAN, 0.0);
+    assert_eq!(report.states, constant::NUM_STATES + constant::PAD_ONLY_NUM_STATES);
+    assert_eq!(report.serialized_len, machine.serialize().len() + pad_only.serialize().len());
+    assert_eq!(report.lines()[2], "Estimated padding bandwidth: unknown");
+}
+
+
+#[test]
+fn client_pads_at_relay_rate_over_upload_ratio() {
+    // A constant-rate machine stands in for a relay machine sending 1000
+    // packets/sec
+    let relay = constant::generate_machine(fixed(1000.0), Trigger::Both, PACKET_SIZE);
+    let client = regulator::generate_client_machine(4.0, CLIENT_LIMIT, PACKET_SIZE);
+
+    // Real packets wait up to 4 packets of the relay in the client
+    let report = regulator::overhead_report(&relay, &client, 4.0);
+    assert_eq!(report.est_padding_bps, 12_000_000.0 + 3_000_000.0);
+    assert_eq!(report.est_max_added_delay_us, 4000.0);
+}
+
+
+
+// The reports of the defaults (see defaults.rs), as printed by --report
+// without arguments
+#[test]
+fn scrambler_default_report() {
+    // Machine #1 pads every 160 usec at most, and holds back a segment of up
+    // to 1500 packets (500 MIN and 1000 trailing) for an interval each
+    assert_eq!(Scrambler::default().overhead(), OverheadReport {
+        states: 10,
+        serialized_len: 624,
+        est_padding_bps: 75_000_000.0,
+        est_max_added_delay_us: 240_000.0,
+    });
+}
+
+
+#[test]
+fn adapted_regulator_default_report() {
+    // 89 relay and 5 client states. The client pads a quarter as much as the
+    // relay, and real packets wait in it for 4 packets of the slowest relay
+    // SEND state (1 packet/sec)
+    assert_eq!(AdaptedRegulator::default().overhead(), OverheadReport {
+        states: 94,
+        serialized_len: 8132,
+        est_padding_bps: 7_456_722.586742331,
+        est_max_added_delay_us: 4_000_000.0,
+    });
+}
@@ -0,0 +1,293 @@
+// Checks that the parameter schema (src/schema.rs) matches the generators: each
+// generator runs with the examples of its parameters, rejects missing required
+// parameters, produces the same machines with a default given explicitly as
+// without it, lists every parameter in its --help text, and describes every
+// positional parameter with --explain.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use raising_the_bar::schema::{parameter_schema, Defense, Kind, Limit, Param, DEFENSES};
+
+
+// A reference trace for parameters of Kind::File, except --size-hist
+const TRACE: &str = "\
+0,s,600
+1000000,s,600
+2000000,s,600
+3000000,s,600
+20000000,r,1500
+20500000,r,1500
+21000000,r,1500
+21500000,r,1500
+";
+
+// A histogram of padding sizes for --size-hist of Interspace
+const SIZE_HIST: &str = "\
+100,200,3
+1500,1
+";
+
+
+fn run(defense: &Defense, args: &[String]) -> Output {
+    let binary = PathBuf::from(env!("CARGO_BIN_EXE_schema")).with_file_name(defense.name);
+    return Command::new(binary).args(args).output().expect("Unable to run generator");
+}
+
+
+// The command-line value of an example or default, with files written out.
+fn argument(defense: &Defense, param: &Param, value: &str) -> String {
+    if param.kind != Kind::File {
+        return value.to_string();
+    }
+
+    let path = std::env::temp_dir().join(format!("schema_{}_{}", defense.name, value));
+    fs::write(&path, if param.flag == Some("--size-hist") { SIZE_HIST } else { TRACE }).unwrap();
+    return path.to_str().unwrap().to_string();
+}
+
+
+// The examples of the required positional parameters.
+fn required_args(defense: &Defense) -> Vec<String> {
+    return defense.params.iter()
+        .filter(|param| param.flag.is_none() && param.default.is_none())
+        .map(|param| argument(defense, param, param.example))
+        .collect();
+}
+
+
+// The defaults of the optional positional parameters before param, which must
+// be given to reach its position (optional positional parameters come last).
+fn leading_defaults(defense: &Defense, param: &Param) -> Vec<String> {
+    return defense.params.iter()
+        .take_while(|other| other.name != param.name)
+        .filter(|other| other.flag.is_none())
+        .filter_map(|other| other.default.map(|default| argument(defense, other, default)))
+        .collect();
+}
+
+
+// A flag with its example value (none for switches).
+fn flag_args(defense: &Defense, flag: &str) -> Vec<String> {
+    let param = defense.params.iter().find(|param| param.flag == Some(flag)).unwrap();
+    if param.kind == Kind::Switch {
+        return vec![flag.to_string()];
+    }
+    return vec![flag.to_string(), argument(defense, param, param.example)];
+}
+
+
+fn within(param: &Param, value: f64) -> bool {
+    let above = match param.min {
+        Some(Limit::Inclusive(min)) => value >= min,
+        Some(Limit::Exclusive(min)) => value > min,
+        None => true,
+    };
+    let below = match param.max {
+        Some(Limit::Inclusive(max)) => value <= max,
+        Some(Limit::Exclusive(max)) => value < max,
+        None => true,
+    };
+    return above && below;
+}
+
+
+#[test]
+fn examples_and_defaults_are_in_range() {
+    for defense in DEFENSES {
+        for param in defense.params {
+            if !matches!(param.kind, Kind::Number | Kind::Integer | Kind::NumberList) {
+                if let Kind::Choice(choices) = param.kind {
+                    assert!(choices.contains(&param.example), "{} {}", defense.name, param.name);
+                    assert!(param.default.is_none_or(|default| choices.contains(&default)), "{} {}", defense.name, param.name);
+                }
+                continue;
+            }
+
+            for value in [Some(param.example), param.default].into_iter().flatten().flat_map(|value| value.split(',')) {
+                let value: f64 = value.parse().unwrap();
+                assert!(within(param, value), "{} {}: {} out of range", defense.name, param.name, value);
+            }
+        }
+    }
+}
+
+
+#[test]
+fn generators_accept_examples() {
+    for defense in DEFENSES {
+        let required = required_args(defense);
+        let output = run(defense, &[&required[..], &[String::from("--dry-run")]].concat());
+        assert!(output.status.success(), "{} {:?}: {}", defense.name, required, String::from_utf8_lossy(&output.stderr));
+
+        // Each option, along with the options it requires
+        for param in defense.params.iter().filter(|param| param.flag.is_some()) {
+            let mut args = required.clone();
+            for flag in [param.flag.unwrap()].iter().chain(param.requires) {
+                args.extend(flag_args(defense, flag));
+            }
+
+            args.push(String::from("--dry-run"));
+            let output = run(defense, &args);
+            assert!(output.status.success(), "{} {:?}: {}", defense.name, args, String::from_utf8_lossy(&output.stderr));
+        }
+
+        // Each option with the examples of the options it excludes
+        for param in defense.params.iter().filter(|param| !param.excludes.is_empty()) {
+            let mut args = required.clone();
+            for flag in [param.flag.unwrap()].iter().chain(param.excludes) {
+                args.extend(flag_args(defense, flag));
+            }
+            assert!(!run(defense, &args).status.success(), "{} {:?}", defense.name, args);
+        }
+    }
+}
+
+
+#[test]
+fn generators_reject_missing_parameters() {
+    for defense in DEFENSES {
+        let required = required_args(defense);
+        for i in 0..required.len() {
+            let mut args = required.clone();
+            args.remove(i);
+            assert!(!run(defense, &args).status.success(), "{} {:?}", defense.name, args);
+        }
+    }
+}
+
+
+// Every number, along with the options it requires, is rejected when it is not
+// finite, and -1 wherever it is out of range.
+#[test]
+fn generators_reject_invalid_numbers() {
+    for defense in DEFENSES {
+        let required = required_args(defense);
+        let mut position = 0;
+
+        for param in defense.params {
+            let required_position = param.flag.is_none() && param.default.is_none();
+            if !matches!(param.kind, Kind::Number | Kind::Integer | Kind::NumberList) {
+                position += required_position as usize;
+                continue;
+            }
+
+            for value in ["nan", "inf", "-inf", "-1"] {
+                if value == "-1" && within(param, -1.0) {
+                    continue;
+                }
+
+                let mut args = required.clone();
+                match param.flag {
+                    Some(flag) => {
+                        args.extend([flag.to_string(), value.to_string()]);
+                        for other in param.requires {
+                            args.extend(flag_args(defense, other));
+                        }
+                    },
+                    None if required_position => args[position] = value.to_string(),
+                    None => {
+                        args.extend(leading_defaults(defense, param));
+                        args.push(value.to_string());
+                    },
+                }
+
+                args.push(String::from("--dry-run"));
+                assert!(!run(defense, &args).status.success(), "{} {:?}", defense.name, args);
+            }
+
+            position += required_position as usize;
+        }
+    }
+}
+
+
+// Defaults are compared with the other options set to their examples, for
+// defaults that only matter along with another option (e.g., the Pareto shape),
+// leaving out the options that require or exclude others.
+#[test]
+fn defaults_match_generators() {
+    for defense in DEFENSES {
+        for param in defense.params {
+            let default = match param.default {
+                Some(default) => argument(defense, param, default),
+                None => continue,
+            };
+
+            let mut args = required_args(defense);
+            for other in defense.params {
+                if let Some(flag) = other.flag.filter(|_| other.flag != param.flag && other.requires.is_empty() && other.excludes.is_empty() && other.kind != Kind::Switch) {
+                    args.extend(flag_args(defense, flag));
+                }
+            }
+
+            let expected = run(defense, &args);
+            assert!(expected.status.success(), "{} {:?}", defense.name, args);
+
+            match param.flag {
+                Some(flag) => args.extend([flag.to_string(), default]),
+                None => {
+                    args.extend(leading_defaults(defense, param));
+                    args.push(default);
+                },
+            }
+
+            let output = run(defense, &args);
+            assert_eq!(String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&expected.stdout), "{} {}", defense.name, param.name);
+        }
+    }
+}
+
+
+#[test]
+fn help_lists_parameters() {
+    for defense in DEFENSES {
+        let help = String::from_utf8(run(defense, &[String::from("--help")]).stdout).unwrap();
+        let usage = help.lines().next().unwrap();
+
+        for param in defense.params {
+            match param.flag {
+                Some(flag) => assert!(help.contains(&format!("{} ", flag)), "{} {}", defense.name, flag),
+                None => {
+                    let name = match param.default {
+                        Some(_) => format!("[{}]", param.name),
+                        None => format!("<{}>", param.name),
+                    };
+                    assert!(usage.contains(&name), "{} {}", defense.name, name);
+                },
+            }
+        }
+    }
+}
+
+
+// --explain needs no other argument, and prints prose rather than machines.
+#[test]
+fn explain_describes_parameters() {
+    for defense in DEFENSES {
+        let output = run(defense, &[String::from("--explain")]);
+        assert!(output.status.success(), "{}", defense.name);
+        let explanation = String::from_utf8(output.stdout).unwrap();
+        assert!(explanation.contains("\nIn the paper: "), "{}", defense.name);
+        assert!(!explanation.lines().any(|line| line.contains(": 789c")), "{}", defense.name);
+
+        for param in defense.params.iter().filter(|param| param.flag.is_none()) {
+            assert!(explanation.contains(&format!("\n  {}  ", param.name)), "{} {}", defense.name, param.name);
+        }
+    }
+}
+
+
+#[test]
+fn schema_numbers_positional_parameters() {
+    for defense in DEFENSES {
+        let schema = parameter_schema(defense.name);
+        let positions: Vec<u64> = schema["parameters"].as_array().unwrap().iter()
+            .filter_map(|param| param["position"].as_u64())
+            .collect();
+
+        let expected: Vec<u64> = (1..=positions.len() as u64).collect();
+        assert_eq!(positions, expected, "{}", defense.name);
+    }
+}
@@ -0,0 +1,182 @@
+// Tests for Machine #1 of the Scrambler with any number of L/R pairs
+// (scrambler::generate_machine_one_pairs()), its worst-case added latency
+// (scrambler::max_added_latency_us()), the cap on its states, and the
+// machines following the received traffic (Direction::Down).
+
+use maybenot::{
+event::Event,
+machine::Machine
+};
+
+use raising_the_bar::{PACKET_SIZE, MAX_STATES};
+use raising_the_bar::defense::{Defense, Scrambler};
+use raising_the_bar::scrambler::{
+parse,
+generate_machine_one,
+generate_machine_one_pairs,
+generate_machine_one_single_pair,
+generate_machine_no_block,
+generate_machine_two,
+num_states_one,
+pair_trails,
+max_added_latency_us,
+TrailDist,
+Direction,
+NUM_PAIRS,
+SECOND_PAIR_DIVISOR
+};
+
+
+const TRAIL: (f64, f64) = (400.0, 1000.0);
+
+// Index of the L state of the first pair, followed by its R state
+const FIRST_LEFT_INDEX: usize = 3;
+
+
+#[test]
+fn default_pairs_match_machine_one() {
+    let pairs = pair_trails(TRAIL, TRAIL, NUM_PAIRS, SECOND_PAIR_DIVISOR);
+    assert_eq!(pairs, [(TRAIL, TRAIL), ((100.0, 250.0), (100.0, 250.0))]);
+
+    let machine = generate_machine_one_pairs(160.0, 160.0, 500.0, &pairs, TrailDist::Uniform, Direction::Up, PACKET_SIZE);
+    assert_eq!(machine.serialize(), generate_machine_one(160.0, 160.0, 500.0, TRAIL, TRAIL, TrailDist::Uniform, PACKET_SIZE).serialize());
+
+    let single = generate_machine_one_pairs(160.0, 160.0, 500.0, &pairs[..1], TrailDist::Uniform, Direction::Up, PACKET_SIZE);
+    assert_eq!(single.serialize(), generate_machine_one_single_pair(160.0, 160.0, 500.0, TRAIL, TRAIL, TrailDist::Uniform, PACKET_SIZE).serialize());
+}
+
+
+// With the paper's parameters, a burst filling a segment (500 + 1000 packets)
+// waits 1500 intervals of 160 usec.
+#[test]
+fn paper_defaults_max_added_latency() {
+    assert_eq!(max_added_latency_us(160.0, 500.0, 1000.0), 240000.0);
+    assert_eq!(max_added_latency_us(160.0, 500.0, 1000.0) * 2.0, max_added_latency_us(320.0, 500.0, 1000.0));
+}
+
+
+// The overhead report gives the same max added delay as the worst-case
+// latency of --report, and none without blocking.
+#[test]
+fn report_delay_is_the_max_added_latency() {
+    assert_eq!(Scrambler::default().overhead().est_max_added_delay_us, 240000.0);
+    assert_eq!(Scrambler { no_block: true, num_pairs: 1, ..Scrambler::default() }.overhead().est_max_added_delay_us, 0.0);
+}
+
+
+#[test]
+fn trailing_counts_reduce_geometrically() {
+    let pairs = pair_trails(TRAIL, (200.0, 800.0), 4, 2.0);
+    let left: Vec<(f64, f64)> = pairs.iter().map(|pair| pair.0).collect();
+    let right: Vec<(f64, f64)> = pairs.iter().map(|pair| pair.1).collect();
+    assert_eq!(left, [(400.0, 1000.0), (200.0, 500.0), (100.0, 250.0), (50.0, 125.0)]);
+    assert_eq!(right, [(200.0, 800.0), (100.0, 400.0), (50.0, 200.0), (25.0, 100.0)]);
+}
+
+
+#[test]
+fn blocking_begin_chains_consecutive_pairs() {
+    let num_pairs = 4;
+    let pairs = pair_trails(TRAIL, TRAIL, num_pairs, SECOND_PAIR_DIVISOR);
+    let machine = generate_machine_one_pairs(160.0, 160.0, 500.0, &pairs, TrailDist::Uniform, Direction::Up, PACKET_SIZE);
+    assert_eq!(machine.states.len(), num_states_one(num_pairs));
+    assert_eq!(machine.states.len(), FIRST_LEFT_INDEX + 2 * num_pairs);
+
+    for (k, pair) in pairs.iter().enumerate() {
+        for index in [FIRST_LEFT_INDEX + 2 * k, FIRST_LEFT_INDEX + 2 * k + 1] {
+            let state = &machine.states[index];
+            assert_eq!(state.limit.param1, pair.0.0, "state {}", index);
+
+            // Each L (R) state moves on to the L (R) state of the next pair,
+            // and the last pair stays put
+            let blocking_begin = state.next_state.get(&Event::BlockingBegin);
+            if k + 1 < num_pairs {
+                assert_eq!(blocking_begin.map(|probs| probs[index + 2]), Some(1.0), "state {}", index);
+            } else {
+                assert!(blocking_begin.is_none(), "state {}", index);
+            }
+        }
+    }
+}
+
+
+#[test]
+fn only_first_default_pair_chains_on_blocking_begin() {
+    let machine = generate_machine_one(160.0, 160.0, 500.0, TRAIL, TRAIL, TrailDist::Uniform, PACKET_SIZE);
+    let first_right_index = FIRST_LEFT_INDEX + 1;
+
+    // L_1 and R_1 move on to L_2 and R_2, and nowhere else
+    for index in [FIRST_LEFT_INDEX, first_right_index] {
+        let probs = &machine.states[index].next_state[&Event::BlockingBegin];
+        let targets: Vec<usize> = (0..probs.len()).filter(|target| probs[*target] > 0.0).collect();
+        assert_eq!(targets, [index + 2], "state {}", index);
+        assert_eq!(probs[index + 2], 1.0, "state {}", index);
+    }
+
+    // L_2 and R_2 have no pair to move on to
+    for index in [FIRST_LEFT_INDEX + 2, first_right_index + 2] {
+        assert!(!machine.states[index].next_state.contains_key(&Event::BlockingBegin), "state {}", index);
+    }
+}
+
+
+#[test]
+fn down_machines_follow_received_traffic() {
+    let pairs = pair_trails(TRAIL, TRAIL, NUM_PAIRS, SECOND_PAIR_DIVISOR);
+    let machines = |direction: Direction| -> Vec<Machine> {
+        return vec![
+            generate_machine_one_pairs(160.0, 160.0, 500.0, &pairs, TrailDist::Uniform, direction, PACKET_SIZE),
+            generate_machine_no_block(160.0, 160.0, 500.0, TRAIL, TRAIL, TrailDist::Uniform, direction, PACKET_SIZE),
+            generate_machine_two(500.0, direction),
+        ];
+    };
+
+    for (up, down) in machines(Direction::Up).iter().zip(machines(Direction::Down)) {
+        assert_eq!(up.states.len(), down.states.len());
+        for (index, (up_state, down_state)) in up.states.iter().zip(&down.states).enumerate() {
+            // Recv events throughout, in place of the sent real packets, and
+            // the same transitions on the other events
+            assert!(!down_state.next_state.contains_key(&Event::NonPaddingSent), "state {}", index);
+            assert!(!down_state.next_state.contains_key(&Event::PaddingRecv) && !up_state.next_state.contains_key(&Event::NonPaddingRecv), "state {}", index);
+            assert_eq!(down_state.next_state.get(&Event::NonPaddingRecv), up_state.next_state.get(&Event::NonPaddingSent), "state {}", index);
+            for event in [Event::PaddingSent, Event::BlockingBegin, Event::LimitReached] {
+                assert_eq!(down_state.next_state.get(&event), up_state.next_state.get(&event), "state {} {:?}", index, event);
+            }
+        }
+    }
+
+    // Every machine starts and segments on real traffic, so each has Recv
+    // transitions
+    for machine in machines(Direction::Down) {
+        assert!(machine.states.iter().any(|state| state.next_state.contains_key(&Event::NonPaddingRecv)));
+    }
+}
+
+
+#[test]
+fn pairs_past_the_state_cap_are_rejected() {
+    let pairs = |num_pairs: usize| vec![String::from("--pairs"), num_pairs.to_string()];
+    let max_pairs = (MAX_STATES - FIRST_LEFT_INDEX) / 2;
+    assert!(num_states_one(max_pairs) <= MAX_STATES && num_states_one(max_pairs + 1) > MAX_STATES);
+
+    assert!(parse(&pairs(max_pairs)).is_ok());
+    assert_eq!(parse(&pairs(max_pairs + 1)).err(), Some(format!("Machine #1 would take {} states, more than the cap of {} (give fewer pairs)", num_states_one(max_pairs + 1), MAX_STATES)));
+
+    // Past the u16 state count of maybenot too
+    assert!(parse(&pairs(40000)).is_err());
+    assert!(parse(&pairs(usize::MAX)).is_err());
+}
+
+
+// An inverted trailing range would give Uniform limits with min > max
+#[test]
+fn inverted_trail_ranges_are_rejected() {
+    let parse = |args: &[&str]| parse(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>());
+    assert_eq!(parse(&["160", "500", "900", "100"]).err(), Some(String::from("Min trail must not exceed max trail, got 900 and 100")));
+    assert_eq!(parse(&["160", "500", "400", "1000", "--left-trail-min", "900", "--left-trail-max", "100"]).err(), Some(String::from("Left trail min must not exceed left trail max, got 900 and 100")));
+    assert_eq!(parse(&["160", "500", "400", "1000", "--right-trail-max", "100"]).err(), Some(String::from("Right trail min must not exceed right trail max, got 400 and 100")));
+
+    // A range of a single count, and sides that only differ from each other
+    assert!(parse(&["160", "500", "400", "400"]).is_ok());
+    assert!(parse(&["160", "500", "400", "1000", "--left-trail-min", "1000", "--right-trail-max", "400"]).is_ok());
+}
@@ -0,0 +1,216 @@
+// Tests for the simulated overhead measurement (src/simulate.rs), checked
+// against the closed-form overhead of the constant defense (src/overhead.rs),
+// for when the RegulaTor relay machine starts in a simulated session, and for
+// the constant interval calibrated to a base trace.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use maybenot::{
+machine::Machine,
+dist::{Dist, DistType}
+};
+
+use raising_the_bar::PACKET_SIZE;
+use raising_the_bar::constant::{self, Trigger};
+use raising_the_bar::regulator;
+use raising_the_bar::overhead::{bandwidth_bps, overhead_ratio, mean_throughput_bps, peak_throughput_bps, calibrate_interval};
+use raising_the_bar::simulate::{parse_base_trace, measure, mean, percentile, behaviorally_equal, Packet};
+
+
+const DELAY: Duration = Duration::from_millis(10);
+const MAX_EVENTS: usize = 100000;
+
+// Base trace interval of the client's packets (usec)
+const BASE_INTERVAL: f64 = 8000.0;
+
+
+// A client sending a packet every BASE_INTERVAL, and receiving a smaller one
+// halfway in between.
+fn base_trace(num_packets: u64) -> Vec<Packet> {
+    let mut trace = String::new();
+    for i in 0..num_packets {
+        let time = i * BASE_INTERVAL as u64 * 1000;
+        trace.push_str(&format!("{},s\n{},r,500\n", time, time + BASE_INTERVAL as u64 * 500));
+    }
+
+    return parse_base_trace(&trace).unwrap();
+}
+
+
+// A video session: the client's request, and the response downloaded after
+// 300 ms. The relay sends the response a network delay before it arrives.
+const REQUEST_FIRST: &str = "\
+0,s,200
+300000000,r
+300500000,r
+301000000,r
+";
+
+// The same download, with the request before the trace
+const RESPONSE_FIRST: &str = "\
+0,r
+500000,r
+300000000,s,200
+";
+
+
+fn relay_machine(args: &[&str]) -> Machine {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    let machines = regulator::generate_adapted(&args).unwrap();
+    return machines.into_iter().find(|(name, _)| name == "Relay machine").unwrap().1;
+}
+
+
+fn constant_machine(interval: f64) -> Machine {
+    let timeout = Dist { dist: DistType::Uniform, param1: interval, param2: interval, start: 0.0, max: 0.0 };
+    return constant::generate_machine(timeout, Trigger::Both, PACKET_SIZE);
+}
+
+
+#[test]
+fn base_trace_format() {
+    let packets = parse_base_trace("0,s\n\n1000, r ,600\n").unwrap();
+    assert_eq!(packets, [
+        Packet { time: 0, sent: true, size: PACKET_SIZE as u16 },
+        Packet { time: 1000, sent: false, size: 600 },
+    ]);
+
+    assert!(parse_base_trace("").is_err());
+    assert!(parse_base_trace("0").is_err());
+    assert!(parse_base_trace("0,s,600,1").is_err());
+    assert!(parse_base_trace("-1,s").is_err());
+    assert!(parse_base_trace("0,x").is_err());
+    assert!(parse_base_trace("0,s,0").is_err());
+    assert!(parse_base_trace("0,s,70000").is_err());
+    assert!(parse_base_trace("1000,s\n0,r").is_err());
+}
+
+
+#[test]
+fn constant_overhead_matches_closed_form() {
+    let packets = base_trace(200);
+    let measurement = measure(&packets, &[constant_machine(4000.0)], &[], DELAY, MAX_EVENTS).unwrap();
+
+    // Each base packet fills one of the constant machine's slots, up to the
+    // last one
+    let expected = overhead_ratio(4000.0, PACKET_SIZE, bandwidth_bps(BASE_INTERVAL, PACKET_SIZE));
+    let overhead = measurement.client.overhead().unwrap();
+    assert!((overhead - expected).abs() < 0.01, "measured {}, expected {}", overhead, expected);
+
+    assert_eq!(measurement.client.real_packets, 200);
+    assert_eq!(measurement.relay.real_packets, 200);
+    assert_eq!(measurement.relay.padding_packets, 0);
+    assert_eq!(mean(&measurement.client.delays), Some(0.0));
+}
+
+
+#[test]
+fn no_machines_add_nothing() {
+    let packets = base_trace(20);
+    let measurement = measure(&packets, &[], &[], DELAY, MAX_EVENTS).unwrap();
+
+    assert_eq!(measurement.client.real_bytes, 20 * PACKET_SIZE as u64);
+    assert_eq!(measurement.relay.real_bytes, 20 * 500);
+    assert_eq!(measurement.overhead(), Some(0.0));
+    assert!(measurement.client.delays.iter().chain(&measurement.relay.delays).all(|&delay| delay == 0.0));
+}
+
+
+#[test]
+fn too_few_events_fail() {
+    let packets = base_trace(200);
+    assert!(measure(&packets, &[constant_machine(4000.0)], &[], DELAY, 100).is_err());
+}
+
+
+#[test]
+fn delay_statistics() {
+    let delays: Vec<f64> = (1..=20).map(|delay| delay as f64).collect();
+    assert_eq!(mean(&delays), Some(10.5));
+    assert_eq!(percentile(&delays, 95.0), Some(19.0));
+    assert_eq!(percentile(&delays, 100.0), Some(20.0));
+    assert_eq!(percentile(&delays, 0.0), Some(1.0));
+    assert_eq!(mean(&[]), None);
+    assert_eq!(percentile(&[], 95.0), None);
+}
+
+
+#[test]
+fn relay_machine_starts_on_the_request() {
+    let relay_padding = |trace: &str, args: &[&str]| {
+        let packets = parse_base_trace(trace).unwrap();
+        let measurement = measure(&packets, &[], &[relay_machine(args)], DELAY, MAX_EVENTS).unwrap();
+        return measurement.relay.padding_packets;
+    };
+
+    // The relay pads from the request on, and is blocking when the response
+    // comes, so its first packet is shaped like the rest
+    let padding = relay_padding(REQUEST_FIRST, &["500", "0.75", "4", "20"]);
+    assert!(padding > 100, "{} padding packets", padding);
+    assert_eq!(relay_padding(REQUEST_FIRST, &["500", "0.75", "4", "20", "--trigger", "sent"]), 0);
+
+    // Starting on the response, either way
+    let padding = relay_padding(RESPONSE_FIRST, &["500", "0.75", "4", "20"]);
+    assert!(padding > 100, "{} padding packets", padding);
+    assert_eq!(relay_padding(RESPONSE_FIRST, &["500", "0.75", "4", "20", "--trigger", "sent"]), padding);
+}
+
+
+#[test]
+fn throughput_of_base_trace() {
+    // The client sends 1500 bytes every 8 ms, and receives 500 bytes halfway
+    let packets = base_trace(100);
+    let sent: Vec<Packet> = packets.iter().filter(|packet| packet.sent).copied().collect();
+    let received: Vec<Packet> = packets.iter().filter(|packet| !packet.sent).copied().collect();
+
+    assert_eq!(mean_throughput_bps(&sent), Some(bandwidth_bps(BASE_INTERVAL, PACKET_SIZE)));
+    assert_eq!(mean_throughput_bps(&received), Some(bandwidth_bps(BASE_INTERVAL, 500.0)));
+    assert_eq!(mean_throughput_bps(&sent[..1]), None);
+
+    // A window of one interval holds one packet, and a shorter one too
+    assert_eq!(peak_throughput_bps(&sent, BASE_INTERVAL), bandwidth_bps(BASE_INTERVAL, PACKET_SIZE));
+    assert_eq!(peak_throughput_bps(&sent, BASE_INTERVAL / 2.0), bandwidth_bps(BASE_INTERVAL / 2.0, PACKET_SIZE));
+}
+
+
+// A burst is averaged out by the mean, but sets the peak, which the calibrated
+// interval pads above.
+#[test]
+fn calibrated_interval_dominates_peak() {
+    let packets = parse_base_trace("0,r\n1000000,r\n2000000,r\n3000000,r\n10000000000,r\n").unwrap();
+    let peak = peak_throughput_bps(&packets, 1000000.0);
+    assert_eq!(peak, 4.0 * PACKET_SIZE * 8.0);
+    assert!(mean_throughput_bps(&packets).unwrap() < peak);
+
+    let interval = calibrate_interval(peak, 0.1, PACKET_SIZE).unwrap();
+    assert_eq!(interval, interval.floor());
+    assert!(bandwidth_bps(interval, PACKET_SIZE) >= peak * 1.1);
+    assert!(bandwidth_bps(interval + 1.0, PACKET_SIZE) < peak * 1.1);
+
+    assert_eq!(calibrate_interval(peak, 0.0, PACKET_SIZE).unwrap(), 250000.0);
+    assert!(calibrate_interval(peak, -0.1, PACKET_SIZE).is_err());
+    assert!(calibrate_interval(0.0, 0.1, PACKET_SIZE).is_err());
+    assert!(calibrate_interval(1e12, 0.1, PACKET_SIZE).is_err());
+}
+
+
+// The constant machine samples nothing, so it is equal to itself and to its
+// serialization, while another interval pads at other times.
+#[test]
+fn behaviorally_equal_constant_machines() {
+    let trace = base_trace(20);
+    let machine = constant_machine(4000.0);
+    let parsed = Machine::from_str(&machine.serialize()).unwrap();
+
+    assert!(behaviorally_equal(&machine, &machine, &trace, 100));
+    assert!(behaviorally_equal(&machine, &parsed, &trace, 100));
+    assert!(!behaviorally_equal(&machine, &constant_machine(5000.0), &trace, 100));
+
+    // Both send the same first real packet, before any padding
+    assert!(behaviorally_equal(&machine, &constant_machine(5000.0), &trace, 1));
+
+    // The constant binary builds the same machine
+    let generated = constant::generate(&[String::from("4000")]).unwrap().remove(0).1;
+    assert!(behaviorally_equal(&machine, &generated, &trace, 100));
+}
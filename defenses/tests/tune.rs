@@ -0,0 +1,89 @@
+// Tests for the parameter search of tune (src/tune.rs): the hill-climb reaches
+// a target it can reach, stops at the cap or a local optimum otherwise, and
+// only measures configurations the scrambler binary takes.
+
+use std::time::Duration;
+
+use raising_the_bar::defense::Scrambler;
+use raising_the_bar::simulate::parse_base_trace;
+use raising_the_bar::tune::{hill_climb, neighbors, round_params, measured_overhead, Search};
+
+
+// A base trace of a download in bursts, with a request before each burst
+fn base_trace() -> String {
+    let mut trace = String::new();
+    for burst in 0..4u64 {
+        let start = burst * 2_000_000_000;
+        trace.push_str(&format!("{},s,600\n", start));
+        for i in 1..=50u64 {
+            trace.push_str(&format!("{},r,1500\n", start + i * 1_000_000));
+        }
+    }
+    return trace;
+}
+
+
+fn search(target: f64) -> Search {
+    return Search { target: target, tolerance: 0.01, max_measurements: 100 };
+}
+
+
+#[test]
+fn hill_climb_reaches_target() {
+    // Overhead inversely proportional to the send interval, as with padding
+    // at a constant rate
+    let tuned = hill_climb(&Scrambler::default(), &search(2.5), |params| Ok(1000.0 / params.interval)).unwrap();
+    assert!(tuned.converged, "{:?}", tuned);
+    assert!((tuned.overhead - 2.5).abs() <= 0.025, "{:?}", tuned);
+    assert_eq!(tuned.params, round_params(&tuned.params));
+    assert!(tuned.measurements <= 100);
+}
+
+
+#[test]
+fn hill_climb_stops_short_of_unreachable_targets() {
+    // Nothing changes the overhead: the search ends at the start, once the
+    // step is below MIN_STEP
+    let mut calls = 0;
+    let tuned = hill_climb(&Scrambler::default(), &search(5.0), |_| { calls += 1; Ok(1.0) }).unwrap();
+    assert!(!tuned.converged);
+    assert_eq!(tuned.params, Scrambler::default());
+    assert_eq!(tuned.measurements, calls);
+
+    // ... or at the cap on measurements
+    let capped = Search { max_measurements: 3, ..search(5.0) };
+    assert_eq!(hill_climb(&Scrambler::default(), &capped, |params| Ok(1.0 / params.interval)).unwrap().measurements, 3);
+
+    // Failed measurements and invalid settings are errors
+    assert!(hill_climb(&Scrambler::default(), &search(5.0), |_| Err(String::from("no progress"))).is_err());
+    assert!(hill_climb(&Scrambler::default(), &search(0.0), |_| Ok(1.0)).is_err());
+    assert!(hill_climb(&Scrambler::default(), &Search { max_measurements: 0, ..search(5.0) }, |_| Ok(1.0)).is_err());
+}
+
+
+#[test]
+fn neighbors_are_whole_configurations() {
+    let start = Scrambler::default();
+    let next = neighbors(&start, 2.0);
+    assert_eq!(next.len(), 6);
+    assert!(next.contains(&Scrambler { interval: 320.0, ..start.clone() }));
+    assert!(next.contains(&Scrambler { min_trail: 200.0, max_trail: 500.0, ..start.clone() }));
+
+    // Steps too small to change a whole count leave it out
+    let small = Scrambler { interval: 3.0, min_count: 1.0, min_trail: 1.0, max_trail: 1.0, ..start };
+    for neighbor in neighbors(&small, 1.1) {
+        assert_ne!(neighbor, small);
+        assert!(neighbor.min_count >= 1.0 && neighbor.min_trail >= 1.0 && neighbor.interval >= 1.0);
+    }
+}
+
+
+#[test]
+fn simulated_overhead_falls_with_the_interval() {
+    let packets = parse_base_trace(&base_trace()).unwrap();
+    let overhead = |interval: f64| measured_overhead(&Scrambler { interval: interval, ..Scrambler::default() }, &packets, Duration::from_millis(10), 1000000).unwrap();
+
+    let fast = overhead(4000.0);
+    let slow = overhead(16000.0);
+    assert!(fast > slow && slow > 0.0, "{} {}", fast, slow);
+}
@@ -0,0 +1,193 @@
+// Property tests for the validators (src/validate.rs), on random machines that
+// resemble the ones this crate generates: Uniform distributions, random
+// bypass/replace/block flags, and transitions to random states (plus
+// STATECANCEL and STATEEND) with random probabilities. Each state is built for
+// a number of states that may be off from the real one, as a generator with a
+// wrong NUM_STATES would do. The flag checks are also run on contrived states
+// of the minimal constant machine.
+
+use std::collections::HashMap;
+
+use maybenot::{
+constants::{STATECANCEL, STATEEND},
+machine::Machine,
+event::Event,
+state::State,
+dist::{Dist, DistType}
+};
+
+use proptest::prelude::*;
+
+use raising_the_bar::constant;
+use raising_the_bar::validate::{validate_indices, unreachable_states, validate_flag_consistency, check_has_states, Level};
+
+
+const MAX_STATES: usize = 8;
+
+
+// A Uniform distribution over [low, low + width].
+fn uniform() -> impl Strategy<Value = Dist> {
+    return (0.0..10000.0f64, 0.0..10000.0f64).prop_map(|(low, width)| Dist {
+        dist: DistType::Uniform,
+        param1: low,
+        param2: low + width,
+        start: 0.0,
+        max: 0.0,
+    });
+}
+
+
+// Transitions for a state built for num_states states: a few events, each to
+// a few targets in range for num_states (or STATECANCEL/STATEEND).
+fn transitions(num_states: usize) -> impl Strategy<Value = HashMap<Event, HashMap<usize, f64>>> {
+    let events: Vec<Event> = Event::iterator().copied().collect();
+    let target = prop_oneof![
+        8 => 0..num_states,
+        1 => Just(STATECANCEL),
+        1 => Just(STATEEND),
+    ];
+
+    return prop::collection::hash_map(
+        prop::sample::select(events),
+        prop::collection::hash_map(target, 0.01..1.0f64, 1..4),
+        0..4,
+    );
+}
+
+
+// A state built for a number of states within 2 of the machine's num_states.
+fn state(num_states: usize) -> impl Strategy<Value = State> {
+    let built_for = num_states.saturating_sub(2).max(1)..=num_states + 2;
+
+    return built_for.prop_flat_map(|built_for| {
+        (transitions(built_for), Just(built_for), uniform(), uniform(), uniform(), any::<(bool, bool, bool)>())
+    }).prop_map(|(transitions, built_for, action, timeout, limit, (action_is_block, bypass, replace))| {
+        let mut state = State::new(transitions, built_for);
+        state.action = action;
+        state.timeout = timeout;
+        state.limit = limit;
+        state.action_is_block = action_is_block;
+        state.bypass = bypass;
+        state.replace = replace;
+        return state;
+    });
+}
+
+
+fn machine() -> impl Strategy<Value = Machine> {
+    return (1..=MAX_STATES).prop_flat_map(|num_states| {
+        prop::collection::vec(state(num_states), num_states)
+    }).prop_map(|states| Machine {
+        allowed_padding_bytes: 0,
+        max_padding_frac: 0.0,
+        allowed_blocked_microsec: 0,
+        max_blocking_frac: 0.0,
+        states: states,
+        include_small_packets: false,
+    });
+}
+
+
+// The targets a state transitions to with non-zero probability, decoding each
+// position of a transition vector for a machine of num_states states.
+fn targets(state: &State, num_states: usize) -> Vec<usize> {
+    let mut targets: Vec<usize> = Vec::new();
+
+    for probs in state.next_state.values() {
+        for (position, p) in probs.iter().enumerate() {
+            if *p <= 0.0 {
+                continue;
+            }
+
+            targets.push(match position {
+                position if position < num_states => position,
+                position if position == num_states => STATECANCEL,
+                position if position == num_states + 1 => STATEEND,
+                position => position, // out of range
+            });
+        }
+    }
+
+    return targets;
+}
+
+
+proptest! {
+    #[test]
+    fn valid_indices_stay_in_range(machine in machine()) {
+        let num_states = machine.states.len();
+        let errors = validate_indices(&machine).iter().any(|issue| issue.level == Level::Error);
+
+        if !errors {
+            for state in &machine.states {
+                for target in targets(state, num_states) {
+                    prop_assert!(target < num_states || target == STATECANCEL || target == STATEEND, "target {} of {} states", target, num_states);
+                }
+            }
+        }
+    }
+
+
+    #[test]
+    fn unentered_states_are_unreachable(machine in machine()) {
+        let num_states = machine.states.len();
+        let unreachable = unreachable_states(&machine);
+        prop_assert!(!unreachable.contains(&0));
+
+        for index in 1..num_states {
+            let entered = machine.states.iter().enumerate()
+                .filter(|(other, _)| *other != index)
+                .any(|(_, state)| targets(state, num_states).contains(&index));
+
+            if !entered {
+                prop_assert!(unreachable.contains(&index), "state {} has no inbound transitions", index);
+            }
+        }
+    }
+}
+
+
+// Flag warnings of the minimal constant machine (START, BLOCK, CONST) with the
+// flags of one state set to (action_is_block, bypass, replace).
+fn flag_warnings(index: usize, flags: (bool, bool, bool)) -> Vec<Option<usize>> {
+    let mut machine = constant::minimal();
+    let state = &mut machine.states[index];
+    (state.action_is_block, state.bypass, state.replace) = flags;
+    return validate_flag_consistency(&machine).iter().map(|issue| issue.state).collect();
+}
+
+
+#[test]
+fn inconsistent_flags_warn() {
+    assert!(validate_flag_consistency(&constant::minimal()).is_empty());
+
+    // START has no action
+    assert_eq!(flag_warnings(constant::START_STATE_INDEX, (false, false, true)), [Some(0)]);
+    assert_eq!(flag_warnings(constant::START_STATE_INDEX, (false, true, false)), [Some(0)]);
+
+    // BLOCK without bypass, with or without replace
+    assert_eq!(flag_warnings(constant::BLOCK_STATE_INDEX, (true, false, true)), [Some(1)]);
+    assert_eq!(flag_warnings(constant::BLOCK_STATE_INDEX, (true, false, false)), [Some(1)]);
+    assert!(flag_warnings(constant::BLOCK_STATE_INDEX, (true, true, false)).is_empty());
+
+    // CONST padding with bypass alone, but not with replace alone or neither
+    assert_eq!(flag_warnings(constant::CONST_STATE_INDEX, (false, true, false)), [Some(2)]);
+    assert!(flag_warnings(constant::CONST_STATE_INDEX, (false, false, true)).is_empty());
+    assert!(flag_warnings(constant::CONST_STATE_INDEX, (false, false, false)).is_empty());
+
+    // Warnings only, which leave the machine valid
+    let mut machine = constant::minimal();
+    machine.states[constant::START_STATE_INDEX].replace = true;
+    assert!(validate_flag_consistency(&machine).iter().all(|issue| issue.level == Level::Warning));
+}
+
+
+#[test]
+fn machines_without_states_are_errors() {
+    let mut machine = constant::minimal();
+    assert!(check_has_states("Machine", &machine).is_ok());
+
+    machine.states.clear();
+    let error = check_has_states("Relay machine", &machine).unwrap_err();
+    assert!(error.contains("Relay machine has no states"), "{}", error);
+}
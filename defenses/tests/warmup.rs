@@ -0,0 +1,62 @@
+// Tests for the warm-up state (src/warmup.rs): the old states keep their
+// transitions, shifted to their new indices, behind a WARMUP state 0 that
+// moves on to the old start, and the machines still validate.
+
+use maybenot::{event::Event, dist::DistType};
+
+use raising_the_bar::PACKET_SIZE;
+use raising_the_bar::scrambler::{generate_machine_one, generate_machine_two, TrailDist, Direction};
+use raising_the_bar::validate::{validate_machine, unreachable_states};
+use raising_the_bar::warmup::{with_warmup, WARMUP_STATE_INDEX};
+
+
+#[test]
+fn warmup_shifts_the_old_states() {
+    let machine = generate_machine_one(160.0, 160.0, 500.0, (400.0, 1000.0), (400.0, 1000.0), TrailDist::Uniform, PACKET_SIZE);
+    let warmed = with_warmup(machine.clone(), 10);
+    assert_eq!(warmed.states.len(), machine.states.len() + 1);
+
+    // Every old state is unchanged but for its targets, one further on (and
+    // never back to the WARMUP state), to STATECANCEL and STATEEND included
+    for (old, new) in machine.states.iter().zip(&warmed.states[WARMUP_STATE_INDEX + 1..]) {
+        assert_eq!(new.next_state.len(), old.next_state.len());
+        for (event, probabilities) in &old.next_state {
+            assert_eq!(new.next_state[event][WARMUP_STATE_INDEX], 0.0);
+            assert_eq!(new.next_state[event][WARMUP_STATE_INDEX + 1..], probabilities[..]);
+        }
+        assert_eq!((new.timeout, new.action, new.limit), (old.timeout, old.action, old.limit));
+    }
+
+    // The WARMUP state counts the real packets sent, and then moves on to the
+    // old start
+    let warmup = &warmed.states[WARMUP_STATE_INDEX];
+    assert_eq!((warmup.limit.dist, warmup.limit.param1, warmup.limit_includes_nonpadding), (DistType::Uniform, 10.0, true));
+    assert_eq!(warmup.action.dist, DistType::None);
+    assert_eq!(warmup.next_state.keys().collect::<Vec<_>>(), [&Event::LimitReached]);
+    assert_eq!(warmup.next_state[&Event::LimitReached][WARMUP_STATE_INDEX + 1], 1.0);
+    assert_eq!(warmup.next_state[&Event::LimitReached].iter().sum::<f64>(), 1.0);
+
+    assert_eq!((warmed.allowed_padding_bytes, warmed.max_padding_frac), (machine.allowed_padding_bytes, machine.max_padding_frac));
+}
+
+
+#[test]
+fn warmed_machines_validate() {
+    for machine in [
+        generate_machine_one(160.0, 160.0, 500.0, (400.0, 1000.0), (400.0, 1000.0), TrailDist::Uniform, PACKET_SIZE),
+        generate_machine_two(500.0, Direction::Up),
+    ] {
+        for count in [1, 10, 1000] {
+            let warmed = with_warmup(machine.clone(), count);
+            assert!(warmed.validate().is_ok(), "{}", count);
+            assert_eq!(validate_machine(&warmed), validate_machine(&machine).into_iter().map(|mut issue| {
+                issue.state = issue.state.map(|state| state + 1);
+                issue
+            }).collect::<Vec<_>>());
+            assert!(unreachable_states(&warmed).is_empty());
+        }
+
+        // No warm-up leaves the machine as it is
+        assert_eq!(with_warmup(machine.clone(), 0), machine);
+    }
+}
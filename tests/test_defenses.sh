@@ -10,6 +10,37 @@ check_output () {
   fi
 }
 
+check_differs () {
+  if grep -F -f "$2" "$1" > /dev/null; then
+    printf "\nTest failed! Output machine unchanged\n" 1>&2
+    rm "$2"
+    exit
+  else
+    printf "OK\n"
+  fi
+}
+
+# Print the limit of each COUNT state of a client machine (all but its last
+# state, SEND), decoding the (zlib-compressed) maybenot v1 serialization format
+count_limits () {
+  python3 -c '
+import sys, zlib, struct
+buf = zlib.decompress(bytes.fromhex(sys.argv[1]))
+num_states = struct.unpack_from("<H", buf, 35)[0]
+state_size = 3 * 34 + 4 + 8 * (num_states + 2) * 8
+print(*[struct.unpack_from("<d", buf, 37 + i * state_size + 34 + 2)[0] for i in range(num_states - 1)])
+' "$1"
+}
+
+check_limits () {
+  if [ "$(count_limits "$1")" = "$2" ]; then
+    printf "OK\n"
+  else
+    printf "\nTest failed! COUNT state limits %s, expected %s\n" "$(count_limits "$1")" "$2" 1>&2
+    exit
+  fi
+}
+
 cd ../defenses
 
 # Load provided machines from file
@@ -59,37 +90,51 @@ printf "Adapted FRONT 6500, 2... "
 ./target/release/adapted_front  2 6500 30 | tr -d '[:space:]' > $result
 check_output "$provided" "$result"
 
-# Test best Adapted RegulaTor configurations
+# Test best Adapted RegulaTor configurations (--trigger sent: the relay
+# machines of the paper)
 printf "Adapted RegulaTor 500, 0.75... "
-./target/release/adapted_regulator  500 0.75 4 20 | tr -d '[:space:]' > $result
+./target/release/adapted_regulator  500 0.75 4 20 --trigger sent | tr -d '[:space:]' > $result
 check_output "$provided" "$result"
 printf "Adapted RegulaTor 500, 0.45... "
-./target/release/adapted_regulator  500 0.45 4 20 | tr -d '[:space:]' > $result
+./target/release/adapted_regulator  500 0.45 4 20 --trigger sent | tr -d '[:space:]' > $result
 check_output "$provided" "$result"
 printf "Adapted RegulaTor 500, 0.25... "
-./target/release/adapted_regulator  500 0.25 4 20 | tr -d '[:space:]' > $result
+./target/release/adapted_regulator  500 0.25 4 20 --trigger sent | tr -d '[:space:]' > $result
 check_output "$provided" "$result"
 
 printf "Adapted RegulaTor 1400, 0.95... "
-./target/release/adapted_regulator 1400 0.95 4 20 | tr -d '[:space:]' > $result
+./target/release/adapted_regulator 1400 0.95 4 20 --trigger sent | tr -d '[:space:]' > $result
 check_output "$provided" "$result"
 printf "Adapted RegulaTor 1300, 0.95... "
-./target/release/adapted_regulator 1300 0.95 4 20 | tr -d '[:space:]' > $result
+./target/release/adapted_regulator 1300 0.95 4 20 --trigger sent | tr -d '[:space:]' > $result
 check_output "$provided" "$result"
 printf "Adapted RegulaTor 1000, 0.95... "
-./target/release/adapted_regulator 1000 0.95 4 20 | tr -d '[:space:]' > $result
+./target/release/adapted_regulator 1000 0.95 4 20 --trigger sent | tr -d '[:space:]' > $result
 check_output "$provided" "$result"
 
 printf "Adapted RegulaTor 1500, 0.85... "
-./target/release/adapted_regulator 1500 0.85 4 20 | tr -d '[:space:]' > $result
+./target/release/adapted_regulator 1500 0.85 4 20 --trigger sent | tr -d '[:space:]' > $result
 check_output "$provided" "$result"
 printf "Adapted RegulaTor 1600, 0.95... "
-./target/release/adapted_regulator 1600 0.95 4 20 | tr -d '[:space:]' > $result
+./target/release/adapted_regulator 1600 0.95 4 20 --trigger sent | tr -d '[:space:]' > $result
 check_output "$provided" "$result"
 printf "Adapted RegulaTor 1900, 0.95... "
-./target/release/adapted_regulator 1900 0.95 4 20 | tr -d '[:space:]' > $result
+./target/release/adapted_regulator 1900 0.95 4 20 --trigger sent | tr -d '[:space:]' > $result
+check_output "$provided" "$result"
+
+# Test Adapted RegulaTor defaults (the paper's parameters, see defaults.rs)
+printf "Adapted RegulaTor defaults... "
+./target/release/adapted_regulator --trigger sent | tr -d '[:space:]' > $result
 check_output "$provided" "$result"
 
+# Test Adapted RegulaTor client limit
+printf "Adapted RegulaTor client limit 2.0... "
+./target/release/adapted_regulator  500 0.75 4 20 2.0 --trigger sent | tr -d '[:space:]' > $result
+check_output "$provided" "$result"
+printf "Adapted RegulaTor client limit 3.0... "
+client=$(./target/release/adapted_regulator 500 0.75 4 20 3.0 | sed -n 's/^Client machine: \([0-9a-f]*\) ([0-9]*)$/\1/p')
+check_limits "$client" "3.0 3.0 3.0 3.0"
+
 # Test best Scrambler configurations
 printf "Scrambler 160, 500... "
 ./target/release/scrambler 160  500 400 1000 | tr -d '[:space:]' > $result
@@ -121,6 +166,39 @@ printf "Scrambler 120, 1500... "
 ./target/release/scrambler 120 1500 400 1000 | tr -d '[:space:]' > $result
 check_output "$provided" "$result"
 
+# Test packet size option
+printf "Scrambler packet size 1500... "
+./target/release/scrambler 160  500 400 1000 --packet-size 1500 | tr -d '[:space:]' > $result
+check_output "$provided" "$result"
+printf "Scrambler packet size 1400... "
+./target/release/scrambler 160  500 400 1000 --packet-size 1400 | tr -d '[:space:]' > $result
+check_differs "$provided" "$result"
+printf "Adapted FRONT packet size 1500... "
+./target/release/adapted_front 12 4000 30 --packet-size 1500 | tr -d '[:space:]' > $result
+check_output "$provided" "$result"
+
+# Test asymmetric Scrambler trailing counts
+printf "Scrambler symmetric trail overrides... "
+./target/release/scrambler 160  500 400 1000 --left-trail-min 400 --right-trail-max 1000 | tr -d '[:space:]' > $result
+check_output "$provided" "$result"
+printf "Scrambler asymmetric trail overrides... "
+./target/release/scrambler 160  500 400 1000 --left-trail-min 200 --left-trail-max 600 | tr -d '[:space:]' > $result
+check_differs "$provided" "$result"
+
+# Test separate MIN state interval
+printf "Scrambler MIN interval 160... "
+./target/release/scrambler 160  500 400 1000 --min-interval 160 | tr -d '[:space:]' > $result
+check_output "$provided" "$result"
+printf "Scrambler MIN interval 120 (L/R interval 160)... "
+./target/release/scrambler 160 1100 400 1000 --min-interval 120 | tr -d '[:space:]' > $result
+check_differs "$provided" "$result"
+printf "Scrambler MIN interval 120 (L/R interval 120)... "
+./target/release/scrambler 120 1100 400 1000 --min-interval 120 | tr -d '[:space:]' > $result
+check_output "$provided" "$result"
+printf "Scrambler L/R interval 120 (MIN interval 160)... "
+./target/release/scrambler 120 1100 400 1000 --min-interval 160 | tr -d '[:space:]' > $result
+check_differs "$provided" "$result"
+
 # delete tmp results file
 rm "$result"
 
@@ -0,0 +1,1167 @@
+#!/bin/sh
+
+check_success () {
+  if "$@" > /dev/null 2>&1; then
+    printf "OK\n"
+  else
+    printf "\nTest failed! Command exited with an error: %s\n" "$*" 1>&2
+    exit 1
+  fi
+}
+
+check_failure () {
+  if "$@" > /dev/null 2>&1; then
+    printf "\nTest failed! Command should have exited with an error: %s\n" "$*" 1>&2
+    exit 1
+  else
+    printf "OK\n"
+  fi
+}
+
+check_contains () {
+  if printf "%s" "$1" | grep -F -e "$2" > /dev/null; then
+    printf "OK\n"
+  else
+    printf "\nTest failed! Output does not contain: %s\n" "$2" 1>&2
+    exit 1
+  fi
+}
+
+check_equal () {
+  if [ "$1" = "$2" ]; then
+    printf "OK\n"
+  else
+    printf "\nTest failed! Expected \"%s\", got \"%s\"\n" "$2" "$1" 1>&2
+    exit 1
+  fi
+}
+
+# Extract the machine string from a "<label>: <machine> (<length>)" line
+machine_from () {
+  printf "%s\n" "$1" | sed -n 's/^.*: \([0-9a-f]*\) ([0-9]*)$/\1/p' | head -n 1
+}
+
+# Print the indices of states with a blocking action in a machine, decoding
+# the (zlib-compressed) maybenot v1 serialization format
+blocking_states () {
+  python3 -c '
+import sys, zlib, struct
+buf = zlib.decompress(bytes.fromhex(sys.argv[1]))
+num_states = struct.unpack_from("<H", buf, 35)[0]
+state_size = 3 * 34 + 4 + 8 * (num_states + 2) * 8
+for i in range(num_states):
+    if buf[37 + i * state_size + 3 * 34]:
+        print(i)
+' "$1"
+}
+
+# Print "<type> <param1> <param2> <start> <max>" of a distribution (action,
+# limit, or timeout) of a state in a machine, decoding as above
+state_dist () {
+  python3 -c '
+import sys, zlib, struct
+buf = zlib.decompress(bytes.fromhex(sys.argv[1]))
+num_states = struct.unpack_from("<H", buf, 35)[0]
+state_size = 3 * 34 + 4 + 8 * (num_states + 2) * 8
+offset = 37 + int(sys.argv[2]) * state_size + ["action", "limit", "timeout"].index(sys.argv[3]) * 34
+print(struct.unpack_from("<H", buf, offset)[0], *struct.unpack_from("<4d", buf, offset + 2))
+' "$1" "$2" "$3"
+}
+
+# Print the events with transitions out of a state in a machine, one per line
+# in maybenot event order, decoding as above
+state_events () {
+  python3 -c '
+import sys, zlib, struct
+buf = zlib.decompress(bytes.fromhex(sys.argv[1]))
+num_states = struct.unpack_from("<H", buf, 35)[0]
+state_size = 3 * 34 + 4 + 8 * (num_states + 2) * 8
+offset = 37 + int(sys.argv[2]) * state_size + 3 * 34 + 4
+events = ["NonPaddingRecv", "PaddingRecv", "NonPaddingSent", "PaddingSent", "BlockingBegin", "BlockingEnd", "LimitReached", "UpdateMTU"]
+for j, event in enumerate(events):
+    if any(struct.unpack_from("<%dd" % (num_states + 2), buf, offset + j * (num_states + 2) * 8)):
+        print(event)
+' "$1" "$2"
+}
+
+# Clear a flag of a state in a machine (0 for action_is_block, 1 for bypass, 2
+# for replace), re-encoding it in the format decoded above
+clear_state_flag () {
+  python3 -c '
+import sys, zlib, struct
+buf = bytearray(zlib.decompress(bytes.fromhex(sys.argv[1])))
+num_states = struct.unpack_from("<H", buf, 35)[0]
+state_size = 3 * 34 + 4 + 8 * (num_states + 2) * 8
+buf[37 + int(sys.argv[2]) * state_size + 3 * 34 + int(sys.argv[3])] = 0
+print(zlib.compress(bytes(buf)).hex())
+' "$1" "$2" "$3"
+}
+
+# Clear the action_is_block flag of a state in a machine
+clear_block_flag () {
+  clear_state_flag "$1" "$2" 0
+}
+
+# Print "<max blocking frac> <allowed blocked microsec>" of a machine, decoding
+# the header of the format above
+blocking_budget () {
+  python3 -c '
+import sys, zlib, struct
+buf = zlib.decompress(bytes.fromhex(sys.argv[1]))
+print(struct.unpack_from("<d", buf, 26)[0], struct.unpack_from("<Q", buf, 18)[0])
+' "$1"
+}
+
+cd ../defenses
+
+# Compile code
+cargo build --release
+
+# A machine with a single state and no transitions (from the maybenot docs)
+noop="789cedca2101000000c230e85f1a8387009f9e351d051503ca0003"
+
+# A machine with two states, where state 1 is never entered
+orphan="789cedd0410d0000084251b598fddb18411a706363e36d10e077518379b9c585806fe907700f0262"
+
+# Test machine linter
+printf "Lint generated machine... "
+check_success ./target/release/lint "$(machine_from "$(./target/release/constant)")"
+printf "Lint no-op machine... "
+check_contains "$(./target/release/lint $noop)" "State 0: warning: no outgoing transitions"
+printf "Lint invalid machine... "
+check_failure ./target/release/lint "not a machine"
+printf "Lint orphaned state... "
+check_contains "$(./target/release/lint $orphan)" "State 1: warning: unreachable from state 0"
+printf "Lint infinite padding action... "
+check_contains "$(./target/release/lint "$(clear_block_flag "$(machine_from "$(./target/release/constant)")" 1)")" "State 1: error: padding action Uniform [inf, inf] is infinite, only blocking actions can be"
+printf "Lint infinite padding action fails... "
+check_failure ./target/release/lint "$(clear_block_flag "$(machine_from "$(./target/release/constant)")" 1)"
+printf "Lint blocking without bypass... "
+check_contains "$(./target/release/lint "$(clear_state_flag "$(machine_from "$(./target/release/constant)")" 1 1)")" "State 1: warning: blocking action without bypass also holds back the machine's own bypass padding"
+printf "Lint bypass padding without replace... "
+check_contains "$(./target/release/lint "$(clear_state_flag "$(machine_from "$(./target/release/constant)")" 2 2)")" "State 2: warning: padding action with bypass but not replace"
+printf "Lint infinite blocking actions... "
+check_contains "$(./target/release/lint "$(machine_from "$(./target/release/scrambler 160 500 400 1000 | grep "^Machine 2")")")" "3 states, 0 error(s), 0 warning(s)"
+
+# Test reachability of generated machines
+printf "Scrambler reachability... "
+check_contains "$(./target/release/lint "$(machine_from "$(./target/release/scrambler 160 500 400 1000)")")" "7 states, 0 error(s), 0 warning(s)"
+printf "Scrambler --warn-unreachable... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --warn-unreachable 2>&1 > /dev/null)" ""
+printf "Adapted RegulaTor reachability... "
+check_contains "$(./target/release/lint "$(machine_from "$(./target/release/adapted_regulator 500 0.75 4 20)")")" "89 states, 0 error(s), 1 warning(s)"
+printf "Adapted RegulaTor --warn-unreachable... "
+check_equal "$(./target/release/adapted_regulator 500 0.75 4 20 --warn-unreachable 2>&1 > /dev/null)" ""
+printf "Adapted RegulaTor --verbose-states... "
+table=$(./target/release/adapted_regulator 500 0.75 4 20 --verbose-states 2>&1 > /dev/null)
+check_contains "$(printf "%s\n" "$table" | sed -n 2p)" "    2  [  0.000000,   0.040232)    0.020116     497.115"
+printf "Adapted RegulaTor contiguous intervals... "
+check_equal "$(printf "%s\n" "$table" | grep '\[' | tr -d '[,)' | awk 'NR > 1 && $2 != prev { gap = 1 } { prev = $3 } END { print gap ? "gap" : "contiguous" }')" "contiguous"
+# Rate model: RD^t is R at t = 0 (narrow intervals sample it almost there),
+# and decays towards 0, ending the table at 1 packet/sec
+printf "Rate model at t = 0... "
+check_equal "$(./target/release/adapted_regulator 500 0.75 4 1 --verbose-states 2>&1 > /dev/null | grep '\[' | tr -d '[,)' | head -n 1 | awk '{ printf "%.0f", $5 }')" "500"
+printf "Rate model at large t... "
+check_equal "$(printf "%s\n" "$table" | grep '\[' | tr -d '[,)' | awk 'NR > 1 && $5 > prev { up = 1 } { prev = $5 } END { print (!up && prev <= 1.0) ? "decays" : "does not decay" }')" "decays"
+printf "Adapted RegulaTor decay horizon (500, 0.75)... "
+check_contains "$(printf "%s\n" "$table" | tail -n 1)" "Decay horizon: 21.602 sec"
+printf "Adapted RegulaTor decay horizon (1000, 0.5)... "
+check_contains "$(./target/release/adapted_regulator 1000 0.5 4 20 --verbose-states 2>&1 > /dev/null | tail -n 1)" "Decay horizon: 9.966 sec"
+printf "Adapted RegulaTor --verbose-states output... "
+check_equal "$(./target/release/adapted_regulator 500 0.75 4 20 --verbose-states 2> /dev/null)" "$(./target/release/adapted_regulator 500 0.75 4 20)"
+
+# Test --progress
+printf "Adapted RegulaTor --progress lines... "
+check_equal "$(./target/release/adapted_regulator 500 0.75 4 1 --progress 2>&1 > /dev/null | grep -c '^Progress: ')" "1"
+printf "Adapted RegulaTor --progress summary... "
+relay_length=$(./target/release/adapted_regulator 500 0.75 4 20 | grep Relay | sed 's/.*(\(.*\))/\1/')
+check_equal "$(./target/release/adapted_regulator 500 0.75 4 20 --progress 2>&1 > /dev/null)" "Relay machine: 89 states, serialized length $relay_length, decay horizon 21.602 sec"
+printf "Adapted RegulaTor --progress output... "
+check_equal "$(./target/release/adapted_regulator 500 0.75 4 20 --progress 2> /dev/null)" "$(./target/release/adapted_regulator 500 0.75 4 20)"
+printf "Adapted RegulaTor --progress with --count-only... "
+check_failure ./target/release/adapted_regulator 500 0.75 4 20 --progress --count-only
+
+# Test the relay START state triggers (the relay machine is printed first)
+for binary in "adapted_regulator 500 0.75 4 20" "regulator 500 0.75 2 4 20"; do
+  printf "%s --trigger sent... " "${binary%% *}"
+  check_equal "$(state_events "$(machine_from "$(./target/release/$binary --trigger sent)")" 0)" "NonPaddingSent"
+  printf "%s --trigger recv... " "${binary%% *}"
+  check_equal "$(state_events "$(machine_from "$(./target/release/$binary --trigger recv)")" 0)" "NonPaddingRecv"
+  printf "%s --trigger both... " "${binary%% *}"
+  check_equal "$(state_events "$(machine_from "$(./target/release/$binary --trigger both)")" 0)" "$(printf "NonPaddingRecv\nNonPaddingSent")"
+  printf "%s default trigger... " "${binary%% *}"
+  check_equal "$(./target/release/$binary --trigger both)" "$(./target/release/$binary)"
+  printf "%s --trigger unknown... " "${binary%% *}"
+  check_failure ./target/release/$binary --trigger none
+done
+
+# Test that --count-only counts the states of the machines it would generate,
+# as listed by --annotate (from states.len())
+for config in "500 0.75 4 20" "500 0.25 4 20" "1900 0.95 4 20" "1000 0.5 2.5 7" "500 0.75 0.5 20 3"; do
+  printf "Adapted RegulaTor --count-only %s... " "$config"
+  check_equal "$(./target/release/adapted_regulator $config --count-only)" "$(./target/release/adapted_regulator $config --annotate | sed -n 's/^# \(Relay\|Client\) machine: \([0-9]*\) states$/\1 states: \2/p')"
+done
+printf "Adapted RegulaTor --count-only --hash... "
+check_failure ./target/release/adapted_regulator 500 0.75 4 20 --count-only --hash
+printf "Adapted RegulaTor --count-only --report... "
+check_failure ./target/release/adapted_regulator 500 0.75 4 20 --count-only --report
+
+# Test --units-check, the self-test of the SEND state timeouts (usec) against
+# the initial rate (packets/sec)
+printf "Adapted RegulaTor --units-check... "
+check_equal "$(./target/release/adapted_regulator 500 0.75 4 20 --units-check)" "Units check: the first SEND state pads every 2011.608 usec, about 1e6 / initial rate (times the time scale) = 2000 usec: OK"
+printf "RegulaTor --units-check... "
+check_contains "$(./target/release/regulator 1000 0.95 3.0 4 100 --units-check)" "= 1000 usec: OK"
+printf "RegulaTor --units-check --time-scale... "
+check_contains "$(./target/release/regulator 500 0.75 2 4 20 --units-check --time-scale 2)" "= 4000 usec: OK"
+
+# The relay machine of 500 0.75 2 4 20 has 89 states; 37 packets per state is
+# the fewest that fit it in 50 (36 take 51)
+printf "RegulaTor --max-states above the relay machine... "
+check_equal "$(./target/release/regulator 500 0.75 2 4 20 --max-states 89)" "$(./target/release/regulator 500 0.75 2 4 20)"
+printf "RegulaTor --max-states below the relay machine... "
+check_contains "$(./target/release/regulator 500 0.75 2 4 20 --max-states 50 2>&1)" "The relay machine would take 89 states, more than --max-states 50"
+printf "RegulaTor --max-states --coarsen... "
+check_equal "$(./target/release/regulator 500 0.75 2 4 20 --max-states 50 --coarsen 2>&1 > /dev/null)" "Coarsened packets per state from 20 to 37, for a relay machine of at most 50 states"
+printf "RegulaTor --coarsen machines... "
+check_equal "$(./target/release/regulator 500 0.75 2 4 20 --max-states 50 --coarsen 2> /dev/null)" "$(./target/release/regulator 500 0.75 2 4 37)"
+printf "RegulaTor --coarsen fewest packets per state... "
+check_contains "$(./target/release/regulator 500 0.75 2 4 36 --max-states 50 2>&1)" "would take 51 states"
+printf "RegulaTor --coarsen without --max-states... "
+check_failure ./target/release/regulator 500 0.75 2 4 20 --coarsen
+printf "RegulaTor --max-states without a SEND state... "
+check_failure ./target/release/regulator 500 0.75 2 4 20 --max-states 2 --coarsen
+
+# The default Adapted RegulaTor relay machine has 89 states; 36 packets per
+# state is the fewest that fit it in 50 (35 take 52)
+printf "Adapted RegulaTor --max-states above the relay machine... "
+check_equal "$(./target/release/adapted_regulator --max-states 89)" "$(./target/release/adapted_regulator)"
+printf "Adapted RegulaTor --max-states below the relay machine... "
+check_contains "$(./target/release/adapted_regulator --max-states 50 2>&1)" "The relay machine would take 89 states, more than --max-states 50"
+printf "Adapted RegulaTor --max-states --coarsen... "
+check_equal "$(./target/release/adapted_regulator --max-states 50 --coarsen 2>&1 > /dev/null)" "Coarsened packets per state from 20 to 36, for a relay machine of at most 50 states"
+printf "Adapted RegulaTor --coarsen machines... "
+check_equal "$(./target/release/adapted_regulator --max-states 50 --coarsen 2> /dev/null)" "$(./target/release/adapted_regulator 500 0.75 4 36)"
+printf "Adapted RegulaTor --coarsen without --max-states... "
+check_failure ./target/release/adapted_regulator --coarsen
+
+# Test hostile arguments, which must fail with an error naming the argument or
+# the cap, not with a parse error's debug output or a hang
+printf "Adapted RegulaTor huge initial rate... "
+check_contains "$(./target/release/adapted_regulator 1e15 0.75 4 20 2>&1)" "more than the cap of 10000"
+printf "Adapted FRONT zero states... "
+check_failure ./target/release/adapted_front 12 4000 0
+printf "Adapted FRONT scientific states... "
+check_contains "$(./target/release/adapted_front 12 4000 1e3 2>&1)" "num states must be a whole number, got 1e3"
+printf "Scrambler --pairs with whitespace... "
+check_success ./target/release/scrambler 160 500 400 1000 --pairs " 2 "
+
+# Test the LimitReached target of the last relay SEND state (state 88), which
+# ends the machine unless --loop-surge restarts the surge at state 2
+last_send_state () {
+  ./target/release/decode "$(./target/release/adapted_regulator 500 0.75 4 20 "$@" --ffi | sed -n 1p)" | sed -n '/^  State 88:$/,$p' | grep "LimitReached"
+}
+printf "Adapted RegulaTor last SEND state... "
+check_equal "$(last_send_state)" "      LimitReached -> END (1)"
+printf "Adapted RegulaTor --loop-surge... "
+check_equal "$(last_send_state --loop-surge)" "      LimitReached -> 2 (1)"
+printf "Adapted RegulaTor --loop-surge earlier states unchanged... "
+check_equal "$(./target/release/decode "$(./target/release/adapted_regulator 500 0.75 4 20 --loop-surge --ffi | sed -n 1p)" | sed -n '1,/^  State 88:$/p')" "$(./target/release/decode "$(./target/release/adapted_regulator 500 0.75 4 20 --ffi | sed -n 1p)" | sed -n '1,/^  State 88:$/p')"
+
+# Test the constant defense interval distributions (DistType 1 = Uniform,
+# 6 = Pareto, 8 = Weibull)
+printf "Constant --dist uniform... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/constant 5000 --dist uniform)")" 2 timeout)" "1 5000.0 5000.0 0.0 0.0"
+printf "Constant --dist exponential... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/constant 5000 --dist exponential)")" 2 timeout)" "8 5000.0 1.0 0.0 0.0"
+printf "Constant --dist pareto... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/constant 5000 --dist pareto)")" 2 timeout)" "6 2500.0 2.0 0.0 0.0"
+printf "Constant --dist pareto --shape 3... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/constant 6000 --dist pareto --shape 3)")" 2 timeout)" "6 4000.0 3.0 0.0 0.0"
+printf "Constant --dist pareto --shape 1... "
+check_failure ./target/release/constant --dist pareto --shape 1
+printf "Constant --dist uniform output... "
+check_equal "$(./target/release/constant --dist uniform)" "$(./target/release/constant)"
+printf "Constant --jitter-frac... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/constant 5000 --jitter-frac 0.1)")" 2 timeout)" "1 4500.0 5500.0 0.0 0.0"
+printf "Constant --jitter-frac 0... "
+check_equal "$(./target/release/constant 5000 --jitter-frac 0)" "$(./target/release/constant 5000)"
+printf "Constant --jitter-frac --dist pareto... "
+check_failure ./target/release/constant --jitter-frac 0.1 --dist pareto
+
+# Test the constant defense overhead solver: the bandwidth of the solved
+# interval must give the requested overhead over the baseline
+interval_from () {
+  printf "%s\n" "$1" | sed -n 's/^Send interval: \([0-9.e+-]*\) usec$/\1/p'
+}
+overhead_of () {
+  awk -v interval="$1" -v size="$2" -v mbps="$3" -v target="$4" 'BEGIN {
+    overhead = (size * 8 / interval - mbps) / mbps
+    diff = overhead - target
+    if (diff < 0) diff = -diff
+    print (diff < 1e-9) ? "ok" : overhead
+  }'
+}
+printf "Constant --target-overhead interval... "
+check_equal "$(interval_from "$(./target/release/constant --target-overhead 0.25 --baseline-mbps 2.4)")" "4000"
+printf "Constant --target-overhead machine... "
+check_equal "$(machine_from "$(./target/release/constant --target-overhead 0.25 --baseline-mbps 2.4)")" "$(machine_from "$(./target/release/constant 4000)")"
+for config in "0.1 2.7 1500" "0.5 1.2 1200" "0 8 1500" "3.7 0.35 600"; do
+  set -- $config
+  printf "Constant --target-overhead %s --baseline-mbps %s --packet-size %s... " "$1" "$2" "$3"
+  check_equal "$(overhead_of "$(interval_from "$(./target/release/constant --target-overhead "$1" --baseline-mbps "$2" --packet-size "$3")")" "$3" "$2" "$1")" "ok"
+done
+printf "Constant --target-overhead negative... "
+check_failure ./target/release/constant --target-overhead -0.1 --baseline-mbps 2.4
+printf "Constant --target-overhead without baseline... "
+check_failure ./target/release/constant --target-overhead 0.25
+printf "Constant --target-overhead with send interval... "
+check_failure ./target/release/constant 4000 --target-overhead 0.25 --baseline-mbps 2.4
+
+# Test the constant defense trigger events (START state transitions)
+printf "Constant --trigger sent... "
+check_equal "$(state_events "$(machine_from "$(./target/release/constant --trigger sent)")" 0)" "NonPaddingSent"
+printf "Constant --trigger recv... "
+check_equal "$(state_events "$(machine_from "$(./target/release/constant --trigger recv)")" 0)" "NonPaddingRecv"
+printf "Constant --trigger both... "
+check_equal "$(state_events "$(machine_from "$(./target/release/constant --trigger both)")" 0)" "$(printf "NonPaddingRecv\nNonPaddingSent")"
+printf "Constant default trigger... "
+check_equal "$(./target/release/constant --trigger both)" "$(./target/release/constant)"
+printf "Constant --trigger stdin... "
+check_equal "$(echo 5000 | ./target/release/constant --stdin --trigger sent)" "$(./target/release/constant 5000 --trigger sent)"
+printf "Constant --trigger unknown... "
+check_failure ./target/release/constant --trigger none
+
+# Test the constant defense without blocking
+printf "Constant --pad-only blocking states... "
+check_equal "$(blocking_states "$(machine_from "$(./target/release/constant --pad-only)")")" ""
+printf "Constant --pad-only machine... "
+check_equal "$(./target/release/constant --pad-only --dry-run)" "Machine: OK (2 states)"
+printf "Constant --pad-only interval... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/constant 5000 --pad-only)")" 1 timeout)" "1 5000.0 5000.0 0.0 0.0"
+printf "Constant --pad-only stdin... "
+check_equal "$(echo 5000 | ./target/release/constant --stdin --pad-only)" "$(./target/release/constant 5000 --pad-only)"
+printf "Constant --pad-only --maybenot-v2... "
+check_failure ./target/release/constant --pad-only --maybenot-v2
+
+# Test the constant defense with an interval per direction
+asymmetric=$(./target/release/constant --asymmetric --up-interval 8000 --down-interval 1000)
+printf "Constant --asymmetric machines... "
+check_equal "$(./target/release/constant --asymmetric --dry-run)" "$(printf "Client machine: OK (3 states)\nRelay machine: OK (3 states)")"
+printf "Constant --asymmetric up interval... "
+check_equal "$(state_dist "$(machine_from "$asymmetric")" 2 timeout)" "1 8000.0 8000.0 0.0 0.0"
+printf "Constant --asymmetric down interval... "
+check_equal "$(state_dist "$(machine_from "$(printf "%s\n" "$asymmetric" | grep "^Relay machine")")" 2 timeout)" "1 1000.0 1000.0 0.0 0.0"
+printf "Constant --asymmetric default intervals... "
+check_equal "$(./target/release/constant 5000 --asymmetric --ffi)" "$(printf "%s\n%s" "$(./target/release/constant 5000 --ffi)" "$(./target/release/constant 5000 --ffi)")"
+printf "Constant --asymmetric --ms... "
+check_equal "$(./target/release/constant --asymmetric --ms --up-interval 8 --down-interval 1)" "$asymmetric"
+both_sides=$(./target/release/constant --both-sides --up-interval 8000 --down-interval 1000)
+printf "Constant --both-sides... "
+check_equal "$both_sides" "$asymmetric"
+printf "Constant --both-sides two machines... "
+check_equal "$(printf "%s\n" "$both_sides" | grep -c "^\(Client\|Relay\) machine: ")" "2"
+printf "Constant --both-sides client timeout... "
+check_equal "$(state_dist "$(machine_from "$both_sides")" 2 timeout)" "1 8000.0 8000.0 0.0 0.0"
+printf "Constant --both-sides relay timeout... "
+check_equal "$(state_dist "$(machine_from "$(printf "%s\n" "$both_sides" | grep "^Relay machine")")" 2 timeout)" "1 1000.0 1000.0 0.0 0.0"
+printf "Constant --minimal... "
+check_contains "$(./target/release/lint "$(machine_from "$(./target/release/constant --minimal)")")" "3 states, 0 error(s), 0 warning(s)"
+printf "Constant --minimal with a send interval... "
+check_failure ./target/release/constant 4000 --minimal
+printf "Constant --up-interval without --asymmetric... "
+check_failure ./target/release/constant --up-interval 8000
+printf "Constant --asymmetric --stdin... "
+check_failure sh -c 'echo 4000 | ./target/release/constant --stdin --asymmetric'
+
+# Test that debug traces (--features logging) never reach stdout
+printf "RUST_LOG=debug output... "
+check_equal "$(RUST_LOG=debug ./target/release/adapted_regulator 1400 0.95 4 20 2> /dev/null)" "$(./target/release/adapted_regulator 1400 0.95 4 20)"
+
+# Test BuFLO
+buflo=$(machine_from "$(./target/release/buflo 10 4000)")
+printf "BuFLO machine... "
+check_contains "$(./target/release/lint "$buflo")" "3 states, 0 error(s), 0 warning(s)"
+printf "BuFLO minimum packets... "
+check_equal "$(state_dist "$buflo" 2 limit)" "1 2500.0 2500.0 0.0 0.0"
+printf "BuFLO minimum packets rounded up... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/buflo 0.001 4000)")" 2 limit)" "1 1.0 1.0 0.0 0.0"
+printf "BuFLO blocking states... "
+check_equal "$(blocking_states "$buflo" | tr '\n' ' ')" "0 1 "
+
+# Test Scrambler minimum count validation
+printf "Scrambler minimum count 0... "
+check_failure ./target/release/scrambler 160 0 400 1000
+printf "Scrambler minimum count 5.5 warning... "
+check_equal "$(./target/release/scrambler 160 5.5 400 1000 2>&1 > /dev/null)" "Warning: minimum count 5.5 rounded to 6"
+printf "Scrambler minimum count 5.5 rounded... "
+check_equal "$(./target/release/scrambler 160 5.5 400 1000 2> /dev/null)" "$(./target/release/scrambler 160 6 400 1000)"
+
+# Test --strict, which rejects fractional counts instead of rounding them
+printf "Scrambler --strict fractional minimum count... "
+check_failure ./target/release/scrambler 160 3.5 400 1000 --strict
+printf "Scrambler --strict integral minimum count... "
+check_equal "$(./target/release/scrambler 160 3.0 400 1000 --strict 2>&1)" "$(./target/release/scrambler 160 3 400 1000)"
+printf "Scrambler --strict fractional trailing count... "
+check_failure ./target/release/scrambler 160 500 400 1000 --left-trail-max 1200.5 --strict
+printf "Adapted RegulaTor --strict client limit... "
+check_failure ./target/release/adapted_regulator 500 0.75 4 20 2.5 --strict
+printf "Constant --strict packet size... "
+check_failure ./target/release/constant --packet-size 1500.4 --strict
+printf "Adapted RegulaTor --strict leaves rates alone... "
+check_success ./target/release/adapted_regulator 500 0.75 4.5 20.5 --strict --count-only
+
+# Test Scrambler without blocking
+printf "Scrambler blocking states... "
+check_equal "$(blocking_states "$(machine_from "$(./target/release/scrambler 160 500 400 1000)")" | tr '\n' ' ')" "0 1 "
+printf "Scrambler --no-block blocking states... "
+check_equal "$(blocking_states "$(machine_from "$(./target/release/scrambler 160 500 400 1000 --no-block)")")" ""
+printf "Scrambler --no-block machines... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --no-block --dry-run)" "Machine 1: OK (4 states)"
+
+# Test Scrambler without the second L/R pair: the first pair (states 3 and 4)
+# keeps its other transitions, and no transition is left pointing past it
+printf "Scrambler --single-pair machines... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --single-pair --dry-run)" "Machine 1: OK (5 states)"
+printf "Scrambler --single-pair lint... "
+check_contains "$(./target/release/lint "$(machine_from "$(./target/release/scrambler 160 500 400 1000 --single-pair)")")" "5 states, 0 error(s), 0 warning(s)"
+printf "Scrambler first pair signal transitions... "
+check_equal "$(state_events "$(machine_from "$(./target/release/scrambler 160 500 400 1000)")" 3 | tr '\n' ' ')" "NonPaddingSent PaddingSent BlockingBegin LimitReached "
+printf "Scrambler --single-pair L_1 transitions... "
+check_equal "$(state_events "$(machine_from "$(./target/release/scrambler 160 500 400 1000 --single-pair)")" 3 | tr '\n' ' ')" "NonPaddingSent PaddingSent LimitReached "
+printf "Scrambler --single-pair R_1 transitions... "
+check_equal "$(state_events "$(machine_from "$(./target/release/scrambler 160 500 400 1000 --single-pair)")" 4 | tr '\n' ' ')" "NonPaddingSent PaddingSent LimitReached "
+printf "Scrambler --single-pair first pair... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/scrambler 160 500 400 1000 --single-pair)")" 4 limit)" "$(state_dist "$(machine_from "$(./target/release/scrambler 160 500 400 1000)")" 4 limit)"
+
+# Test --only: a single Scrambler machine, serialized alone
+printf "Scrambler --only 1... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --only 1)" "$(machine_from "$(./target/release/scrambler 160 500 400 1000)")"
+printf "Scrambler --only 2... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --only 2)" "$(./target/release/scrambler 160 500 400 1000 | grep "^Machine 2: " | cut -d' ' -f3)"
+printf "Scrambler --only 2 without a second machine... "
+check_failure ./target/release/scrambler 160 500 400 1000 --no-block --only 2
+printf "Scrambler --only unknown machine... "
+check_failure ./target/release/scrambler 160 500 400 1000 --only 3
+printf "Scrambler --only 2 --dry-run... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --only 2 --dry-run)" "$(./target/release/scrambler 160 500 400 1000 --dry-run | grep "^Machine 2: ")"
+printf "Scrambler --only with --hash... "
+check_failure ./target/release/scrambler 160 500 400 1000 --only 1 --hash
+
+# Test --pairs and --pair-reduction: each L/R pair divides the trailing counts
+# of the previous one, and BlockingBegin moves on to the next pair
+printf "Scrambler --pairs default... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --pairs 2 --pair-reduction 4)" "$(./target/release/scrambler 160 500 400 1000)"
+printf "Scrambler --pairs 1... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --pairs 1)" "$(./target/release/scrambler 160 500 400 1000 --single-pair)"
+printf "Scrambler --pairs 3 machines... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --pairs 3 --dry-run)" "$(printf "Machine 1: OK (9 states)\nMachine 2: OK (3 states)")"
+printf "Scrambler --pairs 3 third pair... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/scrambler 160 500 400 1000 --pairs 3)")" 8 limit)" "1 25.0 62.5 0.0 0.0"
+printf "Scrambler --pairs 3 L_2 transitions... "
+check_equal "$(state_events "$(machine_from "$(./target/release/scrambler 160 500 400 1000 --pairs 3)")" 5 | tr '\n' ' ')" "NonPaddingSent PaddingSent BlockingBegin LimitReached "
+printf "Scrambler --pairs 3 L_3 transitions... "
+check_equal "$(state_events "$(machine_from "$(./target/release/scrambler 160 500 400 1000 --pairs 3)")" 7 | tr '\n' ' ')" "NonPaddingSent PaddingSent LimitReached "
+printf "Scrambler --pair-reduction... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/scrambler 160 500 400 1000 --pair-reduction 2)")" 6 limit)" "1 200.0 500.0 0.0 0.0"
+printf "Scrambler --pairs --report... "
+check_contains "$(./target/release/scrambler 160 500 400 1000 --pairs 3 --report)" "Trailing packets, third L/R pair: L 25 - 62.5, R 25 - 62.5"
+printf "Scrambler --pairs 0... "
+check_failure ./target/release/scrambler 160 500 400 1000 --pairs 0
+printf "Scrambler --pair-reduction 0... "
+check_failure ./target/release/scrambler 160 500 400 1000 --pair-reduction 0
+printf "Scrambler --pairs --single-pair... "
+check_failure ./target/release/scrambler 160 500 400 1000 --pairs 3 --single-pair
+printf "Scrambler --pairs --no-block... "
+check_failure ./target/release/scrambler 160 500 400 1000 --pairs 3 --no-block
+
+# Test --trail-dist: the L and R limits are Geometric with the mean of the
+# trailing count range, p = 1 / (mean + 1)
+geometric=$(machine_from "$(./target/release/scrambler 160 500 400 1000 --trail-dist geometric)")
+printf "Scrambler --trail-dist default... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/scrambler 160 500 400 1000)")" 3 limit)" "1 400.0 1000.0 0.0 0.0"
+printf "Scrambler --trail-dist uniform... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --trail-dist uniform)" "$(./target/release/scrambler 160 500 400 1000)"
+printf "Scrambler --trail-dist geometric L_1... "
+check_equal "$(state_dist "$geometric" 3 limit)" "5 $(python3 -c 'print(1 / 701)') 0.0 0.0 0.0"
+printf "Scrambler --trail-dist geometric R_1... "
+check_equal "$(state_dist "$geometric" 4 limit)" "5 $(python3 -c 'print(1 / 701)') 0.0 0.0 0.0"
+printf "Scrambler --trail-dist geometric second pair... "
+check_equal "$(state_dist "$geometric" 5 limit) / $(state_dist "$geometric" 6 limit)" "5 $(python3 -c 'print(1 / 176)') 0.0 0.0 0.0 / 5 $(python3 -c 'print(1 / 176)') 0.0 0.0 0.0"
+printf "Scrambler --trail-dist geometric separate ranges... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/scrambler 160 500 400 1000 --trail-dist geometric --left-trail-min 0 --left-trail-max 198 --no-block)")" 2 limit)" "5 0.01 0.0 0.0 0.0"
+printf "Scrambler --trail-dist geometric MIN state... "
+check_equal "$(state_dist "$geometric" 2 limit)" "$(state_dist "$(machine_from "$(./target/release/scrambler 160 500 400 1000)")" 2 limit)"
+printf "Scrambler --trail-dist geometric lint... "
+check_contains "$(./target/release/lint "$geometric")" "0 error(s), 0 warning(s)"
+printf "Scrambler --trail-dist geometric report... "
+check_contains "$(./target/release/scrambler 160 500 400 1000 --trail-dist geometric --report)" "Trailing packets, first L/R pair: L geometric, mean 700 (400 - 1000), R geometric, mean 700 (400 - 1000)"
+printf "Scrambler --trail-dist unknown... "
+check_failure ./target/release/scrambler 160 500 400 1000 --trail-dist pareto
+
+# Test --direction: the down machines follow the received real packets, in
+# place of the sent ones
+down=$(./target/release/scrambler 160 500 400 1000 --direction down)
+printf "Scrambler --direction default... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --direction up)" "$(./target/release/scrambler 160 500 400 1000)"
+printf "Scrambler --direction down Recv events... "
+check_equal "$(for machine in $(printf "%s\n" "$down" | sed -n 's/^.*: \([0-9a-f]*\) ([0-9]*)$/\1/p'); do ./target/release/decode "$machine"; done | grep -c "NonPaddingSent")" "0"
+printf "Scrambler --direction down START... "
+check_contains "$(./target/release/decode "$(machine_from "$down")" | sed -n '/^  State 0:$/,/^  State 1:$/p')" "NonPaddingRecv -> 1 (1)"
+printf "Scrambler --direction down lint... "
+check_contains "$(./target/release/lint "$(machine_from "$down")")" "0 error(s), 0 warning(s)"
+printf "Scrambler --direction unknown... "
+check_failure ./target/release/scrambler 160 500 400 1000 --direction sideways
+
+# Test Scrambler segment report
+printf "Scrambler report... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --report)" "$(printf "Minimum packets per segment: 500 (MIN state, every 160 usec)\nTrailing packets, first L/R pair: L 400 - 1000, R 400 - 1000\nTrailing packets, second L/R pair: L 100 - 250, R 100 - 250\nMachine 2 signaling limit: 625 packets\nSend rate: MIN 6250 packets/sec (every 160 usec), L/R 6250 packets/sec (every 160 usec)\nWorst-case added latency: 240000 usec (a burst of 1500 packets, one every 160 usec)\nStates: 10\nSerialized length: 624\nEstimated padding bandwidth: 75000000 bits/sec\nEstimated max added delay: 240000 usec")"
+printf "Scrambler report matches Machine 2 limit... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/scrambler 160 500 400 1000 | grep "^Machine 2")")" 0 limit)" "1 625.0 625.0 0.0 0.0"
+printf "Scrambler report without blocking... "
+check_contains "$(./target/release/scrambler 160 500 400 1000 --report --no-block)" "Machine 2 signaling limit: not used (--no-block)"
+
+# Test the defaults (defaults.rs): without arguments, the paper's machines, and
+# omitted trailing arguments keep their defaults
+printf "Constant defaults... "
+check_equal "$(./target/release/constant)" "$(./target/release/constant 4000)"
+printf "Adapted FRONT defaults... "
+check_equal "$(./target/release/adapted_front)" "$(./target/release/adapted_front 12 4000 30)"
+printf "Adapted RegulaTor defaults... "
+check_equal "$(./target/release/adapted_regulator --trigger sent)" "$(./target/release/adapted_regulator 500 0.75 4 20 2 --trigger sent)"
+printf "Scrambler defaults... "
+check_equal "$(./target/release/scrambler)" "$(./target/release/scrambler 160 500 400 1000)"
+printf "Scrambler trailing defaults... "
+check_equal "$(./target/release/scrambler 200)" "$(./target/release/scrambler 200 500 400 1000)"
+printf "Adapted FRONT extra argument... "
+check_failure ./target/release/adapted_front 12 4000 30 1
+
+# Test the overhead reports (--report) of the defenses, with the parameters of
+# the paper or the schema examples
+report_of () {
+  ./target/release/"$@" --report | tail -n 4 | tr '\n' ';'
+}
+printf "Constant report... "
+check_equal "$(report_of constant)" "States: 3;Serialized length: 174;Estimated padding bandwidth: 3000000 bits/sec;Estimated max added delay: 4000 usec;"
+printf "Constant send rate... "
+check_equal "$(./target/release/constant --report | sed -n 1p)" "Send rate: 250 packets/sec (every 4000 usec)"
+printf "Constant --asymmetric send rate... "
+check_equal "$(./target/release/constant --asymmetric --up-interval 8000 --down-interval 1000 --report | sed -n 1p)" "Send rate: up 125 packets/sec (every 8000 usec), down 1000 packets/sec (every 1000 usec)"
+printf "Scrambler --min-interval send rate... "
+check_contains "$(./target/release/scrambler 160 500 400 1000 --min-interval 1000 --report)" "Send rate: MIN 1000 packets/sec (every 1000 usec), L/R 6250 packets/sec (every 160 usec)"
+printf "Constant --pad-only report... "
+check_equal "$(report_of constant --pad-only)" "States: 2;Serialized length: 134;Estimated padding bandwidth: 3000000 bits/sec;Estimated max added delay: 0 usec;"
+printf "BuFLO report... "
+check_equal "$(report_of buflo 10 4000)" "States: 3;Serialized length: 212;Estimated padding bandwidth: 3000000 bits/sec;Estimated max added delay: 4000 usec;"
+printf "Adapted FRONT report... "
+check_equal "$(report_of adapted_front 12 4000 30)" "States: 31;Serialized length: 2972;Estimated padding bandwidth: 2424819 bits/sec;Estimated max added delay: 0 usec;"
+printf "Adapted RegulaTor report... "
+check_equal "$(report_of adapted_regulator 1400 0.95 4 20)" "States: 1372;Serialized length: 346238;Estimated padding bandwidth: 20992305 bits/sec;Estimated max added delay: 4000000 usec;"
+printf "RegulaTor report... "
+check_equal "$(report_of regulator 1000 0.95 3.0 4 100)" "States: 202;Serialized length: 20408;Estimated padding bandwidth: 14961497 bits/sec;Estimated max added delay: 4000000 usec;"
+printf "Scrambler --no-block report... "
+check_equal "$(report_of scrambler 160 500 400 1000 --no-block)" "States: 4;Serialized length: 252;Estimated padding bandwidth: 75000000 bits/sec;Estimated max added delay: 0 usec;"
+printf "Adaptive Padding report... "
+check_equal "$(report_of adaptive_pad 4000 1000 8000 2000 10)" "States: 3;Serialized length: 208;Estimated padding bandwidth: 3000000 bits/sec;Estimated max added delay: 0 usec;"
+printf "Adaptive Padding report, other distribution... "
+check_contains "$(report_of adaptive_pad 4000 1000 8000 2000 10 --dist weibull)" "Estimated padding bandwidth: unknown;"
+printf "Walkie-Talkie report... "
+check_equal "$(report_of walkie_talkie 8 20 1000)" "States: 6;Serialized length: 324;Estimated padding bandwidth: 24000000 bits/sec;Estimated max added delay: 0 usec;"
+printf "DynaFlow report... "
+check_equal "$(report_of dynaflow 1000,2000,4000 4)" "States: 26;Serialized length: 1576;Estimated padding bandwidth: 24000000 bits/sec;Estimated max added delay: 0 usec;"
+printf "Interspace report... "
+check_equal "$(report_of interspace 4000 1000 100 1500)" "States: 2;Serialized length: 134;Estimated padding bandwidth: 1600000 bits/sec;Estimated max added delay: 0 usec;"
+printf "CS-BuFLO report... "
+check_equal "$(report_of cs_buflo 125 1000 2)" "States: 6;Serialized length: 362;Estimated padding bandwidth: 12000000 bits/sec;Estimated max added delay: 8000 usec;"
+printf "Constant --stdin --report... "
+check_failure sh -c 'echo 4000 | ./target/release/constant --stdin --report'
+
+# Test --annotate
+annotated=$(./target/release/scrambler 160 500 400 1000 --annotate)
+printf "Scrambler --annotate defense... "
+check_contains "$annotated" "# defense: scrambler"
+printf "Scrambler --annotate parameters... "
+check_contains "$annotated" "# minimum count: 500"
+printf "Scrambler --annotate state count... "
+check_contains "$annotated" "# Machine 1: 7 states"
+printf "Scrambler --annotate stripped... "
+check_equal "$(printf "%s\n" "$annotated" | grep -v '^#')" "$(./target/release/scrambler 160 500 400 1000)"
+printf "Adapted RegulaTor --annotate stripped... "
+check_equal "$(./target/release/adapted_regulator 500 0.75 4 20 --annotate | grep -v '^#')" "$(./target/release/adapted_regulator 500 0.75 4 20)"
+
+# Test client machines with upload ratios below 1.0
+printf "Adapted RegulaTor upload ratio 0.5... "
+check_contains "$(./target/release/lint "$(machine_from "$(./target/release/adapted_regulator 500 0.75 0.5 20 | grep Client)")")" "2 states, 0 error(s), 0 warning(s)"
+printf "Adapted RegulaTor upload ratio 0.25... "
+check_contains "$(./target/release/lint "$(machine_from "$(./target/release/adapted_regulator 500 0.75 0.25 20 | grep Client)")")" "2 states, 0 error(s), 0 warning(s)"
+printf "Adapted RegulaTor upload ratio 0.5 and 0.25 differ... "
+if [ "$(./target/release/adapted_regulator 500 0.75 0.5 20 | grep Client)" = "$(./target/release/adapted_regulator 500 0.75 0.25 20 | grep Client)" ]; then
+  printf "\nTest failed! Client machines are identical\n" 1>&2
+  exit 1
+fi
+printf "OK\n"
+printf "Adapted RegulaTor upload ratio 0... "
+check_failure ./target/release/adapted_regulator 500 0.75 0 20
+for ratio in -1 NaN inf; do
+  printf "Adapted RegulaTor upload ratio $ratio... "
+  check_failure ./target/release/adapted_regulator 500 0.75 $ratio 20
+  printf "RegulaTor upload ratio $ratio... "
+  check_failure ./target/release/regulator 500 0.75 3.95 $ratio 20
+done
+for count in 0 -20; do
+  printf "Adapted RegulaTor packets per state $count... "
+  check_failure ./target/release/adapted_regulator 500 0.75 4 $count --count-only
+  printf "RegulaTor packets per state $count... "
+  check_failure ./target/release/regulator 500 0.75 3.95 4 $count
+done
+printf "Adapted RegulaTor --count-only upload ratio inf... "
+check_failure ./target/release/adapted_regulator 500 0.75 inf 20 --count-only
+
+# Test --dry-run
+printf "Constant --dry-run... "
+check_equal "$(./target/release/constant --dry-run)" "Machine: OK (3 states)"
+printf "Scrambler --dry-run... "
+check_contains "$(./target/release/scrambler 160 500 400 1000 --dry-run)" "Machine 2: OK (3 states)"
+printf "Adapted RegulaTor --dry-run... "
+check_contains "$(./target/release/adapted_regulator 500 0.75 4 20 --dry-run)" "Relay machine: OK (89 states)"
+printf "Adapted FRONT --dry-run with no states... "
+check_failure ./target/release/adapted_front 10 1000 0 --dry-run
+
+printf "RegulaTor relay machine... "
+check_contains "$(./target/release/lint "$(machine_from "$(./target/release/regulator 277 0.94 3.55 3.95 20)")")" "226 states, 0 error(s), 0 warning(s)"
+
+# Test adaptive padding
+printf "Adaptive padding machine... "
+check_contains "$(./target/release/lint "$(machine_from "$(./target/release/adaptive_pad 4000 1000 2000 500 50)")")" "3 states, 0 error(s), 0 warning(s)"
+printf "Adaptive padding --dist... "
+check_success ./target/release/lint "$(machine_from "$(./target/release/adaptive_pad 4000 1.5 2000 1.5 50 --dist weibull)")"
+printf "Adaptive padding --dist changes machine... "
+if [ "$(./target/release/adaptive_pad 4000 1000 2000 500 50 --dist normal)" = "$(./target/release/adaptive_pad 4000 1000 2000 500 50 --dist lognormal)" ]; then
+  printf "\nTest failed! --dist had no effect\n" 1>&2
+  exit 1
+fi
+printf "OK\n"
+printf "Adaptive padding unknown --dist... "
+check_failure ./target/release/adaptive_pad 4000 1000 2000 500 50 --dist histogram
+
+# Test Walkie-Talkie
+printf "Walkie-Talkie machines... "
+check_equal "$(./target/release/walkie_talkie 8 20 1000 --dry-run)" "$(printf "Client machine: OK (3 states)\nRelay machine: OK (3 states)")"
+printf "Walkie-Talkie send burst... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/walkie_talkie 8 20 1000)")" 1 limit)" "1 8.0 8.0 0.0 0.0"
+printf "Walkie-Talkie recv burst... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/walkie_talkie 8 20 1000 | grep "^Relay machine")")" 1 limit)" "1 20.0 20.0 0.0 0.0"
+printf "Walkie-Talkie send interval... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/walkie_talkie 8 20 1000)")" 1 timeout)" "1 1000.0 1000.0 0.0 0.0"
+printf "Walkie-Talkie never blocks... "
+check_equal "$(blocking_states "$(machine_from "$(./target/release/walkie_talkie 8 20 1000)")")" ""
+printf "Walkie-Talkie invalid burst... "
+check_failure ./target/release/walkie_talkie 0 20 1000
+
+# Test DynaFlow
+dynaflow=$(machine_from "$(./target/release/dynaflow 1000,2000,4000 2)")
+printf "DynaFlow machines... "
+check_equal "$(./target/release/dynaflow 1000,2000,4000 2 --dry-run)" "$(printf "Client machine: OK (7 states)\nRelay machine: OK (7 states)")"
+printf "DynaFlow lint... "
+check_contains "$(./target/release/lint "$dynaflow")" "7 states, 0 error(s), 0 warning(s)"
+printf "DynaFlow intervals... "
+check_equal "$(state_dist "$dynaflow" 2 timeout) $(state_dist "$dynaflow" 3 timeout) $(state_dist "$dynaflow" 6 timeout)" "1 1000.0 1000.0 0.0 0.0 1 2000.0 2000.0 0.0 0.0 1 4000.0 4000.0 0.0 0.0"
+printf "DynaFlow block size... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/dynaflow 1000,2000,4000 2 --block-size 10)")" 1 limit)" "1 10.0 10.0 0.0 0.0"
+printf "DynaFlow last state never steps... "
+check_equal "$(state_events "$dynaflow" 6 | tr '\n' ' ')" "NonPaddingSent PaddingSent "
+printf "DynaFlow --ms... "
+check_equal "$(./target/release/dynaflow 1,2,4 2 --ms)" "$(./target/release/dynaflow 1000,2000,4000 2)"
+printf "DynaFlow invalid interval... "
+check_failure ./target/release/dynaflow 1000,0 2
+printf "DynaFlow state cap... "
+check_failure ./target/release/dynaflow 1000,2000,4000 500
+
+# Test Interspace
+interspace=$(machine_from "$(./target/release/interspace 4000 1000 100 1500)")
+printf "Interspace machine... "
+check_equal "$(./target/release/interspace 4000 1000 100 1500 --dry-run)" "Machine: OK (2 states)"
+printf "Interspace lint... "
+check_contains "$(./target/release/lint "$interspace")" "2 states, 0 error(s), 0 warning(s)"
+printf "Interspace padding sizes... "
+check_equal "$(state_dist "$interspace" 1 action)" "1 100.0 1500.0 0.0 0.0"
+printf "Interspace timeout... "
+check_equal "$(state_dist "$interspace" 1 timeout)" "2 4000.0 1000.0 0.0 0.0"
+printf "Interspace real packets do not re-arm the timer... "
+check_equal "$(state_events "$interspace" 1 | tr '\n' ' ')" "PaddingSent "
+printf "Interspace --dist... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/interspace 8.0 0.5 100 1500 --dist lognormal)")" 1 timeout)" "3 8.0 0.5 0.0 0.0"
+printf "Interspace inverted size range... "
+check_failure ./target/release/interspace 4000 1000 1500 100
+printf "Interspace --packet-size... "
+check_failure ./target/release/interspace 4000 1000 100 1500 --packet-size 500
+size_hist="../tests/interspace_sizes.csv"
+printf "100,20\n500,600,30\n1400,1500,50\n" > "$size_hist"
+interspace_hist=$(machine_from "$(./target/release/interspace 4000 1000 100 1500 --size-hist "$size_hist")")
+printf "Interspace --size-hist machine... "
+check_equal "$(./target/release/interspace 4000 1000 100 1500 --size-hist "$size_hist" --dry-run)" "Machine: OK (4 states)"
+printf "Interspace --size-hist padding sizes... "
+check_equal "$(state_dist "$interspace_hist" 1 action) / $(state_dist "$interspace_hist" 2 action) / $(state_dist "$interspace_hist" 3 action)" "1 100.0 100.0 0.0 0.0 / 1 500.0 600.0 0.0 0.0 / 1 1400.0 1500.0 0.0 0.0"
+printf "Interspace --size-hist lint... "
+check_contains "$(./target/release/lint "$interspace_hist")" "4 states, 0 error(s), 0 warning(s)"
+printf "Interspace --size-hist report uses the mean size... "
+check_contains "$(./target/release/interspace 4000 1000 100 1500 --size-hist "$size_hist" --report)" "Estimated padding bandwidth: 1820000 bits/sec"
+printf "Interspace --size-hist outside the size range... "
+check_failure ./target/release/interspace 4000 1000 200 1500 --size-hist "$size_hist"
+printf "Interspace --size-hist invalid histogram... "
+check_failure ./target/release/interspace 4000 1000 100 1500 --size-hist ../tests/test_tools.sh
+rm "$size_hist"
+
+# Test CS-BuFLO: rates of 1000, 500, 250 and 125 packets/sec
+cs_buflo=$(machine_from "$(./target/release/cs_buflo 125 1000 2)")
+printf "CS-BuFLO machine... "
+check_equal "$(./target/release/cs_buflo 125 1000 2 --dry-run)" "Machine: OK (6 states)"
+printf "CS-BuFLO lint... "
+check_contains "$(./target/release/lint "$cs_buflo")" "6 states, 0 error(s), 0 warning(s)"
+printf "CS-BuFLO fastest rate... "
+check_equal "$(state_dist "$cs_buflo" 2 timeout)" "1 1000.0 1000.0 0.0 0.0"
+printf "CS-BuFLO slowest rate... "
+check_equal "$(state_dist "$cs_buflo" 5 timeout)" "1 8000.0 8000.0 0.0 0.0"
+printf "CS-BuFLO stepping up and down... "
+check_equal "$(state_events "$cs_buflo" 3 | tr '\n' ' ')" "NonPaddingSent PaddingSent LimitReached "
+printf "CS-BuFLO fastest level only steps down... "
+check_equal "$(state_events "$cs_buflo" 2 | tr '\n' ' ')" "PaddingSent LimitReached "
+printf "CS-BuFLO slowest level has no limit... "
+check_equal "$(state_events "$cs_buflo" 5 | tr '\n' ' ')" "NonPaddingSent PaddingSent "
+printf "CS-BuFLO --window... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/cs_buflo 125 1000 2 --window 50)")" 2 limit)" "1 50.0 50.0 0.0 0.0"
+printf "CS-BuFLO factor of 1... "
+check_failure ./target/release/cs_buflo 125 1000 1
+printf "CS-BuFLO inverted rates... "
+check_failure ./target/release/cs_buflo 1000 125 2
+printf "CS-BuFLO state cap... "
+check_failure ./target/release/cs_buflo 1 1000 1.001
+
+# Test Surakav: 11 packets sent 1 ms apart, then 21 packets received 0.5 ms
+# apart, in states of 5 packets (gaps)
+reference="../tests/surakav_reference.csv"
+awk 'BEGIN {
+  for (i = 0; i <= 10; i++) printf "%d,s,600\n", i * 1000000
+  for (i = 0; i <= 20; i++) printf "%d,r,1500\n", 20000000 + i * 500000
+}' > "$reference"
+printf "Surakav machines... "
+check_equal "$(./target/release/surakav "$reference" 1 5 --dry-run 2> /dev/null | grep -v warning)" "$(printf "Client machine: OK (4 states)\nRelay machine: OK (6 states)")"
+printf "Surakav client timeout... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/surakav "$reference" 1 5)")" 2 timeout)" "1 1000.0 1000.0 0.0 0.0"
+printf "Surakav relay timeout... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/surakav "$reference" 1 5 | grep "^Relay machine")")" 5 timeout)" "1 500.0 500.0 0.0 0.0"
+printf "Surakav rate... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/surakav "$reference" 2 5)")" 3 timeout)" "1 500.0 500.0 0.0 0.0"
+printf "Surakav packets per state... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/surakav "$reference" 1 4)")" 4 limit)" "1 2.0 2.0 0.0 0.0"
+printf "Surakav report... "
+check_equal "$(./target/release/surakav "$reference" 1 5 --report 2> /dev/null)" "$(printf "States: 10\nSerialized length: 580\nEstimated padding bandwidth: 36000000 bits/sec\nEstimated max added delay: 0 usec")"
+printf "Surakav --max-states... "
+check_equal "$(./target/release/surakav "$reference" 1 5 --max-states 2 --dry-run 2> /dev/null | grep -v warning)" "$(printf "Client machine: OK (4 states)\nRelay machine: OK (4 states)")"
+printf "Surakav --max-states warning... "
+check_contains "$(./target/release/surakav "$reference" 1 5 --max-states 2 2>&1 > /dev/null)" "reference trace needs 4 SEND states, following only the first 2"
+printf "Surakav invalid trace... "
+printf "0,s,600\n1000,x,600\n" > "$reference"
+check_failure ./target/release/surakav "$reference" 1 5
+rm "$reference"
+
+# Test streaming parameters through stdin
+stream=$(printf "4000\n\n5000\nabc\n-1\n4000 5000\n4000\n" | ./target/release/constant --stdin 2> /dev/null)
+printf "Constant --stdin machines... "
+check_equal "$(printf "%s\n" "$stream" | sed -n 1p)" "$(./target/release/constant 4000 | head -n 1)"
+printf "Constant --stdin second machine... "
+check_equal "$(printf "%s\n" "$stream" | sed -n 2p)" "$(./target/release/constant 5000 | head -n 1)"
+printf "Constant --stdin one line per config... "
+check_equal "$(printf "%s\n" "$stream" | wc -l | tr -d ' ')" "6"
+printf "Constant --stdin errors in place... "
+check_equal "$(printf "%s\n" "$stream" | sed -n 3,5p)" "$(printf "Machine: error: invalid send interval \"abc\"\nMachine: error: send interval must be positive, got -1\nMachine: error: expected 1 parameter (send interval), got 2")"
+printf "Constant --stdin continues after errors... "
+check_equal "$(printf "%s\n" "$stream" | sed -n 6p)" "$(./target/release/constant 4000 | head -n 1)"
+printf "Constant --stdin line numbers... "
+check_contains "$(printf "abc\n" | ./target/release/constant --stdin 2>&1 > /dev/null)" "Line 1: invalid send interval"
+printf "Constant --stdin options... "
+check_equal "$(printf "5000\n" | ./target/release/constant --stdin --dist pareto)" "$(./target/release/constant 5000 --dist pareto | head -n 1)"
+
+# Test batch generation
+sweep_input="../tests/sweep_input.csv"
+printf "interval,min_count,min_trail,max_trail\n160,500,400,1000\n120,abc,400,1000\n200,1500,400\n" > "$sweep_input"
+printf "Sweep header... "
+check_equal "$(./target/release/sweep "$sweep_input" 2> /dev/null | head -n 1)" "interval,min_count,min_trail,max_trail,machine1,machine1_len,machine2,machine2_len"
+printf "Sweep machines match Scrambler... "
+check_equal "$(./target/release/sweep "$sweep_input" 2> /dev/null | sed -n 2p | cut -d, -f5)" "$(machine_from "$(./target/release/scrambler 160 500 400 1000)")"
+printf "Sweep malformed rows... "
+check_contains "$(./target/release/sweep "$sweep_input" 2>&1 > /dev/null)" "Line 3: invalid number \"abc\""
+printf "Sweep summary... "
+check_contains "$(./target/release/sweep "$sweep_input" 2>&1 > /dev/null)" "1 of 3 rows succeeded"
+rm "$sweep_input"
+
+# Test sizes, against the states and serialized length of --report
+size_row () {
+  report_of "$@" | sed 's/^States: \([0-9]*\);Serialized length: \([0-9]*\);.*/\1,\2/'
+}
+printf "Sizes table... "
+check_equal "$(./target/release/sizes "packets per state" 20 60 20 -- adapted_regulator 500 0.75 4 100 2> /dev/null | tr '\n' ' ')" "packets per state,states,serialized_len 20,$(size_row adapted_regulator 500 0.75 4 20) 40,$(size_row adapted_regulator 500 0.75 4 40) 60,$(size_row adapted_regulator 500 0.75 4 60) "
+printf "Sizes prints no machines... "
+check_equal "$(./target/release/sizes "packets per state" 20 60 20 -- adapted_regulator 500 0.75 4 100 2> /dev/null | grep -c '[0-9a-f]\{64\}')" "0"
+printf "Sizes --count-only... "
+check_equal "$(./target/release/sizes "packets per state" 20 20 1 --count-only -- adapted_regulator 500 0.75 4 100 2> /dev/null | tail -n 1)" "20,$(./target/release/adapted_regulator 500 0.75 4 20 --count-only | awk '{ n += $3 } END { print n }')"
+printf "Sizes option... "
+check_equal "$(./target/release/sizes "window" 10 30 10 -- cs_buflo 125 1000 2 --window 5 2> /dev/null | cut -d, -f1 | tr '\n' ' ')" "window 10 20 30 "
+printf "Sizes omitted positional parameter... "
+check_equal "$(./target/release/sizes "client limit" 3 3 1 -- adapted_regulator 500 0.75 4 20 2> /dev/null | tail -n 1)" "3,$(size_row adapted_regulator 500 0.75 4 20 3)"
+printf "Sizes rejected values... "
+check_contains "$(./target/release/sizes "factor" 1 2 0.5 -- cs_buflo 125 1000 2 2>&1 > /dev/null)" "2 of 3 values succeeded"
+printf "Sizes unknown parameter... "
+check_failure ./target/release/sizes "rate" 1 2 1 -- cs_buflo 125 1000 2
+printf "Sizes non-numeric parameter... "
+check_failure ./target/release/sizes "trigger" 1 2 1 -- adapted_regulator 500 0.75 4 20
+
+# Test machine lists
+scrambler1=$(machine_from "$(./target/release/scrambler 160 500 400 1000)")
+constant1=$(machine_from "$(./target/release/constant)")
+list=$(./target/release/combine --machine "$scrambler1" --machine "$constant1" 2> /dev/null)
+printf "Combine machines... "
+check_equal "$list" "$(printf "%s\n%s" "$scrambler1" "$constant1")"
+printf "Combine round trip... "
+check_equal "$(./target/release/combine --machine "$list" 2> /dev/null)" "$list"
+printf "Combine invalid machine... "
+check_failure ./target/release/combine --machine "$scrambler1" --machine "not a machine"
+printf "Combine blocking machines... "
+check_contains "$(./target/release/combine --machine "$(./target/release/scrambler 160 500 400 1000 --ffi)" 2>&1 > /dev/null)" "Warning: machines 1 and 2 both block"
+printf "Combine non-blocking machines... "
+check_equal "$(./target/release/combine --machine "$constant1" --machine "$(./target/release/scrambler 160 500 400 1000 --no-block --ffi)" 2>&1 > /dev/null)" ""
+printf "Combine --warmup... "
+check_success ./target/release/lint "$(./target/release/combine --machine "$constant1" --warmup 10)"
+printf "Combine --warmup 0... "
+check_equal "$(./target/release/combine --machine "$constant1" --warmup 0)" "$constant1"
+printf "Combine invalid --warmup... "
+check_failure ./target/release/combine --machine "$constant1" --warmup -1
+
+# Test --ffi output: the machines alone, one per line (a machine list)
+printf "Constant --ffi... "
+check_equal "$(./target/release/constant --ffi)" "$constant1"
+printf "Scrambler --ffi... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --ffi)" "$(./target/release/scrambler 160 500 400 1000 | sed -n 's/^.*: \([0-9a-f]*\) ([0-9]*)$/\1/p')"
+printf "Adapted RegulaTor --ffi... "
+check_equal "$(./target/release/adapted_regulator 500 0.75 4 20 --ffi | wc -l)" "2"
+printf "Walkie-Talkie --ffi combined... "
+check_equal "$(./target/release/combine --machine "$(./target/release/walkie_talkie 20 40 2000 --ffi)")" "$(./target/release/walkie_talkie 20 40 2000 --ffi)"
+printf "Constant --ffi --hash... "
+check_failure ./target/release/constant --ffi --hash
+printf "Constant --ffi --annotate... "
+check_failure ./target/release/constant --ffi --annotate
+printf "Constant --ffi --stdin... "
+check_failure sh -c 'echo 4000 | ./target/release/constant --stdin --ffi'
+
+# Test --emit-rust output: a &str constant per machine, holding the machine
+printf "Constant --emit-rust... "
+check_equal "$(./target/release/constant --emit-rust | sed -n 's/^pub const MACHINE: &str = "\([0-9a-f]*\)";$/\1/p')" "$constant1"
+printf "Adapted RegulaTor --emit-rust... "
+check_equal "$(./target/release/adapted_regulator 500 0.75 4 20 --emit-rust | sed -n 's/^pub const \([A-Z_]*\): .*$/\1/p' | tr '\n' ' ')" "RELAY_MACHINE CLIENT_MACHINE "
+printf "Constant --emit-rust --ffi... "
+check_failure ./target/release/constant --emit-rust --ffi
+printf "Constant --emit-rust --maybenot-v2... "
+check_failure ./target/release/constant --emit-rust --maybenot-v2
+
+# Test --ms: intervals in milliseconds give the same machines as in usec
+printf "Constant --ms... "
+check_equal "$(./target/release/constant --ms 4)" "$(./target/release/constant 4000.0)"
+printf "Constant --ms exact... "
+check_equal "$(./target/release/constant --ms 1.005)" "$(./target/release/constant 1005)"
+printf "Constant --ms exponent... "
+check_equal "$(./target/release/constant --ms 2.5e-1)" "$(./target/release/constant 250)"
+printf "Constant --ms --stdin... "
+check_equal "$(printf "4\n1.005\n" | ./target/release/constant --stdin --ms)" "$(printf "4000\n1005\n" | ./target/release/constant --stdin)"
+printf "BuFLO --ms... "
+check_equal "$(./target/release/buflo 10 4 --ms)" "$(./target/release/buflo 10 4000)"
+printf "Scrambler --ms... "
+check_equal "$(./target/release/scrambler 0.16 500 400 1000 --ms --min-interval 0.12)" "$(./target/release/scrambler 160 500 400 1000 --min-interval 120)"
+printf "Walkie-Talkie --ms... "
+check_equal "$(./target/release/walkie_talkie 8 20 1 --ms)" "$(./target/release/walkie_talkie 8 20 1000)"
+printf "Constant --ms invalid... "
+check_failure ./target/release/constant --ms 4ms
+printf "Regulator --ms... "
+check_failure ./target/release/regulator 500 0.75 2 4 20 --ms
+
+# Test the blocking budget options, set on every machine of a defense
+printf "Blocking budget default... "
+check_equal "$(blocking_budget "$constant1")" "0.0 0"
+printf "Blocking budget... "
+check_equal "$(blocking_budget "$(./target/release/constant --max-blocking-frac 0.25 --allowed-blocked-microsec 5000 --ffi)")" "0.25 5000"
+printf "Blocking budget of every machine... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --max-blocking-frac 0.1 --allowed-blocked-microsec 250000 --ffi | while read -r m; do blocking_budget "$m"; done | uniq -c | tr -s ' ')" " 2 0.1 250000"
+printf "Blocking budget with --stdin... "
+check_equal "$(blocking_budget "$(machine_from "$(echo 4000 | ./target/release/constant --stdin --max-blocking-frac 0.5)")")" "0.5 0"
+printf "Blocking budget annotated... "
+check_contains "$(./target/release/regulator 500 0.75 2 4 20 --annotate --max-blocking-frac 0.3)" "# max blocking frac: 0.3"
+printf "Blocking budget dry run... "
+check_success ./target/release/walkie_talkie 8 20 1000 --max-blocking-frac 1 --allowed-blocked-microsec 1000000 --dry-run
+printf "Blocking budget invalid fraction... "
+check_failure ./target/release/buflo 10 4000 --max-blocking-frac 1.5
+printf "Blocking budget negative microsec... "
+check_failure ./target/release/adapted_front 12 4000 30 --allowed-blocked-microsec -1
+
+# Test --time-scale, which scales the timeouts of every machine of a defense
+printf "Time scale constant interval... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/constant 4000 --time-scale 2)")" 1 timeout)" "$(state_dist "$(machine_from "$(./target/release/constant 8000)")" 1 timeout)"
+printf "Time scale default... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --time-scale 1)" "$(./target/release/scrambler 160 500 400 1000)"
+printf "Time scale leaves padding sizes... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/interspace 4000 1000 100 1500 --time-scale 0.5)")" 1 action)" "1 100.0 1500.0 0.0 0.0"
+printf "Time scale LogNormal... "
+check_equal "$(state_dist "$(machine_from "$(./target/release/adaptive_pad 8.0 0.5 8.0 0.5 10 --dist lognormal --time-scale 2)")" 1 timeout)" "3 8.693147180559945 0.5 0.0 0.0"
+printf "Time scale report... "
+check_contains "$(report_of constant 4000 --time-scale 2)" "Estimated padding bandwidth: 1500000 bits/sec;Estimated max added delay: 8000 usec;"
+printf "Time scale count distribution... "
+check_failure ./target/release/adaptive_pad 10 0.5 10 0.5 10 --dist binomial --time-scale 2
+printf "Time scale invalid factor... "
+check_failure ./target/release/buflo 10 4000 --time-scale 0
+printf "Time scale with --target-overhead... "
+check_failure ./target/release/constant --target-overhead 0.25 --baseline-mbps 10 --time-scale 2
+
+# Test --include-small-packets, which sets the field of every machine
+printf "Include small packets... "
+check_contains "$(./target/release/decode "$(./target/release/constant --ffi --include-small-packets)")" "include_small_packets: true"
+printf "Include small packets default... "
+check_contains "$(./target/release/decode "$(./target/release/constant --ffi)")" "include_small_packets: false"
+printf "Include small packets, every machine... "
+check_equal "$(./target/release/scrambler --ffi --include-small-packets | while read -r machine; do ./target/release/decode "$machine" | grep include_small_packets; done)" "$(printf "  include_small_packets: true\n  include_small_packets: true")"
+
+# Test that machines are deterministic: each run is a new process, so any
+# HashMap iteration order leaking into the serialized form would show up here
+printf "Machines identical across runs... "
+while read -r defense; do
+  check_equal "$(./target/release/$defense)" "$(./target/release/$defense)" > /dev/null
+done <<EOF
+constant
+constant 5000 --dist pareto
+buflo 10 4000
+adapted_front 12 4000 30
+adapted_regulator 500 0.75 4 20
+regulator 500 0.75 2 4 20
+scrambler 160 500 400 1000
+scrambler 160 500 400 1000 --no-block
+adaptive_pad 4000 1000 2000 500 50
+walkie_talkie 8 20 1000
+EOF
+printf "OK\n"
+
+# Test machine hashes
+hash_of () {
+  printf "%s\n" "$1" | sed -n 's/^SHA-256: //p'
+}
+printf "Hash identical for identical parameters... "
+check_equal "$(hash_of "$(./target/release/constant 5000 --hash)")" "$(hash_of "$(./target/release/constant 5000 --hash)")"
+printf "Hash differs for differing parameters... "
+if [ "$(hash_of "$(./target/release/constant 5000 --hash)")" = "$(hash_of "$(./target/release/constant 4000 --hash)")" ]; then
+  printf "\nTest failed! Hashes of different machines are equal\n" 1>&2
+  exit 1
+fi
+printf "OK\n"
+printf "Hash of the serialized machine... "
+check_equal "$(hash_of "$(./target/release/constant --hash)")" "$(printf "%s" "$(machine_from "$(./target/release/constant)")" | sha256sum | cut -d ' ' -f 1)"
+printf "Hash over the Scrambler machines in order... "
+check_equal "$(hash_of "$(./target/release/scrambler 160 500 400 1000 --hash)")" "$(./target/release/scrambler 160 500 400 1000 | sed -n 's/^.*: \([0-9a-f]*\) ([0-9]*)$/\1/p' | head -c -1 | sha256sum | cut -d ' ' -f 1)"
+printf "Hash leaves the machines unchanged... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --hash | grep -v '^SHA-256: ')" "$(./target/release/scrambler 160 500 400 1000)"
+
+# Test --base64, which prints the machines in base64 with their encoded length
+base64_from () {
+  printf "%s\n" "$1" | sed -n 's/^.*: \([A-Za-z0-9+/=]*\) ([0-9]*)$/\1/p' | head -n 1
+}
+printf "Base64 encoded length... "
+check_equal "$(./target/release/constant --base64 | sed -n 's/^Machine: [^ ]* (\([0-9]*\))$/\1/p')" "$(base64_from "$(./target/release/constant --base64)" | tr -d '\n' | wc -c)"
+printf "Base64 alphabet... "
+check_equal "$(./target/release/adapted_regulator 500 0.75 4 20 --base64 | grep -v '^$' | grep -cv '^[A-Za-z ]*: [A-Za-z0-9+/=]* ([0-9]*)$')" "0"
+printf "Base64 hash... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --base64 --hash | grep '^SHA-256: ')" "$(./target/release/scrambler 160 500 400 1000 --hash | grep '^SHA-256: ')"
+printf "Base64 --stdin... "
+check_equal "$(echo 4000 | ./target/release/constant --stdin --base64)" "$(./target/release/constant 4000 --base64 | head -n 1)"
+printf "Base64 with --ffi... "
+check_failure ./target/release/constant --base64 --ffi
+
+# Test --pretty, which lists each machine in "#" lines after it
+printf "Pretty first line... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --pretty | head -n 1)" "$(./target/release/scrambler 160 500 400 1000 | head -n 1)"
+# A reader closing the pipe early ends the output quietly, as with head
+printf "Pretty closed pipe... "
+check_equal "$({ { ./target/release/adapted_regulator 1400 0.95 4 20 --pretty 2>&3; echo $? >&3; } | head -n 1 > /dev/null; } 3>&1)" "0"
+printf "Pretty stripped... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --pretty | grep -v '^#')" "$(./target/release/scrambler 160 500 400 1000)"
+printf "Pretty listing... "
+check_equal "$(./target/release/constant --pretty | sed -n 's/^# //p')" "$(./target/release/decode "$(./target/release/constant | sed -n 's/^Machine: \([^ ]*\) ([0-9]*)$/\1/p')")"
+printf "Pretty with --ffi... "
+check_failure ./target/release/constant --pretty --ffi
+
+# Test machine decoder
+decoded=$(./target/release/decode "$constant1")
+printf "Decode machine... "
+check_contains "$decoded" "Machine: 3 states"
+printf "Decode state flags... "
+check_contains "$decoded" "$(printf "  State 1:\n    action_is_block: true\n    bypass: true\n    replace: true")"
+printf "Decode distributions... "
+check_contains "$decoded" "timeout: Uniform [4000.0, 4000.0]"
+printf "Decode transitions... "
+check_contains "$decoded" "$(printf "    transitions:\n      NonPaddingRecv -> 1 (1)\n      NonPaddingSent -> 1 (1)")"
+printf "Decode transition to END... "
+check_contains "$(./target/release/decode "$(./target/release/adapted_regulator 500 0.75 4 20 --ffi)")" "LimitReached -> END (1)"
+printf "Decode machine budget... "
+check_contains "$(./target/release/decode "$(./target/release/constant --max-blocking-frac 0.25 --ffi)")" "max_blocking_frac: 0.25"
+printf "Decode machine list... "
+check_equal "$(./target/release/decode "$(./target/release/scrambler 160 500 400 1000 --ffi)" | grep "^Machine")" "$(printf "Machine 1: 7 states\nMachine 2: 3 states")"
+printf "Decode machine file... "
+./target/release/constant --ffi > ../tests/decode_machine.txt
+check_equal "$(./target/release/decode ../tests/decode_machine.txt)" "$decoded"
+rm ../tests/decode_machine.txt
+printf "Decode invalid machine... "
+check_failure ./target/release/decode "not a machine"
+printf "Decode noop machine... "
+check_contains "$(./target/release/decode "$noop")" "transitions: none"
+printf "Decode --base64... "
+check_equal "$(./target/release/decode --base64 "$(base64_from "$(./target/release/constant --base64)")")" "$decoded"
+printf "Decode --base64 --only... "
+check_equal "$(./target/release/decode --base64 "$(./target/release/scrambler 160 500 400 1000 --only 1 --base64)")" "$(./target/release/decode "$(./target/release/scrambler 160 500 400 1000 --only 1)")"
+printf "Decode --base64 of a hex machine... "
+check_failure ./target/release/decode --base64 "$constant1"
+
+# Test machine diff
+printf "Diff same machines... "
+check_equal "$(./target/release/diff "$constant1" "$(./target/release/constant --ffi)"; echo "exit $?")" "exit 0"
+printf "Diff changed machines... "
+check_equal "$(./target/release/diff "$constant1" "$(./target/release/constant 5000 --max-blocking-frac 0.5 --trigger sent --ffi)")" "$(printf "Machine:\n  max_blocking_frac: 0 -> 0.5\n  State 0:\n    transitions on NonPaddingRecv: 1 (1) -> none\n  State 2:\n    timeout: Uniform [4000.0, 4000.0] -> Uniform [5000.0, 5000.0]")"
+printf "Diff exit status... "
+check_failure ./target/release/diff "$constant1" "$(./target/release/constant 5000 --ffi)"
+printf "Diff removed states... "
+check_contains "$(./target/release/diff "$(./target/release/scrambler 160 500 400 1000 --ffi)" "$(./target/release/scrambler 160 500 400 1000 --single-pair --ffi)")" "$(printf "Machine 1:\n  states: 7 -> 5")"
+printf "Diff removed machines... "
+check_contains "$(./target/release/diff "$(./target/release/scrambler 160 500 400 1000 --ffi)" "$(./target/release/scrambler 160 500 400 1000 --single-pair --ffi)")" "Machine 2: removed (3 states)"
+printf "Diff machine files... "
+./target/release/constant --ffi > ../tests/diff_machine.txt
+check_equal "$(./target/release/diff ../tests/diff_machine.txt "$constant1"; echo "exit $?")" "exit 0"
+rm ../tests/diff_machine.txt
+printf "Diff invalid machine... "
+check_equal "$(./target/release/diff "not a machine" "$constant1" 2> /dev/null; echo "exit $?")" "exit 2"
+
+# Test the parameter schema (checked against the generators by cargo test)
+printf "Schema JSON... "
+check_equal "$(./target/release/schema constant | python3 -c 'import json, sys; print(json.load(sys.stdin)["parameters"][0]["name"])')" "send interval"
+printf "Schema of every defense... "
+check_equal "$(./target/release/schema | python3 -c 'import json, sys; print(" ".join(d["defense"] for d in json.load(sys.stdin)))')" "constant buflo adapted_front adapted_regulator regulator scrambler adaptive_pad walkie_talkie surakav dynaflow interspace cs_buflo"
+printf "Schema unknown defense... "
+check_failure ./target/release/schema lint
+
+# Test the defense registry
+printf "Generate --list... "
+check_equal "$(./target/release/generate --list | tr '\n' ' ')" "constant buflo adapted_front adapted_regulator regulator scrambler adaptive_pad walkie_talkie surakav dynaflow interspace cs_buflo "
+printf "Generate constant... "
+check_equal "$(./target/release/generate constant 4000)" "$(./target/release/constant 4000)"
+printf "Generate Scrambler with options... "
+check_equal "$(./target/release/generate scrambler 160 500 400 1000 --annotate)" "$(./target/release/scrambler 160 500 400 1000)"
+printf "Generate --packet-sizes... "
+check_equal "$(./target/release/generate --packet-sizes 1200,1500 constant 4000 | sed 's/ (packet size [0-9]*)//')" "$(./target/release/constant 4000 --packet-size 1200; ./target/release/constant 4000 --packet-size 1500)"
+printf "Generate --packet-sizes names... "
+check_equal "$(./target/release/generate --packet-sizes 1200,1500 constant 4000 | grep . | cut -d: -f1 | tr '\n' ',')" "Machine (packet size 1200),Machine (packet size 1500),"
+printf "Generate --packet-sizes with --packet-size... "
+check_failure ./target/release/generate --packet-sizes 1200 constant 4000 --packet-size 1500
+printf "Generate invalid packet sizes... "
+check_failure ./target/release/generate --packet-sizes 1200,big constant 4000
+printf "Generate unknown defense... "
+check_failure ./target/release/generate lint
+printf "Generate defense failure... "
+check_failure ./target/release/generate constant fast
+
+# Test the simulated overhead measurement, on a client sending a packet every
+# 8 ms and receiving one halfway in between
+python3 -c 'for i in range(200): print("%d,s\n%d,r,500" % (i * 8000000, i * 8000000 + 4000000))' > ../tests/measure_trace.csv
+printf "Measure constant overhead... "
+check_contains "$(./target/release/measure ../tests/measure_trace.csv -- constant 4000)" "Client: 1 machine(s), 200 real packets (300000 bytes), 199 padding packets (298500 bytes), overhead 0.9950, delay mean 0.0 usec, p95 0.0 usec"
+printf "Measure total overhead... "
+check_contains "$(./target/release/measure ../tests/measure_trace.csv -- constant 4000)" "Total overhead: 0.7462"
+printf "Measure --side relay... "
+check_contains "$(./target/release/measure ../tests/measure_trace.csv --side relay -- constant 4000)" "Relay: 1 machine(s), 200 real packets (100000 bytes), 200 padding packets (300000 bytes)"
+printf "Measure relay machines... "
+check_contains "$(./target/release/measure ../tests/measure_trace.csv -- walkie_talkie 8 20 1000)" "Relay: 1 machine(s)"
+printf "Measure unknown defense... "
+check_failure ./target/release/measure ../tests/measure_trace.csv -- lint
+printf "Measure defense failure... "
+check_failure ./target/release/measure ../tests/measure_trace.csv -- constant 0
+printf "Measure too few events... "
+check_failure ./target/release/measure ../tests/measure_trace.csv --max-events 500 -- constant 4000
+printf "Measure invalid base trace... "
+check_failure ./target/release/measure ../tests/test_tools.sh -- constant 4000
+printf "Measure without a defense... "
+check_failure ./target/release/measure ../tests/measure_trace.csv
+
+# Test the calibration of the constant rate, on the same trace
+printf "Calibrate relay side... "
+check_contains "$(./target/release/calibrate ../tests/measure_trace.csv)" "Send interval: 21818 usec (550005 bits/sec, margin 0.1 over the peak)"
+printf "Calibrate throughput... "
+check_contains "$(./target/release/calibrate ../tests/measure_trace.csv)" "$(printf "Peak throughput: 500000 bits/sec (over 1000000 usec)\nMean throughput: 500000 bits/sec")"
+printf "Calibrate machine... "
+check_equal "$(./target/release/calibrate ../tests/measure_trace.csv | grep '^Machine')" "$(./target/release/constant 21818)"
+printf "Calibrate client side without margin... "
+check_contains "$(./target/release/calibrate ../tests/measure_trace.csv --side client --margin 0)" "Send interval: 8000 usec"
+printf "Calibrate negative margin... "
+check_failure ./target/release/calibrate ../tests/measure_trace.csv --margin -0.1
+printf "Calibrate invalid base trace... "
+check_failure ./target/release/calibrate ../tests/test_tools.sh
+
+# Test the search of Scrambler parameters, on the same trace
+printf "Tune converges... "
+check_contains "$(./target/release/tune ../tests/measure_trace.csv 5)" "$(printf "Scrambler: 1042 500 400 1000\nMeasured overhead: 5.0025 (target 5, within the tolerance)")"
+printf "Tune matches measure... "
+check_contains "$(./target/release/measure ../tests/measure_trace.csv --side relay -- scrambler 1042 500 400 1000 --single-pair)" "Total overhead: 5.0025"
+printf "Tune measurement cap... "
+check_contains "$(./target/release/tune ../tests/measure_trace.csv 5 --max-measurements 1)" "$(printf "Scrambler: 160 500 400 1000\nMeasured overhead: 36.7050 (target 5, closest found, not within the tolerance)\nMeasurements: 1")"
+printf "Tune zero target... "
+check_failure ./target/release/tune ../tests/measure_trace.csv 0
+rm ../tests/measure_trace.csv
+
+# Test help text
+printf "Help text... "
+for binary in constant buflo adapted_front adapted_regulator regulator scrambler adaptive_pad walkie_talkie surakav dynaflow interspace cs_buflo sweep sizes combine lint decode diff generate measure schema calibrate tune; do
+  check_contains "$(./target/release/$binary --help)" "Usage: $binary" > /dev/null
+done
+printf "OK\n"
+printf "Help text units... "
+check_contains "$(./target/release/scrambler --help)" "(usec, > 0,"
+printf "Help text positional arguments unchanged... "
+check_equal "$(./target/release/scrambler 160 500 400 1000 --help | head -n 1)" "Usage: scrambler [send interval] [minimum count] [min trail] [max trail] [options]"
+printf "Explain text... "
+for binary in constant buflo adapted_front adapted_regulator regulator scrambler adaptive_pad walkie_talkie surakav dynaflow interspace cs_buflo; do
+  check_contains "$(./target/release/$binary --explain)" "In the paper: " > /dev/null
+done
+printf "OK\n"
+printf "Explain text parameters... "
+check_contains "$(./target/release/scrambler --explain)" "  min trail"
+
+# Test the maybenot v2 builders, last as they rebuild the binaries with the
+# counters feature (which includes maybenot-v2)
+printf "Constant --maybenot-v2 without the feature... "
+check_failure ./target/release/constant --maybenot-v2
+printf "Scrambler --counters without the feature... "
+check_failure ./target/release/scrambler --counters
+constant_v1=$(./target/release/constant)
+scrambler_v1=$(./target/release/scrambler)
+cargo build --release --features counters
+printf "Scrambler --counters... "
+check_contains "$(./target/release/scrambler --counters)" "Machine 1: 02"
+printf "Scrambler --counters machines... "
+check_equal "$(./target/release/scrambler --counters | grep -c ': ')" "1"
+printf "Scrambler --counters with two pairs... "
+check_failure ./target/release/scrambler --counters --pairs 2
+printf "Scrambler --counters with an L range... "
+check_failure ./target/release/scrambler --counters --left-trail-max 1200
+printf "Scrambler v1 output unchanged with the feature... "
+check_equal "$(./target/release/scrambler)" "$scrambler_v1"
+printf "Constant --maybenot-v2... "
+check_contains "$(./target/release/constant --maybenot-v2)" "Machine: 02"
+printf "Constant --maybenot-v2 with --include-small-packets... "
+check_failure ./target/release/constant --maybenot-v2 --include-small-packets
+printf "Constant v1 output unchanged with the feature... "
+check_equal "$(./target/release/constant)" "$constant_v1"
+printf "Constant v1 and v2 machines, and Scrambler counters, in the simulators... "
+check_success cargo test --release --features counters
+
+# report success
+printf "All tests succeeded\n"